@@ -16,6 +16,7 @@
 
 mod chain_type;
 
+use std::collections::HashMap;
 use std::fs;
 use std::str::{self, FromStr};
 use std::time::Duration;
@@ -24,7 +25,7 @@ use ccore::{MinerOptions, StratumConfig};
 use cidr::IpCidr;
 use ckey::PlatformAddress;
 use clap;
-use cnetwork::{FilterEntry, NetworkConfig, SocketAddr};
+use cnetwork::{BandwidthLimits, FilterEntry, NetworkConfig, ReservedPeer, SocketAddr};
 use toml;
 
 pub use self::chain_type::ChainType;
@@ -164,6 +165,43 @@ impl Config {
             .map(|s| SocketAddr::from_str(s).unwrap())
             .collect::<Vec<_>>();
 
+        let port_mapping = match self.network.nat.as_ref().map(String::as_str) {
+            None | Some("none") => None,
+            Some(nat) => Some(nat.parse()?),
+        };
+
+        let per_extension = self
+            .network
+            .extension_bandwidth_limits
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|s| {
+                let mut split = s.splitn(2, '=');
+                match (split.next(), split.next().map(str::parse::<usize>)) {
+                    (Some(name), Some(Ok(limit))) if !name.is_empty() => Ok((name.to_string(), limit)),
+                    _ => Err(format!("Invalid extension bandwidth limit {}, expected NAME=BYTES", s)),
+                }
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        let reserved_peers = self
+            .network
+            .reserved_peers
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|s| ReservedPeer::from_str(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let reserved_only = self.network.reserved_only.unwrap_or(false);
+        if reserved_only && reserved_peers.is_empty() {
+            return Err("The reserved only mode needs at least one reserved peer".to_string())
+        }
+
+        let bandwidth_limits = BandwidthLimits {
+            per_peer: self.network.peer_bandwidth_limit,
+            per_extension,
+        };
+
         let whitelist = make_ipaddr_list(self.network.whitelist_path.as_ref(), "white")?;
         let blacklist = make_ipaddr_list(self.network.blacklist_path.as_ref(), "black")?;
 
@@ -175,6 +213,10 @@ impl Config {
             max_peers: self.network.max_peers.unwrap(),
             whitelist,
             blacklist,
+            port_mapping,
+            bandwidth_limits,
+            reserved_peers,
+            reserved_only,
         })
     }
 
@@ -244,6 +286,11 @@ pub struct Network {
     pub discovery_bucket_size: Option<u8>,
     pub blacklist_path: Option<String>,
     pub whitelist_path: Option<String>,
+    pub nat: Option<String>,
+    pub peer_bandwidth_limit: Option<usize>,
+    pub extension_bandwidth_limits: Option<Vec<String>>,
+    pub reserved_peers: Option<Vec<String>>,
+    pub reserved_only: Option<bool>,
 }
 
 #[derive(Deserialize)]
@@ -276,6 +323,8 @@ fn default_enable_devel_api() -> bool {
 pub struct Snapshot {
     pub disable: Option<bool>,
     pub path: Option<String>,
+    /// The number of the latest snapshots to keep. Every snapshot is kept if it is not given.
+    pub keep: Option<usize>,
 }
 
 #[derive(Deserialize)]
@@ -487,6 +536,21 @@ impl Network {
         if other.whitelist_path.is_some() {
             self.whitelist_path = other.whitelist_path.clone();
         }
+        if other.nat.is_some() {
+            self.nat = other.nat.clone();
+        }
+        if other.peer_bandwidth_limit.is_some() {
+            self.peer_bandwidth_limit = other.peer_bandwidth_limit;
+        }
+        if other.extension_bandwidth_limits.is_some() {
+            self.extension_bandwidth_limits = other.extension_bandwidth_limits.clone();
+        }
+        if other.reserved_peers.is_some() {
+            self.reserved_peers = other.reserved_peers.clone();
+        }
+        if other.reserved_only.is_some() {
+            self.reserved_only = other.reserved_only;
+        }
     }
 
     pub fn overwrite_with(&mut self, matches: &clap::ArgMatches) -> Result<(), String> {
@@ -541,6 +605,21 @@ impl Network {
         if let Some(file_path) = matches.value_of("blacklist-path") {
             self.blacklist_path = Some(file_path.to_string());
         }
+        if let Some(nat) = matches.value_of("nat") {
+            self.nat = Some(nat.to_string());
+        }
+        if let Some(limit) = matches.value_of("peer-bandwidth-limit") {
+            self.peer_bandwidth_limit = Some(limit.parse().map_err(|_| "Invalid peer-bandwidth-limit")?);
+        }
+        if let Some(limits) = matches.values_of("extension-bandwidth-limits") {
+            self.extension_bandwidth_limits = Some(limits.map(|a| a.into()).collect());
+        }
+        if let Some(peers) = matches.values_of("reserved-peers") {
+            self.reserved_peers = Some(peers.map(|a| a.into()).collect());
+        }
+        if matches.is_present("reserved-only") {
+            self.reserved_only = Some(true);
+        }
 
         Ok(())
     }
@@ -630,6 +709,9 @@ impl Snapshot {
         if other.path.is_some() {
             self.path = other.path.clone();
         }
+        if other.keep.is_some() {
+            self.keep = other.keep;
+        }
     }
 
     pub fn overwrite_with(&mut self, matches: &clap::ArgMatches) -> Result<(), String> {
@@ -640,6 +722,9 @@ impl Snapshot {
         if let Some(snapshot_path) = matches.value_of("snapshot-path") {
             self.path = Some(snapshot_path.to_string());
         }
+        if let Some(keep) = matches.value_of("snapshot-keep") {
+            self.keep = Some(keep.parse().map_err(|_| "Invalid number of snapshots to keep")?);
+        }
         Ok(())
     }
 }
@@ -14,4 +14,6 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+pub const DEFAULT_DB_PATH: &str = "db";
 pub const DEFAULT_KEYS_PATH: &str = "keys";
+pub const DEFAULT_SNAPSHOT_PATH: &str = "snapshot";
@@ -19,7 +19,7 @@ use std::net::IpAddr;
 
 use cidr::IpCidr;
 use ckey::Public;
-use cnetwork::{FilterEntry, NetworkControl, NetworkControlError, SocketAddr};
+use cnetwork::{FilterEntry, NetworkControl, NetworkControlError, PeerScore, SocketAddr};
 
 pub struct DummyNetworkService {}
 
@@ -110,4 +110,8 @@ impl NetworkControl for DummyNetworkService {
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>, NetworkControlError> {
         Err(NetworkControlError::Disabled)
     }
+
+    fn peer_scores(&self) -> Result<Vec<(SocketAddr, PeerScore)>, NetworkControlError> {
+        Err(NetworkControlError::Disabled)
+    }
 }
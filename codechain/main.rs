@@ -52,6 +52,7 @@ extern crate never_type;
 extern crate panic_hook;
 extern crate parking_lot;
 extern crate primitives;
+extern crate rlp;
 extern crate rpassword;
 extern crate toml;
 
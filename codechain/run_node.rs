@@ -22,15 +22,18 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use ccore::{
     AccountProvider, AccountProviderError, ChainNotify, Client, ClientConfig, ClientService, EngineInfo, EngineType,
-    Miner, MinerService, Scheme, Stratum, StratumConfig, StratumError, NUM_COLUMNS,
+    Miner, MinerService, Scheme, Stratum, StratumConfig, StratumError, COL_PEER, NUM_COLUMNS,
 };
 use cdiscovery::{Config, Discovery};
-use ckey::{Address, NetworkId, PlatformAddress};
+use ckey::{Address, KeyPair, NetworkId, PlatformAddress, Public};
 use ckeystore::accounts_dir::RootDiskDirectory;
 use ckeystore::KeyStore;
 use clap::ArgMatches;
 use clogger::{self, LoggerConfig};
-use cnetwork::{Filters, NetworkConfig, NetworkControl, NetworkService, RoutingTable, SocketAddr};
+use cnetwork::{
+    load_or_create_node_key, AddressBook, Filters, NetworkConfig, NetworkControl, NetworkService, Reputation,
+    ReservedPeers, RoutingTable, SocketAddr,
+};
 use creactor::EventLoop;
 use csync::{BlockSyncExtension, BlockSyncSender, SnapshotService, TransactionSyncExtension};
 use ctimer::TimerLoop;
@@ -53,6 +56,9 @@ fn network_start(
     timer_loop: TimerLoop,
     cfg: &NetworkConfig,
     routing_table: Arc<RoutingTable>,
+    reputation: Arc<Reputation>,
+    address_book: Arc<AddressBook>,
+    node_key: KeyPair,
 ) -> Result<Arc<NetworkService>, String> {
     let addr = cfg.address.parse().map_err(|_| format!("Invalid NETWORK listen host given: {}", cfg.address))?;
     let sockaddress = SocketAddr::new(addr, cfg.port);
@@ -66,6 +72,12 @@ fn network_start(
         cfg.max_peers,
         filters,
         routing_table,
+        reputation,
+        address_book,
+        cfg.port_mapping,
+        cfg.bandwidth_limits.clone(),
+        node_key,
+        ReservedPeers::new(cfg.reserved_peers.clone(), cfg.reserved_only),
     )
     .map_err(|e| format!("Network service error: {:?}", e))?;
 
@@ -76,22 +88,26 @@ fn discovery_start(
     service: &NetworkService,
     cfg: &config::Network,
     routing_table: Arc<RoutingTable>,
+    node_key: Public,
 ) -> Result<(), String> {
     let config = Config {
         bucket_size: cfg.discovery_bucket_size.unwrap(),
         t_refresh: cfg.discovery_refresh.unwrap(),
     };
     let use_kademlia = match cfg.discovery_type.as_ref().map(String::as_str) {
-        Some("unstructured") => true,
+        Some("unstructured") => false,
         Some("kademlia") => true,
         Some(discovery_type) => return Err(format!("Unknown discovery {}", discovery_type)),
         None => return Ok(()),
     };
-    service.register_extension(move |api| Discovery::new(routing_table, config, api, use_kademlia));
+    let external_address = service.external_address();
+    service.register_extension(move |api| {
+        Discovery::new(routing_table, external_address, config, api, use_kademlia, node_key)
+    });
     Ok(())
 }
 
-fn client_start(
+pub fn client_start(
     client_config: &ClientConfig,
     timer_loop: &TimerLoop,
     db: Arc<KeyValueDB>,
@@ -264,6 +280,9 @@ pub fn run_node(matches: &ArgMatches) -> Result<(), String> {
     let db = open_db(&config.operating, &client_config)?;
 
     let miner = new_miner(&config, &scheme, ap.clone(), Arc::clone(&db))?;
+    let reputation = Reputation::new(Arc::clone(&db), COL_PEER);
+    let address_book = AddressBook::new(Arc::clone(&db), COL_PEER);
+    let node_key = load_or_create_node_key(&*db, COL_PEER)?;
     let client = client_start(&client_config, &timer_loop, db, &scheme, miner.clone())?;
     miner.recover_from_db(client.client().as_ref());
 
@@ -277,10 +296,19 @@ pub fn run_node(matches: &ArgMatches) -> Result<(), String> {
             let network_config = config.network_config()?;
             let network_id = client.client().common_params().network_id;
             let routing_table = RoutingTable::new();
-            let service = network_start(network_id, timer_loop, &network_config, Arc::clone(&routing_table))?;
+            let node_public = *node_key.public();
+            let service = network_start(
+                network_id,
+                timer_loop,
+                &network_config,
+                Arc::clone(&routing_table),
+                reputation,
+                address_book,
+                node_key,
+            )?;
 
             if config.network.discovery.unwrap() {
-                discovery_start(&service, &config.network, routing_table)?;
+                discovery_start(&service, &config.network, routing_table, node_public)?;
             } else {
                 cwarn!(DISCOVERY, "Node runs without discovery extension");
             }
@@ -288,7 +316,12 @@ pub fn run_node(matches: &ArgMatches) -> Result<(), String> {
             if config.network.sync.unwrap() {
                 let sync_sender = {
                     let client = client.client();
-                    service.register_extension(move |api| BlockSyncExtension::new(client, api))
+                    let snapshot_dir = if !config.snapshot.disable.unwrap() {
+                        config.snapshot.path.clone()
+                    } else {
+                        None
+                    };
+                    service.register_extension(move |api| BlockSyncExtension::new(client, api, snapshot_dir))
                 };
                 let sync = Arc::new(BlockSyncSender::from(sync_sender.clone()));
                 client.client().add_notify(Arc::downgrade(&sync) as Weak<ChainNotify>);
@@ -346,8 +379,12 @@ pub fn run_node(matches: &ArgMatches) -> Result<(), String> {
 
     let _snapshot_service = {
         if !config.snapshot.disable.unwrap() {
-            let service =
-                SnapshotService::new(client.client(), config.snapshot.path.unwrap(), scheme.params().snapshot_period);
+            let service = SnapshotService::new(
+                client.client(),
+                config.snapshot.path.unwrap(),
+                scheme.params().snapshot_period,
+                config.snapshot.keep,
+            );
             client.client().add_notify(Arc::downgrade(&service) as Weak<ChainNotify>);
             Some(service)
         } else {
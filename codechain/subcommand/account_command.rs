@@ -20,13 +20,14 @@ use std::str::FromStr;
 use rpassword;
 
 use ccore::AccountProvider;
-use ckey::{NetworkId, Password, PlatformAddress, Private};
-use ckeystore::accounts_dir::RootDiskDirectory;
-use ckeystore::KeyStore;
+use ckey::{DerivationPath, NetworkId, Password, PlatformAddress, Private, HARDENED_INDEX};
+use ckeystore::accounts_dir::{KeyDirectory, RootDiskDirectory, VaultDiskDirectory};
+use ckeystore::{export_archive, generate_mnemonic, import_archive, KeyStore, KEY_ITERATIONS};
 use clap::ArgMatches;
 use clogger::{self, LoggerConfig};
 use primitives::remove_0x_prefix;
 
+use super::get_global_argument;
 use crate::config::ChainType;
 use crate::constants::DEFAULT_KEYS_PATH;
 
@@ -39,17 +40,49 @@ pub fn run_account_command(matches: &ArgMatches) -> Result<(), String> {
     clogger::init(&LoggerConfig::new(0)).expect("Logger must be successfully initialized");
 
     let keys_path = get_global_argument(matches, "keys-path").unwrap_or_else(|| DEFAULT_KEYS_PATH.into());
-    let dir = RootDiskDirectory::create(keys_path).expect("Cannot read key path directory");
-    let keystore = KeyStore::open(Box::new(dir)).unwrap();
-    let ap = AccountProvider::new(keystore);
+    let root = RootDiskDirectory::create(&keys_path).expect("Cannot read key path directory");
+    match matches.subcommand() {
+        ("create-vault", Some(matches)) => {
+            let name = matches.value_of("NAME").expect("NAME arg is required and its index is 1");
+            return create_vault(&keys_path, name)
+        }
+        ("list-vaults", _) => return list_vaults(&keys_path),
+        _ => {}
+    }
+    let dir: Box<KeyDirectory> = match get_global_argument(matches, "vault") {
+        Some(name) => {
+            let password = prompt_password("Vault Password: ");
+            Box::new(VaultDiskDirectory::at(&keys_path, &name, &password).map_err(|err| err.to_string())?)
+        }
+        None => Box::new(root),
+    };
     let chain = get_global_argument(matches, "chain").unwrap_or_else(|| "mainnet".into());
     let chain_type: ChainType = chain.parse().unwrap();
     let network_id: NetworkId = chain_type.scheme().map(|scheme| scheme.params().network_id)?;
 
+    match matches.subcommand() {
+        ("export", Some(matches)) => {
+            let path = matches.value_of("to").expect("to arg is required");
+            let address = if matches.is_present("all") {
+                None
+            } else {
+                Some(matches.value_of("ADDRESS").expect("ADDRESS arg is required unless all is given"))
+            };
+            return export(dir.as_ref(), network_id, address, path)
+        }
+        ("import", Some(matches)) if matches.is_present("from") => {
+            let path = matches.value_of("from").expect("from arg is present");
+            return import_from_archive(dir.as_ref(), network_id, path)
+        }
+        _ => {}
+    }
+    let keystore = KeyStore::open(dir).unwrap();
+    let ap = AccountProvider::new(keystore);
+
     match matches.subcommand() {
         ("create", _) => create(&ap, network_id),
         ("import", Some(matches)) => {
-            let json_path = matches.value_of("JSON_PATH").expect("JSON_PATH arg is required and its index is 1");
+            let json_path = matches.value_of("JSON_PATH").expect("JSON_PATH arg is required unless from is given");
             import(&ap, network_id, json_path)
         }
         ("import-raw", Some(matches)) => {
@@ -65,6 +98,12 @@ pub fn run_account_command(matches: &ArgMatches) -> Result<(), String> {
             let address = matches.value_of("ADDRESS").expect("ADDRESS arg is required and its index is 1");
             change_password(&ap, address)
         }
+        ("create-from-mnemonic", Some(matches)) => create_from_mnemonic(&ap, network_id, matches.value_of("path")),
+        ("derive", Some(matches)) => {
+            let address = matches.value_of("ADDRESS").expect("ADDRESS arg is required and its index is 1");
+            let index = matches.value_of("INDEX").expect("INDEX arg is required and its index is 2");
+            derive(&ap, network_id, address, index, matches.is_present("hardened"))
+        }
         _ => Err("Invalid subcommand".to_string()),
     }
 }
@@ -120,6 +159,97 @@ fn change_password(ap: &AccountProvider, address: &str) -> Result<(), String> {
     Ok(())
 }
 
+fn create_from_mnemonic(ap: &AccountProvider, network_id: NetworkId, path: Option<&str>) -> Result<(), String> {
+    let path = match path {
+        Some(path) => DerivationPath::from_str(path).map_err(|err| err.to_string())?,
+        None => DerivationPath::new(vec![HARDENED_INDEX]),
+    };
+    println!("Mnemonic (leave empty to generate a new one): ");
+    let mut mnemonic = String::new();
+    std::io::stdin().read_line(&mut mnemonic).map_err(|e| format!("Failed to read line: {}", e))?;
+    let mnemonic = match mnemonic.trim() {
+        "" => {
+            let mnemonic = generate_mnemonic();
+            println!("Write down the mnemonic below. It is the only way to recover the account.");
+            println!("{}", mnemonic);
+            mnemonic
+        }
+        mnemonic => mnemonic.to_string(),
+    };
+    let password = read_password_and_confirm().ok_or("The password does not match")?;
+    let address = ap.insert_account_from_mnemonic(&mnemonic, path, &password).map_err(|err| err.to_string())?;
+    println!("{}", PlatformAddress::new_v1(network_id, address));
+    Ok(())
+}
+
+fn derive(
+    ap: &AccountProvider,
+    network_id: NetworkId,
+    address: &str,
+    index: &str,
+    hardened: bool,
+) -> Result<(), String> {
+    let address = PlatformAddress::from_str(address).map_err(|err| err.to_string())?;
+    let index = match index.parse::<u32>() {
+        Ok(index) if index < HARDENED_INDEX => index,
+        _ => return Err(format!("{} is not a valid child index", index)),
+    };
+    let index = if hardened {
+        index + HARDENED_INDEX
+    } else {
+        index
+    };
+    let password = prompt_password("Password: ");
+    let child = ap.derive_account(&address.into_address(), &password, index).map_err(|err| err.to_string())?;
+    println!("{}", PlatformAddress::new_v1(network_id, child));
+    Ok(())
+}
+
+fn create_vault(keys_path: &str, name: &str) -> Result<(), String> {
+    let password = read_password_and_confirm().ok_or("The password does not match")?;
+    VaultDiskDirectory::create(keys_path, name, &password, KEY_ITERATIONS).map_err(|err| err.to_string())?;
+    println!("Vault {} is created", name);
+    Ok(())
+}
+
+fn list_vaults(keys_path: &str) -> Result<(), String> {
+    for name in VaultDiskDirectory::list(keys_path).map_err(|err| err.to_string())? {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+fn export(dir: &KeyDirectory, network_id: NetworkId, address: Option<&str>, path: &str) -> Result<(), String> {
+    let addresses = match address {
+        Some(address) => Some(vec![PlatformAddress::from_str(address).map_err(|err| err.to_string())?.into_address()]),
+        None => None,
+    };
+    println!("The archive is encrypted with the password below. The keys in it keep their own passwords.");
+    let password = read_password_and_confirm().ok_or("The password does not match")?;
+    let mut file = fs::File::create(path).map_err(|err| err.to_string())?;
+    let exported = export_archive(dir, addresses.as_ref().map(Vec::as_slice), &password, KEY_ITERATIONS, &mut file)
+        .map_err(|err| err.to_string())?;
+    for address in &exported {
+        println!("{}", PlatformAddress::new_v1(network_id, *address));
+    }
+    println!("{} account(s) are exported to {}", exported.len(), path);
+    Ok(())
+}
+
+fn import_from_archive(dir: &KeyDirectory, network_id: NetworkId, path: &str) -> Result<(), String> {
+    let file = fs::File::open(path).map_err(|err| err.to_string())?;
+    let password = prompt_password("Archive Password: ");
+    let restored = import_archive(file, &password, dir).map_err(|err| err.to_string())?;
+    for address in &restored.imported {
+        println!("{}", PlatformAddress::new_v1(network_id, *address));
+    }
+    for address in &restored.duplicated {
+        println!("{} already exists. Skipped", PlatformAddress::new_v1(network_id, *address));
+    }
+    println!("{} account(s) are imported", restored.imported.len());
+    Ok(())
+}
+
 fn prompt_password(prompt: &str) -> Password {
     rpassword::prompt_password_stdout(prompt).map(Password::from).unwrap()
 }
@@ -140,13 +270,3 @@ fn read_password_and_confirm() -> Option<Password> {
         None
     }
 }
-
-fn get_global_argument(matches: &ArgMatches, arg_name: &str) -> Option<String> {
-    match matches.value_of(arg_name) {
-        Some(value) => Some(value.to_string()),
-        None => match matches.subcommand() {
-            (_, Some(matches)) => matches.value_of(arg_name).map(ToString::to_string),
-            _ => None,
-        },
-    }
-}
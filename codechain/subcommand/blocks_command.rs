@@ -0,0 +1,325 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Lines, Read, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ccore::encoded;
+use ccore::{
+    BlockChainClient, BlockId, BlockImportError, BlockInfo, BlockStatus, ChainInfo, ClientConfig, ClientService,
+    EngineInfo, Header, ImportBlock, ImportError, Miner, MinerOptions, NUM_COLUMNS,
+};
+use ckey::hex::{FromHex, ToHex};
+use clap::ArgMatches;
+use clogger::{self, LoggerConfig};
+use ctimer::TimerLoop;
+use ctypes::BlockNumber;
+use kvdb::KeyValueDB;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use primitives::{remove_0x_prefix, H256};
+use rlp::UntrustedRlp;
+
+use super::get_global_argument;
+use crate::config::ChainType;
+use crate::constants::DEFAULT_DB_PATH;
+use crate::run_node::client_start;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/// The bound of the header and the seal of a block, which is added to the maximum body size
+const MAX_HEADER_SIZE: usize = 1024 * 1024;
+
+#[derive(Clone, Copy)]
+enum Format {
+    /// The RLP of the blocks, one after another.
+    Rlp,
+    /// A JSON object per line, which has the RLP of the block in the `rlp` field.
+    Json,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonBlock {
+    number: BlockNumber,
+    hash: H256,
+    parent_hash: H256,
+    timestamp: u64,
+    transactions_count: usize,
+    rlp: String,
+}
+
+impl From<encoded::Block> for JsonBlock {
+    fn from(block: encoded::Block) -> Self {
+        JsonBlock {
+            number: block.number(),
+            hash: block.hash(),
+            parent_hash: block.parent_hash(),
+            timestamp: block.timestamp(),
+            transactions_count: block.transactions_count(),
+            rlp: format!("0x{}", block.into_inner().to_hex()),
+        }
+    }
+}
+
+pub fn run_export_blocks_command(matches: &ArgMatches) -> Result<(), String> {
+    clogger::init(&LoggerConfig::new(0)).expect("Logger must be successfully initialized");
+
+    let path = matches.value_of("FILE").expect("FILE arg is required and its index is 1");
+    let from = match matches.value_of("from") {
+        Some(from) => from.parse().map_err(|_| format!("Invalid block number {}", from))?,
+        None => 1,
+    };
+    let to = match matches.value_of("to") {
+        Some(to) => Some(to.parse().map_err(|_| format!("Invalid block number {}", to))?),
+        None => None,
+    };
+    let format = parse_format(matches);
+
+    let timer_loop = TimerLoop::new(1);
+    let service = open_client(matches, &timer_loop)?;
+    let client = service.client();
+
+    let to = to.unwrap_or_else(|| client.chain_info().best_block_number);
+    if from > to {
+        return Err(format!("There are no blocks from #{} to #{}", from, to))
+    }
+
+    let file = File::create(path).map_err(|err| format!("Cannot create {}: {}", path, err))?;
+    let mut writer = BufWriter::new(file);
+    let mut last_report = Instant::now();
+    for number in from..=to {
+        let block = client.block(&BlockId::Number(number)).ok_or_else(|| format!("Block #{} is not found", number))?;
+        let written = match format {
+            Format::Rlp => writer.write_all(&block.into_inner()),
+            Format::Json => serde_json::to_writer(&mut writer, &JsonBlock::from(block))
+                .map_err(io::Error::from)
+                .and_then(|_| writer.write_all(b"\n")),
+        };
+        written.map_err(|err| format!("Cannot write to {}: {}", path, err))?;
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            println!("Exported #{} ({}/{})", number, number - from + 1, to - from + 1);
+            last_report = Instant::now();
+        }
+    }
+    writer.flush().map_err(|err| format!("Cannot write to {}: {}", path, err))?;
+    println!("Exported {} blocks from #{} to #{} into {}", to - from + 1, from, to, path);
+    Ok(())
+}
+
+pub fn run_import_blocks_command(matches: &ArgMatches) -> Result<(), String> {
+    clogger::init(&LoggerConfig::new(0)).expect("Logger must be successfully initialized");
+
+    let path = matches.value_of("FILE").expect("FILE arg is required and its index is 1");
+    let format = parse_format(matches);
+    let file = File::open(path).map_err(|err| format!("Cannot open {}: {}", path, err))?;
+
+    let timer_loop = TimerLoop::new(1);
+    let service = open_client(matches, &timer_loop)?;
+    let client = service.client();
+    let max_block_size = client.common_params().max_body_size + MAX_HEADER_SIZE;
+    let blocks = BlockReader::new(file, format, max_block_size);
+
+    let mut queued = Vec::new();
+    let mut skipped = 0;
+    let mut last_report = Instant::now();
+    for bytes in blocks {
+        let bytes = bytes.map_err(|err| format!("Cannot read {}: {}", path, err))?;
+        let header: Header =
+            UntrustedRlp::new(&bytes).val_at(0).map_err(|err| format!("Invalid block in {}: {}", path, err))?;
+
+        while client.queue_info().is_full() {
+            thread::sleep(Duration::from_millis(100));
+        }
+        match client.import_block(bytes) {
+            Ok(hash) => queued.push((header.number(), hash)),
+            Err(BlockImportError::Import(ImportError::AlreadyInChain))
+            | Err(BlockImportError::Import(ImportError::AlreadyQueued)) => skipped += 1,
+            Err(err) => {
+                return Err(format!("Cannot import #{} 0x{:x}: {:?}", header.number(), header.hash(), err))
+            }
+        }
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            println!("Queued #{}, the best block is #{}", header.number(), client.chain_info().best_block_number);
+            last_report = Instant::now();
+        }
+    }
+
+    while !client.queue_info().is_empty() {
+        thread::sleep(Duration::from_millis(100));
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            let queue_info = client.queue_info();
+            println!(
+                "{} blocks are in the queue, the best block is #{}",
+                queue_info.total_queue_size(),
+                client.chain_info().best_block_number
+            );
+            last_report = Instant::now();
+        }
+    }
+
+    // A queued block can still be rejected by the verification or the execution.
+    let failed: Vec<_> =
+        queued.iter().filter(|(_, hash)| client.block_status(&BlockId::Hash(*hash)) != BlockStatus::InChain).collect();
+    for (number, hash) in &failed {
+        println!("Failed to import #{} 0x{:x}", number, hash);
+    }
+
+    let chain_info = client.chain_info();
+    println!(
+        "Imported {} blocks ({} skipped, {} failed). The best block is #{} 0x{:x}",
+        queued.len() - failed.len(),
+        skipped,
+        failed.len(),
+        chain_info.best_block_number,
+        chain_info.best_block_hash
+    );
+    if !failed.is_empty() {
+        return Err(format!("{} blocks in {} cannot be imported", failed.len(), path))
+    }
+    Ok(())
+}
+
+fn parse_format(matches: &ArgMatches) -> Format {
+    match matches.value_of("format") {
+        Some("json") => Format::Json,
+        _ => Format::Rlp,
+    }
+}
+
+pub fn open_client(matches: &ArgMatches, timer_loop: &TimerLoop) -> Result<ClientService, String> {
+    let chain_type: ChainType =
+        get_global_argument(matches, "chain").map(|chain| chain.parse().unwrap()).unwrap_or_default();
+    let scheme = chain_type.scheme()?;
+
+    let db_path = matches.value_of("db-path").unwrap_or(DEFAULT_DB_PATH);
+    let db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
+    let db = Database::open(&db_config, db_path)
+        .map_err(|_e| "Low level database error. Some issue with disk?".to_string())?;
+    let db: Arc<KeyValueDB> = Arc::new(db);
+
+    let miner = Miner::new(MinerOptions::default(), &scheme, None, Arc::clone(&db));
+    client_start(&ClientConfig::default(), timer_loop, db, &scheme, miner)
+}
+
+enum BlockReader {
+    Rlp(BufReader<File>, usize),
+    Json(Lines<BufReader<File>>),
+}
+
+impl BlockReader {
+    /// The blocks in the RLP format cannot be larger than `max_len`.
+    fn new(file: File, format: Format, max_len: usize) -> Self {
+        let reader = BufReader::new(file);
+        match format {
+            Format::Rlp => BlockReader::Rlp(reader, max_len),
+            Format::Json => BlockReader::Json(reader.lines()),
+        }
+    }
+}
+
+impl Iterator for BlockReader {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            BlockReader::Rlp(reader, max_len) => read_rlp_list(reader, *max_len).transpose(),
+            BlockReader::Json(lines) => {
+                let line = match lines.next()? {
+                    Ok(line) => line,
+                    Err(err) => return Some(Err(err)),
+                };
+                Some(serde_json::from_str::<JsonBlock>(&line).map_err(io::Error::from).and_then(|block| {
+                    FromHex::from_hex(remove_0x_prefix(&block.rlp))
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+                }))
+            }
+        }
+    }
+}
+
+/// Reads the next RLP list from the stream. Returns `None` at the end of the stream.
+/// A list whose payload is longer than `max_len` is an error, so that a corrupted length doesn't exhaust the memory.
+fn read_rlp_list<R: Read>(reader: &mut R, max_len: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut prefix = [0u8; 1];
+    match reader.read_exact(&mut prefix) {
+        Ok(()) => {}
+        Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let mut item = prefix.to_vec();
+    let payload_len = match prefix[0] {
+        0xc0..=0xf7 => u64::from(prefix[0] - 0xc0),
+        0xf8..=0xff => {
+            let mut len = vec![0u8; usize::from(prefix[0] - 0xf7)];
+            reader.read_exact(&mut len)?;
+            item.extend_from_slice(&len);
+            len.iter().fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte))
+        }
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "A block must be an RLP list")),
+    };
+    if payload_len > max_len as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("A block cannot be longer than {} bytes, but it is {} bytes", max_len, payload_len),
+        ))
+    }
+    let payload_len = payload_len as usize;
+    let header_len = item.len();
+    item.resize(header_len + payload_len, 0);
+    reader.read_exact(&mut item[header_len..])?;
+    Ok(Some(item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_rlp_lists_one_after_another() {
+        let short = rlp::encode_list(&[1u8, 2, 3]).into_vec();
+        let long = rlp::encode_list(&vec![H256::random(); 4]).into_vec();
+        let mut stream = short.clone();
+        stream.extend_from_slice(&long);
+
+        let mut reader = &stream[..];
+        assert_eq!(Some(short), read_rlp_list(&mut reader, 1024).unwrap());
+        assert_eq!(Some(long), read_rlp_list(&mut reader, 1024).unwrap());
+        assert_eq!(None, read_rlp_list(&mut reader, 1024).unwrap());
+    }
+
+    #[test]
+    fn reading_a_too_long_list_fails() {
+        // The prefix claims a payload of 2^56 bytes.
+        let stream = [0xff, 1, 0, 0, 0, 0, 0, 0, 0];
+        let mut reader = &stream[..];
+        assert!(read_rlp_list(&mut reader, 1024).is_err());
+
+        let stream = rlp::encode_list(&vec![H256::random(); 4]).into_vec();
+        let mut reader = &stream[..];
+        assert!(read_rlp_list(&mut reader, 100).is_err());
+    }
+
+    #[test]
+    fn reading_a_non_list_fails() {
+        let stream = rlp::encode(&1u8).into_vec();
+        let mut reader = &stream[..];
+        assert!(read_rlp_list(&mut reader, 1024).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use ccore::{DatabaseInspector, NUM_COLUMNS};
+use clap::ArgMatches;
+use ctypes::BlockNumber;
+use kvdb::KeyValueDB;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+
+use super::get_global_argument;
+use crate::constants::DEFAULT_DB_PATH;
+
+const COLUMN_NAMES: [&str; 7] = ["state", "headers", "bodies", "extra", "mempool", "error hint", "peer"];
+const CHECK_PROGRESS_BLOCKS: BlockNumber = 10_000;
+
+pub fn run_db_command(matches: &ArgMatches) -> Result<(), String> {
+    let subcommand = matches.subcommand.as_ref().ok_or_else(|| "db subcommand is required".to_string())?;
+    let db_path = get_global_argument(matches, "db-path").unwrap_or_else(|| DEFAULT_DB_PATH.to_string());
+    if !Path::new(&db_path).exists() {
+        return Err(format!("The database {} doesn't exist", db_path))
+    }
+    let db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
+    let db = Database::open(&db_config, &db_path)
+        .map_err(|_e| "Low level database error. Some issue with disk?".to_string())?;
+
+    match subcommand.name.as_str() {
+        "sizes" => print_column_sizes(&db),
+        "check" => {
+            let from = parse_block_number(&subcommand.matches, "from")?.unwrap_or(0);
+            check_db(&open_inspector(db)?, from)
+        }
+        "rewind" => {
+            let number = parse_block_number(&subcommand.matches, "NUMBER")?.expect("NUMBER is required");
+            let hash = open_inspector(db)?.rewind(number).map_err(|e| e.to_string())?;
+            println!("The best block is rewound to #{}({})", number, hash);
+            Ok(())
+        }
+        _ => Err("Invalid db subcommand".to_string()),
+    }
+}
+
+fn open_inspector(db: Database) -> Result<DatabaseInspector, String> {
+    let db: Arc<KeyValueDB> = Arc::new(db);
+    DatabaseInspector::new(db).map_err(|e| e.to_string())
+}
+
+fn parse_block_number(matches: &ArgMatches, name: &str) -> Result<Option<BlockNumber>, String> {
+    match matches.value_of(name) {
+        Some(number) => number.parse().map(Some).map_err(|_| format!("Invalid block number: {}", number)),
+        None => Ok(None),
+    }
+}
+
+fn print_column_sizes(db: &Database) -> Result<(), String> {
+    let mut total_keys = 0;
+    let mut total_bytes = 0;
+    for (col, name) in COLUMN_NAMES.iter().enumerate() {
+        let size = db.column_size(Some(col as u32)).ok_or_else(|| format!("The column {} doesn't exist", name))?;
+        println!("{:>10}: {:>12} keys {:>16} bytes", name, size.keys, size.bytes);
+        total_keys += size.keys;
+        total_bytes += size.bytes;
+    }
+    println!("{:>10}: {:>12} keys {:>16} bytes", "total", total_keys, total_bytes);
+    Ok(())
+}
+
+fn check_db(inspector: &DatabaseInspector, from: BlockNumber) -> Result<(), String> {
+    let mut error_count = 0;
+    let mut report = |errors: Vec<_>| {
+        for error in &errors {
+            println!("{}", error);
+        }
+        error_count += errors.len();
+    };
+
+    report(inspector.check_pointers());
+    let best_block = inspector.best_block_number().ok_or_else(|| "The best block is unknown".to_string())?;
+
+    let started = Instant::now();
+    let mut start = from;
+    while start <= best_block {
+        let end = std::cmp::min(start + CHECK_PROGRESS_BLOCKS - 1, best_block);
+        report(inspector.check_blocks(start, end));
+        println!("Checked #{}-#{} in {:?}", start, end, started.elapsed());
+        start = end + 1;
+    }
+    if let Err(error) = inspector.check_state(best_block) {
+        report(vec![error]);
+    }
+
+    if error_count == 0 {
+        println!("No problem is found up to #{}", best_block);
+        Ok(())
+    } else {
+        Err(format!("{} problems are found", error_count))
+    }
+}
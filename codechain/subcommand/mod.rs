@@ -15,18 +15,34 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod account_command;
+mod blocks_command;
 mod convert_command;
+mod db_command;
+mod replay_command;
+mod scheme_command;
+mod snapshot_command;
 
 use clap::ArgMatches;
 
 use self::account_command::run_account_command;
+use self::blocks_command::{run_export_blocks_command, run_import_blocks_command};
 use self::convert_command::run_convert_command;
+use self::db_command::run_db_command;
+use self::replay_command::run_replay_command;
+use self::scheme_command::run_scheme_command;
+use self::snapshot_command::run_snapshot_command;
 
 pub fn run_subcommand(matches: &ArgMatches) -> Result<(), String> {
     let subcommand = matches.subcommand.as_ref().unwrap();
     match subcommand.name.as_str() {
         "account" => run_account_command(&subcommand.matches),
         "convert" => run_convert_command(&subcommand.matches),
+        "db" => run_db_command(&subcommand.matches),
+        "export-blocks" => run_export_blocks_command(&subcommand.matches),
+        "import-blocks" => run_import_blocks_command(&subcommand.matches),
+        "replay" => run_replay_command(&subcommand.matches),
+        "scheme" => run_scheme_command(&subcommand.matches),
+        "snapshot" => run_snapshot_command(&subcommand.matches),
         "commit-hash" => {
             println!("{}", env!("VERGEN_SHA"));
             Ok(())
@@ -34,3 +50,13 @@ pub fn run_subcommand(matches: &ArgMatches) -> Result<(), String> {
         _ => Err("Invalid subcommand".to_string()),
     }
 }
+
+fn get_global_argument(matches: &ArgMatches, arg_name: &str) -> Option<String> {
+    match matches.value_of(arg_name) {
+        Some(value) => Some(value.to_string()),
+        None => match matches.subcommand() {
+            (_, Some(matches)) => matches.value_of(arg_name).map(ToString::to_string),
+            _ => None,
+        },
+    }
+}
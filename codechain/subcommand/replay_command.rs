@@ -0,0 +1,105 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+
+use ccore::{BlockChainClient, BlockId, ChainInfo, DivergenceCause};
+use ckey::hex::ToHex;
+use clap::ArgMatches;
+use clogger::{self, LoggerConfig};
+use ctimer::TimerLoop;
+use ctypes::BlockNumber;
+
+use super::blocks_command::open_client;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn run_replay_command(matches: &ArgMatches) -> Result<(), String> {
+    clogger::init(&LoggerConfig::new(0)).expect("Logger must be successfully initialized");
+
+    let from = parse_block_number(matches, "from")?.unwrap_or(1);
+    let to = parse_block_number(matches, "to")?;
+
+    let timer_loop = TimerLoop::new(1);
+    let service = open_client(matches, &timer_loop)?;
+    let client = service.client();
+
+    let to = to.unwrap_or_else(|| client.chain_info().best_block_number);
+    if from == 0 {
+        return Err("The genesis block cannot be replayed".to_string())
+    }
+    if from > to {
+        return Err(format!("There are no blocks from #{} to #{}", from, to))
+    }
+
+    let started = Instant::now();
+    let mut last_report = Instant::now();
+    for number in from..=to {
+        let block = client.block(&BlockId::Number(number)).ok_or_else(|| format!("Block #{} is not found", number))?;
+        let divergence = client
+            .replay_block(&block)
+            .map_err(|err| format!("Cannot replay #{} 0x{:x}: {}", number, block.hash(), err))?;
+        if let Some(divergence) = divergence {
+            println!("#{} 0x{:x} diverged", divergence.number, divergence.hash);
+            println!("{}", divergence.cause);
+            let (before, after) = match divergence.cause {
+                DivergenceCause::StateRoot {
+                    ..
+                } => {
+                    println!("{} state entries differ", divergence.state_changes.len());
+                    ("stored:  ", "replayed:")
+                }
+                _ => {
+                    println!("The transaction changed {} state entries", divergence.state_changes.len());
+                    ("before:  ", "after:   ")
+                }
+            };
+            for change in divergence.state_changes {
+                println!("  0x{:x}", change.key);
+                println!("    {} {}", before, format_value(change.before));
+                println!("    {} {}", after, format_value(change.after));
+            }
+            return Err(format!("The replay diverged at #{}", number))
+        }
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            println!("Replayed #{} ({}/{})", number, number - from + 1, to - from + 1);
+            last_report = Instant::now();
+        }
+    }
+    println!(
+        "Replayed {} blocks from #{} to #{} in {:?} without divergence",
+        to - from + 1,
+        from,
+        to,
+        started.elapsed()
+    );
+    Ok(())
+}
+
+fn parse_block_number(matches: &ArgMatches, name: &str) -> Result<Option<BlockNumber>, String> {
+    match matches.value_of(name) {
+        Some(number) => number.parse().map(Some).map_err(|_| format!("Invalid block number {}", number)),
+        None => Ok(None),
+    }
+}
+
+fn format_value(value: Option<Vec<u8>>) -> String {
+    match value {
+        Some(value) => format!("0x{}", value.to_hex()),
+        None => "(none)".to_string(),
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::str::FromStr;
+
+use ccore::Scheme;
+use ckey::hex::ToHex;
+use ckey::{public_to_address, Address, NetworkId, PlatformAddress, Public};
+use clap::ArgMatches;
+use primitives::remove_0x_prefix;
+use serde_json::{json, Map, Value};
+
+const DEFAULT_NAME: &str = "Private";
+const DEFAULT_ENGINE: &str = "tendermint";
+const DEFAULT_NETWORK_ID: &str = "tc";
+const DEFAULT_VALIDATOR_STAKE: u64 = 1;
+
+pub fn run_scheme_command(matches: &ArgMatches) -> Result<(), String> {
+    let subcommand = matches.subcommand.as_ref().ok_or_else(|| "scheme subcommand is required".to_string())?;
+    match subcommand.name.as_str() {
+        "new" => run_new_scheme_command(&subcommand.matches),
+        _ => Err("Invalid scheme subcommand".to_string()),
+    }
+}
+
+fn run_new_scheme_command(matches: &ArgMatches) -> Result<(), String> {
+    let output = matches.value_of("output").expect("output is required");
+    let network_id = NetworkId::from_str(matches.value_of("network-id").unwrap_or(DEFAULT_NETWORK_ID))
+        .map_err(|e| format!("Invalid network id: {}", e))?;
+    let validator_stake = match matches.value_of("validator-stake") {
+        Some(stake) => stake.parse().map_err(|_| format!("Invalid stake: {}", stake))?,
+        None => DEFAULT_VALIDATOR_STAKE,
+    };
+    let validators =
+        matches.values_of("validators").into_iter().flatten().map(parse_public).collect::<Result<_, _>>()?;
+    let accounts = matches
+        .values_of("accounts")
+        .into_iter()
+        .flatten()
+        .map(|account| parse_account(account, network_id))
+        .collect::<Result<_, _>>()?;
+    let shard_owners = matches
+        .values_of("shard-owners")
+        .into_iter()
+        .flatten()
+        .map(|owner| parse_address(owner, network_id))
+        .collect::<Result<_, _>>()?;
+    let options = NewScheme {
+        name: matches.value_of("name").unwrap_or(DEFAULT_NAME).to_string(),
+        engine: matches.value_of("engine").unwrap_or(DEFAULT_ENGINE).to_string(),
+        network_id,
+        validators,
+        validator_stake,
+        accounts,
+        shard_owners,
+    };
+
+    let (json, scheme) = new_scheme(&options)?;
+    let json = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+    fs::write(output, json + "\n").map_err(|e| format!("Cannot write the scheme to {}: {}", output, e))?;
+
+    println!("The scheme is written to {}", output);
+    println!("Genesis hash: 0x{:x}", scheme.genesis_header().hash());
+    println!("State root: 0x{:x}", scheme.state_root());
+    Ok(())
+}
+
+struct NewScheme {
+    name: String,
+    engine: String,
+    network_id: NetworkId,
+    validators: Vec<Public>,
+    validator_stake: u64,
+    accounts: Vec<(PlatformAddress, u64)>,
+    /// A shard is created for each owner, so the shard ids are the indices.
+    shard_owners: Vec<PlatformAddress>,
+}
+
+/// Makes the scheme JSON and loads it, so the written file is always valid.
+fn new_scheme(options: &NewScheme) -> Result<(Value, Scheme), String> {
+    let mut json = scheme_json(options)?;
+    let scheme = load_scheme(&json)?;
+
+    // The genesis header has the hash of the common params as its extra data.
+    let extra_data = scheme.genesis_header().extra_data().to_hex();
+    json["genesis"]["extraData"] = Value::String(format!("0x{}", extra_data));
+    let scheme = load_scheme(&json)?;
+    Ok((json, scheme))
+}
+
+fn load_scheme(json: &Value) -> Result<Scheme, String> {
+    let json = serde_json::to_vec(json).map_err(|e| e.to_string())?;
+    Scheme::load(&json[..])
+}
+
+fn scheme_json(options: &NewScheme) -> Result<Value, String> {
+    let network_id = options.network_id;
+    for (i, validator) in options.validators.iter().enumerate() {
+        if options.validators[..i].contains(validator) {
+            return Err(format!("The validator 0x{:x} is given twice", validator))
+        }
+    }
+    let mut accounts = Map::new();
+    for (address, balance) in &options.accounts {
+        let previous = accounts.insert(
+            address.to_string(),
+            json!({
+                "balance": balance.to_string(),
+                "seq": "0",
+            }),
+        );
+        if previous.is_some() {
+            return Err(format!("The account {} is given twice", address))
+        }
+    }
+    let mut shards = Map::new();
+    for (shard_id, owner) in options.shard_owners.iter().enumerate() {
+        shards.insert(
+            shard_id.to_string(),
+            json!({
+                "seq": 0,
+                "owners": [owner.to_string()],
+                "users": [],
+            }),
+        );
+    }
+
+    let (engine, author, seal) = match options.engine.as_str() {
+        "solo" => {
+            if !options.validators.is_empty() {
+                return Err("The validators are used only by tendermint".to_string())
+            }
+            let engine = json!({
+                "solo": {
+                    "params": {
+                        "hit": {}
+                    }
+                }
+            });
+            let author = PlatformAddress::new_v1(network_id, Address::default());
+            let seal = json!({
+                "generic": "0x0"
+            });
+            (engine, author, seal)
+        }
+        "tendermint" => {
+            let first_validator =
+                options.validators.first().ok_or_else(|| "tendermint needs validators".to_string())?;
+            let validator_address = |public: &Public| PlatformAddress::new_v1(network_id, public_to_address(public));
+            let genesis_stakes: Map<String, Value> = options
+                .validators
+                .iter()
+                .map(|validator| (validator_address(validator).to_string(), json!(options.validator_stake)))
+                .collect();
+            let validators: Vec<String> =
+                options.validators.iter().map(|validator| format!("0x{:x}", validator)).collect();
+            let engine = json!({
+                "tendermint": {
+                    "params": {
+                        "validators": validators,
+                        "timeoutPropose": 10000,
+                        "timeoutProposeDelta": 5000,
+                        "timeoutPrevote": 10000,
+                        "timeoutPrevoteDelta": 5000,
+                        "timeoutPrecommit": 10000,
+                        "timeoutPrecommitDelta": 5000,
+                        "timeoutCommit": 10000,
+                        "genesisStakes": genesis_stakes,
+                    }
+                }
+            });
+            let author = validator_address(first_validator);
+            let seal = json!({
+                "tendermint": {
+                    "prev_view": "0x0",
+                    "cur_view": "0x0",
+                    "precommits": [format!("0x{}", "00".repeat(65))],
+                }
+            });
+            (engine, author, seal)
+        }
+        engine => return Err(format!("Unsupported engine: {}", engine)),
+    };
+
+    Ok(json!({
+        "name": options.name,
+        "engine": engine,
+        "params": {
+            "maxExtraDataSize": "0x20",
+            "maxAssetSchemeMetadataSize": "0x0400",
+            "maxTransferMetadataSize": "0x0100",
+            "maxTextContentSize": "0x0200",
+            "networkID": network_id.to_string(),
+            "minPayCost": 10,
+            "minSetRegularKeyCost": 10,
+            "minCreateShardCost": 10,
+            "minSetShardOwnersCost": 10,
+            "minSetShardUsersCost": 10,
+            "minWrapCccCost": 10,
+            "minCustomCost": 10,
+            "minStoreCost": 10,
+            "minRemoveCost": 10,
+            "minMintAssetCost": 10,
+            "minTransferAssetCost": 10,
+            "minChangeAssetSchemeCost": 10,
+            "minIncreaseAssetSupplyCost": 10,
+            "minComposeAssetCost": 10,
+            "minDecomposeAssetCost": 10,
+            "minUnwrapCccCost": 10,
+            "maxBodySize": 4_194_304,
+            "snapshotPeriod": 16_384,
+        },
+        "genesis": {
+            "seal": seal,
+            "score": "0x20000",
+            "author": author.to_string(),
+            "timestamp": "0x00",
+            "parentHash": format!("0x{}", "00".repeat(32)),
+            "extraData": "0x",
+        },
+        "accounts": accounts,
+        "shards": shards,
+    }))
+}
+
+fn parse_public(input: &str) -> Result<Public, String> {
+    Public::from_str(remove_0x_prefix(input)).map_err(|e| format!("Error on reading public key {}: {}", input, e))
+}
+
+fn parse_address(input: &str, network_id: NetworkId) -> Result<PlatformAddress, String> {
+    let address =
+        PlatformAddress::from_str(input).map_err(|e| format!("Error on reading accountId {}: {}", input, e))?;
+    if address.network_id != network_id {
+        return Err(format!("The network id of {} is not {}", input, network_id))
+    }
+    Ok(address)
+}
+
+/// Parses `ADDRESS=BALANCE`.
+fn parse_account(input: &str, network_id: NetworkId) -> Result<(PlatformAddress, u64), String> {
+    let mut parts = input.splitn(2, '=');
+    let address = parse_address(parts.next().unwrap_or_default(), network_id)?;
+    let balance = parts.next().ok_or_else(|| format!("The balance of {} is not given", input))?;
+    let balance = balance.parse().map_err(|_| format!("Invalid balance: {}", balance))?;
+    Ok((address, balance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str = "0339c7db9ad207a418f1790cb286b17ccf7f5c5ac0d6bf00942c7d010d731c798d7d97ac3cbc1e59e0a10dcae27e0e2f182fa2371589a28c1d49e7161f9ce4bf";
+    const ACCOUNT_ID: &str = "tccq8862mk4ppd0wddyt08vd5j8f3z4j732kyhdnfj9";
+
+    fn options(engine: &str, validators: Vec<Public>) -> NewScheme {
+        let network_id = NetworkId::from("tc");
+        NewScheme {
+            name: "Test".to_string(),
+            engine: engine.to_string(),
+            network_id,
+            validators,
+            validator_stake: DEFAULT_VALIDATOR_STAKE,
+            accounts: vec![parse_account(&format!("{}=1000", ACCOUNT_ID), network_id).unwrap()],
+            shard_owners: vec![parse_address(ACCOUNT_ID, network_id).unwrap()],
+        }
+    }
+
+    #[test]
+    fn new_tendermint_scheme() {
+        let (json, scheme) = new_scheme(&options("tendermint", vec![parse_public(PUBLIC_KEY).unwrap()])).unwrap();
+        assert_eq!(json["genesis"]["author"], ACCOUNT_ID);
+        assert_eq!(json["engine"]["tendermint"]["params"]["genesisStakes"][ACCOUNT_ID], 1);
+        assert_eq!(1, scheme.genesis_accounts().len());
+        assert_eq!(&scheme.extra_data, scheme.genesis_header().extra_data());
+    }
+
+    #[test]
+    fn new_solo_scheme() {
+        let (_, scheme) = new_scheme(&options("solo", vec![])).unwrap();
+        assert_eq!(&scheme.extra_data, scheme.genesis_header().extra_data());
+    }
+
+    #[test]
+    fn tendermint_needs_validators() {
+        assert!(new_scheme(&options("tendermint", vec![])).is_err());
+        assert!(new_scheme(&options("solo", vec![parse_public(PUBLIC_KEY).unwrap()])).is_err());
+    }
+}
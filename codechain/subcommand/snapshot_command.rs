@@ -0,0 +1,109 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use ccore::NUM_COLUMNS;
+use clap::ArgMatches;
+use clogger::{self, LoggerConfig};
+use csync::{list_snapshots, ReadSnapshot, Snapshot, SnapshotManifest};
+use kvdb::KeyValueDB;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use primitives::{remove_0x_prefix, H256};
+
+use super::get_global_argument;
+use crate::constants::{DEFAULT_DB_PATH, DEFAULT_SNAPSHOT_PATH};
+
+pub fn run_snapshot_command(matches: &ArgMatches) -> Result<(), String> {
+    if matches.subcommand.is_none() {
+        println!("{}", matches.usage());
+        return Ok(())
+    }
+
+    clogger::init(&LoggerConfig::new(0)).expect("Logger must be successfully initialized");
+
+    let snapshot_path = get_global_argument(matches, "snapshot-path").unwrap_or_else(|| DEFAULT_SNAPSHOT_PATH.into());
+    match matches.subcommand() {
+        ("list", _) => list(&snapshot_path),
+        ("verify", Some(matches)) => {
+            let block_hash = matches.value_of("BLOCK_HASH").expect("BLOCK_HASH arg is required and its index is 1");
+            verify(&snapshot_path, block_hash)
+        }
+        ("export", Some(matches)) => {
+            let block_hash = matches.value_of("BLOCK_HASH").expect("BLOCK_HASH arg is required and its index is 1");
+            let db_path = matches.value_of("db-path").unwrap_or(DEFAULT_DB_PATH);
+            export(&snapshot_path, block_hash, db_path)
+        }
+        _ => Err("Invalid subcommand".to_string()),
+    }
+}
+
+fn list(snapshot_path: &str) -> Result<(), String> {
+    let manifests = list_snapshots(snapshot_path).map_err(|err| err.to_string())?;
+    if manifests.is_empty() {
+        println!("No snapshots in {}", snapshot_path);
+    }
+    for manifest in manifests {
+        let size: u64 = manifest.chunks.iter().map(|chunk| chunk.size).sum();
+        println!(
+            "#{} 0x{:x} (state root: 0x{:x}, shards: {}, chunks: {}, {} bytes)",
+            manifest.block_number,
+            manifest.block_hash,
+            manifest.state_root,
+            manifest.shard_roots.len(),
+            manifest.chunks.len(),
+            size
+        );
+    }
+    Ok(())
+}
+
+fn verify(snapshot_path: &str, block_hash: &str) -> Result<(), String> {
+    let (snapshot, manifest) = open_snapshot(snapshot_path, block_hash)?;
+    snapshot.verify(&manifest).map_err(|err| err.to_string())?;
+    println!("Snapshot of #{} 0x{:x} is valid", manifest.block_number, manifest.block_hash);
+    Ok(())
+}
+
+fn export(snapshot_path: &str, block_hash: &str, db_path: &str) -> Result<(), String> {
+    let (snapshot, manifest) = open_snapshot(snapshot_path, block_hash)?;
+    snapshot.verify(&manifest).map_err(|err| err.to_string())?;
+
+    let db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
+    let db = Database::open(&db_config, db_path)
+        .map_err(|_e| "Low level database error. Some issue with disk?".to_string())?;
+    let db: Arc<KeyValueDB> = Arc::new(db);
+    let roots = Some(&manifest.state_root).into_iter().chain(manifest.shard_roots.iter().map(|(_, root)| root));
+    for root in roots {
+        snapshot.read_snapshot(Arc::clone(&db), root).map_err(|err| err.to_string())?;
+    }
+    db.flush().map_err(|err| err.to_string())?;
+    println!("State of #{} 0x{:x} is written to {}", manifest.block_number, manifest.block_hash, db_path);
+    Ok(())
+}
+
+fn open_snapshot(snapshot_path: &str, block_hash: &str) -> Result<(Snapshot, SnapshotManifest), String> {
+    let block_hash = H256::from_str(remove_0x_prefix(block_hash)).map_err(|_| "Invalid block hash".to_string())?;
+    let path: PathBuf = [snapshot_path.to_string(), format!("{:x}", block_hash)].iter().collect();
+    let snapshot = Snapshot::open(&path).map_err(|_| format!("Snapshot of 0x{:x} is not found", block_hash))?;
+    let manifest = SnapshotManifest::load(&path).map_err(|err| err.to_string())?;
+    if manifest.block_hash != block_hash {
+        return Err(format!("Manifest is for another block 0x{:x}", manifest.block_hash))
+    }
+    Ok((snapshot, manifest))
+}
@@ -21,9 +21,13 @@ use std::ops::Deref;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use ckey::{public_to_address, Address, Error as KeyError, Generator, KeyPair, Password, Private, Public, Random};
+use ckey::{
+    public_to_address, Address, DerivationPath, Error as KeyError, Generator, KeyPair, Password, Private, Public, Random,
+};
 use ckeystore::accounts_dir::MemoryDirectory;
-use ckeystore::{DecryptedAccount, Error as KeystoreError, KeyStore, SecretStore, SimpleSecretStore};
+use ckeystore::{
+    mnemonic_to_seed, DecryptedAccount, Error as KeystoreError, KeyStore, SecretStore, SimpleSecretStore,
+};
 use parking_lot::RwLock;
 
 /// Type of unlock.
@@ -122,6 +126,22 @@ impl AccountProvider {
         Ok((address, public))
     }
 
+    /// Inserts the account derived from `mnemonic` along `path`.
+    pub fn insert_account_from_mnemonic(
+        &self,
+        mnemonic: &str,
+        path: DerivationPath,
+        password: &Password,
+    ) -> Result<Address, Error> {
+        let seed = mnemonic_to_seed(mnemonic)?;
+        Ok(self.keystore.insert_derived_account(&seed, path, password)?)
+    }
+
+    /// Inserts the `index`th child of a derived account.
+    pub fn derive_account(&self, address: &Address, password: &Password, index: u32) -> Result<Address, Error> {
+        Ok(self.keystore.derive_account(address, password, index)?)
+    }
+
     pub fn remove_account(&self, address: Address) -> Result<(), Error> {
         self.keystore.remove_account(&address)?;
         Ok(())
@@ -179,10 +179,11 @@ impl<'x> OpenBlock<'x> {
 
         let hash = tx.hash();
         let tracker = tx.tracker();
-        let error = match self.block.state.apply(
+        let error = match self.block.state.apply_with_co_signers(
             &tx,
             &hash,
             &tx.signer_public(),
+            tx.co_signer_publics(),
             client,
             parent_block_number,
             parent_block_timestamp,
@@ -195,10 +196,12 @@ impl<'x> OpenBlock<'x> {
             }
             Err(err) => Some(err),
         };
+        let events = self.block.state.take_events();
         self.block.invoices.push(Invoice {
             hash,
             tracker,
-            error: error.clone().map(|err| err.to_string()),
+            error: error.clone().map(Into::into),
+            events,
         });
 
         match error {
@@ -222,7 +225,7 @@ impl<'x> OpenBlock<'x> {
     }
 
     /// Populate self from a header.
-    fn populate_from(&mut self, header: &Header) {
+    pub fn populate_from(&mut self, header: &Header) {
         self.block.header.set_score(*header.score());
         self.block.header.set_timestamp(header.timestamp());
         self.block.header.set_author(*header.author());
@@ -286,6 +289,17 @@ impl<'x> OpenBlock<'x> {
     pub fn set_timestamp(&mut self, timestamp: u64) {
         self.block.header.set_timestamp(timestamp);
     }
+
+    /// Commit the state changed by the transactions pushed so far and return its root.
+    pub fn commit_state(&mut self) -> Result<H256, Error> {
+        Ok(self.block.state.commit()?)
+    }
+
+    /// Drop this object and return the state database, which has the committed states of this block.
+    pub fn drain(self) -> Result<StateDB, Error> {
+        let (db, _) = self.block.state.commit_and_into_db()?;
+        Ok(db)
+    }
 }
 
 /// Just like `OpenBlock`, except that we've applied `Engine::on_close_block`, finished up the non-seal header fields.
@@ -359,6 +373,12 @@ impl LockedBlock {
             }),
         }
     }
+
+    /// Drop this object and return the state database, which has the committed state of this block.
+    pub fn drain(self) -> Result<StateDB, Error> {
+        let (db, _) = self.block.state.commit_and_into_db()?;
+        Ok(db)
+    }
 }
 
 /// A block that has a valid seal.
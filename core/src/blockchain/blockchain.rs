@@ -17,6 +17,7 @@
 use std::mem;
 use std::sync::Arc;
 
+use ctypes::event::{Event, LogBloom};
 use ctypes::BlockNumber;
 use kvdb::{DBTransaction, KeyValueDB};
 use parking_lot::RwLock;
@@ -28,13 +29,13 @@ use super::body_db::{BodyDB, BodyProvider};
 use super::extras::{BlockDetails, EpochTransitions, TransactionAddress, EPOCH_KEY_PREFIX};
 use super::headerchain::{HeaderChain, HeaderProvider};
 use super::invoice_db::{InvoiceDB, InvoiceProvider};
-use super::route::{tree_route, ImportRoute};
+use super::route::{tree_route, ImportRoute, TreeRoute};
 use crate::blockchain_info::BlockChainInfo;
 use crate::consensus::epoch::{PendingTransition as PendingEpochTransition, Transition as EpochTransition};
 use crate::consensus::CodeChainEngine;
 use crate::db::{self, Readable, Writable};
 use crate::encoded;
-use crate::invoice::Invoice;
+use crate::invoice::{Invoice, TransactionFailure};
 use crate::transaction::LocalizedTransaction;
 use crate::views::{BlockView, HeaderView};
 
@@ -104,6 +105,37 @@ impl BlockChain {
         }
     }
 
+    /// Opens the chain in the database without initializing it, so that reading a broken database doesn't modify it.
+    /// Returns `None` if the best block or header pointers don't exist.
+    pub fn open(db: Arc<KeyValueDB>) -> kvdb::Result<Option<Self>> {
+        let best_block_hash = match db.get(db::COL_EXTRA, BEST_BLOCK_KEY)? {
+            Some(hash) => H256::from_slice(&hash),
+            None => return Ok(None),
+        };
+        let best_proposal_block_hash = match db.get(db::COL_EXTRA, BEST_PROPOSAL_BLOCK_KEY)? {
+            Some(hash) => H256::from_slice(&hash),
+            None => return Ok(None),
+        };
+        let headerchain = match HeaderChain::open(db.clone())? {
+            Some(headerchain) => headerchain,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            best_block_hash: RwLock::new(best_block_hash),
+            best_proposal_block_hash: RwLock::new(best_proposal_block_hash),
+
+            headerchain,
+            body_db: BodyDB::open(db.clone()),
+            invoice_db: InvoiceDB::new(db.clone()),
+
+            db,
+
+            pending_best_block_hash: RwLock::new(None),
+            pending_best_proposal_block_hash: RwLock::new(None),
+        }))
+    }
+
     pub fn insert_header(
         &self,
         batch: &mut DBTransaction,
@@ -146,9 +178,11 @@ impl BlockChain {
         self.headerchain.insert_header(batch, &new_header, engine);
         self.body_db.insert_body(batch, &new_block);
         self.body_db.update_best_block(batch, &best_block_changed);
+        let log_bloom = LogBloom::from_events(invoices.iter().flat_map(|invoice| &invoice.events));
         for invoice in invoices {
-            self.invoice_db.insert_invoice(batch, invoice.hash, invoice.tracker, invoice.error);
+            self.invoice_db.insert_invoice(batch, invoice.hash, invoice.tracker, invoice.error, invoice.events);
         }
+        self.invoice_db.insert_log_bloom(batch, new_block_hash, log_bloom);
 
         if let Some(best_block_hash) = best_block_changed.new_best_hash() {
             let mut pending_best_block_hash = self.pending_best_block_hash.write();
@@ -286,6 +320,71 @@ impl BlockChain {
         ImportRoute::new(block_hash, &best_block_changed)
     }
 
+    /// Inserts the body of a block whose state is restored from a snapshot, and makes it the best block.
+    ///
+    /// The header of the block should be already imported.
+    /// The body of its parent is not required.
+    pub fn insert_bootstrap_block(&self, batch: &mut DBTransaction, bytes: &[u8]) -> ImportRoute {
+        let new_block = BlockView::new(bytes);
+        let new_block_hash = new_block.hash();
+
+        ctrace!(
+            BLOCKCHAIN,
+            "Inserting bootstrap block #{}({}) to the blockchain.",
+            new_block.header_view().number(),
+            new_block_hash
+        );
+
+        assert!(self.pending_best_block_hash.read().is_none());
+        assert!(self.pending_best_proposal_block_hash.read().is_none());
+        assert!(self.is_known_header(&new_block_hash), "The header of the bootstrap block should be imported");
+
+        let best_block_changed = BestBlockChanged::CanonChainAppended {
+            best_block: bytes.to_vec(),
+        };
+
+        self.body_db.insert_body(batch, &new_block);
+        self.body_db.update_best_block(batch, &best_block_changed);
+
+        let mut pending_best_block_hash = self.pending_best_block_hash.write();
+        batch.put(db::COL_EXTRA, BEST_BLOCK_KEY, &new_block_hash);
+        *pending_best_block_hash = Some(new_block_hash);
+
+        let mut pending_best_proposal_block_hash = self.pending_best_proposal_block_hash.write();
+        batch.put(db::COL_EXTRA, BEST_PROPOSAL_BLOCK_KEY, &*new_block_hash);
+        *pending_best_proposal_block_hash = Some(new_block_hash);
+
+        ImportRoute::new(new_block_hash, &best_block_changed)
+    }
+
+    /// Makes the canonical block of the given number the best block,
+    /// and removes the canonical blocks above it so that they can be imported again.
+    /// The caches are not updated, so the chain must not be used after the batch is written.
+    pub fn rewind(&self, batch: &mut DBTransaction, number: BlockNumber) -> Option<H256> {
+        let block_hash = self.block_hash(number)?;
+        let best_block = self.block(&block_hash)?.into_inner();
+        // The best block may be above the best header if the database is broken.
+        let best_block_number = self.block_details(&self.best_block_hash()).map_or(0, |details| details.number);
+        let removed = self.headerchain.rewind(batch, number, best_block_number)?;
+
+        let retracted = removed.iter().filter(|hash| self.is_known_body(hash)).cloned().collect();
+        self.body_db.update_best_block(batch, &BestBlockChanged::BranchBecomingCanonChain {
+            best_block,
+            tree_route: TreeRoute {
+                ancestor: block_hash,
+                enacted: Vec::new(),
+                retracted,
+            },
+        });
+        for hash in &removed {
+            batch.delete(db::COL_BODIES, hash);
+        }
+
+        batch.put(db::COL_EXTRA, BEST_BLOCK_KEY, &block_hash);
+        batch.put(db::COL_EXTRA, BEST_PROPOSAL_BLOCK_KEY, &block_hash);
+        Some(block_hash)
+    }
+
     /// Returns general blockchain information
     pub fn chain_info(&self) -> BlockChainInfo {
         let best_block_hash = self.best_block_hash();
@@ -319,6 +418,16 @@ impl BlockChain {
         *self.best_proposal_block_hash.read()
     }
 
+    /// Get best header hash.
+    pub fn best_header_hash(&self) -> H256 {
+        self.headerchain.best_header_hash()
+    }
+
+    /// Get best_proposal header hash.
+    pub fn best_proposal_header_hash(&self) -> H256 {
+        self.headerchain.best_proposal_header_hash()
+    }
+
     /// Get best block detail
     pub fn best_block_detail(&self) -> BlockDetails {
         self.block_details(&self.best_block_hash()).expect("Best block always exists")
@@ -590,6 +699,18 @@ impl InvoiceProvider for BlockChain {
     fn error_hint(&self, hash: &H256) -> Option<String> {
         self.invoice_db.error_hint(hash)
     }
+
+    fn failure(&self, hash: &H256) -> Option<TransactionFailure> {
+        self.invoice_db.failure(hash)
+    }
+
+    fn events(&self, hash: &H256) -> Vec<Event> {
+        self.invoice_db.events(hash)
+    }
+
+    fn log_bloom(&self, block_hash: &H256) -> LogBloom {
+        self.invoice_db.log_bloom(block_hash)
+    }
 }
 
 impl BlockProvider for BlockChain {}
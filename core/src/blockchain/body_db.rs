@@ -48,16 +48,7 @@ type TransactionHashAndAddress = (H256, TransactionAddresses);
 impl BodyDB {
     /// Create new instance of blockchain from given Genesis.
     pub fn new(genesis: &BlockView, db: Arc<KeyValueDB>) -> Self {
-        let bdb = Self {
-            body_cache: RwLock::new(HashMap::new()),
-            parcel_address_cache: RwLock::new(HashMap::new()),
-            pending_parcel_addresses: RwLock::new(HashMap::new()),
-
-            transaction_address_cache: Default::default(),
-            pending_transaction_addresses: Default::default(),
-
-            db,
-        };
+        let bdb = Self::open(db);
 
         let genesis_hash = genesis.hash();
         if bdb.block_body(&genesis_hash).is_none() {
@@ -70,6 +61,20 @@ impl BodyDB {
         bdb
     }
 
+    /// Opens the bodies in the database without inserting the genesis block.
+    pub fn open(db: Arc<KeyValueDB>) -> Self {
+        Self {
+            body_cache: RwLock::new(HashMap::new()),
+            parcel_address_cache: RwLock::new(HashMap::new()),
+            pending_parcel_addresses: RwLock::new(HashMap::new()),
+
+            transaction_address_cache: Default::default(),
+            pending_transaction_addresses: Default::default(),
+
+            db,
+        }
+    }
+
     /// Inserts the block body into backing cache database.
     /// Expects the body to be valid and already verified.
     /// If the body is already known, does nothing.
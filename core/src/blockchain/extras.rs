@@ -17,6 +17,7 @@
 use std::io::Write;
 use std::ops::{self, Add, AddAssign, Deref, Sub, SubAssign};
 
+use ctypes::event::LogBloom;
 use ctypes::BlockNumber;
 use kvdb::PREFIX_LEN as DB_PREFIX_LEN;
 use primitives::{H256, H264, U256};
@@ -40,6 +41,8 @@ enum ExtrasIndex {
     EpochTransitions = 4,
     /// Pending epoch transition data index.
     PendingEpochTransition = 5,
+    /// Log bloom index
+    LogBloom = 6,
 }
 
 fn with_index(hash: &H256, i: ExtrasIndex) -> H264 {
@@ -98,6 +101,14 @@ impl Key<TransactionAddresses> for H256 {
     }
 }
 
+impl Key<LogBloom> for H256 {
+    type Target = H264;
+
+    fn key(&self) -> H264 {
+        with_index(self, ExtrasIndex::LogBloom)
+    }
+}
+
 /// length of epoch keys.
 const EPOCH_KEY_LEN: usize = DB_PREFIX_LEN + 16;
 
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::cmp;
 use std::collections::HashMap;
 use std::mem;
 use std::sync::Arc;
@@ -28,7 +29,7 @@ use super::block_info::BestHeaderChanged;
 use super::extras::BlockDetails;
 use super::route::tree_route;
 use crate::consensus::CodeChainEngine;
-use crate::db::{self, CacheUpdatePolicy, Readable, Writable};
+use crate::db::{self, CacheUpdatePolicy, Key, Readable, Writable};
 use crate::encoded;
 use crate::header::{Header, Seal};
 use crate::views::HeaderView;
@@ -95,6 +96,24 @@ impl HeaderChain {
                 .expect("best proposal header is set by best header"),
         );
 
+        Self::with_best_hashes(best_header_hash, best_proposal_header_hash, db)
+    }
+
+    /// Opens the header chain in the database without initializing it.
+    /// Returns `None` if the best header pointers don't exist.
+    pub fn open(db: Arc<KeyValueDB>) -> kvdb::Result<Option<Self>> {
+        let best_header_hash = match db.get(db::COL_EXTRA, BEST_HEADER_KEY)? {
+            Some(hash) => H256::from_slice(&hash),
+            None => return Ok(None),
+        };
+        let best_proposal_header_hash = match db.get(db::COL_EXTRA, BEST_PROPOSAL_HEADER_KEY)? {
+            Some(hash) => H256::from_slice(&hash),
+            None => return Ok(None),
+        };
+        Ok(Some(Self::with_best_hashes(best_header_hash, best_proposal_header_hash, db)))
+    }
+
+    fn with_best_hashes(best_header_hash: H256, best_proposal_header_hash: H256, db: Arc<KeyValueDB>) -> Self {
         Self {
             best_header_hash: RwLock::new(best_header_hash),
             best_proposal_header_hash: RwLock::new(best_proposal_header_hash),
@@ -303,6 +322,44 @@ impl HeaderChain {
         batch.extend_with_cache(db::COL_EXTRA, &mut *pending_hashes, new_hashes, CacheUpdatePolicy::Overwrite);
     }
 
+    /// Makes the canonical header of the given number the best header,
+    /// and removes the canonical headers above it with their details.
+    /// The canonical hashes may have gaps, so it doesn't stop at a missing hash below `best_number` or the best header.
+    /// Returns the hashes of the removed headers.
+    /// The caches are not updated, so it must not be used while the chain is running.
+    pub fn rewind(
+        &self,
+        batch: &mut DBTransaction,
+        number: BlockNumber,
+        best_number: BlockNumber,
+    ) -> Option<Vec<H256>> {
+        let block_hash = self.block_hash(number)?;
+        // The best header pointer may be the broken one.
+        let best_header_number = self.block_details(&self.best_header_hash()).map_or(0, |details| details.number);
+        let last_number = cmp::max(best_number, best_header_number);
+
+        let mut removed = Vec::new();
+        let mut removed_number = number + 1;
+        loop {
+            // Reads the database directly, because `block_hash` hides the hashes above the best header.
+            let hash: Option<H256> = self.db.read(db::COL_EXTRA, &removed_number);
+            if hash.is_none() && removed_number > last_number {
+                break
+            }
+            batch.delete(db::COL_EXTRA, &<BlockNumber as Key<H256>>::key(&removed_number));
+            if let Some(hash) = hash {
+                batch.delete(db::COL_EXTRA, &<H256 as Key<BlockDetails>>::key(&hash));
+                batch.delete(db::COL_HEADERS, &hash);
+                removed.push(hash);
+            }
+            removed_number += 1;
+        }
+
+        batch.put(db::COL_EXTRA, BEST_HEADER_KEY, &block_hash);
+        batch.put(db::COL_EXTRA, BEST_PROPOSAL_HEADER_KEY, &block_hash);
+        Some(removed)
+    }
+
     /// Get best block hash.
     pub fn best_header_hash(&self) -> H256 {
         *self.best_header_hash.read()
@@ -0,0 +1,447 @@
+// Copyright 2018-2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::Arc;
+
+use ctypes::BlockNumber;
+use journaldb::{self, JournalDB};
+use kvdb::{DBTransaction, KeyValueDB};
+use primitives::H256;
+
+use super::blockchain::BlockChain;
+use super::body_db::BodyProvider;
+use super::extras::TransactionAddress;
+use super::headerchain::HeaderProvider;
+use crate::db;
+
+/// A problem found in the chain database.
+#[derive(Debug, PartialEq)]
+pub enum IntegrityError {
+    /// The block pointed by the best block pointer is not known.
+    UnknownBestBlock {
+        name: &'static str,
+        hash: H256,
+    },
+    /// The best block is not on the canonical chain.
+    BestBlockNotCanonical {
+        number: BlockNumber,
+        hash: H256,
+    },
+    /// The best block is higher than the best header.
+    BestBlockAboveBestHeader {
+        best_block: BlockNumber,
+        best_header: BlockNumber,
+    },
+    /// There is no canonical hash for the block number.
+    MissingCanonicalHash(BlockNumber),
+    /// The details of the canonical block are missing.
+    MissingBlockDetails {
+        number: BlockNumber,
+        hash: H256,
+    },
+    /// The details of the canonical block have a wrong number.
+    WrongBlockNumber {
+        hash: H256,
+        expected: BlockNumber,
+        got: BlockNumber,
+    },
+    /// The details of the canonical block have a wrong parent.
+    WrongParent {
+        number: BlockNumber,
+        hash: H256,
+        expected: H256,
+        got: H256,
+    },
+    /// The header of the canonical block is missing.
+    MissingHeader {
+        number: BlockNumber,
+        hash: H256,
+    },
+    /// The body of the canonical block is missing.
+    MissingBody {
+        number: BlockNumber,
+        hash: H256,
+    },
+    /// The transaction index doesn't point to the canonical block that contains the transaction.
+    WrongTransactionAddress {
+        transaction: H256,
+        expected: TransactionAddress,
+        got: Option<TransactionAddress>,
+    },
+    /// The state of the block doesn't exist in the state database.
+    MissingState {
+        number: BlockNumber,
+        state_root: H256,
+    },
+    /// The chain cannot be rewound to the block.
+    CannotRewind {
+        number: BlockNumber,
+        best_block: BlockNumber,
+    },
+    /// The best block or header pointers don't exist, so the chain has never been initialized.
+    MissingPointers,
+    /// The database cannot be read or written.
+    Database(String),
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegrityError::UnknownBestBlock {
+                name,
+                hash,
+            } => write!(f, "The {} {} is unknown", name, hash),
+            IntegrityError::BestBlockNotCanonical {
+                number,
+                hash,
+            } => write!(f, "The best block #{}({}) is not canonical", number, hash),
+            IntegrityError::BestBlockAboveBestHeader {
+                best_block,
+                best_header,
+            } => write!(f, "The best block #{} is above the best header #{}", best_block, best_header),
+            IntegrityError::MissingCanonicalHash(number) => write!(f, "The canonical hash of #{} is missing", number),
+            IntegrityError::MissingBlockDetails {
+                number,
+                hash,
+            } => write!(f, "The details of #{}({}) are missing", number, hash),
+            IntegrityError::WrongBlockNumber {
+                hash,
+                expected,
+                got,
+            } => write!(f, "The details of {} have the number {}, expected {}", hash, got, expected),
+            IntegrityError::WrongParent {
+                number,
+                hash,
+                expected,
+                got,
+            } => write!(f, "The details of #{}({}) have the parent {}, expected {}", number, hash, got, expected),
+            IntegrityError::MissingHeader {
+                number,
+                hash,
+            } => write!(f, "The header of #{}({}) is missing", number, hash),
+            IntegrityError::MissingBody {
+                number,
+                hash,
+            } => write!(f, "The body of #{}({}) is missing", number, hash),
+            IntegrityError::WrongTransactionAddress {
+                transaction,
+                expected,
+                got: Some(got),
+            } => write!(
+                f,
+                "The transaction {} is indexed at {}:{}, expected {}:{}",
+                transaction, got.block_hash, got.index, expected.block_hash, expected.index
+            ),
+            IntegrityError::WrongTransactionAddress {
+                transaction,
+                expected,
+                got: None,
+            } => write!(
+                f,
+                "The transaction {} is not indexed, expected {}:{}",
+                transaction, expected.block_hash, expected.index
+            ),
+            IntegrityError::MissingState {
+                number,
+                state_root,
+            } => write!(f, "The state {} of #{} is missing", state_root, number),
+            IntegrityError::CannotRewind {
+                number,
+                best_block,
+            } => write!(f, "Cannot rewind to #{} from the best block #{}", number, best_block),
+            IntegrityError::MissingPointers => write!(f, "The best block and header pointers are missing"),
+            IntegrityError::Database(err) => write!(f, "Low level database error: {}", err),
+        }
+    }
+}
+
+/// Reads the chain database without the client to find and repair broken indices.
+pub struct DatabaseInspector {
+    chain: BlockChain,
+    journal: Box<JournalDB>,
+    db: Arc<KeyValueDB>,
+}
+
+impl DatabaseInspector {
+    /// Opens the chain without initializing it, so that a broken database is not modified until it is rewound.
+    pub fn new(db: Arc<KeyValueDB>) -> Result<Self, IntegrityError> {
+        let chain = BlockChain::open(Arc::clone(&db))
+            .map_err(|err| IntegrityError::Database(err.to_string()))?
+            .ok_or(IntegrityError::MissingPointers)?;
+        let journal = journaldb::new(Arc::clone(&db), journaldb::Algorithm::Archive, db::COL_STATE);
+        Ok(Self {
+            chain,
+            journal,
+            db,
+        })
+    }
+
+    pub fn best_block_number(&self) -> Option<BlockNumber> {
+        self.chain.block_details(&self.chain.best_block_hash()).map(|details| details.number)
+    }
+
+    /// Checks that the best block and header pointers point to the known canonical blocks.
+    pub fn check_pointers(&self) -> Vec<IntegrityError> {
+        let mut errors = Vec::new();
+        let pointers = [
+            ("best block", self.chain.best_block_hash()),
+            ("best proposal block", self.chain.best_proposal_block_hash()),
+            ("best header", self.chain.best_header_hash()),
+            ("best proposal header", self.chain.best_proposal_header_hash()),
+        ];
+        for &(name, hash) in pointers.iter() {
+            if self.chain.block_details(&hash).is_none() {
+                errors.push(IntegrityError::UnknownBestBlock {
+                    name,
+                    hash,
+                });
+            }
+        }
+        if !errors.is_empty() {
+            return errors
+        }
+
+        let best_block_hash = self.chain.best_block_hash();
+        let best_block = self.chain.block_details(&best_block_hash).expect("Checked above").number;
+        if self.chain.block_hash(best_block) != Some(best_block_hash) {
+            errors.push(IntegrityError::BestBlockNotCanonical {
+                number: best_block,
+                hash: best_block_hash,
+            });
+        }
+        let best_header = self.chain.block_details(&self.chain.best_header_hash()).expect("Checked above").number;
+        if best_block > best_header {
+            errors.push(IntegrityError::BestBlockAboveBestHeader {
+                best_block,
+                best_header,
+            });
+        }
+        errors
+    }
+
+    /// Checks the canonical blocks in the range and the indices of their transactions.
+    pub fn check_blocks(&self, from: BlockNumber, to: BlockNumber) -> Vec<IntegrityError> {
+        let mut errors = Vec::new();
+        let mut parent = None;
+        for number in from..=to {
+            let hash = match self.chain.block_hash(number) {
+                Some(hash) => hash,
+                None => {
+                    errors.push(IntegrityError::MissingCanonicalHash(number));
+                    parent = None;
+                    continue
+                }
+            };
+            self.check_block(number, hash, parent, &mut errors);
+            parent = Some(hash);
+        }
+        errors
+    }
+
+    fn check_block(&self, number: BlockNumber, hash: H256, parent: Option<H256>, errors: &mut Vec<IntegrityError>) {
+        match self.chain.block_details(&hash) {
+            Some(details) => {
+                if details.number != number {
+                    errors.push(IntegrityError::WrongBlockNumber {
+                        hash,
+                        expected: number,
+                        got: details.number,
+                    });
+                }
+                match parent {
+                    Some(parent) if parent != details.parent => errors.push(IntegrityError::WrongParent {
+                        number,
+                        hash,
+                        expected: parent,
+                        got: details.parent,
+                    }),
+                    _ => {}
+                }
+            }
+            None => errors.push(IntegrityError::MissingBlockDetails {
+                number,
+                hash,
+            }),
+        }
+
+        if self.chain.block_header_data(&hash).is_none() {
+            errors.push(IntegrityError::MissingHeader {
+                number,
+                hash,
+            });
+        }
+
+        let body = match self.chain.block_body(&hash) {
+            Some(body) => body,
+            None => {
+                errors.push(IntegrityError::MissingBody {
+                    number,
+                    hash,
+                });
+                return
+            }
+        };
+        for (index, transaction) in body.transaction_hashes().into_iter().enumerate() {
+            let expected = TransactionAddress {
+                block_hash: hash,
+                index,
+            };
+            let got = self.chain.transaction_address(&transaction);
+            if got != Some(expected) {
+                errors.push(IntegrityError::WrongTransactionAddress {
+                    transaction,
+                    expected,
+                    got,
+                });
+            }
+        }
+    }
+
+    /// Checks that the state of the canonical block exists in the state database.
+    pub fn check_state(&self, number: BlockNumber) -> Result<(), IntegrityError> {
+        let hash = self.chain.block_hash(number).ok_or(IntegrityError::MissingCanonicalHash(number))?;
+        let header = self.chain.block_header_data(&hash).ok_or(IntegrityError::MissingHeader {
+            number,
+            hash,
+        })?;
+        let state_root = header.state_root();
+        if !self.journal.contains(&state_root) {
+            return Err(IntegrityError::MissingState {
+                number,
+                state_root,
+            })
+        }
+        Ok(())
+    }
+
+    /// Makes the canonical block of the given number the best block.
+    /// The blocks above it are removed, so they will be imported again from the peers.
+    pub fn rewind(self, number: BlockNumber) -> Result<H256, IntegrityError> {
+        // The best block pointer may be the broken one, so it is checked only when it is known.
+        if let Some(best_block) = self.best_block_number() {
+            if number >= best_block {
+                return Err(IntegrityError::CannotRewind {
+                    number,
+                    best_block,
+                })
+            }
+        }
+        self.check_state(number)?;
+
+        let mut batch = DBTransaction::new();
+        let hash = self.chain.rewind(&mut batch, number).ok_or(IntegrityError::MissingCanonicalHash(number))?;
+        self.db.write(batch).map_err(|err| IntegrityError::Database(err.to_string()))?;
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cstate::StateDB;
+    use kvdb_memorydb;
+    use rlp::RlpStream;
+
+    use super::*;
+    use crate::db::{Key, Readable, NUM_COLUMNS};
+    use crate::header::Header;
+    use crate::scheme::Scheme;
+
+    /// Initializes the database with the genesis state and the empty blocks on top of the genesis block.
+    /// Returns the hashes of the canonical blocks.
+    fn new_chain(scheme: &Scheme, database: &Arc<KeyValueDB>, count: BlockNumber) -> Vec<H256> {
+        let journal = journaldb::new(Arc::clone(database), journaldb::Algorithm::Archive, db::COL_STATE);
+        let mut state_db = scheme.ensure_genesis_state(StateDB::new(journal)).unwrap();
+        let mut batch = DBTransaction::new();
+        state_db.journal_under(&mut batch, 0, scheme.genesis_header().hash()).unwrap();
+        database.write(batch).unwrap();
+
+        let chain = BlockChain::new(&scheme.genesis_block(), Arc::clone(database));
+        let genesis = scheme.genesis_header();
+        let mut hashes = vec![genesis.hash()];
+        for number in 1..=count {
+            let mut header = Header::new();
+            header.set_parent_hash(*hashes.last().unwrap());
+            header.set_number(number);
+            header.set_timestamp(genesis.timestamp() + number);
+            header.set_score(1.into());
+            header.set_state_root(*genesis.state_root());
+            header.set_transactions_root(*genesis.transactions_root());
+            let mut block = RlpStream::new_list(2);
+            block.append(&header);
+            block.begin_list(0);
+
+            let mut batch = DBTransaction::new();
+            chain.insert_block(&mut batch, &block.out(), Vec::new(), &*scheme.engine);
+            database.write(batch).unwrap();
+            chain.commit();
+            hashes.push(header.hash());
+        }
+        hashes
+    }
+
+    #[test]
+    fn inspector_does_not_initialize_an_empty_database() {
+        let database: Arc<KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap()));
+        assert_eq!(Some(IntegrityError::MissingPointers), DatabaseInspector::new(Arc::clone(&database)).err());
+        assert_eq!(None, database.get(db::COL_EXTRA, b"best-header").unwrap());
+        assert_eq!(None, database.get(db::COL_EXTRA, b"best-block").unwrap());
+    }
+
+    #[test]
+    fn inspector_opens_an_initialized_database() {
+        let scheme = Scheme::new_test();
+        let database: Arc<KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap()));
+        BlockChain::new(&scheme.genesis_block(), Arc::clone(&database));
+
+        let inspector = DatabaseInspector::new(database).unwrap();
+        assert_eq!(Some(0), inspector.best_block_number());
+        assert_eq!(Vec::<IntegrityError>::new(), inspector.check_pointers());
+        assert_eq!(Vec::<IntegrityError>::new(), inspector.check_blocks(0, 0));
+    }
+
+    #[test]
+    fn rewind_removes_the_blocks_above_a_gap() {
+        let scheme = Scheme::new_test();
+        let database: Arc<KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap()));
+        let hashes = new_chain(&scheme, &database, 4);
+        // Breaks the canonical chain in the middle.
+        let mut batch = DBTransaction::new();
+        batch.delete(db::COL_EXTRA, &<BlockNumber as Key<H256>>::key(&3));
+        database.write(batch).unwrap();
+
+        let inspector = DatabaseInspector::new(Arc::clone(&database)).unwrap();
+        assert_eq!(Some(4), inspector.best_block_number());
+        assert_eq!(vec![IntegrityError::MissingCanonicalHash(3)], inspector.check_blocks(0, 4));
+        assert_eq!(Ok(hashes[1]), inspector.rewind(1));
+
+        let inspector = DatabaseInspector::new(Arc::clone(&database)).unwrap();
+        assert_eq!(Some(1), inspector.best_block_number());
+        assert_eq!(hashes[1], inspector.chain.best_header_hash());
+        assert_eq!(hashes[1], inspector.chain.best_proposal_block_hash());
+        assert_eq!(Vec::<IntegrityError>::new(), inspector.check_pointers());
+        assert_eq!(Vec::<IntegrityError>::new(), inspector.check_blocks(0, 1));
+        for number in 2..=4 {
+            let hash: Option<H256> = database.read(db::COL_EXTRA, &number);
+            assert_eq!(None, hash, "The canonical hash of #{} must be removed", number);
+        }
+        for hash in &hashes[2..] {
+            assert_eq!(None, inspector.chain.block_details(hash));
+            assert_eq!(None, inspector.chain.block_header_data(hash));
+            assert_eq!(None, inspector.chain.block_body(hash));
+        }
+    }
+}
@@ -18,12 +18,14 @@ use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
+use ctypes::event::{Event, LogBloom};
 use kvdb::{DBTransaction, KeyValueDB};
 use parking_lot::RwLock;
 use primitives::{H256, H264};
 use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
 
 use crate::db::{self, CacheUpdatePolicy, Key, Readable, Writable};
+use crate::invoice::TransactionFailure;
 
 /// Structure providing fast access to blockchain data.
 ///
@@ -33,6 +35,10 @@ pub struct InvoiceDB {
     tracker_cache: RwLock<HashMap<H256, TrackerInvoices>>,
     // transaction hash -> error hint
     hash_cache: RwLock<HashMap<H256, Option<String>>>,
+    // transaction hash -> events
+    events_cache: RwLock<HashMap<H256, TransactionEvents>>,
+    // transaction hash -> failure
+    failure_cache: RwLock<HashMap<H256, TransactionFailure>>,
 
     db: Arc<KeyValueDB>,
 }
@@ -43,6 +49,8 @@ impl InvoiceDB {
         Self {
             tracker_cache: Default::default(),
             hash_cache: Default::default(),
+            events_cache: Default::default(),
+            failure_cache: Default::default(),
 
             db,
         }
@@ -56,7 +64,8 @@ impl InvoiceDB {
         batch: &mut DBTransaction,
         hash: H256,
         tracker: Option<H256>,
-        error_hint: Option<String>,
+        failure: Option<TransactionFailure>,
+        events: Vec<Event>,
     ) {
         if self.is_known_error_hint(&hash) {
             return
@@ -64,6 +73,7 @@ impl InvoiceDB {
 
         let mut hashes_cache = self.tracker_cache.write();
         let mut hint_cache = self.hash_cache.write();
+        let error_hint = failure.as_ref().map(ToString::to_string);
 
         if let Some(tracker) = tracker {
             let mut hashes =
@@ -73,6 +83,30 @@ impl InvoiceDB {
         }
 
         batch.write_with_cache(db::COL_ERROR_HINT, &mut *hint_cache, hash, error_hint, CacheUpdatePolicy::Remove);
+
+        if let Some(failure) = failure {
+            let mut failure_cache = self.failure_cache.write();
+            batch.write_with_cache(db::COL_ERROR_HINT, &mut *failure_cache, hash, failure, CacheUpdatePolicy::Remove);
+        }
+
+        if !events.is_empty() {
+            let mut events_cache = self.events_cache.write();
+            batch.write_with_cache(
+                db::COL_ERROR_HINT,
+                &mut *events_cache,
+                hash,
+                TransactionEvents(events),
+                CacheUpdatePolicy::Remove,
+            );
+        }
+    }
+
+    /// Stores the bloom of the events in the block.
+    /// The empty bloom is not stored.
+    pub fn insert_log_bloom(&self, batch: &mut DBTransaction, block_hash: H256, bloom: LogBloom) {
+        if !bloom.is_empty() {
+            batch.write(db::COL_EXTRA, &block_hash, &bloom);
+        }
     }
 }
 
@@ -86,6 +120,15 @@ pub trait InvoiceProvider {
 
     /// Get error hint
     fn error_hint(&self, hash: &H256) -> Option<String>;
+
+    /// Get the reason why the transaction failed.
+    fn failure(&self, hash: &H256) -> Option<TransactionFailure>;
+
+    /// Get the events emitted by the transaction.
+    fn events(&self, hash: &H256) -> Vec<Event>;
+
+    /// Get the bloom of the events in the block.
+    fn log_bloom(&self, block_hash: &H256) -> LogBloom;
 }
 
 impl InvoiceProvider for InvoiceDB {
@@ -103,8 +146,26 @@ impl InvoiceProvider for InvoiceDB {
     fn error_hint(&self, hash: &H256) -> Option<String> {
         self.db.read_with_cache(db::COL_ERROR_HINT, &mut *self.hash_cache.write(), hash)?
     }
+
+    fn failure(&self, hash: &H256) -> Option<TransactionFailure> {
+        self.db.read_with_cache(db::COL_ERROR_HINT, &mut *self.failure_cache.write(), hash)
+    }
+
+    fn events(&self, hash: &H256) -> Vec<Event> {
+        self.db
+            .read_with_cache(db::COL_ERROR_HINT, &mut *self.events_cache.write(), hash)
+            .map(|events: TransactionEvents| events.0)
+            .unwrap_or_default()
+    }
+
+    fn log_bloom(&self, block_hash: &H256) -> LogBloom {
+        self.db.read(db::COL_EXTRA, block_hash).unwrap_or_default()
+    }
 }
 
+#[derive(Clone, Default, RlpEncodableWrapper, RlpDecodableWrapper)]
+pub struct TransactionEvents(Vec<Event>);
+
 #[derive(Clone, Default)]
 pub struct TrackerInvoices(Vec<(H256, Option<String>)>);
 
@@ -159,6 +220,8 @@ impl DerefMut for TrackerInvoices {
 enum ErrorHintIndex {
     TrackerToHashes = 0,
     HashToHint = 1,
+    HashToEvents = 2,
+    HashToFailure = 3,
 }
 
 impl From<ErrorHintIndex> for u8 {
@@ -175,6 +238,22 @@ impl Key<Option<String>> for H256 {
     }
 }
 
+impl Key<TransactionEvents> for H256 {
+    type Target = H264;
+
+    fn key(&self) -> H264 {
+        with_index(self, ErrorHintIndex::HashToEvents)
+    }
+}
+
+impl Key<TransactionFailure> for H256 {
+    type Target = H264;
+
+    fn key(&self) -> H264 {
+        with_index(self, ErrorHintIndex::HashToFailure)
+    }
+}
+
 impl Key<TrackerInvoices> for H256 {
     type Target = H264;
 
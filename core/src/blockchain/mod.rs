@@ -20,6 +20,7 @@ mod blockchain;
 mod body_db;
 mod extras;
 mod headerchain;
+mod integrity;
 mod invoice_db;
 mod route;
 
@@ -27,5 +28,6 @@ pub use self::blockchain::{BlockChain, BlockProvider};
 pub use self::body_db::BodyProvider;
 pub use self::extras::{BlockDetails, TransactionAddress, TransactionAddresses};
 pub use self::headerchain::HeaderProvider;
+pub use self::integrity::{DatabaseInspector, IntegrityError};
 pub use self::invoice_db::InvoiceProvider;
 pub use self::route::ImportRoute;
@@ -24,7 +24,8 @@ use ckey::{Address, PlatformAddress, Public};
 use cmerkle::Result as TrieResult;
 use cnetwork::NodeId;
 use cstate::{
-    ActionHandler, AssetScheme, FindActionHandler, OwnedAsset, StateDB, StateResult, Text, TopLevelState, TopStateView,
+    prove, state_diff, ActionHandler, AssetScheme, FindActionHandler, OwnedAsset, StateChange, StateDB, StateKey,
+    StateProof, StateResult, Text, TopLevelState, TopStateView,
 };
 use ctimer::{TimeoutHandler, TimerApi, TimerScheduleError, TimerToken};
 use ctypes::transaction::{AssetTransferInput, PartialHashing, ShardTransaction};
@@ -42,19 +43,23 @@ use super::{
     AccountData, AssetClient, Balance, BlockChain as BlockChainTrait, BlockChainClient, BlockChainInfo, BlockInfo,
     BlockProducer, ChainInfo, ChainNotify, ClientConfig, DatabaseClient, EngineClient, EngineInfo,
     Error as ClientError, ExecuteClient, ImportBlock, ImportResult, ImportSealedBlock, MiningBlockChainClient,
-    PrepareOpenBlock, RegularKey, RegularKeyOwner, ReopenBlock, ResealTimer, Seq, Shard, StateInfo, StateOrBlock,
-    TextClient, TransactionInfo,
+    PrepareOpenBlock, RegularKey, RegularKeyOwner, ReopenBlock, ResealTimer, Seq, Shard, StateDiffError, StateInfo,
+    StateOrBlock, TextClient, TransactionInfo, MAX_STATE_DIFF_CHANGES,
 };
 use crate::block::{ClosedBlock, IsBlock, OpenBlock, SealedBlock};
 use crate::blockchain::{BlockChain, BlockProvider, BodyProvider, HeaderProvider, InvoiceProvider, TransactionAddress};
 use crate::consensus::CodeChainEngine;
 use crate::encoded;
 use crate::error::{BlockImportError, Error, ImportError, SchemeError};
+use crate::invoice::{
+    LocalizedEvent, LocalizedReceipt, LogFilter, LogFilterError, TransactionFailure, MAX_LOG_FILTER_RANGE,
+};
 use crate::miner::{Miner, MinerService};
 use crate::scheme::{CommonParams, Scheme};
 use crate::service::ClientIoMessage;
-use crate::transaction::{LocalizedTransaction, PendingSignedTransactions, UnverifiedTransaction};
+use crate::transaction::{LocalizedTransaction, PendingSignedTransactions, SignedTransaction, UnverifiedTransaction};
 use crate::types::{BlockId, BlockStatus, TransactionId, VerificationQueueInfo as BlockQueueInfo};
+use crate::views::BlockView;
 
 const MAX_MEM_POOL_SIZE: usize = 4096;
 
@@ -467,6 +472,31 @@ impl StateInfo for Client {
             TopLevelState::from_existing(self.state_db.read().clone(&root), root).ok()
         })
     }
+
+    fn state_diff(&self, from: BlockId, to: BlockId) -> Result<Option<Vec<StateChange>>, StateDiffError> {
+        let (from, to) = match (self.block_header(&from), self.block_header(&to)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Ok(None),
+        };
+        let state_db = self.state_db.read();
+        for header in &[&from, &to] {
+            if !state_db.as_hashdb().contains(&header.state_root()) {
+                return Err(StateDiffError::PrunedState(header.number()))
+            }
+        }
+        match state_diff(state_db.as_hashdb(), &from.state_root(), &to.state_root(), MAX_STATE_DIFF_CHANGES)? {
+            Some(changes) => Ok(Some(changes)),
+            None => Err(StateDiffError::TooManyChanges),
+        }
+    }
+
+    fn state_proof(&self, key: &StateKey, id: BlockId) -> TrieResult<Option<(H256, StateProof)>> {
+        let root = match self.block_header(&id) {
+            Some(header) => header.state_root(),
+            None => return Ok(None),
+        };
+        Ok(Some((root, prove(self.state_db.read().as_hashdb(), &root, key)?)))
+    }
 }
 
 impl ChainInfo for Client {
@@ -541,6 +571,10 @@ impl EngineClient for Client {
         }
     }
 
+    fn find_mem_pool_transactions(&self, predicate: &Fn(&H256) -> bool) -> Vec<SignedTransaction> {
+        self.importer.miner.find_mem_pool_transactions(predicate)
+    }
+
     fn get_kvdb(&self) -> Arc<KeyValueDB> {
         self.db.clone()
     }
@@ -605,6 +639,28 @@ impl ImportBlock for Client {
         }
         Ok(self.importer.header_queue.import(unverified)?)
     }
+
+    fn import_bootstrap_block(&self, bytes: Bytes) -> Result<H256, BlockImportError> {
+        let header = BlockView::new(&bytes).header();
+        let hash = header.hash();
+        {
+            let chain = self.block_chain();
+            if chain.is_known(&hash) {
+                return Err(BlockImportError::Import(ImportError::AlreadyInChain))
+            }
+            if !chain.is_known_header(&hash) {
+                return Err(BlockImportError::Other(format!(
+                    "The header of the bootstrap block {} is not imported",
+                    hash
+                )))
+            }
+        }
+        let state_root = *header.state_root();
+        let state = TopLevelState::from_existing(self.state_db.read().clone(&state_root), state_root).map_err(|_| {
+            BlockImportError::Other(format!("The state {} of the bootstrap block is not restored", state_root))
+        })?;
+        Ok(self.importer.import_bootstrap_block(&bytes, &state, self)?)
+    }
 }
 
 impl BlockChainTrait for Client {}
@@ -684,6 +740,24 @@ impl BlockChainClient for Client {
         chain.error_hint(hash)
     }
 
+    fn transaction_receipt(&self, hash: &H256) -> Option<LocalizedReceipt> {
+        let chain = self.block_chain();
+        if !chain.is_known_error_hint(hash) {
+            return None
+        }
+        // The invoices written before the failures were stored only have the hint.
+        let error = chain.error_hint(hash).map(|hint| chain.failure(hash).unwrap_or(TransactionFailure::Other(hint)));
+        let transaction = chain.transaction_address(hash).and_then(|address| chain.transaction(&address));
+        Some(LocalizedReceipt {
+            transaction_hash: *hash,
+            block_number: transaction.as_ref().map(|transaction| transaction.block_number),
+            block_hash: transaction.as_ref().map(|transaction| transaction.block_hash),
+            transaction_index: transaction.as_ref().map(|transaction| transaction.transaction_index),
+            fee: transaction.as_ref().map_or(0, |transaction| transaction.fee),
+            error,
+        })
+    }
+
     fn transaction_by_tracker(&self, tracker: &H256) -> Option<LocalizedTransaction> {
         let chain = self.block_chain();
         let address = self.transaction_addresses(tracker);
@@ -694,6 +768,52 @@ impl BlockChainClient for Client {
         let chain = self.block_chain();
         chain.error_hints_by_tracker(tracker)
     }
+
+    fn logs(&self, filter: &LogFilter) -> Result<Vec<LocalizedEvent>, LogFilterError> {
+        let (from, to) = match (self.block_number_ref(&filter.from_block), self.block_number_ref(&filter.to_block)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Ok(Vec::new()),
+        };
+        if to >= from && to - from >= MAX_LOG_FILTER_RANGE {
+            return Err(LogFilterError::TooLargeRange {
+                from,
+                to,
+            })
+        }
+        let topics = filter.bloom_topics();
+        let chain = self.block_chain();
+
+        let mut logs = Vec::new();
+        for block_number in from..=to {
+            let block_hash = match chain.block_hash(block_number) {
+                Some(block_hash) => block_hash,
+                None => break,
+            };
+            // The body and the events are loaded only for the blocks whose bloom may have the topics.
+            let bloom = chain.log_bloom(&block_hash);
+            if bloom.is_empty() || !topics.iter().all(|topic| bloom.contains(topic)) {
+                continue
+            }
+            let transactions = chain.transactions(&block_hash).expect("The body of a canonical block must exist");
+            for transaction in transactions {
+                let transaction_hash = transaction.hash();
+                for (event_index, event) in chain.events(&transaction_hash).into_iter().enumerate() {
+                    if !filter.matches(&event) {
+                        continue
+                    }
+                    logs.push(LocalizedEvent {
+                        event,
+                        block_number,
+                        block_hash,
+                        transaction_index: transaction.transaction_index,
+                        transaction_hash,
+                        event_index,
+                    });
+                }
+            }
+        }
+        Ok(logs)
+    }
 }
 
 impl AccountData for Client {}
@@ -16,6 +16,8 @@
 
 use std::fmt::{Display, Error as FmtError, Formatter};
 
+use cmerkle::TrieError;
+use ctypes::BlockNumber;
 use kvdb;
 use util_error::UtilError;
 
@@ -42,3 +44,31 @@ impl Display for Error {
         }
     }
 }
+
+/// The maximum number of entries that a state diff can have.
+pub const MAX_STATE_DIFF_CHANGES: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StateDiffError {
+    /// The state of the block has been pruned from the state database.
+    PrunedState(BlockNumber),
+    /// More than `MAX_STATE_DIFF_CHANGES` entries differ.
+    TooManyChanges,
+    Trie(TrieError),
+}
+
+impl From<TrieError> for StateDiffError {
+    fn from(err: TrieError) -> Self {
+        StateDiffError::Trie(err)
+    }
+}
+
+impl Display for StateDiffError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            StateDiffError::PrunedState(number) => write!(f, "The state of block {} has been pruned", number),
+            StateDiffError::TooManyChanges => write!(f, "More than {} state entries differ", MAX_STATE_DIFF_CHANGES),
+            StateDiffError::Trie(err) => write!(f, "{}", err),
+        }
+    }
+}
@@ -20,6 +20,7 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use cio::IoChannel;
+use cstate::TopLevelState;
 use kvdb::DBTransaction;
 use parking_lot::{Mutex, MutexGuard};
 use primitives::H256;
@@ -368,6 +369,34 @@ impl Importer {
         Ok(locked_block)
     }
 
+    /// Imports a block whose state is restored from a snapshot, and makes it the best block.
+    /// The body and the state of its parent are not required.
+    pub fn import_bootstrap_block(&self, bytes: &[u8], state: &TopLevelState, client: &Client) -> Result<H256, Error> {
+        let _import_lock = self.import_lock.lock();
+
+        let header = BlockView::new(bytes).header();
+        let hash = header.hash();
+        let chain = client.block_chain();
+        let parent = chain.block_header(header.parent_hash()).expect("The header of the bootstrap block is imported");
+        verification::verify_block_basic(&header, bytes, &*self.engine)?;
+        verification::verify_block_family::<Client>(bytes, &header, &parent, &*self.engine, None)?;
+
+        let mut batch = DBTransaction::new();
+        let route = chain.insert_bootstrap_block(&mut batch, bytes);
+        client.db().write_buffered(batch);
+        chain.commit();
+        client.state_db().write().override_state(state);
+
+        let (enacted, retracted) = self.calculate_enacted_retracted(&[route]);
+        self.miner.chain_new_blocks(client, &[hash], &[], &enacted, &retracted);
+        client.notify(|notify| {
+            notify.new_blocks(vec![hash], Vec::new(), enacted.clone(), retracted.clone(), Vec::new(), 0);
+        });
+
+        client.db().flush().expect("DB flush failed.");
+        Ok(hash)
+    }
+
     /// This is triggered by a message coming from a header queue when the header is ready for insertion
     pub fn import_verified_headers(&self, client: &Client) -> usize {
         let max_headers_to_import = 256;
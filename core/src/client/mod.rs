@@ -20,13 +20,15 @@ mod client;
 mod config;
 mod error;
 mod importer;
+mod replay;
 mod test_client;
 
 pub use self::chain_notify::ChainNotify;
 
 pub use self::client::Client;
 pub use self::config::ClientConfig;
-pub use self::error::Error;
+pub use self::error::{Error, StateDiffError, MAX_STATE_DIFF_CHANGES};
+pub use self::replay::{Divergence, DivergenceCause};
 pub use self::test_client::TestBlockChainClient;
 
 use std::ops::Range;
@@ -35,7 +37,10 @@ use std::sync::Arc;
 use ckey::{Address, PlatformAddress, Public};
 use cmerkle::Result as TrieResult;
 use cnetwork::NodeId;
-use cstate::{AssetScheme, FindActionHandler, OwnedAsset, StateResult, Text, TopLevelState, TopStateView};
+use cstate::{
+    AssetScheme, FindActionHandler, OwnedAsset, StateChange, StateKey, StateProof, StateResult, Text, TopLevelState,
+    TopStateView,
+};
 use ctypes::transaction::{AssetTransferInput, PartialHashing, ShardTransaction};
 use ctypes::{BlockNumber, ShardId};
 use cvm::ChainTimeInfo;
@@ -46,8 +51,9 @@ use crate::block::{ClosedBlock, OpenBlock, SealedBlock};
 use crate::blockchain_info::BlockChainInfo;
 use crate::encoded;
 use crate::error::BlockImportError;
+use crate::invoice::{LocalizedEvent, LocalizedReceipt, LogFilter, LogFilterError};
 use crate::scheme::CommonParams;
-use crate::transaction::{LocalizedTransaction, PendingSignedTransactions};
+use crate::transaction::{LocalizedTransaction, PendingSignedTransactions, SignedTransaction};
 use crate::types::{BlockId, BlockStatus, TransactionId, VerificationQueueInfo as BlockQueueInfo};
 
 /// Provides `chain_info` method
@@ -116,6 +122,11 @@ pub trait EngineClient: Sync + Send + ChainInfo + ImportBlock + BlockInfo {
     fn update_best_as_committed(&self, block_hash: H256);
 
     fn get_kvdb(&self) -> Arc<KeyValueDB>;
+
+    /// Get the transactions in the mem pool whose hashes satisfy the predicate.
+    ///
+    /// Used in Tendermint, to reconstruct a compact proposal block.
+    fn find_mem_pool_transactions(&self, predicate: &Fn(&H256) -> bool) -> Vec<SignedTransaction>;
 }
 
 /// Provides `seq` and `latest_seq` methods
@@ -213,6 +224,9 @@ pub trait ImportBlock {
 
     /// Import a header into the blockchain
     fn import_header(&self, bytes: Bytes) -> Result<H256, BlockImportError>;
+
+    /// Import a block whose state is restored from a snapshot as the best block.
+    fn import_bootstrap_block(&self, bytes: Bytes) -> Result<H256, BlockImportError>;
 }
 
 /// Provides various blockchain information, like block header, chain state etc.
@@ -259,10 +273,17 @@ pub trait BlockChainClient:
     /// Get invoice with given hash.
     fn error_hint(&self, hash: &H256) -> Option<String>;
 
+    /// Get the receipt of the transaction with given hash.
+    fn transaction_receipt(&self, hash: &H256) -> Option<LocalizedReceipt>;
+
     /// Get the transaction with given tracker.
     fn transaction_by_tracker(&self, tracker: &H256) -> Option<LocalizedTransaction>;
 
     fn error_hints_by_tracker(&self, tracker: &H256) -> Vec<(H256, Option<String>)>;
+
+    /// Get the events of the canonical chain that match the filter.
+    /// Returns an error if the range of the filter is larger than `MAX_LOG_FILTER_RANGE`.
+    fn logs(&self, filter: &LogFilter) -> Result<Vec<LocalizedEvent>, LogFilterError>;
 }
 
 /// Result of import block operation.
@@ -336,4 +357,15 @@ pub trait StateInfo {
     /// Otherwise, this can fail (but may not) if the DB prunes state or the block
     /// is unknown.
     fn state_at(&self, id: BlockId) -> Option<TopLevelState>;
+
+    /// Get the entries that differ between the states of two blocks.
+    ///
+    /// Returns None if one of the blocks is unknown.
+    /// Fails if the state of a block has been pruned or if more than `MAX_STATE_DIFF_CHANGES` entries differ.
+    fn state_diff(&self, from: BlockId, to: BlockId) -> Result<Option<Vec<StateChange>>, StateDiffError>;
+
+    /// Get the state root of the block and the proof of the entry in its state.
+    ///
+    /// Returns None if the block is unknown.
+    fn state_proof(&self, key: &StateKey, id: BlockId) -> TrieResult<Option<(H256, StateProof)>>;
 }
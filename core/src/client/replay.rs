@@ -0,0 +1,363 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use cmerkle::{TrieChange, TrieDiff};
+use cstate::StateDB;
+use ctypes::event::Event;
+use ctypes::BlockNumber;
+use hashdb::AsHashDB;
+use primitives::H256;
+
+use super::Client;
+use crate::block::{IsBlock, OpenBlock};
+use crate::blockchain::{HeaderProvider, InvoiceProvider};
+use crate::encoded;
+use crate::error::{BlockError, Error};
+use crate::header::Header;
+use crate::invoice::{Invoice, TransactionFailure};
+use crate::transaction::SignedTransaction;
+
+/// The first difference between the stored result of a block and the result of executing it again.
+#[derive(Debug)]
+pub struct Divergence {
+    pub number: BlockNumber,
+    pub hash: H256,
+    pub cause: DivergenceCause,
+    /// The changes made by the divergent transaction.
+    /// If only the state root is different, the changes from the stored state of the block to the executed one.
+    pub state_changes: Vec<TrieChange>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DivergenceCause {
+    /// The transaction succeeded or failed differently from the stored result.
+    Failure {
+        index: usize,
+        transaction: H256,
+        /// `None` means the transaction succeeded.
+        expected: Option<TransactionFailure>,
+        got: Option<TransactionFailure>,
+    },
+    /// The transaction emitted different events.
+    Events {
+        index: usize,
+        transaction: H256,
+        expected: Vec<Event>,
+        got: Vec<Event>,
+    },
+    /// Every transaction has the same result, but the state root is different.
+    StateRoot {
+        expected: H256,
+        got: H256,
+    },
+}
+
+impl fmt::Display for DivergenceCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DivergenceCause::Failure {
+                index,
+                transaction,
+                expected,
+                got,
+            } => write!(
+                f,
+                "The transaction #{}({}) {}, expected it {}",
+                index,
+                transaction,
+                describe_result(got),
+                describe_result(expected)
+            ),
+            DivergenceCause::Events {
+                index,
+                transaction,
+                expected,
+                got,
+            } => write!(f, "The transaction #{}({}) emitted {:?}, expected {:?}", index, transaction, got, expected),
+            DivergenceCause::StateRoot {
+                expected,
+                got,
+            } => write!(f, "The state root is {}, expected {}", got, expected),
+        }
+    }
+}
+
+fn describe_result(result: &Option<TransactionFailure>) -> String {
+    match result {
+        Some(failure) => format!("failed: {}", failure),
+        None => "succeeded".to_string(),
+    }
+}
+
+impl Client {
+    /// Executes the block again on the state of its parent,
+    /// and compares the results of the transactions and the state root with the stored ones.
+    /// The execution stops at the first transaction whose result is different.
+    /// The database is not changed.
+    pub fn replay_block(&self, block: &encoded::Block) -> Result<Option<Divergence>, Error> {
+        let header = block.decode_header();
+        let (parent, is_epoch_begin) = {
+            let chain = self.block_chain();
+            let parent = chain
+                .block_header(header.parent_hash())
+                .ok_or_else(|| BlockError::UnknownParent(*header.parent_hash()))?;
+            let is_epoch_begin = chain.epoch_transition(parent.number(), *header.parent_hash()).is_some();
+            (parent, is_epoch_begin)
+        };
+        let transactions =
+            block.transactions().into_iter().map(SignedTransaction::try_new).collect::<Result<Vec<_>, _>>()?;
+        let stored_results: Vec<_> = {
+            let chain = self.block_chain();
+            transactions
+                .iter()
+                .map(|transaction| {
+                    let hash = transaction.hash();
+                    StoredResult {
+                        failure: chain.failure(&hash),
+                        hint: chain.error_hint(&hash),
+                        events: chain.events(&hash),
+                    }
+                })
+                .collect()
+        };
+
+        let db = self.state_db().read().clone(parent.state_root());
+        let mut open_block = OpenBlock::try_new(
+            self.engine(),
+            db,
+            &parent,
+            *header.author(),
+            header.extra_data().clone(),
+            is_epoch_begin,
+        )?;
+        open_block.populate_from(&header);
+        let mut state_root = open_block.commit_state()?;
+        for (index, (transaction, stored)) in transactions.into_iter().zip(stored_results).enumerate() {
+            let result = open_block.push_transaction(transaction, None, self, parent.number(), parent.timestamp());
+            // The failure of an executed transaction is recorded in its invoice.
+            let invoice = match open_block.invoices().get(index) {
+                Some(invoice) => invoice.clone(),
+                None => return Err(result.expect_err("An executed transaction must have an invoice")),
+            };
+            let state_root_before = state_root;
+            state_root = open_block.commit_state()?;
+            if let Some(cause) = stored.compare(index, &invoice) {
+                let db = open_block.drain()?;
+                return Ok(Some(Divergence::new(&header, cause, &db, &state_root_before, &state_root)?))
+            }
+        }
+
+        // The failed transactions are not in the executed block, so its transactions root is not checked.
+        let locked_block = open_block.close(*parent.transactions_root())?.lock();
+        let expected_root = *header.state_root();
+        let state_root = *locked_block.header().state_root();
+        if state_root == expected_root {
+            return Ok(None)
+        }
+        let cause = DivergenceCause::StateRoot {
+            expected: expected_root,
+            got: state_root,
+        };
+        let db = locked_block.drain()?;
+        Ok(Some(Divergence::new(&header, cause, &db, &expected_root, &state_root)?))
+    }
+}
+
+impl Divergence {
+    fn new(header: &Header, cause: DivergenceCause, db: &StateDB, from: &H256, to: &H256) -> Result<Self, Error> {
+        let state_changes = TrieDiff::try_new(db.as_hashdb(), from, to)?.collect::<Result<_, _>>()?;
+        Ok(Divergence {
+            number: header.number(),
+            hash: header.hash(),
+            cause,
+            state_changes,
+        })
+    }
+}
+
+/// The result of a transaction that is stored in the chain.
+struct StoredResult {
+    failure: Option<TransactionFailure>,
+    hint: Option<String>,
+    events: Vec<Event>,
+}
+
+impl StoredResult {
+    fn compare(self, index: usize, invoice: &Invoice) -> Option<DivergenceCause> {
+        // The invoices written before the failures were stored only have the hint,
+        // so only whether the transaction failed is compared for them.
+        let is_same_result = match (&self.failure, &invoice.error) {
+            (Some(expected), Some(got)) => expected == got,
+            (Some(_), None) => false,
+            (None, got) => self.hint.is_some() == got.is_some(),
+        };
+        if !is_same_result {
+            let hint = self.hint;
+            return Some(DivergenceCause::Failure {
+                index,
+                transaction: invoice.hash,
+                expected: self.failure.or_else(|| hint.map(TransactionFailure::Other)),
+                got: invoice.error.clone(),
+            })
+        }
+        if self.events != invoice.events {
+            return Some(DivergenceCause::Events {
+                index,
+                transaction: invoice.hash,
+                expected: self.events,
+                got: invoice.events.clone(),
+            })
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cio::IoService;
+    use ckey::{Address, Private};
+    use ctimer::TimerLoop;
+    use ctypes::errors::RuntimeError;
+    use ctypes::transaction::{Action, Transaction};
+    use kvdb::{DBTransaction, KeyValueDB};
+    use rlp::RlpStream;
+
+    use super::*;
+    use crate::client::ClientConfig;
+    use crate::db::NUM_COLUMNS;
+    use crate::miner::Miner;
+    use crate::scheme::Scheme;
+    use crate::service::ClientIoMessage;
+
+    fn new_client(scheme: &Scheme) -> Arc<Client> {
+        let db: Arc<KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap()));
+        let miner = Arc::new(Miner::with_scheme(scheme, Arc::clone(&db)));
+        let timer_loop = TimerLoop::new(2);
+        let reseal_timer = timer_loop.new_timer_with_name("Client reseal timer");
+        let io_service = IoService::<ClientIoMessage>::start("Client").unwrap();
+        Client::try_new(&ClientConfig::default(), scheme, db, miner, io_service.channel(), reseal_timer).unwrap()
+    }
+
+    fn pay(seq: u64, quantity: u64) -> SignedTransaction {
+        let private: Private = "ede1d4ccb4ec9a8bbbae9a13db3f4a7b56ea04189be86ac3a6a439d9a0a1addd".into();
+        SignedTransaction::new_with_sign(
+            Transaction {
+                seq,
+                fee: 10,
+                network_id: "tc".into(),
+                action: Action::Pay {
+                    receiver: Address::random(),
+                    quantity,
+                },
+            },
+            &private,
+        )
+    }
+
+    /// Executes the transactions on the genesis block, and returns the block and its invoices.
+    fn new_block(
+        client: &Client,
+        scheme: &Scheme,
+        transactions: Vec<SignedTransaction>,
+    ) -> (encoded::Block, Vec<Invoice>) {
+        let genesis = scheme.genesis_header();
+        let db = client.state_db().read().clone(genesis.state_root());
+        let mut open_block =
+            OpenBlock::try_new(client.engine(), db, &genesis, Address::default(), vec![], false).unwrap();
+        for transaction in transactions {
+            open_block.push_transaction(transaction, None, client, genesis.number(), genesis.timestamp()).unwrap();
+        }
+        let sealed_block =
+            open_block.close_and_lock(*genesis.transactions_root()).unwrap().seal(client.engine(), vec![]).unwrap();
+        (encoded::Block::new(sealed_block.rlp_bytes()), sealed_block.invoices().to_vec())
+    }
+
+    fn insert_block(client: &Client, block: &encoded::Block, invoices: Vec<Invoice>) {
+        let chain = client.block_chain();
+        let mut batch = DBTransaction::new();
+        chain.insert_block(&mut batch, block.rlp().as_raw(), invoices, client.engine());
+        client.db().write(batch).unwrap();
+        chain.commit();
+    }
+
+    #[test]
+    fn replaying_a_stored_block() {
+        let scheme = Scheme::new_test();
+        let client = new_client(&scheme);
+        let (block, invoices) = new_block(&client, &scheme, vec![pay(0, 100), pay(1, 200)]);
+        insert_block(&client, &block, invoices);
+
+        assert_eq!(None, client.replay_block(&block).unwrap().map(|divergence| divergence.cause));
+    }
+
+    #[test]
+    fn replaying_a_transaction_whose_result_is_different() {
+        let scheme = Scheme::new_test();
+        let client = new_client(&scheme);
+        let second = pay(1, 200);
+        let (block, mut invoices) = new_block(&client, &scheme, vec![pay(0, 100), second.clone(), pay(2, 300)]);
+        let failure = TransactionFailure::Other("Not enough balance".to_string());
+        invoices[1].error = Some(failure.clone());
+        insert_block(&client, &block, invoices);
+
+        let divergence = client.replay_block(&block).unwrap().unwrap();
+        assert_eq!(1, divergence.number);
+        assert_eq!(block.hash(), divergence.hash);
+        assert_eq!(
+            DivergenceCause::Failure {
+                index: 1,
+                transaction: second.hash(),
+                expected: Some(failure),
+                got: None,
+            },
+            divergence.cause
+        );
+        // Only the changes made by the second transaction are reported:
+        // the fee payer and the receiver that is created by it.
+        assert_eq!(2, divergence.state_changes.len());
+        assert_eq!(1, divergence.state_changes.iter().filter(|change| change.before.is_none()).count());
+    }
+
+    #[test]
+    fn replaying_a_transaction_that_fails() {
+        let scheme = Scheme::new_test();
+        let client = new_client(&scheme);
+        let (block, _) = new_block(&client, &scheme, vec![pay(0, 100)]);
+        // The stored header expects the transaction to succeed, but its seq is wrong.
+        let transaction = pay(5, 100);
+        let mut rlp = RlpStream::new_list(2);
+        rlp.append(&block.decode_header());
+        rlp.append_list(&[transaction.clone()]);
+        let block = encoded::Block::new(rlp.out());
+
+        let divergence = client.replay_block(&block).unwrap().unwrap();
+        match divergence.cause {
+            DivergenceCause::Failure {
+                index: 0,
+                transaction: hash,
+                expected: None,
+                got: Some(TransactionFailure::Runtime(RuntimeError::InvalidSeq(_))),
+            } => assert_eq!(transaction.hash(), hash),
+            cause => panic!("Unexpected cause {:?}", cause),
+        }
+        // The failed transaction is reverted.
+        assert_eq!(Vec::<TrieChange>::new(), divergence.state_changes);
+    }
+}
@@ -63,6 +63,7 @@ use crate::db::{COL_STATE, NUM_COLUMNS};
 use crate::encoded;
 use crate::error::BlockImportError;
 use crate::header::Header as BlockHeader;
+use crate::invoice::{LocalizedEvent, LocalizedReceipt, LogFilter, LogFilterError};
 use crate::miner::{Miner, MinerService, TransactionImportResult};
 use crate::scheme::Scheme;
 use crate::transaction::{LocalizedTransaction, PendingSignedTransactions, SignedTransaction};
@@ -470,6 +471,10 @@ impl ImportBlock for TestBlockChainClient {
     fn import_header(&self, _bytes: Bytes) -> Result<H256, BlockImportError> {
         unimplemented!()
     }
+
+    fn import_bootstrap_block(&self, _bytes: Bytes) -> Result<H256, BlockImportError> {
+        unimplemented!()
+    }
 }
 
 impl BlockChainClient for TestBlockChainClient {
@@ -543,6 +548,10 @@ impl BlockChainClient for TestBlockChainClient {
         unimplemented!();
     }
 
+    fn transaction_receipt(&self, _hash: &H256) -> Option<LocalizedReceipt> {
+        unimplemented!();
+    }
+
     fn transaction_by_tracker(&self, _: &H256) -> Option<LocalizedTransaction> {
         unimplemented!();
     }
@@ -550,6 +559,10 @@ impl BlockChainClient for TestBlockChainClient {
     fn error_hints_by_tracker(&self, _: &H256) -> Vec<(H256, Option<String>)> {
         unimplemented!();
     }
+
+    fn logs(&self, _filter: &LogFilter) -> Result<Vec<LocalizedEvent>, LogFilterError> {
+        unimplemented!();
+    }
 }
 
 impl TimeoutHandler for TestBlockChainClient {
@@ -595,4 +608,8 @@ impl super::EngineClient for TestBlockChainClient {
         let db = kvdb_memorydb::create(NUM_COLUMNS.unwrap_or(0));
         Arc::new(db)
     }
+
+    fn find_mem_pool_transactions(&self, predicate: &Fn(&H256) -> bool) -> Vec<SignedTransaction> {
+        self.miner.find_mem_pool_transactions(predicate)
+    }
 }
@@ -231,9 +231,18 @@ impl CodeChainMachine {
             } => self.params.min_asset_unwrap_ccc_cost,
             Action::Pay {
                 ..
+            }
+            | Action::GrantVesting {
+                ..
             } => self.params.min_pay_transaction_cost,
             Action::SetRegularKey {
                 ..
+            }
+            | Action::SetMultisig {
+                ..
+            }
+            | Action::SetSpendingLimit {
+                ..
             } => self.params.min_set_regular_key_tranasction_cost,
             Action::CreateShard {
                 ..
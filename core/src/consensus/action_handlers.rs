@@ -0,0 +1,58 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use cjson;
+use cstate::{ActionHandler, ActionHandlerRegistry};
+use primitives::Bytes;
+
+use crate::error::SchemeError;
+
+/// Params of a custom action handler loaded from a WebAssembly module.
+#[derive(Clone)]
+pub struct WasmActionHandlerParams {
+    pub handler_id: u64,
+    pub code: Bytes,
+    pub gas_limit: u64,
+}
+
+impl From<cjson::scheme::WasmActionHandler> for WasmActionHandlerParams {
+    fn from(p: cjson::scheme::WasmActionHandler) -> Self {
+        WasmActionHandlerParams {
+            handler_id: p.handler_id.into(),
+            code: p.code.into(),
+            gas_limit: p.gas_limit.into(),
+        }
+    }
+}
+
+/// Registers the built-in handlers of an engine and the handlers configured in the scheme.
+pub fn load_action_handlers(
+    builtin_handlers: Vec<Arc<ActionHandler>>,
+    wasm_handlers: &[WasmActionHandlerParams],
+) -> Result<Vec<Arc<ActionHandler>>, SchemeError> {
+    let mut registry = ActionHandlerRegistry::new();
+    for handler in builtin_handlers {
+        registry.register(handler).map_err(SchemeError::InvalidActionHandler)?;
+    }
+    for params in wasm_handlers {
+        registry
+            .register_wasm(params.handler_id, &params.code, params.gas_limit)
+            .map_err(SchemeError::InvalidActionHandler)?;
+    }
+    Ok(registry.into_handlers())
+}
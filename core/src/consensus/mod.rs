@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod action_handlers;
 mod blake_pow;
 mod cuckoo;
 pub mod epoch;
@@ -25,6 +26,7 @@ mod tendermint;
 mod validator_set;
 mod vote_collector;
 
+pub use self::action_handlers::WasmActionHandlerParams;
 pub use self::blake_pow::BlakePoW;
 pub use self::cuckoo::Cuckoo;
 pub use self::null_engine::NullEngine;
@@ -441,11 +443,21 @@ pub trait CodeChainEngine: ConsensusEngine<CodeChainMachine> {
     fn verify_transaction_basic(&self, tx: &UnverifiedTransaction, header: &Header) -> Result<(), Error> {
         if let Action::Custom {
             handler_id,
-            ..
+            bytes,
         } = &tx.action
         {
-            if self.find_action_handler_for(*handler_id).is_none() {
-                return Err(SyntaxError::InvalidCustomAction.into())
+            let handler = self.find_action_handler_for(*handler_id).ok_or(SyntaxError::InvalidCustomAction)?;
+            handler.verify(bytes).map_err(|hint| SyntaxError::InvalidCustomActionBytes {
+                handler_id: *handler_id,
+                hint,
+            })?;
+            let min_cost = handler.fee(bytes, self.params().min_custom_transaction_cost);
+            if tx.fee < min_cost {
+                return Err(SyntaxError::InsufficientFee {
+                    minimal: min_cost,
+                    got: tx.fee,
+                }
+                .into())
             }
         }
         self.machine().verify_transaction_basic(tx, header)
@@ -22,8 +22,10 @@ use cstate::{ActionHandler, HitHandler};
 use ctypes::machine::{Header, LiveBlock, Transactions, WithBalances};
 
 use self::params::SoloParams;
+use super::action_handlers::load_action_handlers;
 use super::{ConsensusEngine, Seal};
 use crate::consensus::EngineType;
+use crate::error::SchemeError;
 use crate::SignedTransaction;
 
 /// A consensus engine which does not provide any consensus mechanism.
@@ -35,17 +37,18 @@ pub struct Solo<M> {
 
 impl<M> Solo<M> {
     /// Returns new instance of Solo over the given state machine.
-    pub fn new(params: SoloParams, machine: M) -> Self {
-        let mut action_handlers: Vec<Arc<ActionHandler>> = Vec::new();
+    pub fn new(params: SoloParams, machine: M) -> Result<Self, SchemeError> {
+        let mut builtin_handlers: Vec<Arc<ActionHandler>> = Vec::new();
         if params.enable_hit_handler {
-            action_handlers.push(Arc::new(HitHandler::new()));
+            builtin_handlers.push(Arc::new(HitHandler::new()));
         }
+        let action_handlers = load_action_handlers(builtin_handlers, &params.wasm_action_handlers)?;
 
-        Solo {
+        Ok(Solo {
             params,
             machine,
             action_handlers,
-        }
+        })
     }
 }
 
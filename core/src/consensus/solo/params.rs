@@ -16,12 +16,15 @@
 
 use cjson;
 
+use super::super::WasmActionHandlerParams;
+
 /// Params for a null engine.
 #[derive(Clone, Default)]
 pub struct SoloParams {
     /// base reward for a block.
     pub block_reward: u64,
     pub enable_hit_handler: bool,
+    pub wasm_action_handlers: Vec<WasmActionHandlerParams>,
 }
 
 impl From<cjson::scheme::SoloParams> for SoloParams {
@@ -29,6 +32,13 @@ impl From<cjson::scheme::SoloParams> for SoloParams {
         SoloParams {
             block_reward: p.block_reward.map_or_else(Default::default, Into::into),
             enable_hit_handler: p.action_handlers.hit.is_some(),
+            wasm_action_handlers: p
+                .action_handlers
+                .wasm_action_handlers
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
         }
     }
 }
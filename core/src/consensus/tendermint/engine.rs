@@ -255,8 +255,10 @@ impl ConsensusEngine<CodeChainMachine> for Tendermint {
         let timeouts = self.timeouts;
 
         let inner = self.inner.clone();
-        let extension = service.register_extension(move |api| TendermintExtension::new(inner, timeouts, api));
         let client = Weak::clone(self.client.read().as_ref().unwrap());
+        let extension_client = Weak::clone(&client);
+        let extension =
+            service.register_extension(move |api| TendermintExtension::new(inner, timeouts, api, extension_client));
         self.extension_initializer.send((extension, client)).unwrap();
 
         let (result, receiver) = crossbeam::bounded(1);
@@ -323,9 +325,18 @@ impl Tendermint {
             } => params.min_asset_unwrap_ccc_cost,
             Action::Pay {
                 ..
+            }
+            | Action::GrantVesting {
+                ..
             } => params.min_pay_transaction_cost,
             Action::SetRegularKey {
                 ..
+            }
+            | Action::SetMultisig {
+                ..
+            }
+            | Action::SetSpendingLimit {
+                ..
             } => params.min_set_regular_key_tranasction_cost,
             Action::CreateShard {
                 ..
@@ -340,8 +351,12 @@ impl Tendermint {
                 ..
             } => params.min_wrap_ccc_transaction_cost,
             Action::Custom {
-                ..
-            } => params.min_custom_transaction_cost,
+                handler_id,
+                bytes,
+            } => match self.find_action_handler_for(*handler_id) {
+                Some(handler) => handler.fee(bytes, params.min_custom_transaction_cost),
+                None => params.min_custom_transaction_cost,
+            },
             Action::Store {
                 ..
             } => params.min_store_transaction_cost,
@@ -16,7 +16,7 @@
 
 use std::cmp;
 
-use ccrypto::blake256;
+use ccrypto::{blake256, blake256_with_key};
 use ckey::{verify_schnorr, Public, SchnorrSignature};
 use primitives::{Bytes, H256};
 use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
@@ -79,6 +79,19 @@ const MESSAGE_ID_PROPOSAL_BLOCK: u8 = 0x02;
 const MESSAGE_ID_STEP_STATE: u8 = 0x03;
 const MESSAGE_ID_REQUEST_MESSAGE: u8 = 0x04;
 const MESSAGE_ID_REQUEST_PROPOSAL: u8 = 0x05;
+const MESSAGE_ID_COMPACT_PROPOSAL_BLOCK: u8 = 0x06;
+const MESSAGE_ID_REQUEST_TRANSACTIONS: u8 = 0x07;
+const MESSAGE_ID_TRANSACTIONS: u8 = 0x08;
+
+/// Identifies a transaction in a compact proposal block.
+pub type ShortId = u64;
+
+/// The first 8 bytes of the transaction hash keyed with the block hash.
+/// The key makes it hard to craft transactions whose short IDs collide in every block.
+pub fn short_transaction_id(block_hash: &H256, transaction_hash: &H256) -> ShortId {
+    let hash = blake256_with_key(transaction_hash, block_hash);
+    hash[..8].iter().fold(0, |id, byte| (id << 8) | ShortId::from(*byte))
+}
 
 #[derive(Debug, PartialEq)]
 pub enum TendermintMessage {
@@ -102,6 +115,22 @@ pub enum TendermintMessage {
         height: Height,
         view: View,
     },
+    /// A proposal block without the transactions, which the receivers find in their mem pools.
+    CompactProposalBlock {
+        signature: SchnorrSignature,
+        view: View,
+        header: Bytes,
+        short_ids: Vec<ShortId>,
+    },
+    /// Requests the transactions of a compact proposal block that are not in the mem pool.
+    RequestTransactions {
+        block_hash: H256,
+        indices: Vec<u64>,
+    },
+    Transactions {
+        block_hash: H256,
+        transactions: Vec<Bytes>,
+    },
 }
 
 impl Encodable for TendermintMessage {
@@ -160,6 +189,37 @@ impl Encodable for TendermintMessage {
                 s.append(height);
                 s.append(view);
             }
+            TendermintMessage::CompactProposalBlock {
+                signature,
+                view,
+                header,
+                short_ids,
+            } => {
+                s.begin_list(5);
+                s.append(&MESSAGE_ID_COMPACT_PROPOSAL_BLOCK);
+                s.append(signature);
+                s.append(view);
+                s.append(header);
+                s.append_list(short_ids);
+            }
+            TendermintMessage::RequestTransactions {
+                block_hash,
+                indices,
+            } => {
+                s.begin_list(3);
+                s.append(&MESSAGE_ID_REQUEST_TRANSACTIONS);
+                s.append(block_hash);
+                s.append_list(indices);
+            }
+            TendermintMessage::Transactions {
+                block_hash,
+                transactions,
+            } => {
+                s.begin_list(3);
+                s.append(&MESSAGE_ID_TRANSACTIONS);
+                s.append(block_hash);
+                s.append_list::<Bytes, Bytes>(transactions);
+            }
         }
     }
 }
@@ -253,6 +313,47 @@ impl Decodable for TendermintMessage {
                     view,
                 }
             }
+            MESSAGE_ID_COMPACT_PROPOSAL_BLOCK => {
+                let item_count = rlp.item_count()?;
+                if item_count != 5 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 5,
+                    })
+                }
+                TendermintMessage::CompactProposalBlock {
+                    signature: rlp.val_at(1)?,
+                    view: rlp.val_at(2)?,
+                    header: rlp.val_at(3)?,
+                    short_ids: rlp.list_at(4)?,
+                }
+            }
+            MESSAGE_ID_REQUEST_TRANSACTIONS => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 3,
+                    })
+                }
+                TendermintMessage::RequestTransactions {
+                    block_hash: rlp.val_at(1)?,
+                    indices: rlp.list_at(2)?,
+                }
+            }
+            MESSAGE_ID_TRANSACTIONS => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 3,
+                    })
+                }
+                TendermintMessage::Transactions {
+                    block_hash: rlp.val_at(1)?,
+                    transactions: rlp.list_at(2)?,
+                }
+            }
             _ => return Err(DecoderError::Custom("Unknown message id detected")),
         })
     }
@@ -433,6 +534,47 @@ mod tests {
         });
     }
 
+    #[test]
+    fn encode_and_decode_tendermint_message_6() {
+        rlp_encode_and_decode_test!(TendermintMessage::CompactProposalBlock {
+            signature: SchnorrSignature::random(),
+            view: 3,
+            header: vec![1u8, 2u8, 3u8],
+            short_ids: vec![0x0123_4567_89ab_cdef, 42],
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_tendermint_message_7() {
+        rlp_encode_and_decode_test!(TendermintMessage::RequestTransactions {
+            block_hash: H256::random(),
+            indices: vec![0, 3, 4],
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_tendermint_message_8() {
+        rlp_encode_and_decode_test!(TendermintMessage::Transactions {
+            block_hash: H256::random(),
+            transactions: vec![vec![1u8, 2u8], vec![3u8]],
+        });
+    }
+
+    #[test]
+    fn short_transaction_id_depends_on_the_block() {
+        let transaction_hash = H256::random();
+        let block_hash1 = H256::random();
+        let block_hash2 = H256::random();
+        assert_eq!(
+            short_transaction_id(&block_hash1, &transaction_hash),
+            short_transaction_id(&block_hash1, &transaction_hash)
+        );
+        assert_ne!(
+            short_transaction_id(&block_hash1, &transaction_hash),
+            short_transaction_id(&block_hash2, &transaction_hash)
+        );
+    }
+
     #[test]
     fn encode_and_decode_consensus_message_1() {
         let message = ConsensusMessage::default();
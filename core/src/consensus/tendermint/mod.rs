@@ -38,9 +38,11 @@ use primitives::H256;
 use self::chain_notify::TendermintChainNotify;
 pub use self::params::{TendermintParams, TimeoutParams};
 use self::types::{Height, Step, View};
+use super::action_handlers::load_action_handlers;
 use super::validator_set::ValidatorSet;
 use crate::client::EngineClient;
 use crate::codechain_machine::CodeChainMachine;
+use crate::error::SchemeError;
 use ChainNotify;
 
 /// Timer token representing the consensus step timeouts.
@@ -88,17 +90,19 @@ impl Drop for Tendermint {
 impl Tendermint {
     #![cfg_attr(feature = "cargo-clippy", allow(clippy::new_ret_no_self))]
     /// Create a new instance of Tendermint engine
-    pub fn new(our_params: TendermintParams, machine: CodeChainMachine) -> Arc<Self> {
+    pub fn new(our_params: TendermintParams, machine: CodeChainMachine) -> Result<Arc<Self>, SchemeError> {
         let stake = stake::Stake::new(our_params.genesis_stakes, Arc::clone(&our_params.validators));
         let timeouts = our_params.timeouts;
         let validators = Arc::clone(&our_params.validators);
         let machine = Arc::new(machine);
+        // The handlers are loaded before the worker is spawned, so that an invalid scheme doesn't leave it running.
+        let builtin_handlers: Vec<Arc<ActionHandler>> = vec![Arc::new(stake)];
+        let action_handlers = load_action_handlers(builtin_handlers, &our_params.wasm_action_handlers)?;
 
         let (join, extension_initializer, inner, quit_tendermint) = worker::spawn(our_params.validators);
-        let action_handlers: Vec<Arc<ActionHandler>> = vec![Arc::new(stake)];
         let chain_notify = Arc::new(TendermintChainNotify::new(inner.clone()));
 
-        Arc::new(Tendermint {
+        Ok(Arc::new(Tendermint {
             client: Default::default(),
             extension_initializer,
             timeouts,
@@ -111,7 +115,7 @@ impl Tendermint {
             action_handlers,
             chain_notify,
             has_signer: false.into(),
-        })
+        }))
     }
 }
 
@@ -15,25 +15,33 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter::Iterator;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
+use ccrypto::blake256;
 use ckey::SchnorrSignature;
-use cnetwork::{Api, NetworkExtension, NodeId};
+use cmerkle::skewed_merkle_root;
+use cnetwork::{Api, Misbehavior, NetworkExtension, NetworkExtensionPriority, NodeId};
 use crossbeam_channel as crossbeam;
 use ctimer::TimerToken;
 use primitives::{Bytes, H256};
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
-use rlp::{Encodable, UntrustedRlp};
+use rlp::{Encodable, RlpStream, UntrustedRlp};
 use time::Duration;
 
 use super::message::*;
 use super::params::TimeoutParams;
 use super::types::{BitSet, Height, PeerState, Step, View};
 use super::worker;
+use crate::client::EngineClient;
 use crate::consensus::EngineError;
+use crate::error::{BlockImportError, ImportError};
+use crate::header::Header;
+use crate::transaction::transaction_hash;
+use crate::views::BlockView;
+use crate::BlockId;
 
 use super::{
     ENGINE_TIMEOUT_BROADCAST_STEP_STATE, ENGINE_TIMEOUT_BROADCAT_STEP_STATE_INTERVAL, ENGINE_TIMEOUT_EMPTY_PROPOSAL,
@@ -45,13 +53,66 @@ pub struct TendermintExtension {
     peers: HashMap<NodeId, PeerState>,
     api: Box<Api>,
     timeouts: TimeoutParams,
+    client: Weak<EngineClient>,
+    /// Peers that understand compact proposal blocks.
+    compact_block_peers: HashSet<NodeId>,
+    /// The hash and the bytes of the last block proposed by this node.
+    own_proposal: Option<(H256, Bytes)>,
+    pending_compact_blocks: HashMap<H256, PendingCompactBlock>,
 }
 
 const MIN_PEERS_PROPAGATION: usize = 4;
 const MAX_PEERS_PROPAGATION: usize = 128;
+const MAX_PENDING_COMPACT_BLOCKS: usize = 16;
+
+const VERSION_WITHOUT_COMPACT_BLOCK: u64 = 0;
+const VERSION_WITH_COMPACT_BLOCK: u64 = 1;
+
+/// A compact proposal block waiting for the transactions that were not in the mem pool.
+struct PendingCompactBlock {
+    signature: SchnorrSignature,
+    view: View,
+    height: Height,
+    header: Bytes,
+    short_ids: Vec<ShortId>,
+    transactions: Vec<Option<Bytes>>,
+}
+
+impl PendingCompactBlock {
+    fn missing_indices(&self) -> Vec<u64> {
+        self.transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, transaction)| transaction.is_none())
+            .map(|(index, _)| index as u64)
+            .collect()
+    }
+
+    fn assemble(&self) -> Option<Bytes> {
+        if self.transactions.iter().any(Option::is_none) {
+            return None
+        }
+        let mut s = RlpStream::new_list(2);
+        s.append_raw(&self.header, 1);
+        s.begin_list(self.transactions.len());
+        for transaction in self.transactions.iter().flatten() {
+            s.append_raw(transaction, 1);
+        }
+        Some(s.out())
+    }
+
+    fn transactions_root(&self, parent_transactions_root: H256) -> H256 {
+        skewed_merkle_root(parent_transactions_root, self.transactions.iter().flatten())
+    }
+}
 
 impl TendermintExtension {
-    pub fn new(inner: crossbeam::Sender<worker::Event>, timeouts: TimeoutParams, api: Box<Api>) -> Self {
+    pub fn new(
+        inner: crossbeam::Sender<worker::Event>,
+        timeouts: TimeoutParams,
+        api: Box<Api>,
+        client: Weak<EngineClient>,
+    ) -> Self {
         let initial = timeouts.initial();
         ctrace!(ENGINE, "Setting the initial timeout to {}.", initial);
         api.set_timer_once(ENGINE_TIMEOUT_TOKEN_NONCE_BASE, initial).expect("Timer set succeeds");
@@ -65,6 +126,10 @@ impl TendermintExtension {
             peers: Default::default(),
             api,
             timeouts,
+            client,
+            compact_block_peers: Default::default(),
+            own_proposal: None,
+            pending_compact_blocks: Default::default(),
         }
     }
 
@@ -122,19 +187,214 @@ impl TendermintExtension {
         }
     }
 
-    fn broadcast_proposal_block(&self, signature: SchnorrSignature, view: View, message: Bytes) {
-        let message = Arc::new(
+    fn broadcast_proposal_block(&mut self, signature: SchnorrSignature, view: View, message: Bytes) {
+        let (block_hash, compact_message) = {
+            let block = BlockView::new(&message);
+            let block_hash = block.hash();
+            let short_ids =
+                block.transaction_hashes().iter().map(|hash| short_transaction_id(&block_hash, hash)).collect();
+            let compact_message = TendermintMessage::CompactProposalBlock {
+                signature,
+                view,
+                header: block.header_rlp().as_raw().to_vec(),
+                short_ids,
+            };
+            (block_hash, Arc::new(compact_message.rlp_bytes().into_vec()))
+        };
+        let full_message = Arc::new(
             TendermintMessage::ProposalBlock {
                 signature,
-                message,
+                message: message.clone(),
                 view,
             }
             .rlp_bytes()
             .into_vec(),
         );
         for token in self.peers.keys() {
-            self.api.send(token, Arc::clone(&message));
+            if self.compact_block_peers.contains(token) {
+                self.api.send(token, Arc::clone(&compact_message));
+            } else {
+                self.api.send(token, Arc::clone(&full_message));
+            }
+        }
+        self.own_proposal = Some((block_hash, message));
+    }
+
+    /// Returns false if the proposal block failed to be imported.
+    fn on_proposal_block(&self, signature: SchnorrSignature, view: View, message: Bytes) -> bool {
+        let (result, receiver) = crossbeam::bounded(1);
+        self.inner
+            .send(worker::Event::ProposalBlock {
+                signature,
+                view,
+                message: message.clone(),
+                result,
+            })
+            .unwrap();
+        if let Some(c) = receiver.recv().unwrap() {
+            match c.import_block(message) {
+                Ok(_)
+                | Err(BlockImportError::Import(ImportError::AlreadyInChain))
+                | Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {}
+                Err(e) => {
+                    cinfo!(ENGINE, "Failed to import proposal block {:?}", e);
+                    return false
+                }
+            }
+        }
+        true
+    }
+
+    /// Imports the proposal block reconstructed from a compact proposal block.
+    /// The full block is requested if the reconstructed one is wrong, e.g. when the short IDs collide.
+    fn on_reconstructed_proposal_block(&self, token: &NodeId, pending: &PendingCompactBlock, message: Bytes) {
+        let header = UntrustedRlp::new(&pending.header).as_val::<Header>().expect("The header is checked on receipt");
+        let parent =
+            self.client.upgrade().and_then(|client| client.block_header(&BlockId::Hash(*header.parent_hash())));
+        // The transactions root is verified when the block is imported if the parent is not known yet.
+        if let Some(parent) = parent {
+            if pending.transactions_root(parent.transactions_root()) != *header.transactions_root() {
+                cinfo!(ENGINE, "The reconstructed proposal block {} is wrong, request the full block", header.hash());
+                self.request_proposal(token, pending.height, pending.view);
+                return
+            }
+        }
+        if !self.on_proposal_block(pending.signature, pending.view, message) {
+            self.request_proposal(token, pending.height, pending.view);
+        }
+    }
+
+    fn on_compact_proposal_block(
+        &mut self,
+        token: &NodeId,
+        signature: SchnorrSignature,
+        view: View,
+        header: Bytes,
+        short_ids: Vec<ShortId>,
+    ) {
+        let height = match UntrustedRlp::new(&header).as_val::<Header>() {
+            Ok(header) => header.number(),
+            Err(err) => {
+                cinfo!(ENGINE, "Invalid compact proposal block from {}: {:?}", token, err);
+                self.api.report(token, Misbehavior::MalformedMessage);
+                return
+            }
+        };
+        let block_hash = blake256(&header);
+        if self.pending_compact_blocks.contains_key(&block_hash) {
+            return
         }
+        let client = match self.client.upgrade() {
+            Some(client) => client,
+            None => return,
+        };
+
+        let wanted: HashSet<ShortId> = short_ids.iter().cloned().collect();
+        let found: HashMap<ShortId, Bytes> = client
+            .find_mem_pool_transactions(&|hash| wanted.contains(&short_transaction_id(&block_hash, hash)))
+            .into_iter()
+            .map(|transaction| {
+                (short_transaction_id(&block_hash, &transaction.hash()), transaction.rlp_bytes().into_vec())
+            })
+            .collect();
+        let pending = PendingCompactBlock {
+            signature,
+            view,
+            height,
+            header,
+            transactions: short_ids.iter().map(|short_id| found.get(short_id).cloned()).collect(),
+            short_ids,
+        };
+        if let Some(message) = pending.assemble() {
+            ctrace!(ENGINE, "Reconstructed the compact proposal block {} from the mem pool", block_hash);
+            self.on_reconstructed_proposal_block(token, &pending, message);
+            return
+        }
+
+        let indices = pending.missing_indices();
+        ctrace!(ENGINE, "Request {} transactions of the proposal block {} to {}", indices.len(), block_hash, token);
+        let message = TendermintMessage::RequestTransactions {
+            block_hash,
+            indices,
+        }
+        .rlp_bytes()
+        .into_vec();
+        self.api.send(token, Arc::new(message));
+
+        if self.pending_compact_blocks.len() >= MAX_PENDING_COMPACT_BLOCKS {
+            let oldest = self
+                .pending_compact_blocks
+                .iter()
+                .min_by_key(|(_, pending)| (pending.height, pending.view))
+                .map(|(hash, _)| *hash)
+                .expect("The pending compact blocks are not empty");
+            self.pending_compact_blocks.remove(&oldest);
+        }
+        self.pending_compact_blocks.insert(block_hash, pending);
+    }
+
+    fn on_request_transactions(&self, token: &NodeId, block_hash: H256, indices: Vec<u64>) {
+        let block = match &self.own_proposal {
+            Some((hash, block)) if *hash == block_hash => block,
+            _ => {
+                ctrace!(ENGINE, "{} requested the transactions of an unknown proposal block {}", token, block_hash);
+                // An empty reply lets the requester fall back to requesting the full proposal block.
+                let message = TendermintMessage::Transactions {
+                    block_hash,
+                    transactions: Vec::new(),
+                }
+                .rlp_bytes()
+                .into_vec();
+                self.api.send(token, Arc::new(message));
+                return
+            }
+        };
+        let transactions = UntrustedRlp::new(block).at(1).and_then(|transactions| {
+            indices
+                .iter()
+                .map(|index| transactions.at(*index as usize).map(|transaction| transaction.as_raw().to_vec()))
+                .collect::<Result<Vec<_>, _>>()
+        });
+        match transactions {
+            Ok(transactions) => {
+                let message = TendermintMessage::Transactions {
+                    block_hash,
+                    transactions,
+                }
+                .rlp_bytes()
+                .into_vec();
+                self.api.send(token, Arc::new(message));
+            }
+            Err(err) => {
+                cinfo!(ENGINE, "Invalid transaction request from {}: {:?}", token, err);
+                self.api.report(token, Misbehavior::MalformedMessage);
+            }
+        }
+    }
+
+    fn on_transactions(&mut self, token: &NodeId, block_hash: H256, transactions: Vec<Bytes>) {
+        let mut pending = match self.pending_compact_blocks.remove(&block_hash) {
+            Some(pending) => pending,
+            None => {
+                ctrace!(ENGINE, "Received the transactions of an unknown proposal block {}", block_hash);
+                return
+            }
+        };
+        let indices = pending.missing_indices();
+        let is_valid = indices.len() == transactions.len()
+            && indices.iter().zip(transactions.iter()).all(|(index, transaction)| {
+                pending.short_ids[*index as usize] == short_transaction_id(&block_hash, &transaction_hash(transaction))
+            });
+        if !is_valid {
+            cinfo!(ENGINE, "Cannot reconstruct the proposal block {}, request the full block", block_hash);
+            self.request_proposal(token, pending.height, pending.view);
+            return
+        }
+        for (index, transaction) in indices.into_iter().zip(transactions) {
+            pending.transactions[index as usize] = Some(transaction);
+        }
+        let message = pending.assemble().expect("All transactions are filled");
+        self.on_reconstructed_proposal_block(token, &pending, message);
     }
 
     fn request_proposal_to_any(&self, height: Height, view: View) {
@@ -219,16 +479,24 @@ impl NetworkExtension<Event> for TendermintExtension {
     }
 
     fn versions() -> &'static [u64] {
-        const VERSIONS: &[u64] = &[0];
+        const VERSIONS: &[u64] = &[VERSION_WITHOUT_COMPACT_BLOCK, VERSION_WITH_COMPACT_BLOCK];
         &VERSIONS
     }
 
-    fn on_node_added(&mut self, token: &NodeId, _version: u64) {
+    fn priority() -> NetworkExtensionPriority {
+        NetworkExtensionPriority::High
+    }
+
+    fn on_node_added(&mut self, token: &NodeId, version: u64) {
         self.peers.insert(*token, PeerState::new());
+        if version >= VERSION_WITH_COMPACT_BLOCK {
+            self.compact_block_peers.insert(*token);
+        }
     }
 
     fn on_node_removed(&mut self, token: &NodeId) {
         self.peers.remove(token);
+        self.compact_block_peers.remove(token);
     }
 
     fn on_message(&mut self, token: &NodeId, data: &[u8]) {
@@ -256,6 +524,13 @@ impl NetworkExtension<Event> for TendermintExtension {
                                 current_height
                             );
                         }
+                        Err(e @ EngineError::MessageWithInvalidSignature {
+                            ..
+                        })
+                        | Err(e @ EngineError::MalformedMessage(_)) => {
+                            cinfo!(ENGINE, "Invalid consensus message from {}: {:?}", token, e);
+                            self.api.report(token, Misbehavior::InvalidConsensusMessage);
+                        }
                         Err(e) => {
                             cinfo!(ENGINE, "Failed to handle message {:?}", e);
                         }
@@ -268,20 +543,27 @@ impl NetworkExtension<Event> for TendermintExtension {
                 view,
                 message,
             }) => {
-                let (result, receiver) = crossbeam::bounded(1);
-                self.inner
-                    .send(worker::Event::ProposalBlock {
-                        signature,
-                        view,
-                        message: message.clone(),
-                        result,
-                    })
-                    .unwrap();
-                if let Some(c) = receiver.recv().unwrap() {
-                    if let Err(e) = c.import_block(message) {
-                        cinfo!(ENGINE, "Failed to import proposal block {:?}", e);
-                    }
-                }
+                self.on_proposal_block(signature, view, message);
+            }
+            Ok(TendermintMessage::CompactProposalBlock {
+                signature,
+                view,
+                header,
+                short_ids,
+            }) => {
+                self.on_compact_proposal_block(token, signature, view, header, short_ids);
+            }
+            Ok(TendermintMessage::RequestTransactions {
+                block_hash,
+                indices,
+            }) => {
+                self.on_request_transactions(token, block_hash, indices);
+            }
+            Ok(TendermintMessage::Transactions {
+                block_hash,
+                transactions,
+            }) => {
+                self.on_transactions(token, block_hash, transactions);
             }
             Ok(TendermintMessage::StepState {
                 vote_step,
@@ -351,7 +633,10 @@ impl NetworkExtension<Event> for TendermintExtension {
                     self.send_votes(token, votes);
                 }
             }
-            _ => cinfo!(ENGINE, "Invalid message from peer {}", token),
+            _ => {
+                cinfo!(ENGINE, "Invalid message from peer {}", token);
+                self.api.report(token, Misbehavior::MalformedMessage);
+            }
         }
     }
 
@@ -444,3 +729,221 @@ pub enum Event {
         message: Bytes,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::Mutex;
+    use std::thread;
+
+    use ckey::{Address, Generator, Random};
+    use cnetwork::NetworkExtensionResult;
+    use ctypes::transaction::{Action, Transaction};
+
+    use super::*;
+    use crate::client::{BlockInfo, TestBlockChainClient};
+    use crate::transaction::SignedTransaction;
+
+    type SentMessages = Arc<Mutex<Vec<(NodeId, Arc<Bytes>)>>>;
+
+    struct TestApi {
+        sent: SentMessages,
+    }
+
+    impl Api for TestApi {
+        fn send(&self, node: &NodeId, message: Arc<Bytes>) {
+            self.sent.lock().unwrap().push((*node, message));
+        }
+
+        fn report(&self, _node: &NodeId, _misbehavior: Misbehavior) {}
+
+        fn set_timer(&self, _timer: TimerToken, _d: Duration) -> NetworkExtensionResult<()> {
+            Ok(())
+        }
+
+        fn set_timer_once(&self, _timer: TimerToken, _d: Duration) -> NetworkExtensionResult<()> {
+            Ok(())
+        }
+
+        fn clear_timer(&self, _timer: TimerToken) -> NetworkExtensionResult<()> {
+            Ok(())
+        }
+    }
+
+    fn new_extension(
+        client: &Arc<TestBlockChainClient>,
+    ) -> (TendermintExtension, crossbeam::Receiver<worker::Event>, SentMessages) {
+        let (sender, receiver) = crossbeam::unbounded();
+        let sent: SentMessages = Default::default();
+        let api = TestApi {
+            sent: Arc::clone(&sent),
+        };
+        let client: Arc<EngineClient> = Arc::<TestBlockChainClient>::clone(client);
+        let extension =
+            TendermintExtension::new(sender, TimeoutParams::default(), Box::new(api), Arc::downgrade(&client));
+        (extension, receiver, sent)
+    }
+
+    fn peer() -> NodeId {
+        NodeId::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 3485)
+    }
+
+    fn transactions(count: u64) -> Vec<Bytes> {
+        let private = *Random.generate().unwrap().private();
+        (0..count)
+            .map(|seq| {
+                let tx = Transaction {
+                    seq,
+                    fee: 10,
+                    network_id: "tc".into(),
+                    action: Action::Pay {
+                        receiver: Address::random(),
+                        quantity: 100,
+                    },
+                };
+                SignedTransaction::new_with_sign(tx, &private).rlp_bytes().into_vec()
+            })
+            .collect()
+    }
+
+    /// Returns a proposal block on top of the genesis block and its compact form whose transactions are all missing.
+    fn proposal_block(client: &TestBlockChainClient, transactions: &[Bytes]) -> (Bytes, PendingCompactBlock) {
+        let genesis = client.block_header(&BlockId::Hash(client.genesis_hash)).unwrap();
+        let mut header = Header::new();
+        header.set_parent_hash(client.genesis_hash);
+        header.set_number(1);
+        header.set_transactions_root(skewed_merkle_root(genesis.transactions_root(), transactions));
+        let header = header.rlp_bytes().into_vec();
+
+        let mut s = RlpStream::new_list(2);
+        s.append_raw(&header, 1);
+        s.begin_list(transactions.len());
+        for transaction in transactions {
+            s.append_raw(transaction, 1);
+        }
+        let block = s.out();
+
+        let block_hash = blake256(&header);
+        let pending = PendingCompactBlock {
+            signature: SchnorrSignature::random(),
+            view: 0,
+            height: 1,
+            header,
+            short_ids: transactions
+                .iter()
+                .map(|transaction| short_transaction_id(&block_hash, &transaction_hash(transaction)))
+                .collect(),
+            transactions: vec![None; transactions.len()],
+        };
+        (block, pending)
+    }
+
+    /// Plays the worker that accepts the next proposal block and returns it.
+    fn accept_proposal_block(receiver: crossbeam::Receiver<worker::Event>) -> thread::JoinHandle<Bytes> {
+        thread::spawn(move || match receiver.recv().unwrap() {
+            worker::Event::ProposalBlock {
+                message,
+                result,
+                ..
+            } => {
+                result.send(None).unwrap();
+                message
+            }
+            _ => panic!("Unexpected event"),
+        })
+    }
+
+    fn is_request_proposal(message: &[u8]) -> bool {
+        match UntrustedRlp::new(message).as_val() {
+            Ok(TendermintMessage::RequestProposal {
+                ..
+            }) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn assemble_when_all_transactions_are_known() {
+        let client = Arc::new(TestBlockChainClient::new());
+        let transactions = transactions(3);
+        let (block, mut pending) = proposal_block(&client, &transactions);
+        pending.transactions = transactions.into_iter().map(Some).collect();
+
+        assert!(pending.missing_indices().is_empty());
+        assert_eq!(Some(block), pending.assemble());
+    }
+
+    #[test]
+    fn missing_transactions_are_filled_by_the_reply() {
+        let client = Arc::new(TestBlockChainClient::new());
+        let (mut extension, receiver, sent) = new_extension(&client);
+        let transactions = transactions(4);
+        let (block, mut pending) = proposal_block(&client, &transactions);
+        pending.transactions[0] = Some(transactions[0].clone());
+        pending.transactions[2] = Some(transactions[2].clone());
+        assert_eq!(vec![1, 3], pending.missing_indices());
+        assert_eq!(None, pending.assemble());
+
+        let block_hash = blake256(&pending.header);
+        extension.pending_compact_blocks.insert(block_hash, pending);
+        let worker = accept_proposal_block(receiver);
+        extension.on_transactions(&peer(), block_hash, vec![transactions[1].clone(), transactions[3].clone()]);
+
+        assert_eq!(block, worker.join().unwrap());
+        assert!(extension.pending_compact_blocks.is_empty());
+        assert!(sent.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn request_the_full_block_on_short_id_collision() {
+        let client = Arc::new(TestBlockChainClient::new());
+        let (extension, receiver, sent) = new_extension(&client);
+        let transactions = transactions(3);
+        let (_, mut pending) = proposal_block(&client, &transactions);
+        // A transaction in the mem pool whose short ID is the same as the proposed one.
+        let colliding = self::transactions(1).remove(0);
+        pending.transactions = vec![Some(transactions[0].clone()), Some(colliding), Some(transactions[2].clone())];
+        let message = pending.assemble().unwrap();
+
+        extension.on_reconstructed_proposal_block(&peer(), &pending, message);
+
+        assert!(receiver.try_recv().is_err(), "The wrong block must not be imported");
+        let sent = sent.lock().unwrap();
+        assert_eq!(1, sent.len());
+        assert!(is_request_proposal(&sent[0].1));
+    }
+
+    #[test]
+    fn request_the_full_block_on_empty_reply() {
+        let client = Arc::new(TestBlockChainClient::new());
+        let (mut extension, _receiver, sent) = new_extension(&client);
+        let (_, pending) = proposal_block(&client, &transactions(2));
+        let block_hash = blake256(&pending.header);
+        extension.pending_compact_blocks.insert(block_hash, pending);
+
+        extension.on_transactions(&peer(), block_hash, Vec::new());
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(1, sent.len());
+        assert!(is_request_proposal(&sent[0].1));
+    }
+
+    #[test]
+    fn reply_empty_transactions_for_unknown_proposal() {
+        let client = Arc::new(TestBlockChainClient::new());
+        let (extension, _receiver, sent) = new_extension(&client);
+        let block_hash = H256::random();
+
+        extension.on_request_transactions(&peer(), block_hash, vec![0, 1]);
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(1, sent.len());
+        assert_eq!(
+            TendermintMessage::Transactions {
+                block_hash,
+                transactions: Vec::new(),
+            },
+            UntrustedRlp::new(&sent[0].1).as_val().unwrap()
+        );
+    }
+}
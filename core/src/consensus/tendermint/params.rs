@@ -22,6 +22,7 @@ use ckey::{Address, PlatformAddress};
 use time::Duration;
 
 use super::super::validator_set::{new_validator_set, ValidatorSet};
+use super::super::WasmActionHandlerParams;
 use super::types::View;
 use super::Step;
 
@@ -35,6 +36,8 @@ pub struct TendermintParams {
     pub block_reward: u64,
     /// Tokens distributed at genesis.
     pub genesis_stakes: HashMap<Address, u64>,
+    /// Custom action handlers loaded from WebAssembly modules.
+    pub wasm_action_handlers: Vec<WasmActionHandlerParams>,
 }
 
 impl From<cjson::scheme::TendermintParams> for TendermintParams {
@@ -58,6 +61,7 @@ impl From<cjson::scheme::TendermintParams> for TendermintParams {
                 .into_iter()
                 .map(|(pa, amount)| (PlatformAddress::into_address(pa), amount))
                 .collect(),
+            wasm_action_handlers: p.wasm_action_handlers.unwrap_or_default().into_iter().map(Into::into).collect(),
         }
     }
 }
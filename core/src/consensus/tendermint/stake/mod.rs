@@ -25,7 +25,8 @@ use std::sync::Arc;
 use ckey::Address;
 use cstate::{ActionHandler, StateResult, TopLevelState};
 use ctypes::errors::RuntimeError;
-use rlp::{Decodable, UntrustedRlp};
+use ctypes::event::Event;
+use rlp::{Decodable, RlpStream, UntrustedRlp};
 
 use self::action_data::{StakeAccount, Stakeholders};
 use self::actions::Action;
@@ -90,6 +91,16 @@ impl ActionHandler for Stake {
         Ok(())
     }
 
+    fn verify(&self, bytes: &[u8]) -> Result<(), String> {
+        let action = Action::decode(&UntrustedRlp::new(bytes)).map_err(|err| err.to_string())?;
+        match action {
+            Action::DelegateCCS {
+                ..
+            } if !self.enable_delegations => Err("DelegateCCS is disabled".to_string()),
+            _ => Ok(()),
+        }
+    }
+
     fn execute(&self, bytes: &[u8], state: &mut TopLevelState, sender: &Address) -> StateResult<()> {
         let action = Action::decode(&UntrustedRlp::new(bytes))
             .map_err(|err| RuntimeError::FailedToHandleCustomAction(err.to_string()))?;
@@ -128,6 +139,7 @@ fn transfer_ccs(state: &mut TopLevelState, sender: &Address, receiver: &Address,
     sender_account.save_to_state(state)?;
     receiver_account.save_to_state(state)?;
 
+    state.emit_event(stake_event("TransferCCS", sender, receiver, quantity));
     Ok(())
 }
 
@@ -151,9 +163,21 @@ fn delegate_ccs(
 
     delegation.save_to_state(state)?;
     delegator.save_to_state(state)?;
+
+    state.emit_event(stake_event("DelegateCCS", sender, delegatee, quantity));
     Ok(())
 }
 
+fn stake_event(topic: &str, from: &Address, to: &Address, quantity: u64) -> Event {
+    let mut data = RlpStream::new_list(3);
+    data.append(from).append(to).append(&quantity);
+    Event::Custom {
+        handler_id: CUSTOM_ACTION_HANDLER_ID,
+        topic: topic.as_bytes().to_vec(),
+        data: data.out(),
+    }
+}
+
 pub fn get_stakes(state: &TopLevelState) -> StateResult<HashMap<Address, u64>> {
     let stakeholders = Stakeholders::load_from_state(state)?;
     let mut result = HashMap::new();
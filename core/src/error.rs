@@ -117,10 +117,11 @@ pub enum BlockError {
     BodySizeIsTooBig,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SchemeError {
     InvalidCommonParams,
     InvalidState,
+    InvalidActionHandler(String),
 }
 
 impl fmt::Display for SchemeError {
@@ -129,6 +130,7 @@ impl fmt::Display for SchemeError {
         let msg: String = match self {
             InvalidCommonParams => "Common params are not matched with gensis block".into(),
             InvalidState => "Genesis state is not same with spec".into(),
+            InvalidActionHandler(err) => format!("Invalid action handler: {}", err),
         };
         f.write_fmt(format_args!("Scheme file error ({})", msg))
     }
@@ -14,11 +14,316 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use primitives::H256;
+use std::fmt;
+
+use ckey::Address;
+use cstate::StateError;
+use ctypes::errors::{RuntimeError, SyntaxError};
+use ctypes::event::Event;
+use ctypes::BlockNumber;
+use primitives::{Bytes, H160, H256};
+use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
+
+use crate::types::BlockId;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Invoice {
     pub tracker: Option<H256>,
     pub hash: H256,
-    pub error: Option<String>,
+    pub error: Option<TransactionFailure>,
+    pub events: Vec<Event>,
+}
+
+/// The reason why a transaction failed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransactionFailure {
+    Runtime(RuntimeError),
+    Syntax(SyntaxError),
+    /// A failure that is not caused by the transaction itself, e.g. a broken state trie.
+    Other(String),
+}
+
+type FailureType = u8;
+const FAILURE_RUNTIME: FailureType = 0x01;
+const FAILURE_SYNTAX: FailureType = 0x02;
+const FAILURE_OTHER: FailureType = 0x03;
+
+impl fmt::Display for TransactionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransactionFailure::Runtime(err) => err.fmt(f),
+            TransactionFailure::Syntax(err) => err.fmt(f),
+            TransactionFailure::Other(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<StateError> for TransactionFailure {
+    fn from(err: StateError) -> Self {
+        match err {
+            StateError::Runtime(err) => TransactionFailure::Runtime(err),
+            StateError::Trie(err) => TransactionFailure::Other(err.to_string()),
+        }
+    }
+}
+
+impl From<RuntimeError> for TransactionFailure {
+    fn from(err: RuntimeError) -> Self {
+        TransactionFailure::Runtime(err)
+    }
+}
+
+impl From<SyntaxError> for TransactionFailure {
+    fn from(err: SyntaxError) -> Self {
+        TransactionFailure::Syntax(err)
+    }
+}
+
+impl Encodable for TransactionFailure {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+        match self {
+            TransactionFailure::Runtime(err) => s.append(&FAILURE_RUNTIME).append(err),
+            TransactionFailure::Syntax(err) => s.append(&FAILURE_SYNTAX).append(err),
+            TransactionFailure::Other(err) => s.append(&FAILURE_OTHER).append(err),
+        };
+    }
+}
+
+impl Decodable for TransactionFailure {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let item_count = rlp.item_count()?;
+        if item_count != 2 {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: item_count,
+                expected: 2,
+            })
+        }
+        Ok(match rlp.val_at(0)? {
+            FAILURE_RUNTIME => TransactionFailure::Runtime(rlp.val_at(1)?),
+            FAILURE_SYNTAX => TransactionFailure::Syntax(rlp.val_at(1)?),
+            FAILURE_OTHER => TransactionFailure::Other(rlp.val_at(1)?),
+            _ => return Err(DecoderError::Custom("Unexpected transaction failure type")),
+        })
+    }
+}
+
+/// The result of a transaction that has been executed in a block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalizedReceipt {
+    pub transaction_hash: H256,
+    /// The block position of the transaction.
+    /// It is `None` if the transaction is not in the canonical chain, e.g. it failed.
+    pub block_number: Option<BlockNumber>,
+    pub block_hash: Option<H256>,
+    pub transaction_index: Option<usize>,
+    /// The fee paid by the transaction. A failed transaction pays nothing.
+    pub fee: u64,
+    pub error: Option<TransactionFailure>,
+}
+
+/// An event with the position of the transaction that emitted it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalizedEvent {
+    pub event: Event,
+    pub block_number: BlockNumber,
+    pub block_hash: H256,
+    pub transaction_index: usize,
+    pub transaction_hash: H256,
+    /// Index of the event within the transaction.
+    pub event_index: usize,
+}
+
+/// The maximum number of blocks that a `LogFilter` can search at once.
+pub const MAX_LOG_FILTER_RANGE: BlockNumber = 10_000;
+
+/// Selects the events in the blocks between `from_block` and `to_block`.
+/// The range cannot be larger than `MAX_LOG_FILTER_RANGE`.
+/// An event matches if it has all the given fields.
+#[derive(Clone, Debug)]
+pub struct LogFilter {
+    pub from_block: BlockId,
+    pub to_block: BlockId,
+    pub address: Option<Address>,
+    pub asset_type: Option<H160>,
+    pub tracker: Option<H256>,
+    pub handler_id: Option<u64>,
+    pub topic: Option<Bytes>,
+}
+
+impl LogFilter {
+    /// The topics that must be in the log bloom of a block that has a matching event.
+    pub fn bloom_topics(&self) -> Vec<Bytes> {
+        let mut topics = Vec::new();
+        if let Some(address) = &self.address {
+            topics.push(address.to_vec());
+        }
+        if let Some(asset_type) = &self.asset_type {
+            topics.push(asset_type.to_vec());
+        }
+        if let Some(tracker) = &self.tracker {
+            topics.push(tracker.to_vec());
+        }
+        if let Some(handler_id) = &self.handler_id {
+            topics.push(handler_id.rlp_bytes().into_vec());
+        }
+        if let Some(topic) = &self.topic {
+            topics.push(topic.clone());
+        }
+        topics
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        let (address, asset_type, tracker, handler_id, topic) = match event {
+            Event::BalanceIncreased {
+                address,
+                ..
+            }
+            | Event::BalanceDecreased {
+                address,
+                ..
+            } => (Some(address), None, None, None, None),
+            Event::AssetCreated {
+                tracker,
+                asset_type,
+                ..
+            }
+            | Event::AssetSpent {
+                tracker,
+                asset_type,
+                ..
+            } => (None, Some(asset_type), Some(tracker), None, None),
+            Event::Custom {
+                handler_id,
+                topic,
+                ..
+            } => (None, None, None, Some(handler_id), Some(topic)),
+        };
+        is_matched(&self.address, address)
+            && is_matched(&self.asset_type, asset_type)
+            && is_matched(&self.tracker, tracker)
+            && is_matched(&self.handler_id, handler_id)
+            && is_matched(&self.topic, topic)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogFilterError {
+    /// The filter covers more blocks than `MAX_LOG_FILTER_RANGE`.
+    TooLargeRange {
+        from: BlockNumber,
+        to: BlockNumber,
+    },
+}
+
+impl fmt::Display for LogFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogFilterError::TooLargeRange {
+                from,
+                to,
+            } => write!(
+                f,
+                "Cannot search the blocks from {} to {}. The maximum is {} blocks",
+                from, to, MAX_LOG_FILTER_RANGE
+            ),
+        }
+    }
+}
+
+fn is_matched<T: PartialEq>(expected: &Option<T>, actual: Option<&T>) -> bool {
+    match expected {
+        None => true,
+        Some(expected) => actual == Some(expected),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckey::NetworkId;
+    use rlp::rlp_encode_and_decode_test;
+
+    use super::*;
+
+    fn filter() -> LogFilter {
+        LogFilter {
+            from_block: BlockId::Earliest,
+            to_block: BlockId::Latest,
+            address: None,
+            asset_type: None,
+            tracker: None,
+            handler_id: None,
+            topic: None,
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let event = Event::BalanceIncreased {
+            address: Address::random(),
+            quantity: 1,
+        };
+        assert!(filter().matches(&event));
+        assert!(filter().bloom_topics().is_empty());
+    }
+
+    #[test]
+    fn filter_by_address() {
+        let address = Address::random();
+        let filter = LogFilter {
+            address: Some(address),
+            ..filter()
+        };
+        assert!(filter.matches(&Event::BalanceDecreased {
+            address,
+            quantity: 1,
+        }));
+        assert!(!filter.matches(&Event::BalanceDecreased {
+            address: Address::random(),
+            quantity: 1,
+        }));
+        assert!(!filter.matches(&Event::Custom {
+            handler_id: 2,
+            topic: vec![],
+            data: vec![],
+        }));
+    }
+
+    #[test]
+    fn filter_by_handler_id_and_topic() {
+        let filter = LogFilter {
+            handler_id: Some(2),
+            topic: Some(b"TransferCCS".to_vec()),
+            ..filter()
+        };
+        assert!(filter.matches(&Event::Custom {
+            handler_id: 2,
+            topic: b"TransferCCS".to_vec(),
+            data: vec![],
+        }));
+        assert!(!filter.matches(&Event::Custom {
+            handler_id: 2,
+            topic: b"DelegateCCS".to_vec(),
+            data: vec![],
+        }));
+    }
+
+    #[test]
+    fn encode_and_decode_runtime_failure() {
+        rlp_encode_and_decode_test!(TransactionFailure::Runtime(RuntimeError::InsufficientBalance {
+            address: Address::random(),
+            balance: 10,
+            cost: 20,
+        }));
+    }
+
+    #[test]
+    fn encode_and_decode_syntax_failure() {
+        rlp_encode_and_decode_test!(TransactionFailure::Syntax(SyntaxError::InvalidNetworkId(NetworkId::default())));
+    }
+
+    #[test]
+    fn encode_and_decode_other_failure() {
+        rlp_encode_and_decode_test!(TransactionFailure::Other("Database error".to_string()));
+    }
 }
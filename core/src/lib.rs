@@ -83,20 +83,25 @@ mod tests;
 
 pub use crate::account_provider::{AccountProvider, Error as AccountProviderError};
 pub use crate::block::Block;
+pub use crate::blockchain::{DatabaseInspector, IntegrityError};
 pub use crate::client::Error::Database;
 pub use crate::client::{
     AssetClient, Balance, BlockChainClient, BlockInfo, ChainInfo, ChainNotify, Client, ClientConfig, DatabaseClient,
-    EngineClient, EngineInfo, ExecuteClient, ImportBlock, MiningBlockChainClient, RegularKey, RegularKeyOwner, Seq,
-    Shard, StateInfo, TestBlockChainClient, TextClient,
+    Divergence, DivergenceCause, EngineClient, EngineInfo, ExecuteClient, ImportBlock, MiningBlockChainClient,
+    RegularKey, RegularKeyOwner, Seq, Shard, StateDiffError, StateInfo, TestBlockChainClient, TextClient,
+    MAX_STATE_DIFF_CHANGES,
 };
 pub use crate::consensus::EngineType;
-pub use crate::db::{COL_STATE, NUM_COLUMNS};
-pub use crate::error::{BlockImportError, Error, ImportError};
+pub use crate::db::{COL_PEER, COL_STATE, NUM_COLUMNS};
+pub use crate::error::{BlockError, BlockImportError, Error, ImportError};
 pub use crate::header::{Header, Seal};
+pub use crate::invoice::{
+    LocalizedEvent, LocalizedReceipt, LogFilter, LogFilterError, TransactionFailure, MAX_LOG_FILTER_RANGE,
+};
 pub use crate::miner::{Miner, MinerOptions, MinerService, Stratum, StratumConfig, StratumError};
 pub use crate::scheme::Scheme;
 pub use crate::service::ClientService;
 pub use crate::transaction::{
     LocalizedTransaction, PendingSignedTransactions, SignedTransaction, UnverifiedTransaction,
 };
-pub use crate::types::{BlockId, TransactionId};
+pub use crate::types::{BlockId, BlockStatus, TransactionId};
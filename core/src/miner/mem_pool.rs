@@ -953,6 +953,11 @@ impl MemPool {
             .collect()
     }
 
+    /// Return the transactions whose hashes satisfy the predicate, both in the current and the future queue.
+    pub fn find_transactions(&self, predicate: &Fn(&H256) -> bool) -> Vec<SignedTransaction> {
+        self.by_hash.iter().filter(|(hash, _)| predicate(hash)).map(|(_, item)| item.tx.clone()).collect()
+    }
+
     /// Returns true if there is at least one local transaction pending
     pub fn has_local_pending_transactions(&self) -> bool {
         self.current.queue.iter().any(|tx| tx.origin.is_local())
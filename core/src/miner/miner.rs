@@ -1016,6 +1016,10 @@ impl MinerService for Miner {
         self.mem_pool.read().future_transactions()
     }
 
+    fn find_mem_pool_transactions(&self, predicate: &Fn(&H256) -> bool) -> Vec<SignedTransaction> {
+        self.mem_pool.read().find_transactions(predicate)
+    }
+
     fn start_sealing<C: MiningBlockChainClient>(&self, client: &C) {
         cdebug!(MINER, "Start sealing");
         self.sealing_enabled.store(true, Ordering::Relaxed);
@@ -146,6 +146,9 @@ pub trait MinerService: Send + Sync {
     /// Get a list of all future transactions.
     fn future_transactions(&self) -> Vec<SignedTransaction>;
 
+    /// Get the transactions in the mem pool whose hashes satisfy the predicate.
+    fn find_mem_pool_transactions(&self, predicate: &Fn(&H256) -> bool) -> Vec<SignedTransaction>;
+
     /// Start sealing.
     fn start_sealing<C: MiningBlockChainClient>(&self, client: &C);
 
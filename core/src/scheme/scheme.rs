@@ -163,17 +163,17 @@ impl Scheme {
 
     /// Convert engine scheme into a arc'd Engine of the right underlying type.
     /// TODO avoid this hard-coded nastiness - use dynamic-linked plugin framework instead.
-    fn engine(engine_scheme: cjson::scheme::Engine, params: CommonParams) -> Arc<CodeChainEngine> {
+    fn engine(engine_scheme: cjson::scheme::Engine, params: CommonParams) -> Result<Arc<CodeChainEngine>, SchemeError> {
         let machine = Self::machine(&engine_scheme, params);
 
-        match engine_scheme {
+        Ok(match engine_scheme {
             cjson::scheme::Engine::Null(null) => Arc::new(NullEngine::new(null.params.into(), machine)),
-            cjson::scheme::Engine::Solo(solo) => Arc::new(Solo::new(solo.params.into(), machine)),
+            cjson::scheme::Engine::Solo(solo) => Arc::new(Solo::new(solo.params.into(), machine)?),
             cjson::scheme::Engine::SimplePoA(simple_poa) => Arc::new(SimplePoA::new(simple_poa.params.into(), machine)),
-            cjson::scheme::Engine::Tendermint(tendermint) => Tendermint::new(tendermint.params.into(), machine),
+            cjson::scheme::Engine::Tendermint(tendermint) => Tendermint::new(tendermint.params.into(), machine)?,
             cjson::scheme::Engine::Cuckoo(cuckoo) => Arc::new(Cuckoo::new(cuckoo.params.into(), machine)),
             cjson::scheme::Engine::BlakePoW(blake_pow) => Arc::new(BlakePoW::new(blake_pow.params.into(), machine)),
-        }
+        })
     }
 
     fn initialize_state(&self, db: StateDB) -> Result<StateDB, Error> {
@@ -388,7 +388,7 @@ fn load_from(s: cjson::scheme::Scheme) -> Result<Scheme, Error> {
     let g = Genesis::from(s.genesis);
     let GenericSeal(seal_rlp) = g.seal.into();
     let params = CommonParams::from(s.params);
-    let engine = Scheme::engine(s.engine, params);
+    let engine = Scheme::engine(s.engine, params)?;
 
     let mut s = Scheme {
         name: s.name.clone(),
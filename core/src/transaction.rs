@@ -19,7 +19,7 @@ use std::ops::Deref;
 use ccrypto::blake256;
 use ckey::{self, public_to_address, recover, sign, Private, Public, Signature};
 use ctypes::errors::SyntaxError;
-use ctypes::transaction::Transaction;
+use ctypes::transaction::{Transaction, MAX_NUMBER_OF_MULTISIG_PUBLICS};
 use ctypes::BlockNumber;
 use primitives::H256;
 use rlp::{self, DecoderError, Encodable, RlpStream, UntrustedRlp};
@@ -27,6 +27,23 @@ use rlp::{self, DecoderError, Encodable, RlpStream, UntrustedRlp};
 use crate::error::Error;
 use crate::scheme::CommonParams;
 
+/// Returns the hash of an RLP encoded transaction.
+/// The co-signatures are excluded, so that anyone who relays the transaction can't change its hash.
+pub fn transaction_hash(bytes: &[u8]) -> H256 {
+    hash_sealed_transaction(&UntrustedRlp::new(bytes)).expect("The transaction is already verified; qed")
+}
+
+fn hash_sealed_transaction(d: &UntrustedRlp) -> Result<H256, DecoderError> {
+    if d.item_count()? != 6 {
+        return Ok(blake256(d.as_raw()))
+    }
+    let mut s = RlpStream::new_list(5);
+    for index in 0..5 {
+        s.append_raw(d.at(index)?.as_raw(), 1);
+    }
+    Ok(blake256(s.as_raw()))
+}
+
 /// Signed transaction information without verified signature.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct UnverifiedTransaction {
@@ -34,6 +51,8 @@ pub struct UnverifiedTransaction {
     unsigned: Transaction,
     /// Signature.
     sig: Signature,
+    /// Additional signatures for multisig accounts.
+    co_sigs: Vec<Signature>,
     /// Hash of the transaction
     hash: H256,
 }
@@ -55,13 +74,25 @@ impl From<UnverifiedTransaction> for Transaction {
 impl rlp::Decodable for UnverifiedTransaction {
     fn decode(d: &UntrustedRlp) -> Result<Self, DecoderError> {
         let item_count = d.item_count()?;
-        if item_count != 5 {
+        if item_count != 5 && item_count != 6 {
             return Err(DecoderError::RlpIncorrectListLen {
                 expected: 5,
                 got: item_count,
             })
         }
-        let hash = blake256(d.as_raw());
+        // Checked before decoding so that a transaction can't make the receiver recover too many signatures.
+        if item_count == 6 && d.at(5)?.item_count()? > MAX_NUMBER_OF_MULTISIG_PUBLICS {
+            return Err(DecoderError::Custom("Too many co-signatures"))
+        }
+        let co_sigs: Vec<Signature> = if item_count == 6 {
+            d.list_at(5)?
+        } else {
+            Vec::new()
+        };
+        if item_count == 6 && co_sigs.is_empty() {
+            return Err(DecoderError::Custom("Empty co-signatures must be omitted"))
+        }
+        let hash = hash_sealed_transaction(d)?;
         Ok(UnverifiedTransaction {
             unsigned: Transaction {
                 seq: d.val_at(0)?,
@@ -70,6 +101,7 @@ impl rlp::Decodable for UnverifiedTransaction {
                 action: d.val_at(3)?,
             },
             sig: d.val_at(4)?,
+            co_sigs,
             hash,
         })
     }
@@ -86,29 +118,44 @@ impl UnverifiedTransaction {
         UnverifiedTransaction {
             unsigned,
             sig,
+            co_sigs: Vec::new(),
             hash: 0.into(),
         }
         .compute_hash()
     }
 
+    /// Appends a co-signature for multisig accounts. The hash is recomputed.
+    pub fn add_co_signature(mut self, sig: Signature) -> Self {
+        self.co_sigs.push(sig);
+        self.compute_hash()
+    }
+
     /// Used to compute hash of created transactions
     fn compute_hash(mut self) -> UnverifiedTransaction {
-        let hash = blake256(&*self.rlp_bytes());
+        let hash = transaction_hash(&self.rlp_bytes());
         self.hash = hash;
         self
     }
 
     /// Append object with a signature into RLP stream
     fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
-        s.begin_list(5);
+        // The co-signatures are appended only when they exist to keep the encoding of single signed transactions.
+        if self.co_sigs.is_empty() {
+            s.begin_list(5);
+        } else {
+            s.begin_list(6);
+        }
         s.append(&self.seq);
         s.append(&self.fee);
         s.append(&self.network_id);
         s.append(&self.action);
         s.append(&self.sig);
+        if !self.co_sigs.is_empty() {
+            s.append_list(&self.co_sigs);
+        }
     }
 
-    /// Get the hash of this header (blake256 of the RLP).
+    /// Get the hash of this transaction (blake256 of the RLP without the co-signatures).
     pub fn hash(&self) -> H256 {
         self.hash
     }
@@ -118,14 +165,25 @@ impl UnverifiedTransaction {
         self.sig
     }
 
+    /// Returns the co-signatures.
+    pub fn co_signatures(&self) -> &[Signature] {
+        &self.co_sigs
+    }
+
     /// Recovers the public key of the signature.
     pub fn recover_public(&self) -> Result<Public, ckey::Error> {
         Ok(recover(&self.signature(), &self.unsigned.hash())?)
     }
 
+    /// Recovers the public keys of the co-signatures.
+    pub fn recover_co_signer_publics(&self) -> Result<Vec<Public>, ckey::Error> {
+        let message = self.unsigned.hash();
+        self.co_sigs.iter().map(|sig| recover(sig, &message)).collect()
+    }
+
     /// Checks whether the signature has a low 's' value.
     pub fn check_low_s(&self) -> Result<(), ckey::Error> {
-        if !self.signature().is_low_s() {
+        if !self.signature().is_low_s() || self.co_sigs.iter().any(|sig| !sig.is_low_s()) {
             Err(ckey::Error::InvalidSignature)
         } else {
             Ok(())
@@ -137,6 +195,9 @@ impl UnverifiedTransaction {
         if self.network_id != params.network_id {
             return Err(SyntaxError::InvalidNetworkId(self.network_id))
         }
+        if self.co_sigs.len() > MAX_NUMBER_OF_MULTISIG_PUBLICS {
+            return Err(SyntaxError::TooManyCoSignatures(self.co_sigs.len()))
+        }
         let byte_size = rlp::encode(self).to_vec().len();
         if byte_size >= params.max_body_size {
             return Err(SyntaxError::TransactionIsTooBig)
@@ -155,6 +216,7 @@ impl UnverifiedTransaction {
 pub struct SignedTransaction {
     tx: UnverifiedTransaction,
     signer_public: Public,
+    co_signer_publics: Vec<Public>,
 }
 
 pub struct PendingSignedTransactions {
@@ -172,13 +234,17 @@ impl rlp::Encodable for SignedTransaction {
 impl rlp::Decodable for SignedTransaction {
     fn decode(d: &UntrustedRlp) -> Result<Self, DecoderError> {
         let unverified_transaction: UnverifiedTransaction = UnverifiedTransaction::decode(d)?;
-        match unverified_transaction.recover_public() {
-            Ok(key) => Ok(SignedTransaction {
-                tx: unverified_transaction,
-                signer_public: key,
-            }),
-            Err(_) => Err(DecoderError::Custom("signer public key recover failed")),
-        }
+        let signer_public = unverified_transaction
+            .recover_public()
+            .map_err(|_| DecoderError::Custom("signer public key recover failed"))?;
+        let co_signer_publics = unverified_transaction
+            .recover_co_signer_publics()
+            .map_err(|_| DecoderError::Custom("co-signer public key recover failed"))?;
+        Ok(SignedTransaction {
+            tx: unverified_transaction,
+            signer_public,
+            co_signer_publics,
+        })
     }
 }
 
@@ -199,11 +265,13 @@ impl SignedTransaction {
     /// Try to verify transaction and recover public.
     pub fn try_new(tx: UnverifiedTransaction) -> Result<Self, Error> {
         let signer_public = tx.recover_public()?;
+        let co_signer_publics = tx.recover_co_signer_publics()?;
         let signer = public_to_address(&signer_public);
         tx.action.verify_with_signer_address(&signer)?;
         Ok(SignedTransaction {
             tx,
             signer_public,
+            co_signer_publics,
         })
     }
 
@@ -218,6 +286,11 @@ impl SignedTransaction {
         self.signer_public
     }
 
+    /// Returns public keys of the co-signers.
+    pub fn co_signer_publics(&self) -> &[Public] {
+        &self.co_signer_publics
+    }
+
     /// Deconstructs this transaction back into `UnverifiedTransaction`
     pub fn deconstruct(self) -> (UnverifiedTransaction, Public) {
         (self.tx, self.signer_public)
@@ -269,7 +342,7 @@ impl From<LocalizedTransaction> for Transaction {
 
 #[cfg(test)]
 mod tests {
-    use ckey::{Address, Public, Signature};
+    use ckey::{Address, Generator, Public, Random, Signature};
     use ctypes::transaction::Action;
     use primitives::H256;
     use rlp::rlp_encode_and_decode_test;
@@ -288,6 +361,7 @@ mod tests {
                 network_id: "tc".into(),
             },
             sig: Signature::default(),
+            co_sigs: Vec::new(),
             hash: H256::default(),
         }
         .compute_hash());
@@ -306,6 +380,7 @@ mod tests {
                 },
             },
             sig: Signature::default(),
+            co_sigs: Vec::new(),
             hash: H256::default(),
         }
         .compute_hash());
@@ -323,6 +398,7 @@ mod tests {
                 },
             },
             sig: Signature::default(),
+            co_sigs: Vec::new(),
             hash: H256::default(),
         }
         .compute_hash());
@@ -340,8 +416,98 @@ mod tests {
                 },
             },
             sig: Signature::default(),
+            co_sigs: Vec::new(),
             hash: H256::default(),
         }
         .compute_hash());
     }
+
+    #[test]
+    fn encode_and_decode_co_signed_transaction() {
+        rlp_encode_and_decode_test!(UnverifiedTransaction {
+            unsigned: Transaction {
+                seq: 30,
+                fee: 40,
+                network_id: "tc".into(),
+                action: Action::Pay {
+                    receiver: Address::random(),
+                    quantity: 300,
+                },
+            },
+            sig: Signature::default(),
+            co_sigs: vec![Signature::random(), Signature::random()],
+            hash: H256::default(),
+        }
+        .compute_hash());
+    }
+
+    #[test]
+    fn recover_co_signers() {
+        let signer = Random.generate().unwrap();
+        let co_signer = Random.generate().unwrap();
+        let tx = Transaction {
+            seq: 30,
+            fee: 40,
+            network_id: "tc".into(),
+            action: Action::Pay {
+                receiver: Address::random(),
+                quantity: 300,
+            },
+        };
+        let sig = sign(signer.private(), &tx.hash()).unwrap();
+        let co_sig = sign(co_signer.private(), &tx.hash()).unwrap();
+        let unverified = UnverifiedTransaction::new(tx, sig).add_co_signature(co_sig);
+        let signed = SignedTransaction::try_new(unverified.clone()).unwrap();
+        assert_eq!(*signer.public(), signed.signer_public());
+        assert_eq!(&[*co_signer.public()], signed.co_signer_publics());
+        assert_eq!(unverified, rlp::decode(&unverified.rlp_bytes()));
+    }
+
+    #[test]
+    fn decode_rejects_too_many_co_signatures() {
+        let tx = UnverifiedTransaction {
+            unsigned: Transaction {
+                seq: 30,
+                fee: 40,
+                network_id: "tc".into(),
+                action: Action::Pay {
+                    receiver: Address::random(),
+                    quantity: 300,
+                },
+            },
+            sig: Signature::default(),
+            co_sigs: vec![Signature::random(); MAX_NUMBER_OF_MULTISIG_PUBLICS],
+            hash: H256::default(),
+        }
+        .compute_hash();
+        assert_eq!(tx, rlp::decode(&tx.rlp_bytes()));
+
+        let tx = tx.add_co_signature(Signature::random());
+        assert_eq!(
+            Err(DecoderError::Custom("Too many co-signatures")),
+            UntrustedRlp::new(&tx.rlp_bytes()).as_val::<UnverifiedTransaction>()
+        );
+    }
+
+    #[test]
+    fn co_signatures_do_not_change_hash() {
+        let signer = Random.generate().unwrap();
+        let tx = Transaction {
+            seq: 30,
+            fee: 40,
+            network_id: "tc".into(),
+            action: Action::Pay {
+                receiver: Address::random(),
+                quantity: 300,
+            },
+        };
+        let sig = sign(signer.private(), &tx.hash()).unwrap();
+        let unverified = UnverifiedTransaction::new(tx, sig);
+        let co_signed = unverified.clone().add_co_signature(Signature::random());
+        assert_eq!(unverified.hash(), co_signed.hash());
+
+        let decoded: UnverifiedTransaction = rlp::decode(&co_signed.rlp_bytes());
+        assert_eq!(unverified.hash(), decoded.hash());
+        assert_eq!(unverified.hash(), transaction_hash(&co_signed.rlp_bytes()));
+    }
 }
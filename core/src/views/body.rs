@@ -14,13 +14,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use ccrypto::blake256;
 use ctypes::BlockNumber;
 use primitives::H256;
 use rlp::Rlp;
 
 use super::TransactionView;
-use crate::transaction::{LocalizedTransaction, UnverifiedTransaction};
+use crate::transaction::{transaction_hash, LocalizedTransaction, UnverifiedTransaction};
 
 /// View onto block rlp.
 pub struct BodyView<'a> {
@@ -79,7 +78,7 @@ impl<'a> BodyView<'a> {
 
     /// Return transaction hashes.
     pub fn transaction_hashes(&self) -> Vec<H256> {
-        self.rlp.at(0).iter().map(|rlp| blake256(rlp.as_raw())).collect()
+        self.rlp.at(0).iter().map(|rlp| transaction_hash(rlp.as_raw())).collect()
     }
 
     /// Returns transaction at given index without deserializing unnecessary data.
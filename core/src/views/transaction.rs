@@ -14,10 +14,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use ccrypto::blake256;
 use primitives::{Bytes, H256, U256};
 use rlp::Rlp;
 
+use crate::transaction::transaction_hash;
+
 /// View onto transaction rlp.
 pub struct TransactionView<'a> {
     rlp: Rlp<'a>,
@@ -45,7 +46,7 @@ impl<'a> TransactionView<'a> {
 
     /// Returns transaction hash.
     pub fn hash(&self) -> H256 {
-        blake256(self.rlp.as_raw())
+        transaction_hash(self.rlp.as_raw())
     }
 
     /// Get the seq field of the transaction.
@@ -14,11 +14,13 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use primitives::{H160, H256};
+use primitives::{H160, H256, H512};
 use rcrypto::digest::Digest;
+use rcrypto::hmac::Hmac;
+use rcrypto::mac::Mac;
 use rcrypto::ripemd160::Ripemd160;
 use rcrypto::sha1::Sha1;
-use rcrypto::sha2::Sha256;
+use rcrypto::sha2::{Sha256, Sha512};
 use rcrypto::sha3::Sha3;
 
 /// RIPEMD160
@@ -54,6 +56,17 @@ pub fn sha256<T: AsRef<[u8]>>(s: T) -> H256 {
     result
 }
 
+/// HMAC-SHA-512
+#[inline]
+pub fn hmac_sha512<T: AsRef<[u8]>>(key: &[u8], s: T) -> H512 {
+    let input = s.as_ref();
+    let mut result = H512::default();
+    let mut hmac = Hmac::new(Sha512::new(), key);
+    hmac.input(input);
+    hmac.raw_result(&mut *result);
+    result
+}
+
 /// KECCAK256
 #[inline]
 pub fn keccak256<T: AsRef<[u8]>>(s: T) -> H256 {
@@ -67,7 +80,7 @@ pub fn keccak256<T: AsRef<[u8]>>(s: T) -> H256 {
 
 #[cfg(test)]
 mod tests {
-    use super::{keccak256, ripemd160, sha1, sha256};
+    use super::{hmac_sha512, keccak256, ripemd160, sha1, sha256};
 
     #[test]
     fn _ripemd160() {
@@ -90,6 +103,14 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn _hmac_sha512() {
+        // The first test case of RFC 4231
+        let expected = "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854".into();
+        let result = hmac_sha512(&[0x0b; 20], b"Hi There");
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn _keccak256() {
         let expected = "1c8aff950685c2ed4bc3174f3472287b56d9517b9c948127319a09a7a36deac8".into();
@@ -39,7 +39,7 @@ pub const KEY_ITERATIONS: usize = 10240;
 pub const KEY_LENGTH_AES: usize = KEY_LENGTH / 2;
 
 pub use crate::blake::*;
-pub use crate::hash::{keccak256, ripemd160, sha1, sha256};
+pub use crate::hash::{hmac_sha512, keccak256, ripemd160, sha1, sha256};
 pub use crate::password::Password;
 
 // Do not move Password. It will make debugger print the password.
@@ -14,31 +14,56 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
 
-use cnetwork::{Api, IntoSocketAddr, NetworkExtension, NodeId, RoutingTable};
+use ckey::Public;
+use cnetwork::{Api, ExternalAddress, IntoSocketAddr, Misbehavior, NetworkExtension, NodeId, RoutingTable};
 use ctimer::TimerToken;
 use never_type::Never;
+use primitives::H256;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 use rlp::{Decodable, Encodable, UntrustedRlp};
 use time::Duration;
 
 use super::message::Message;
-use super::node_id::{address_to_hash, KademliaId};
+use super::node_id::{address_to_hash, public_to_node_id, KademliaId};
+use super::table::{NodeEntry, Table};
 use super::Config;
 
+/// The number of peers that are asked in parallel in a lookup
+const ALPHA: usize = 3;
+
+struct Peer {
+    version: u64,
+    /// Known after the Hello message from the peer
+    public: Option<Public>,
+    /// The targets of the FindNode requests that the peer hasn't answered yet
+    pending_targets: HashSet<H256>,
+}
+
 pub struct Extension {
     config: Config,
     routing_table: Arc<RoutingTable>,
+    external_address: Arc<ExternalAddress>,
     api: Box<Api>,
-    nodes: HashSet<NodeId>, // FIXME: Find the optimized data structure for it
+    peers: HashMap<NodeId, Peer>,
     use_kademlia: bool,
+    node_key: Public,
+    table: Table,
 }
 
 impl Extension {
-    pub fn new(routing_table: Arc<RoutingTable>, config: Config, api: Box<Api>, use_kademlia: bool) -> Self {
+    pub fn new(
+        routing_table: Arc<RoutingTable>,
+        external_address: Arc<ExternalAddress>,
+        config: Config,
+        api: Box<Api>,
+        use_kademlia: bool,
+        node_key: Public,
+    ) -> Self {
         if use_kademlia {
             cinfo!(DISCOVERY, "Discovery starts with kademlia option");
         } else {
@@ -46,13 +71,113 @@ impl Extension {
         }
         api.set_timer(REFRESH_TOKEN, Duration::milliseconds(i64::from(config.t_refresh)))
             .expect("Refresh must be registered");
+        let table = Table::new(public_to_node_id(&node_key), config.bucket_size as usize);
+        cinfo!(DISCOVERY, "The node ID is {}", table.local_id());
         Self {
             config,
             routing_table,
+            external_address,
             api,
-            nodes: Default::default(),
+            peers: Default::default(),
             use_kademlia,
+            node_key,
+            table,
+        }
+    }
+
+    fn t_refresh(&self) -> ::std::time::Duration {
+        ::std::time::Duration::from_millis(u64::from(self.config.t_refresh))
+    }
+
+    fn send(&self, node: &NodeId, message: &Message) {
+        self.api.send(node, Arc::new(message.rlp_bytes().into_vec()));
+    }
+
+    fn on_legacy_request(&self, node: &NodeId, len: u8) {
+        let addresses = if self.use_kademlia {
+            let datum = address_to_hash(&node.into_addr());
+            let mut addresses = self
+                .routing_table
+                .reachable_addresses(&node.into_addr())
+                .into_iter()
+                .map(|address| KademliaId::new(address, &datum))
+                .collect::<Vec<_>>();
+
+            addresses.sort_unstable();
+
+            addresses
+                .into_iter()
+                .map(From::from)
+                .take(::std::cmp::min(self.config.bucket_size, len) as usize)
+                .collect()
+        } else {
+            let mut addresses = self.routing_table.reachable_addresses(&node.into_addr());
+            addresses.shuffle(&mut thread_rng());
+            addresses.into_iter().take(::std::cmp::min(self.config.bucket_size, len) as usize).collect()
+        };
+        self.send(node, &Message::Response(addresses));
+    }
+
+    fn on_find_node(&self, node: &NodeId, target: H256, len: u8) {
+        let len = ::std::cmp::min(self.config.bucket_size, len) as usize;
+        let from = node.into_addr();
+        let nodes = if self.use_kademlia {
+            // One more, because the requester can be one of them.
+            let mut nodes = self.table.closest(&target, len + 1);
+            nodes.retain(|entry| from.is_reachable(&entry.address));
+            nodes.truncate(len);
+            nodes
+        } else {
+            let mut nodes = self.table.closest(&target, self.table.len());
+            nodes.retain(|entry| from.is_reachable(&entry.address));
+            nodes.shuffle(&mut thread_rng());
+            nodes.truncate(len);
+            nodes
+        };
+        self.send(node, &Message::Neighbours {
+            target,
+            nodes,
+        });
+    }
+
+    fn on_neighbours(&mut self, node: &NodeId, target: H256, nodes: Vec<NodeEntry>) {
+        let is_requested = match self.peers.get_mut(node) {
+            Some(peer) => peer.pending_targets.remove(&target),
+            None => false,
+        };
+        if !is_requested {
+            cwarn!(DISCOVERY, "{} sent the neighbours of {} that is not requested", node, target);
+            self.api.report(node, Misbehavior::ProtocolViolation);
+            return
         }
+        let local_id = *self.table.local_id();
+        // The nodes are added to the table after they introduce themselves through the connection.
+        self.routing_table.touch_addresses(
+            nodes.into_iter().filter(|entry| entry.id() != local_id).map(|entry| entry.address),
+        );
+    }
+
+    /// Asks the connected peers that are the closest to the target.
+    fn find_node(&mut self, target: &H256) {
+        let mut peers: Vec<_> = self
+            .peers
+            .iter()
+            .filter_map(|(node, peer)| peer.public.map(|public| (public_to_node_id(&public) ^ *target, *node)))
+            .collect();
+        peers.sort_unstable_by_key(|(distance, _)| *distance);
+        for (_, node) in peers.into_iter().take(ALPHA) {
+            self.request_find_node(&node, *target);
+        }
+    }
+
+    fn request_find_node(&mut self, node: &NodeId, target: H256) {
+        if let Some(peer) = self.peers.get_mut(node) {
+            peer.pending_targets.insert(target);
+        }
+        self.send(node, &Message::FindNode {
+            target,
+            len: self.config.bucket_size,
+        });
     }
 }
 
@@ -68,17 +193,31 @@ impl NetworkExtension<Never> for Extension {
     }
 
     fn versions() -> &'static [u64] {
-        const VERSIONS: &[u64] = &[0];
+        const VERSIONS: &[u64] = &[0, 1];
         &VERSIONS
     }
 
-    fn on_node_added(&mut self, node: &NodeId, _version: u64) {
-        self.nodes.insert(*node);
-        self.api.send(&node, Arc::new(Message::Request(self.config.bucket_size).rlp_bytes().into_vec()));
+    fn on_node_added(&mut self, node: &NodeId, version: u64) {
+        self.peers.insert(*node, Peer {
+            version,
+            public: None,
+            pending_targets: Default::default(),
+        });
+        if version == 0 {
+            self.send(node, &Message::Request(self.config.bucket_size));
+            return
+        }
+        self.send(node, &Message::Hello {
+            public: self.node_key,
+            external_address: self.external_address.get(),
+        });
+        // Looking up the local node fills the buckets close to it.
+        let local_id = *self.table.local_id();
+        self.request_find_node(node, local_id);
     }
 
     fn on_node_removed(&mut self, node: &NodeId) {
-        self.nodes.remove(node);
+        self.peers.remove(node);
     }
 
     fn on_message(&mut self, node: &NodeId, message: &[u8]) {
@@ -89,47 +228,179 @@ impl NetworkExtension<Never> for Extension {
                 return
             }
         };
-        match message {
-            Message::Request(len) => {
-                let addresses = if self.use_kademlia {
-                    let datum = address_to_hash(&node.into_addr());
-                    let mut addresses = self
-                        .routing_table
-                        .reachable_addresses(&node.into_addr())
-                        .into_iter()
-                        .map(|address| KademliaId::new(address, &datum))
-                        .collect::<Vec<_>>();
-
-                    addresses.sort_unstable();
-
-                    addresses
-                        .into_iter()
-                        .map(From::from)
-                        .take(::std::cmp::min(self.config.bucket_size, len) as usize)
-                        .collect()
-                } else {
-                    let mut addresses = self.routing_table.reachable_addresses(&node.into_addr());
-                    addresses.shuffle(&mut thread_rng());
-                    addresses.into_iter().take(::std::cmp::min(self.config.bucket_size, len) as usize).collect()
-                };
-                let response = Arc::new(Message::Response(addresses).rlp_bytes().into_vec());
-                self.api.send(&node, response);
+        let version = match self.peers.get(node) {
+            Some(peer) => peer.version,
+            None => {
+                cdebug!(DISCOVERY, "Message from {} but it's already removed", node);
+                return
             }
-            Message::Response(addresses) => {
+        };
+        match (version, message) {
+            (0, Message::Request(len)) => self.on_legacy_request(node, len),
+            (0, Message::Response(addresses)) => {
                 self.routing_table.touch_addresses(addresses);
             }
+            (1, Message::Hello {
+                public,
+                external_address,
+            }) => {
+                // The key must be the one that the peer proved in the identity message of the session.
+                if self.routing_table.remote_node_key(&node.into_addr()) != Some(public) {
+                    cwarn!(DISCOVERY, "{} sent a hello message with a key that is not its node key", node);
+                    self.api.report(node, Misbehavior::ProtocolViolation);
+                    return
+                }
+                // The advertised address is used only if it has the same IP with the connection,
+                // so that a node cannot make the others flood a third party.
+                let address = match external_address {
+                    Some(address) if address.ip() == node.into_addr().ip() => address,
+                    _ => node.into_addr(),
+                };
+                let entry = NodeEntry {
+                    public,
+                    address,
+                };
+                let id = entry.id();
+                if id == *self.table.local_id() {
+                    cwarn!(DISCOVERY, "{} has the same node ID", node);
+                    return
+                }
+                if let Some(peer) = self.peers.get_mut(node) {
+                    if let Some(previous) = peer.public.replace(public) {
+                        self.table.remove(&public_to_node_id(&previous));
+                    }
+                }
+                let stale_after = self.t_refresh() * 3;
+                if !self.table.insert(entry, Instant::now(), stale_after) {
+                    cdebug!(DISCOVERY, "The bucket for {}({}) is full", node, id);
+                }
+            }
+            (1, Message::FindNode {
+                target,
+                len,
+            }) => self.on_find_node(node, target, len),
+            (1, Message::Neighbours {
+                target,
+                nodes,
+            }) => self.on_neighbours(node, target, nodes),
+            (version, message) => {
+                cwarn!(DISCOVERY, "Unexpected message from {}(version {}): {:?}", node, version, message);
+            }
         }
     }
 
     fn on_timeout(&mut self, timer: TimerToken) {
         match timer {
             REFRESH_TOKEN => {
-                let request = Arc::new(Message::Request(self.config.bucket_size).rlp_bytes().into_vec());
-                for node in &self.nodes {
-                    self.api.send(node, Arc::clone(&request));
+                let request = Message::Request(self.config.bucket_size);
+                for (node, _) in self.peers.iter().filter(|(_, peer)| peer.version == 0) {
+                    self.send(node, &request);
+                }
+
+                // Keeps the connected peers alive in the table.
+                let stale_after = self.t_refresh() * 3;
+                let now = Instant::now();
+                for (node, peer) in &self.peers {
+                    if let Some(public) = peer.public {
+                        self.table.insert(
+                            NodeEntry {
+                                public,
+                                address: node.into_addr(),
+                            },
+                            now,
+                            stale_after,
+                        );
+                    }
+                }
+
+                let targets = self.table.targets_to_refresh(now, self.t_refresh());
+                ctrace!(DISCOVERY, "Refresh {} buckets of {} nodes", targets.len(), self.table.len());
+                for target in targets {
+                    self.find_node(&target);
                 }
             }
             _ => unreachable!(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::Mutex;
+
+    use ckey::{Generator, Random};
+    use cnetwork::{NetworkExtensionResult, SocketAddr};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct TestApi {
+        reports: Arc<Mutex<Vec<(NodeId, Misbehavior)>>>,
+    }
+
+    impl Api for TestApi {
+        fn send(&self, _node: &NodeId, _message: Arc<Vec<u8>>) {}
+
+        fn report(&self, node: &NodeId, misbehavior: Misbehavior) {
+            self.reports.lock().unwrap().push((*node, misbehavior));
+        }
+
+        fn set_timer(&self, _timer: TimerToken, _d: Duration) -> NetworkExtensionResult<()> {
+            Ok(())
+        }
+
+        fn set_timer_once(&self, _timer: TimerToken, _d: Duration) -> NetworkExtensionResult<()> {
+            Ok(())
+        }
+
+        fn clear_timer(&self, _timer: TimerToken) -> NetworkExtensionResult<()> {
+            Ok(())
+        }
+    }
+
+    fn neighbours(target: H256, address: SocketAddr) -> Vec<u8> {
+        Message::Neighbours {
+            target,
+            nodes: vec![NodeEntry {
+                public: *Random.generate().unwrap().public(),
+                address,
+            }],
+        }
+        .rlp_bytes()
+        .into_vec()
+    }
+
+    #[test]
+    fn neighbours_must_be_requested() {
+        let api = TestApi::default();
+        let reports = Arc::clone(&api.reports);
+        let routing_table = RoutingTable::new();
+        let node_key = *Random.generate().unwrap().public();
+        let mut extension = Extension::new(
+            Arc::clone(&routing_table),
+            ExternalAddress::new(3485),
+            Config::new(None, None),
+            Box::new(api),
+            true,
+            node_key,
+        );
+        let peer = NodeId::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 3485);
+        let from = SocketAddr::v4(1, 2, 3, 5, 3485);
+        extension.on_node_added(&peer, 1);
+
+        extension.on_message(&peer, &neighbours(H256::random(), SocketAddr::v4(5, 6, 7, 8, 3485)));
+        assert_eq!(vec![(peer, Misbehavior::ProtocolViolation)], *reports.lock().unwrap());
+        assert!(routing_table.reachable_addresses(&from).is_empty());
+
+        let local_id = public_to_node_id(&node_key);
+        extension.on_message(&peer, &neighbours(local_id, SocketAddr::v4(5, 6, 7, 8, 3485)));
+        assert_eq!(1, reports.lock().unwrap().len());
+        assert_eq!(vec![SocketAddr::v4(5, 6, 7, 8, 3485)], routing_table.reachable_addresses(&from));
+
+        // The request is answered only once.
+        extension.on_message(&peer, &neighbours(local_id, SocketAddr::v4(5, 6, 7, 9, 3485)));
+        assert_eq!(2, reports.lock().unwrap().len());
+        assert_eq!(1, routing_table.reachable_addresses(&from).len());
+    }
+}
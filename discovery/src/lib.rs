@@ -23,6 +23,8 @@ extern crate parking_lot;
 extern crate primitives;
 extern crate rand;
 extern crate rlp;
+#[macro_use]
+extern crate rlp_derive;
 extern crate time;
 
 extern crate codechain_crypto as ccrypto;
@@ -36,6 +38,7 @@ mod config;
 mod extension;
 mod message;
 mod node_id;
+mod table;
 
 const K: u8 = 16;
 const T_REFRESH: u32 = 60_000;
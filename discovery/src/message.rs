@@ -14,13 +14,39 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use ckey::Public;
 use cnetwork::SocketAddr;
+use primitives::H256;
 use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
 
+use super::table::NodeEntry;
+
+const MESSAGE_ID_HELLO: u8 = 0x01;
+const MESSAGE_ID_FIND_NODE: u8 = 0x02;
+const MESSAGE_ID_NEIGHBOURS: u8 = 0x03;
+
 #[derive(Debug, PartialEq)]
 pub enum Message {
+    /// Version 0
     Request(u8),
+    /// Version 0
     Response(Vec<SocketAddr>),
+    /// Version 1. The public key from which the node ID of the sender is derived,
+    /// and the address that the sender learned through the port mapping or from its peers.
+    Hello {
+        public: Public,
+        external_address: Option<SocketAddr>,
+    },
+    /// Version 1
+    FindNode {
+        target: H256,
+        len: u8,
+    },
+    /// Version 1. The nodes that are the closest to the target, the closest first.
+    Neighbours {
+        target: H256,
+        nodes: Vec<NodeEntry>,
+    },
 }
 
 impl Encodable for Message {
@@ -32,6 +58,32 @@ impl Encodable for Message {
             Message::Response(addresses) => {
                 s.append_list(addresses);
             }
+            Message::Hello {
+                public,
+                external_address,
+            } => {
+                let item_count = if external_address.is_some() {
+                    3
+                } else {
+                    2
+                };
+                s.begin_list(item_count).append(&MESSAGE_ID_HELLO).append(public);
+                if let Some(external_address) = external_address {
+                    s.append(external_address);
+                }
+            }
+            Message::FindNode {
+                target,
+                len,
+            } => {
+                s.begin_list(3).append(&MESSAGE_ID_FIND_NODE).append(target).append(len);
+            }
+            Message::Neighbours {
+                target,
+                nodes,
+            } => {
+                s.begin_list(3).append(&MESSAGE_ID_NEIGHBOURS).append(target).append_list(nodes);
+            }
         }
     }
 }
@@ -39,9 +91,56 @@ impl Encodable for Message {
 impl Decodable for Message {
     fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
         if rlp.is_int() {
-            Ok(Message::Request(rlp.as_val()?))
-        } else {
-            Ok(Message::Response(rlp.as_list()?))
+            return Ok(Message::Request(rlp.as_val()?))
+        }
+        // The items of a version 0 response are lists, while a version 1 message starts with its ID.
+        let is_version_0 = rlp.is_empty() || rlp.at(0)?.is_list();
+        if is_version_0 {
+            return Ok(Message::Response(rlp.as_list()?))
+        }
+        let item_count = rlp.item_count()?;
+        match rlp.val_at(0)? {
+            MESSAGE_ID_HELLO => {
+                if item_count != 2 && item_count != 3 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 3,
+                    })
+                }
+                Ok(Message::Hello {
+                    public: rlp.val_at(1)?,
+                    external_address: if item_count == 3 {
+                        Some(rlp.val_at(2)?)
+                    } else {
+                        None
+                    },
+                })
+            }
+            MESSAGE_ID_FIND_NODE => {
+                if item_count != 3 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 3,
+                    })
+                }
+                Ok(Message::FindNode {
+                    target: rlp.val_at(1)?,
+                    len: rlp.val_at(2)?,
+                })
+            }
+            MESSAGE_ID_NEIGHBOURS => {
+                if item_count != 3 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 3,
+                    })
+                }
+                Ok(Message::Neighbours {
+                    target: rlp.val_at(1)?,
+                    nodes: rlp.list_at(2)?,
+                })
+            }
+            _ => Err(DecoderError::Custom("Unknown message id detected")),
         }
     }
 }
@@ -89,4 +188,45 @@ mod tests {
             SocketAddr::v4(127, 0, 0, 1, 3481),
         ]));
     }
+
+    #[test]
+    fn encode_and_decode_hello() {
+        rlp_encode_and_decode_test!(Message::Hello {
+            public: Public::random(),
+            external_address: None,
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_hello_with_external_address() {
+        rlp_encode_and_decode_test!(Message::Hello {
+            public: Public::random(),
+            external_address: Some(SocketAddr::v4(1, 2, 3, 4, 3485)),
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_find_node() {
+        rlp_encode_and_decode_test!(Message::FindNode {
+            target: H256::random(),
+            len: 16,
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_neighbours() {
+        rlp_encode_and_decode_test!(Message::Neighbours {
+            target: H256::random(),
+            nodes: vec![
+                NodeEntry {
+                    public: Public::random(),
+                    address: SocketAddr::v4(127, 0, 0, 1, 3480),
+                },
+                NodeEntry {
+                    public: Public::random(),
+                    address: SocketAddr::v4(127, 0, 0, 1, 3481),
+                },
+            ],
+        });
+    }
 }
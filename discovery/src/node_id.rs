@@ -18,8 +18,9 @@ use std::net::IpAddr;
 
 use ccrypto::Blake;
 
+use ckey::Public;
 use cnetwork::{IntoSocketAddr, NodeId, SocketAddr};
-use primitives::H128;
+use primitives::{H128, H256};
 
 #[derive(Eq, Ord, PartialEq, PartialOrd)]
 pub struct KademliaId {
@@ -67,25 +68,52 @@ pub fn address_to_hash(addr: &SocketAddr) -> H128 {
 
 fn log2_distance(addr: &SocketAddr, datum: &H128) -> usize {
     let hash = address_to_hash(addr);
+    bit_length(&*(hash ^ *datum))
+}
 
-    let distance = hash ^ *datum;
-    const B: usize = 16 * 8;
-    const BYTES_SIZE: usize = B / 8;
-    debug_assert_eq!(B % 8, 0);
-    let mut distance_as_bytes: [u8; BYTES_SIZE] = [0; BYTES_SIZE];
-    distance.copy_to(&mut distance_as_bytes);
+/// The identifier of a node in the Kademlia table, which is derived from its public key.
+pub fn public_to_node_id(public: &Public) -> H256 {
+    H256::blake(public)
+}
 
+/// The bit length of `a ^ b`, which is zero only if they are the same.
+pub fn log2_distance_between(a: &H256, b: &H256) -> usize {
+    bit_length(&*(*a ^ *b))
+}
+
+fn bit_length(bytes: &[u8]) -> usize {
     let mut same_prefix_length: usize = 0;
-    const MASKS: [u8; 8] =
-        [0b1000_0000, 0b0100_0000, 0b0010_0000, 0b0001_0000, 0b0000_1000, 0b0000_0100, 0b0000_0010, 0b0000_0001];
-    'outer: for byte in distance_as_bytes.iter() {
-        for mask in MASKS.iter() {
-            if byte & mask != 0 {
-                break 'outer
-            }
-            same_prefix_length += 1
+    for byte in bytes {
+        if *byte != 0 {
+            same_prefix_length += byte.leading_zeros() as usize;
+            break
         }
+        same_prefix_length += 8;
     }
+    bytes.len() * 8 - same_prefix_length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    B - same_prefix_length
+    #[test]
+    fn distance_to_itself_is_zero() {
+        let id = H256::random();
+        assert_eq!(0, log2_distance_between(&id, &id));
+    }
+
+    #[test]
+    fn distance_is_the_bit_length_of_xor() {
+        let a = H256::zero();
+        let mut b = H256::zero();
+        b[31] = 1;
+        assert_eq!(1, log2_distance_between(&a, &b));
+        b[31] = 0b1000_0000;
+        assert_eq!(8, log2_distance_between(&a, &b));
+        b[0] = 0b0100_0000;
+        assert_eq!(255, log2_distance_between(&a, &b));
+        b[0] = 0b1000_0000;
+        assert_eq!(256, log2_distance_between(&b, &a));
+    }
 }
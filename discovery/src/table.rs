@@ -0,0 +1,228 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ckey::Public;
+use cnetwork::SocketAddr;
+use primitives::H256;
+
+use super::node_id::{log2_distance_between, public_to_node_id};
+
+const NUM_BUCKETS: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct NodeEntry {
+    pub public: Public,
+    pub address: SocketAddr,
+}
+
+impl NodeEntry {
+    pub fn id(&self) -> H256 {
+        public_to_node_id(&self.public)
+    }
+}
+
+struct Bucket {
+    /// The least recently seen node comes first.
+    entries: VecDeque<(NodeEntry, Instant)>,
+    last_lookup: Option<Instant>,
+}
+
+/// The Kademlia table. The `i`th bucket keeps the nodes whose distance from the local node is `i + 1`.
+pub struct Table {
+    local_id: H256,
+    bucket_size: usize,
+    buckets: Vec<Bucket>,
+}
+
+impl Table {
+    pub fn new(local_id: H256, bucket_size: usize) -> Self {
+        Self {
+            local_id,
+            bucket_size,
+            buckets: (0..NUM_BUCKETS)
+                .map(|_| Bucket {
+                    entries: VecDeque::new(),
+                    last_lookup: None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn local_id(&self) -> &H256 {
+        &self.local_id
+    }
+
+    fn bucket_index(&self, id: &H256) -> Option<usize> {
+        match log2_distance_between(&self.local_id, id) {
+            0 => None,
+            distance => Some(distance - 1),
+        }
+    }
+
+    /// Moves the node to the tail of its bucket.
+    /// If the bucket is full, the node replaces the least recently seen one when it hasn't been seen for `stale_after`.
+    /// Returns false if the node is not inserted.
+    pub fn insert(&mut self, entry: NodeEntry, now: Instant, stale_after: Duration) -> bool {
+        let index = match self.bucket_index(&entry.id()) {
+            Some(index) => index,
+            None => return false,
+        };
+        let bucket_size = self.bucket_size;
+        let bucket = &mut self.buckets[index];
+        if let Some(position) = bucket.entries.iter().position(|(e, _)| e.public == entry.public) {
+            bucket.entries.remove(position);
+        } else if bucket.entries.len() >= bucket_size {
+            let is_head_stale = bucket.entries.front().map_or(true, |(_, seen)| *seen + stale_after <= now);
+            if !is_head_stale {
+                return false
+            }
+            bucket.entries.pop_front();
+        }
+        bucket.entries.push_back((entry, now));
+        true
+    }
+
+    pub fn remove(&mut self, id: &H256) {
+        if let Some(index) = self.bucket_index(id) {
+            self.buckets[index].entries.retain(|(entry, _)| entry.id() != *id);
+        }
+    }
+
+    /// At most `count` nodes that are the closest to the target, the closest first.
+    pub fn closest(&self, target: &H256, count: usize) -> Vec<NodeEntry> {
+        let mut entries: Vec<_> =
+            self.buckets.iter().flat_map(|bucket| bucket.entries.iter().map(|(entry, _)| *entry)).collect();
+        entries.sort_unstable_by_key(|entry| entry.id() ^ *target);
+        entries.truncate(count);
+        entries
+    }
+
+    /// Random targets of the buckets that haven't been looked up for `t_refresh`.
+    /// Only the buckets up to the farthest non-empty one are refreshed, because the others are likely to be empty.
+    pub fn targets_to_refresh(&mut self, now: Instant, t_refresh: Duration) -> Vec<H256> {
+        let farthest = match self.buckets.iter().rposition(|bucket| !bucket.entries.is_empty()) {
+            Some(farthest) => farthest,
+            None => return Vec::new(),
+        };
+        let mut targets = Vec::new();
+        for index in 0..=farthest {
+            let bucket = &mut self.buckets[index];
+            if bucket.last_lookup.map_or(false, |last_lookup| now < last_lookup + t_refresh) {
+                continue
+            }
+            bucket.last_lookup = Some(now);
+            targets.push(random_id_in_bucket(&self.local_id, index));
+        }
+        targets
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.entries.len()).sum()
+    }
+}
+
+/// A random ID whose distance from `local_id` is `index + 1`.
+fn random_id_in_bucket(local_id: &H256, index: usize) -> H256 {
+    let mut distance = H256::random();
+    let bytes_len = distance.len();
+    let (byte, bit) = (bytes_len - 1 - index / 8, index % 8);
+    for b in distance[..byte].iter_mut() {
+        *b = 0;
+    }
+    distance[byte] &= (1u8 << bit) - 1;
+    distance[byte] |= 1u8 << bit;
+    *local_id ^ distance
+}
+
+#[cfg(test)]
+mod tests {
+    use ckey::{Generator, Random};
+
+    use super::*;
+
+    fn entry(port: u16) -> NodeEntry {
+        NodeEntry {
+            public: *Random.generate().unwrap().public(),
+            address: SocketAddr::v4(10, 0, 0, 1, port),
+        }
+    }
+
+    #[test]
+    fn random_id_is_in_the_bucket() {
+        let local_id = H256::random();
+        for index in 0..NUM_BUCKETS {
+            assert_eq!(index + 1, log2_distance_between(&local_id, &random_id_in_bucket(&local_id, index)));
+        }
+    }
+
+    #[test]
+    fn closest_first() {
+        let mut table = Table::new(H256::random(), 16);
+        let now = Instant::now();
+        let entries: Vec<_> = (0..10).map(entry).collect();
+        for entry in &entries {
+            table.insert(*entry, now, Duration::from_secs(60));
+        }
+        assert_eq!(10, table.len());
+
+        let target = entries[3].id();
+        let closest = table.closest(&target, 3);
+        assert_eq!(3, closest.len());
+        assert_eq!(entries[3], closest[0]);
+        assert!((closest[1].id() ^ target) <= (closest[2].id() ^ target));
+    }
+
+    #[test]
+    fn full_bucket_keeps_live_nodes() {
+        let local_id = H256::zero();
+        let mut table = Table::new(local_id, 2);
+        let now = Instant::now();
+        let stale_after = Duration::from_secs(60);
+        // Most of the random nodes fall in the farthest bucket.
+        let entries: Vec<_> =
+            (0..100).map(entry).filter(|e| log2_distance_between(&local_id, &e.id()) == 256).collect();
+        assert!(entries.len() >= 3);
+
+        assert!(table.insert(entries[0], now, stale_after));
+        assert!(table.insert(entries[1], now, stale_after));
+        assert!(!table.insert(entries[2], now, stale_after));
+        // The least recently seen node is replaced after it becomes stale.
+        assert!(table.insert(entries[2], now + stale_after, stale_after));
+        assert_eq!(2, table.len());
+        let remaining = table.closest(&local_id, 2);
+        assert!(!remaining.contains(&entries[0]));
+        assert!(remaining.contains(&entries[1]));
+        assert!(remaining.contains(&entries[2]));
+    }
+
+    #[test]
+    fn refresh_only_the_buckets_that_were_not_looked_up() {
+        let mut table = Table::new(H256::zero(), 16);
+        let now = Instant::now();
+        let t_refresh = Duration::from_secs(60);
+        assert!(table.targets_to_refresh(now, t_refresh).is_empty());
+
+        let node = entry(1);
+        assert!(table.insert(node, now, t_refresh));
+        let distance = log2_distance_between(table.local_id(), &node.id());
+        assert_eq!(distance, table.targets_to_refresh(now, t_refresh).len());
+        assert!(table.targets_to_refresh(now + t_refresh / 2, t_refresh).is_empty());
+        assert!(!table.targets_to_refresh(now + t_refresh, t_refresh).is_empty());
+    }
+}
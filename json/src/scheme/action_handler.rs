@@ -0,0 +1,53 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::bytes::Bytes;
+use crate::uint::Uint;
+
+/// An action handler loaded from a WebAssembly module.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmActionHandler {
+    /// The ID used by `Action::Custom`.
+    pub handler_id: Uint,
+    /// The WebAssembly module.
+    pub code: Bytes,
+    /// The gas that a single execution can use.
+    pub gas_limit: Uint,
+}
+
+#[cfg(test)]
+mod tests {
+    use primitives::U256;
+    use serde_json;
+
+    use super::WasmActionHandler;
+    use crate::uint::Uint;
+
+    #[test]
+    fn wasm_action_handler_deserialization() {
+        let s = r#"{
+            "handlerId": "0x3e8",
+            "code": "0x0061736d01000000",
+            "gasLimit": "0x0f4240"
+        }"#;
+
+        let deserialized: WasmActionHandler = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.handler_id, Uint(U256::from(1000)));
+        assert_eq!(&*deserialized.code, &[0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]);
+        assert_eq!(deserialized.gas_limit, Uint(U256::from(1_000_000)));
+    }
+}
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod account;
+mod action_handler;
 mod blake_pow;
 mod cuckoo;
 mod engine;
@@ -31,6 +32,7 @@ mod state;
 mod tendermint;
 
 pub use self::account::Account;
+pub use self::action_handler::WasmActionHandler;
 pub use self::blake_pow::{BlakePoW, BlakePoWParams};
 pub use self::cuckoo::{Cuckoo, CuckooParams};
 pub use self::engine::Engine;
@@ -16,6 +16,7 @@
 
 use std::collections::HashMap;
 
+use super::WasmActionHandler;
 use crate::uint::Uint;
 
 /// Solo params deserialization.
@@ -32,6 +33,7 @@ pub struct SoloParams {
 #[serde(rename_all = "camelCase")]
 pub struct SoloActionHandlersParams {
     pub hit: Option<HashMap<(), ()>>,
+    pub wasm_action_handlers: Option<Vec<WasmActionHandler>>,
 }
 
 /// Solo engine deserialization.
@@ -62,5 +64,6 @@ mod tests {
         let deserialized: Solo = serde_json::from_str(s).unwrap();
         assert_eq!(deserialized.params.block_reward, Some(Uint(U256::from(0x0d))));
         assert_eq!(deserialized.params.action_handlers.hit, Some(HashMap::new()));
+        assert_eq!(deserialized.params.action_handlers.wasm_action_handlers, None);
     }
 }
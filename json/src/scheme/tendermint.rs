@@ -18,6 +18,7 @@ use std::collections::HashMap;
 
 use ckey::{PlatformAddress, Public};
 
+use super::WasmActionHandler;
 use crate::uint::Uint;
 
 /// Tendermint params deserialization.
@@ -44,6 +45,8 @@ pub struct TendermintParams {
     pub block_reward: Option<Uint>,
     /// How much tokens are distributed at Genesis?
     pub genesis_stakes: Option<HashMap<PlatformAddress, u64>>,
+    /// Custom action handlers loaded from WebAssembly modules.
+    pub wasm_action_handlers: Option<Vec<WasmActionHandler>>,
 }
 
 /// Tendermint engine deserialization.
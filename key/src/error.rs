@@ -40,6 +40,7 @@ pub enum Error {
     Bech32InvalidData(u8),
     Bech32MixedCase,
     Bech32UnknownHRP,
+    InvalidDerivationPath(String),
     Custom(String),
 }
 
@@ -65,6 +66,7 @@ impl fmt::Display for Error {
             Error::Bech32InvalidData(_) => "Invalid data point".into(),
             Error::Bech32MixedCase => "Mixed-case strings not allowed".into(),
             Error::Bech32UnknownHRP => "Unknown human-readable part".into(),
+            Error::InvalidDerivationPath(ref path) => format!("{} is an invalid derivation path", path),
             Error::Custom(ref s) => s.clone(),
         };
 
@@ -0,0 +1,195 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! BIP32 hierarchical deterministic key derivation.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crypto::hmac_sha512;
+use primitives::H256;
+use secp256k1::key;
+
+use crate::{Error, Private, SECP256K1};
+
+/// Indices equal to or greater than this derive hardened children.
+pub const HARDENED_INDEX: u32 = 0x8000_0000;
+
+const MASTER_KEY_SALT: &[u8] = b"Bitcoin seed";
+
+/// A path from the master key to a child key, e.g. `m/44'/0'/0'/0/0`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    pub fn new(indices: Vec<u32>) -> Self {
+        DerivationPath(indices)
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// Returns the path of the `index`th child of this path.
+    pub fn child(&self, index: u32) -> Self {
+        let mut indices = self.0.clone();
+        indices.push(index);
+        DerivationPath(indices)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut components = s.split('/');
+        if components.next() != Some("m") {
+            return Err(Error::InvalidDerivationPath(s.to_string()))
+        }
+        let indices = components
+            .map(|component| {
+                let (number, offset) = if component.ends_with('\'') || component.ends_with('h') {
+                    (&component[..component.len() - 1], HARDENED_INDEX)
+                } else {
+                    (component, 0)
+                };
+                match number.parse::<u32>() {
+                    Ok(index) if index < HARDENED_INDEX => Ok(index + offset),
+                    _ => Err(Error::InvalidDerivationPath(s.to_string())),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(DerivationPath(indices))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if *index >= HARDENED_INDEX {
+                write!(f, "/{}'", index - HARDENED_INDEX)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A private key with its chain code, which can derive child keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtendedPrivate {
+    private: Private,
+    chain_code: H256,
+}
+
+impl ExtendedPrivate {
+    /// Creates the master key from a seed.
+    pub fn from_seed(seed: &[u8]) -> Result<Self, Error> {
+        let hash = hmac_sha512(MASTER_KEY_SALT, seed);
+        key::SecretKey::from_slice(&SECP256K1, &hash[0..32])?;
+        Ok(ExtendedPrivate {
+            private: Private::from_slice(&hash[0..32]),
+            chain_code: H256::from(&hash[32..64]),
+        })
+    }
+
+    pub fn private(&self) -> &Private {
+        &self.private
+    }
+
+    pub fn chain_code(&self) -> &H256 {
+        &self.chain_code
+    }
+
+    /// Derives the `index`th child key.
+    pub fn derive_child(&self, index: u32) -> Result<Self, Error> {
+        let context = &SECP256K1;
+        let mut secret = key::SecretKey::from_slice(context, &self.private[..])?;
+
+        let mut data = Vec::with_capacity(37);
+        if index >= HARDENED_INDEX {
+            data.push(0);
+            data.extend_from_slice(&self.private[..]);
+        } else {
+            let public = key::PublicKey::from_secret_key(context, &secret)?;
+            data.extend_from_slice(&public.serialize_vec(context, true));
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let hash = hmac_sha512(&self.chain_code, &data);
+        let tweak = key::SecretKey::from_slice(context, &hash[0..32])?;
+        secret.add_assign(context, &tweak)?;
+        Ok(ExtendedPrivate {
+            private: Private::from(secret),
+            chain_code: H256::from(&hash[32..64]),
+        })
+    }
+
+    /// Derives the key at `path` from this key.
+    pub fn derive(&self, path: &DerivationPath) -> Result<Self, Error> {
+        path.indices().iter().try_fold(self.clone(), |key, index| key.derive_child(*index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hex::FromHex;
+
+    use super::*;
+
+    #[test]
+    fn parse_and_display_derivation_path() {
+        let path: DerivationPath = "m/44'/0'/0'/0/1".parse().unwrap();
+        assert_eq!(&[44 + HARDENED_INDEX, HARDENED_INDEX, HARDENED_INDEX, 0, 1], path.indices());
+        assert_eq!("m/44'/0'/0'/0/1", path.to_string());
+        assert_eq!(Ok(path), "m/44h/0h/0h/0/1".parse());
+        assert_eq!(Ok(DerivationPath::default()), "m".parse());
+    }
+
+    #[test]
+    fn invalid_derivation_path() {
+        for path in &["", "44'/0'", "m/", "m/a", "m/2147483648", "m/-1"] {
+            assert_eq!(Err(Error::InvalidDerivationPath(path.to_string())), path.parse::<DerivationPath>());
+        }
+    }
+
+    #[test]
+    fn bip32_test_vector_1() {
+        let seed: Vec<u8> = "000102030405060708090a0b0c0d0e0f".from_hex().unwrap();
+        let master = ExtendedPrivate::from_seed(&seed).unwrap();
+        assert_eq!(&Private::from("e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"), master.private());
+        assert_eq!(
+            &H256::from("873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508"),
+            master.chain_code()
+        );
+
+        let child = master.derive(&"m/0'".parse().unwrap()).unwrap();
+        assert_eq!(&Private::from("edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"), child.private());
+        assert_eq!(&H256::from("47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141"), child.chain_code());
+
+        let grandchild = child.derive_child(1).unwrap();
+        assert_eq!(
+            &Private::from("3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"),
+            grandchild.private()
+        );
+        assert_eq!(
+            &H256::from("2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19"),
+            grandchild.chain_code()
+        );
+    }
+}
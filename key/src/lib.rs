@@ -36,6 +36,7 @@ mod address;
 mod ecdsa;
 mod error;
 mod exchange;
+mod hd;
 mod keypair;
 mod network;
 mod password;
@@ -51,6 +52,7 @@ pub use crate::ecdsa::{
 };
 pub use crate::error::Error;
 pub use crate::exchange::exchange;
+pub use crate::hd::{DerivationPath, ExtendedPrivate, HARDENED_INDEX};
 pub use crate::keypair::{public_to_address, KeyPair};
 pub use crate::network::NetworkId;
 pub use crate::password::Password;
@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use ccrypto;
-use ckey::{Address, KeyPair, Password};
+use ckey::{Address, DerivationPath, ExtendedPrivate, KeyPair, Password, Secret};
 
 use super::crypto::Crypto;
 use crate::account::Version;
@@ -32,7 +32,10 @@ pub struct SafeAccount {
     /// Account address
     pub address: Address,
     /// Account private key derivation definition.
+    /// It encrypts the seed instead of the private key if `derivation_path` exists.
     pub crypto: Crypto,
+    /// Path to derive the private key from the seed.
+    pub derivation_path: Option<DerivationPath>,
     /// Account filename
     pub filename: Option<String>,
     /// Account metadata
@@ -47,6 +50,7 @@ impl From<SafeAccount> for json::KeyFile {
             address: Some(account.address.into()),
             crypto: account.crypto.into(),
             meta: Some(account.meta),
+            derivation_path: account.derivation_path.map(|path| path.to_string()),
         }
     }
 }
@@ -64,6 +68,29 @@ impl SafeAccount {
             id,
             version: Version::V3,
             crypto: Crypto::with_secret(keypair.private(), password, iterations)?,
+            derivation_path: None,
+            address: keypair.address(),
+            filename: None,
+            meta,
+        })
+    }
+
+    /// Create a new account whose private key is derived from `seed` along `path`.
+    pub fn create_derived(
+        seed: &[u8],
+        path: DerivationPath,
+        id: [u8; 16],
+        password: &Password,
+        iterations: u32,
+        meta: String,
+    ) -> Result<Self, Error> {
+        let private = ExtendedPrivate::from_seed(seed)?.derive(&path)?;
+        let keypair = KeyPair::from_private(*private.private())?;
+        Ok(SafeAccount {
+            id,
+            version: Version::V3,
+            crypto: Crypto::with_plain(seed, password, iterations)?,
+            derivation_path: Some(path),
             address: keypair.address(),
             filename: None,
             meta,
@@ -79,17 +106,27 @@ impl SafeAccount {
         password: Option<&Password>,
     ) -> Result<Self, Error> {
         let crypto = Crypto::from(json.crypto);
+        let derivation_path = match json.derivation_path {
+            Some(path) => Some(
+                path.parse::<DerivationPath>()
+                    .map_err(|_| Error::InvalidKeyFile("Derivation path field is invalid".to_string()))?,
+            ),
+            None => None,
+        };
+        let decrypted_address = |password: &Password| -> Result<Address, Error> {
+            let secret = decrypt_secret(&crypto, derivation_path.as_ref(), password)?;
+            Ok(KeyPair::from_private(secret.into())?.address())
+        };
         let address = match (json.address, password) {
             (Some(address), Some(password)) => {
                 let address = address.into();
-                let decrypted_address = crypto.address(password)?;
-                if decrypted_address != address {
+                if decrypted_address(password)? != address {
                     Err(Error::InvalidKeyFile("Address field is invalid".to_string()))
                 } else {
                     Ok(address)
                 }
             }
-            (None, Some(password)) => crypto.address(password),
+            (None, Some(password)) => decrypted_address(password),
             (Some(address), None) => Ok(address.into()),
             (None, None) => Err(Error::InvalidKeyFile("Cannot create account if address is not given".to_string())),
         }?;
@@ -99,11 +136,26 @@ impl SafeAccount {
             version: json.version.into(),
             address,
             crypto,
+            derivation_path,
             filename,
             meta: json.meta.unwrap_or_else(|| "{}".to_string()),
         })
     }
 
+    /// Decrypt the private key of the account.
+    pub fn secret(&self, password: &Password) -> Result<Secret, Error> {
+        decrypt_secret(&self.crypto, self.derivation_path.as_ref(), password)
+    }
+
+    /// Decrypt the seed of the account.
+    /// Returns an error if the account is not derived from a seed.
+    pub fn seed(&self, password: &Password) -> Result<Vec<u8>, Error> {
+        if self.derivation_path.is_none() {
+            return Err(Error::NotDerivedAccount)
+        }
+        self.crypto.decrypt(password)
+    }
+
     /// Change account's password.
     pub fn change_password(
         &self,
@@ -111,11 +163,18 @@ impl SafeAccount {
         new_password: &Password,
         iterations: u32,
     ) -> Result<Self, Error> {
-        let secret = self.crypto.secret(old_password)?;
+        let crypto = if self.derivation_path.is_some() {
+            let seed = self.seed(old_password)?;
+            Crypto::with_plain(&seed, new_password, iterations)?
+        } else {
+            let secret = self.crypto.secret(old_password)?;
+            Crypto::with_secret(&secret, new_password, iterations)?
+        };
         let result = SafeAccount {
             id: self.id,
             version: self.version,
-            crypto: Crypto::with_secret(&secret, new_password, iterations)?,
+            crypto,
+            derivation_path: self.derivation_path.clone(),
             address: self.address,
             filename: self.filename.clone(),
             meta: self.meta.clone(),
@@ -129,7 +188,21 @@ impl SafeAccount {
     }
 
     pub fn decrypt(&self, password: &Password) -> Result<DecryptedAccount, Error> {
-        Ok(DecryptedAccount::new(self.crypto.secret(password)?))
+        Ok(DecryptedAccount::new(self.secret(password)?))
+    }
+}
+
+fn decrypt_secret(
+    crypto: &Crypto,
+    derivation_path: Option<&DerivationPath>,
+    password: &Password,
+) -> Result<Secret, Error> {
+    match derivation_path {
+        Some(path) => {
+            let seed = crypto.decrypt(password)?;
+            Ok(**ExtendedPrivate::from_seed(&seed)?.derive(path)?.private())
+        }
+        None => crypto.secret(password),
     }
 }
 
@@ -193,10 +266,44 @@ mod tests {
                 mac: "46325c5d4e8c991ad2683d525c7854da387138b6ca45068985aa4959fa2b8c8f".into(),
             },
             meta: Some(meta.to_string()),
+            derivation_path: None,
         };
 
         let safe_account = SafeAccount::from_file(expected, None, None).unwrap();
         assert_eq!(Address::from_str(address), Ok(safe_account.address));
         assert_eq!(meta, safe_account.meta);
     }
+
+    #[test]
+    fn derived_account() {
+        let seed = [7u8; 64];
+        let path: DerivationPath = "m/0'/1".parse().unwrap();
+        let password = &"hello world".into();
+        let account =
+            SafeAccount::create_derived(&seed, path.clone(), [0u8; 16], password, 10240, "{}".to_string()).unwrap();
+
+        let expected = ExtendedPrivate::from_seed(&seed).unwrap().derive(&path).unwrap();
+        assert_eq!(**expected.private(), account.secret(password).unwrap());
+        assert_eq!(KeyPair::from_private(*expected.private()).unwrap().address(), account.address);
+        assert_eq!(seed.to_vec(), account.seed(password).unwrap());
+
+        let key_file: KeyFile = account.clone().into();
+        assert_eq!(Some("m/0'/1".to_string()), key_file.derivation_path);
+        let loaded = SafeAccount::from_file(key_file, None, Some(password)).unwrap();
+        assert_eq!(account.address, loaded.address);
+        assert_eq!(Some(path), loaded.derivation_path);
+
+        let new_password = &"this is sparta".into();
+        let changed = account.change_password(password, new_password, 10240).unwrap();
+        assert_eq!(seed.to_vec(), changed.seed(new_password).unwrap());
+        assert!(changed.check_password(new_password));
+    }
+
+    #[test]
+    fn seed_of_non_derived_account() {
+        let keypair = Random.generate().unwrap();
+        let password = &"hello world".into();
+        let account = SafeAccount::create(&keypair, [0u8; 16], password, 10240, "{}".to_string()).unwrap();
+        assert_matches!(account.seed(password), Err(Error::NotDerivedAccount));
+    }
 }
@@ -22,6 +22,7 @@ use std::{fs, io};
 
 use time;
 
+use super::vault::VAULT_FILE_NAME;
 use super::KeyDirectory;
 use crate::json::Uuid;
 use crate::{json, Error, SafeAccount};
@@ -112,7 +113,9 @@ where
 					// hidden files
 					!name.starts_with('.') &&
 					// other ignored files
-					!IGNORED_FILES.contains(&&*name)
+					!IGNORED_FILES.contains(&&*name) &&
+					// the password check of a vault
+					name != VAULT_FILE_NAME
             })
             .map(|entry| entry.path())
             .collect::<Vec<PathBuf>>())
@@ -22,6 +22,7 @@ use crate::{Error, SafeAccount};
 
 mod disk;
 mod memory;
+mod vault;
 
 /// `VaultKeyDirectory::set_key` error
 #[derive(Debug)]
@@ -52,5 +53,6 @@ pub trait KeyDirectory: Send + Sync {
     fn unique_repr(&self) -> Result<u64, Error>;
 }
 
-pub use self::disk::{DiskKeyFileManager, KeyFileManager, RootDiskDirectory};
+pub use self::disk::{DiskDirectory, DiskKeyFileManager, KeyFileManager, RootDiskDirectory};
 pub use self::memory::MemoryDirectory;
+pub use self::vault::VaultDiskDirectory;
@@ -0,0 +1,249 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use ckey::Password;
+use serde_json;
+
+use super::{DiskDirectory, KeyDirectory, KeyFileManager};
+use crate::account::Kdf;
+use crate::{json, Crypto, Error, SafeAccount};
+
+/// The file in a vault directory which checks the vault password.
+pub const VAULT_FILE_NAME: &str = "vault.json";
+const VAULT_PASSWORD_CHECK: &[u8] = b"codechain vault";
+
+/// Keys directory in a sub-directory of the root keys directory.
+/// A vault is protected by its own password in addition to the passwords of the keys in it.
+pub struct VaultDiskDirectory {
+    name: String,
+    dir: DiskDirectory<VaultKeyFileManager>,
+}
+
+/// Vault key file manager.
+/// The crypto of a key file is encrypted again with the vault password,
+/// so the key can't be decrypted without both the vault password and its own password.
+pub struct VaultKeyFileManager {
+    password: Password,
+    iterations: u32,
+}
+
+impl VaultDiskDirectory {
+    /// Create a new vault named `name` in the root keys directory.
+    pub fn create<P>(root: P, name: &str, password: &Password, iterations: u32) -> Result<Self, Error>
+    where
+        P: AsRef<Path>, {
+        let path = vault_path(root, name)?;
+        if path.exists() {
+            return Err(Error::VaultAlreadyExists(name.to_string()))
+        }
+        fs::create_dir_all(&path)?;
+
+        let crypto: json::Crypto = Crypto::with_plain(VAULT_PASSWORD_CHECK, password, iterations)?.into();
+        let mut file = fs::File::create(path.join(VAULT_FILE_NAME))?;
+        serde_json::to_writer(&mut file, &crypto).map_err(|e| Error::Custom(format!("{:?}", e)))?;
+        file.flush()?;
+        file.sync_all()?;
+
+        let key_manager = VaultKeyFileManager {
+            password: password.clone(),
+            iterations,
+        };
+        Ok(VaultDiskDirectory {
+            name: name.to_string(),
+            dir: DiskDirectory::new(path, key_manager),
+        })
+    }
+
+    /// Open the vault named `name` in the root keys directory.
+    pub fn at<P>(root: P, name: &str, password: &Password) -> Result<Self, Error>
+    where
+        P: AsRef<Path>, {
+        let path = vault_path(root, name)?;
+        let file = fs::File::open(path.join(VAULT_FILE_NAME)).map_err(|_| Error::VaultNotFound(name.to_string()))?;
+        let crypto: json::Crypto =
+            serde_json::from_reader(file).map_err(|e| Error::InvalidKeyFile(format!("Invalid vault file: {}", e)))?;
+        let crypto = Crypto::from(crypto);
+        if crypto.decrypt(password)? != VAULT_PASSWORD_CHECK {
+            return Err(Error::InvalidPassword)
+        }
+        // The keys are encrypted with the same parameters as the vault file.
+        let iterations = match crypto.kdf {
+            Kdf::Pbkdf2(params) => params.c,
+            Kdf::Scrypt(_) => return Err(Error::InvalidKeyFile("The vault file must use PBKDF2".to_string())),
+        };
+
+        let key_manager = VaultKeyFileManager {
+            password: password.clone(),
+            iterations,
+        };
+        Ok(VaultDiskDirectory {
+            name: name.to_string(),
+            dir: DiskDirectory::new(path, key_manager),
+        })
+    }
+
+    /// Names of the vaults in the root keys directory.
+    pub fn list<P>(root: P) -> Result<Vec<String>, Error>
+    where
+        P: AsRef<Path>, {
+        let mut names: Vec<String> = fs::read_dir(root)?
+            .flat_map(Result::ok)
+            .filter(|entry| entry.path().join(VAULT_FILE_NAME).is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl KeyDirectory for VaultDiskDirectory {
+    fn load(&self) -> Result<Vec<SafeAccount>, Error> {
+        self.dir.load()
+    }
+
+    fn update(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
+        self.dir.update(account)
+    }
+
+    fn insert(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
+        self.dir.insert(account)
+    }
+
+    fn remove(&self, account: &SafeAccount) -> Result<(), Error> {
+        self.dir.remove(account)
+    }
+
+    fn path(&self) -> Option<&PathBuf> {
+        self.dir.path()
+    }
+
+    fn unique_repr(&self) -> Result<u64, Error> {
+        self.dir.unique_repr()
+    }
+}
+
+impl KeyFileManager for VaultKeyFileManager {
+    fn read<T>(&self, filename: Option<String>, reader: T) -> Result<SafeAccount, Error>
+    where
+        T: io::Read, {
+        let mut key_file = json::KeyFile::load(reader).map_err(|e| Error::Custom(format!("{:?}", e)))?;
+        let crypto = Crypto::from(key_file.crypto).decrypt(&self.password)?;
+        key_file.crypto = serde_json::from_slice(&crypto)
+            .map_err(|e| Error::InvalidKeyFile(format!("Invalid crypto in the vault: {}", e)))?;
+        SafeAccount::from_file(key_file, filename, None)
+    }
+
+    fn write<T>(&self, account: SafeAccount, writer: &mut T) -> Result<(), Error>
+    where
+        T: io::Write, {
+        let mut key_file: json::KeyFile = account.into();
+        let crypto = serde_json::to_vec(&key_file.crypto).map_err(|e| Error::Custom(format!("{:?}", e)))?;
+        key_file.crypto = Crypto::with_plain(&crypto, &self.password, self.iterations)?.into();
+        key_file.write(writer).map_err(|e| Error::Custom(format!("{:?}", e)))
+    }
+}
+
+fn vault_path<P>(root: P, name: &str) -> Result<PathBuf, Error>
+where
+    P: AsRef<Path>, {
+    let is_valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !is_valid {
+        return Err(Error::InvalidVaultName(name.to_string()))
+    }
+    Ok(root.as_ref().join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+
+    use ckey::{Generator, Random};
+
+    use self::tempdir::TempDir;
+    use super::*;
+    use crate::accounts_dir::RootDiskDirectory;
+
+    #[test]
+    fn create_and_open_vault() {
+        let root = TempDir::new("").unwrap();
+        let password = &"vault".into();
+        let vault = VaultDiskDirectory::create(&root, "savings", password, 1024).unwrap();
+        assert_eq!("savings", vault.name());
+
+        let keypair = Random.generate().unwrap();
+        let account = SafeAccount::create(&keypair, [0u8; 16], &"account".into(), 1024, "{}".to_string()).unwrap();
+        vault.insert(account).unwrap();
+
+        let vault = VaultDiskDirectory::at(&root, "savings", password).unwrap();
+        let accounts = vault.load().unwrap();
+        assert_eq!(1, accounts.len());
+        assert_eq!(keypair.address(), accounts[0].address);
+
+        assert!(RootDiskDirectory::at(&root).load().unwrap().is_empty(), "The root directory doesn't have the key");
+        assert_eq!(vec!["savings".to_string()], VaultDiskDirectory::list(&root).unwrap());
+    }
+
+    #[test]
+    fn keys_in_vault_are_encrypted_with_vault_password() {
+        let root = TempDir::new("").unwrap();
+        let vault = VaultDiskDirectory::create(&root, "savings", &"vault".into(), 1024).unwrap();
+
+        let keypair = Random.generate().unwrap();
+        let password = &"account".into();
+        let account = SafeAccount::create(&keypair, [0u8; 16], password, 1024, "{}".to_string()).unwrap();
+        vault.insert(account).unwrap();
+
+        let raw_accounts = RootDiskDirectory::at(root.path().join("savings")).load().unwrap();
+        assert_eq!(1, raw_accounts.len());
+        assert_matches!(raw_accounts[0].secret(password), Err(Error::InvalidPassword));
+
+        let accounts = vault.load().unwrap();
+        assert_eq!(keypair.address(), accounts[0].address);
+        assert!(accounts[0].secret(password).is_ok());
+    }
+
+    #[test]
+    fn open_vault_with_wrong_password() {
+        let root = TempDir::new("").unwrap();
+        VaultDiskDirectory::create(&root, "savings", &"vault".into(), 1024).unwrap();
+        assert_matches!(VaultDiskDirectory::at(&root, "savings", &"wrong".into()), Err(Error::InvalidPassword));
+    }
+
+    #[test]
+    fn vault_errors() {
+        let root = TempDir::new("").unwrap();
+        let password = &"vault".into();
+        assert_matches!(VaultDiskDirectory::at(&root, "savings", password), Err(Error::VaultNotFound(_)));
+        VaultDiskDirectory::create(&root, "savings", password, 1024).unwrap();
+        assert_matches!(
+            VaultDiskDirectory::create(&root, "savings", password, 1024),
+            Err(Error::VaultAlreadyExists(_))
+        );
+        for name in &["", "..", "../savings", "a/b"] {
+            assert_matches!(VaultDiskDirectory::create(&root, name, password, 1024), Err(Error::InvalidVaultName(_)));
+        }
+    }
+}
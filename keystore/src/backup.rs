@@ -0,0 +1,202 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Password-protected archives of all the keys in a directory.
+
+use std::collections::HashSet;
+use std::io::{Read, Write};
+
+use ccrypto::blake256;
+use ckey::{Address, Password};
+use serde_json;
+
+use crate::accounts_dir::KeyDirectory;
+use crate::json::{self, KeyArchive};
+use crate::{Crypto, Error, SafeAccount};
+
+const ARCHIVE_VERSION: u32 = 1;
+
+/// The result of restoring an archive.
+#[derive(Debug, Default, PartialEq)]
+pub struct RestoredAccounts {
+    /// Accounts inserted to the directory.
+    pub imported: Vec<Address>,
+    /// Accounts skipped because the directory already has them.
+    pub duplicated: Vec<Address>,
+}
+
+/// Writes the accounts of `src` to an archive encrypted with `password`.
+/// The key files in the archive are still encrypted with their own passwords.
+/// Exports all accounts if `addresses` is `None`.
+pub fn export_archive<W>(
+    src: &KeyDirectory,
+    addresses: Option<&[Address]>,
+    password: &Password,
+    iterations: u32,
+    writer: &mut W,
+) -> Result<Vec<Address>, Error>
+where
+    W: Write, {
+    let mut accounts: Vec<SafeAccount> = src.load()?;
+    if let Some(addresses) = addresses {
+        if let Some(missing) = addresses.iter().find(|address| accounts.iter().all(|a| a.address != **address)) {
+            return Err(Error::Custom(format!("{} is not in the key directory", missing)))
+        }
+        accounts.retain(|account| addresses.contains(&account.address));
+    }
+    accounts.sort_by_key(|account| account.address);
+
+    let exported = accounts.iter().map(|account| account.address).collect();
+    let key_files: Vec<json::KeyFile> = accounts.into_iter().map(Into::into).collect();
+    let plain = serde_json::to_vec(&key_files).map_err(|e| Error::Custom(format!("{:?}", e)))?;
+    let archive = KeyArchive {
+        version: ARCHIVE_VERSION,
+        crypto: Crypto::with_plain(&plain, password, iterations)?.into(),
+        checksum: <[u8; 32]>::from(blake256(&plain)).into(),
+        count: key_files.len(),
+    };
+    archive.write(writer).map_err(|e| Error::Custom(format!("{:?}", e)))?;
+    Ok(exported)
+}
+
+/// Inserts the accounts in an archive to `dst`.
+/// The archive is verified before any account is inserted, and the accounts that `dst` already has are skipped.
+pub fn import_archive<R>(reader: R, password: &Password, dst: &KeyDirectory) -> Result<RestoredAccounts, Error>
+where
+    R: Read, {
+    let archive =
+        KeyArchive::load(reader).map_err(|err| Error::InvalidKeyFile(format!("Invalid archive format: {}", err)))?;
+    if archive.version != ARCHIVE_VERSION {
+        return Err(Error::InvalidKeyFile(format!("Unsupported archive version: {}", archive.version)))
+    }
+    let plain = Crypto::from(archive.crypto).decrypt(password)?;
+    if blake256(&plain)[..] != archive.checksum[..] {
+        return Err(Error::InvalidKeyFile("Checksum mismatch".to_string()))
+    }
+    let key_files: Vec<json::KeyFile> = serde_json::from_slice(&plain)
+        .map_err(|err| Error::InvalidKeyFile(format!("Invalid key files in the archive: {}", err)))?;
+    if key_files.len() != archive.count {
+        return Err(Error::InvalidKeyFile(format!(
+            "The archive should have {} keys, but it has {}",
+            archive.count,
+            key_files.len()
+        )))
+    }
+    let accounts = key_files
+        .into_iter()
+        .map(|key_file| SafeAccount::from_file(key_file, None, None))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut existing_accounts = dst.load()?.into_iter().map(|a| a.address).collect::<HashSet<_>>();
+    let mut restored = RestoredAccounts::default();
+    for account in accounts {
+        let address = account.address;
+        if existing_accounts.insert(address) {
+            dst.insert(account)?;
+            restored.imported.push(address);
+        } else {
+            restored.duplicated.push(address);
+        }
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use ckey::{Generator, Random};
+
+    use super::*;
+    use crate::accounts_dir::MemoryDirectory;
+
+    fn directory_with_accounts(n: usize) -> (MemoryDirectory, Vec<Address>) {
+        let directory = MemoryDirectory::default();
+        let mut addresses = Vec::with_capacity(n);
+        for i in 0..n {
+            let keypair = Random.generate().unwrap();
+            let account = SafeAccount::create(&keypair, [i as u8; 16], &"account".into(), 1024, "{}".to_string());
+            addresses.push(directory.insert(account.unwrap()).unwrap().address);
+        }
+        (directory, addresses)
+    }
+
+    #[test]
+    fn export_and_import_all() {
+        let (src, mut addresses) = directory_with_accounts(3);
+        let mut archive = Vec::new();
+        let exported = export_archive(&src, None, &"archive".into(), 1024, &mut archive).unwrap();
+        addresses.sort();
+        assert_eq!(addresses, exported);
+
+        let dst = MemoryDirectory::default();
+        let restored = import_archive(&archive[..], &"archive".into(), &dst).unwrap();
+        assert_eq!(addresses, restored.imported);
+        assert!(restored.duplicated.is_empty());
+        assert_eq!(3, dst.load().unwrap().len());
+    }
+
+    #[test]
+    fn export_some_accounts() {
+        let (src, addresses) = directory_with_accounts(3);
+        let mut archive = Vec::new();
+        let exported = export_archive(&src, Some(&addresses[1..2]), &"archive".into(), 1024, &mut archive).unwrap();
+        assert_eq!(addresses[1..2].to_vec(), exported);
+
+        let missing = [Address::random()];
+        assert_matches!(
+            export_archive(&src, Some(&missing), &"archive".into(), 1024, &mut Vec::new()),
+            Err(Error::Custom(_))
+        );
+    }
+
+    #[test]
+    fn skip_duplicated_accounts() {
+        let (src, addresses) = directory_with_accounts(2);
+        let mut archive = Vec::new();
+        export_archive(&src, None, &"archive".into(), 1024, &mut archive).unwrap();
+
+        let restored = import_archive(&archive[..], &"archive".into(), &src).unwrap();
+        assert!(restored.imported.is_empty());
+        assert_eq!(2, restored.duplicated.len());
+        assert!(addresses.iter().all(|address| restored.duplicated.contains(address)));
+        assert_eq!(2, src.load().unwrap().len());
+    }
+
+    #[test]
+    fn wrong_archive_password() {
+        let (src, _) = directory_with_accounts(1);
+        let mut archive = Vec::new();
+        export_archive(&src, None, &"archive".into(), 1024, &mut archive).unwrap();
+
+        let dst = MemoryDirectory::default();
+        assert_matches!(import_archive(&archive[..], &"wrong".into(), &dst), Err(Error::InvalidPassword));
+        assert!(dst.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn tampered_archive() {
+        let (src, _) = directory_with_accounts(1);
+        let mut archive = Vec::new();
+        export_archive(&src, None, &"archive".into(), 1024, &mut archive).unwrap();
+
+        let mut key_archive = KeyArchive::load(&archive[..]).unwrap();
+        key_archive.count += 1;
+        let mut tampered = Vec::new();
+        key_archive.write(&mut tampered).unwrap();
+
+        let dst = MemoryDirectory::default();
+        assert_matches!(import_archive(&tampered[..], &"archive".into(), &dst), Err(Error::InvalidKeyFile(_)));
+    }
+}
@@ -39,6 +39,16 @@ pub enum Error {
     CreationFailed,
     /// Account already exists.
     AlreadyExists,
+    /// Mnemonic is invalid.
+    InvalidMnemonic(String),
+    /// Account is not derived from a seed.
+    NotDerivedAccount,
+    /// Vault name is invalid.
+    InvalidVaultName(String),
+    /// Vault does not exist.
+    VaultNotFound(String),
+    /// Vault already exists.
+    VaultAlreadyExists(String),
     /// `ckeys` error
     CKey(CKeyError),
     /// `CCrypto` error
@@ -58,6 +68,11 @@ impl fmt::Display for Error {
             Error::InvalidKeyFile(ref reason) => format!("Invalid key file: {}", reason),
             Error::CreationFailed => "Account creation failed".into(),
             Error::AlreadyExists => "Account already exists".into(),
+            Error::InvalidMnemonic(ref reason) => format!("Invalid mnemonic: {}", reason),
+            Error::NotDerivedAccount => "Account is not derived from a seed".into(),
+            Error::InvalidVaultName(ref name) => format!("Invalid vault name: {}", name),
+            Error::VaultNotFound(ref name) => format!("Vault {} does not exist", name),
+            Error::VaultAlreadyExists(ref name) => format!("Vault {} already exists", name),
             Error::CKey(ref err) => err.to_string(),
             Error::CCrypto(ref err) => err.to_string(),
             Error::Custom(ref s) => s.clone(),
@@ -0,0 +1,45 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io::{Read, Write};
+
+use serde_json;
+
+use super::{Crypto, H256};
+
+/// A password-protected archive of key files.
+/// `crypto` encrypts the JSON array of the key files, and `checksum` is the blake256 hash of the array.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyArchive {
+    pub version: u32,
+    pub crypto: Crypto,
+    pub checksum: H256,
+    pub count: usize,
+}
+
+impl KeyArchive {
+    pub fn load<R>(reader: R) -> Result<Self, serde_json::Error>
+    where
+        R: Read, {
+        serde_json::from_reader(reader)
+    }
+
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), serde_json::Error>
+    where
+        W: Write, {
+        serde_json::to_writer(writer, self)
+    }
+}
@@ -53,6 +53,9 @@ pub struct KeyFile {
     pub crypto: Crypto,
     pub address: Option<H160>,
     pub meta: Option<String>,
+    /// The path to derive the private key from the seed in `crypto`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
 }
 
 impl KeyFile {
@@ -122,6 +125,7 @@ mod tests {
                 mac: "46325c5d4e8c991ad2683d525c7854da387138b6ca45068985aa4959fa2b8c8f".into(),
             },
             meta: Some("{}".to_string()),
+            derivation_path: None,
         };
 
         let keyfile: KeyFile = serde_json::from_str(json).unwrap();
@@ -175,6 +179,7 @@ mod tests {
                 mac: "46325c5d4e8c991ad2683d525c7854da387138b6ca45068985aa4959fa2b8c8f".into(),
             },
             meta: Some("{}".to_string()),
+            derivation_path: None,
         };
 
         let keyfile: KeyFile = serde_json::from_str(json).unwrap();
@@ -225,6 +230,7 @@ mod tests {
                 mac: "46325c5d4e8c991ad2683d525c7854da387138b6ca45068985aa4959fa2b8c8f".into(),
             },
             meta: Some("{}".to_string()),
+            derivation_path: None,
         };
 
         let keyfile: KeyFile = serde_json::from_str(JSON).unwrap();
@@ -283,6 +289,7 @@ mod tests {
                 mac: "46325c5d4e8c991ad2683d525c7854da387138b6ca45068985aa4959fa2b8c8f".into(),
             },
             meta: None,
+            derivation_path: None,
         };
 
         let serialized = serde_json::to_string(&file).unwrap();
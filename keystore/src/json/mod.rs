@@ -16,6 +16,7 @@
 
 //! Contract interface specification.
 
+mod archive;
 mod cipher;
 mod crypto;
 mod error;
@@ -25,6 +26,7 @@ mod kdf;
 mod key_file;
 mod version;
 
+pub use self::archive::KeyArchive;
 pub use self::cipher::{Aes128Ctr, Cipher, CipherSer, CipherSerParams};
 pub use self::crypto::{CipherText, Crypto};
 pub use self::error::Error;
@@ -20,7 +20,7 @@ use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 use ccrypto::KEY_ITERATIONS;
-use ckey::{Address, KeyPair, Password, Secret};
+use ckey::{Address, DerivationPath, KeyPair, Password, Secret};
 use parking_lot::{Mutex, RwLock};
 
 use crate::account::{DecryptedAccount, SafeAccount};
@@ -68,6 +68,16 @@ impl SimpleSecretStore for KeyStore {
         }
     }
 
+    fn insert_derived_account(&self, seed: &[u8], path: DerivationPath, password: &Password) -> Result<Address, Error> {
+        let id: [u8; 16] = Random::random();
+        let account = SafeAccount::create_derived(seed, path, id, password, self.store.iterations, "{}".to_string())?;
+        if self.has_account(&account.address)? {
+            Err(Error::AlreadyExists)
+        } else {
+            self.store.import(account)
+        }
+    }
+
     fn accounts(&self) -> Result<Vec<Address>, Error> {
         self.store.accounts()
     }
@@ -108,11 +118,18 @@ impl SecretStore for KeyStore {
             safe_account.id = Random::random();
         }
 
-        let secret = safe_account.crypto.secret(password).map_err(|_| Error::InvalidPassword)?;
+        let secret = safe_account.secret(password).map_err(|_| Error::InvalidPassword)?;
         safe_account.address = KeyPair::from_private(secret.into())?.address();
         self.store.import(safe_account)
     }
 
+    fn derive_account(&self, account: &Address, password: &Password, index: u32) -> Result<Address, Error> {
+        let parent = self.store.get_verified_account(account, password)?.account;
+        let path = parent.derivation_path.as_ref().ok_or(Error::NotDerivedAccount)?.child(index);
+        let seed = parent.seed(password)?;
+        self.insert_derived_account(&seed, path, password)
+    }
+
     fn test_password(&self, account: &Address, password: &Password) -> Result<bool, Error> {
         match self.store.get_verified_account(account, password) {
             Ok(_) => Ok(true),
@@ -268,7 +285,7 @@ impl KeyMultiStore {
 
     fn get_verified_account(&self, account: &Address, password: &Password) -> Result<VerifiedAccount, Error> {
         for account in self.get_safe_accounts(account)?.into_iter() {
-            match account.crypto.secret(password) {
+            match account.secret(password) {
                 Ok(secret) => {
                     return Ok(VerifiedAccount {
                         account,
@@ -341,6 +358,12 @@ impl SimpleSecretStore for KeyMultiStore {
         self.import(account)
     }
 
+    fn insert_derived_account(&self, seed: &[u8], path: DerivationPath, password: &Password) -> Result<Address, Error> {
+        let id: [u8; 16] = Random::random();
+        let account = SafeAccount::create_derived(seed, path, id, password, self.iterations, "{}".to_string())?;
+        self.import(account)
+    }
+
     fn accounts(&self) -> Result<Vec<Address>, Error> {
         self.reload_if_changed()?;
         Ok(self.cache.read().keys().cloned().collect())
@@ -408,7 +431,7 @@ struct VerifiedAccount {
 mod tests {
     extern crate tempdir;
 
-    use ckey::{Generator, Random};
+    use ckey::{ExtendedPrivate, Generator, Random};
 
     use super::*;
     use crate::accounts_dir::MemoryDirectory;
@@ -530,6 +553,35 @@ mod tests {
         assert_eq!(multi_store.accounts().unwrap().len(), 1);
     }
 
+    #[test]
+    fn insert_and_derive_account() {
+        // given
+        let store = store();
+        let seed = [1u8; 64];
+        let password = &"test".into();
+        let address = store.insert_derived_account(&seed, "m/0'".parse().unwrap(), password).unwrap();
+
+        // when
+        let child = store.derive_account(&address, password, 3).unwrap();
+
+        // then
+        let expected = ExtendedPrivate::from_seed(&seed).unwrap().derive(&"m/0'/3".parse().unwrap()).unwrap();
+        assert_eq!(KeyPair::from_private(*expected.private()).unwrap().address(), child);
+        assert_eq!(store.accounts().unwrap().len(), 2);
+        assert_matches!(
+            store.insert_derived_account(&seed, "m/0'/3".parse().unwrap(), password),
+            Err(Error::AlreadyExists)
+        );
+    }
+
+    #[test]
+    fn cannot_derive_from_non_derived_account() {
+        let store = store();
+        let keypair = keypair();
+        let address = store.insert_account(**keypair.private(), &"test".into()).unwrap();
+        assert_matches!(store.derive_account(&address, &"test".into(), 0), Err(Error::NotDerivedAccount));
+    }
+
     #[test]
     fn export_account() {
         // given
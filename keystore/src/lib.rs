@@ -32,6 +32,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+extern crate bip39;
 extern crate libc;
 extern crate parking_lot;
 extern crate rand;
@@ -62,18 +63,24 @@ pub mod accounts_dir;
 pub mod ckeys;
 
 mod account;
+mod backup;
 mod json;
 
 mod error;
 mod import;
 mod keystore;
+mod mnemonic;
 mod random;
 mod secret_store;
 
+pub use ccrypto::KEY_ITERATIONS;
+
 pub use crate::account::{Crypto, DecryptedAccount, SafeAccount};
+pub use crate::backup::{export_archive, import_archive, RestoredAccounts};
 pub use crate::error::Error;
 pub use crate::import::{import_account, import_accounts};
 pub use crate::json::OpaqueKeyFile as KeyFile;
 pub use crate::keystore::{KeyMultiStore, KeyStore};
+pub use crate::mnemonic::{generate_mnemonic, mnemonic_to_seed};
 pub use crate::random::random_string;
 pub use crate::secret_store::{SecretStore, SimpleSecretStore};
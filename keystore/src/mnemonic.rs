@@ -0,0 +1,61 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! BIP39 mnemonics.
+
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+
+use crate::Error;
+
+/// Generates a new 24-word English mnemonic.
+pub fn generate_mnemonic() -> String {
+    Mnemonic::new(MnemonicType::Words24, Language::English).into_phrase()
+}
+
+/// Converts an English mnemonic to the seed of hierarchical deterministic keys.
+pub fn mnemonic_to_seed(phrase: &str) -> Result<Vec<u8>, Error> {
+    let mnemonic =
+        Mnemonic::from_phrase(phrase, Language::English).map_err(|err| Error::InvalidMnemonic(err.to_string()))?;
+    Ok(Seed::new(&mnemonic, "").as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hex::ToHex;
+
+    use super::*;
+
+    #[test]
+    fn generated_mnemonic_is_valid() {
+        let phrase = generate_mnemonic();
+        assert_eq!(24, phrase.split_whitespace().count());
+        assert_eq!(64, mnemonic_to_seed(&phrase).unwrap().len());
+    }
+
+    #[test]
+    fn seed_of_mnemonic() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert_eq!(
+            "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc19a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4",
+            mnemonic_to_seed(phrase).unwrap().to_hex()
+        );
+    }
+
+    #[test]
+    fn invalid_mnemonic() {
+        assert_matches!(mnemonic_to_seed("abandon abandon abandon"), Err(Error::InvalidMnemonic(_)));
+    }
+}
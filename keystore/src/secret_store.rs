@@ -32,7 +32,7 @@
 
 use std::path::PathBuf;
 
-use ckey::{Address, Password, Secret};
+use ckey::{Address, DerivationPath, Password, Secret};
 
 use crate::json::{OpaqueKeyFile, Uuid};
 use crate::{DecryptedAccount, Error};
@@ -42,6 +42,8 @@ use crate::{DecryptedAccount, Error};
 pub trait SimpleSecretStore: Send + Sync {
     /// Inserts new accounts to the store with given password.
     fn insert_account(&self, secret: Secret, password: &Password) -> Result<Address, Error>;
+    /// Inserts a new account derived from `seed` along `path` to the store with given password.
+    fn insert_derived_account(&self, seed: &[u8], path: DerivationPath, password: &Password) -> Result<Address, Error>;
     /// Returns all accounts in this secret store.
     fn accounts(&self) -> Result<Vec<Address>, Error>;
     /// Check existance of account
@@ -62,6 +64,9 @@ pub trait SecretStore: SimpleSecretStore {
     /// Imports existing JSON wallet
     fn import_wallet(&self, json: &[u8], password: &Password, gen_id: bool) -> Result<Address, Error>;
 
+    /// Derives the `index`th child of a derived account and inserts it to the store with the same password.
+    fn derive_account(&self, account: &Address, password: &Password, index: u32) -> Result<Address, Error>;
+
     /// Checks if password matches given account.
     fn test_password(&self, account: &Address, password: &Password) -> Result<bool, Error>;
 
@@ -0,0 +1,228 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ckey::Public;
+use kvdb::{DBTransaction, KeyValueDB};
+use parking_lot::RwLock;
+use rlp::{DecoderError, RlpStream, UntrustedRlp};
+
+use crate::reputation::now;
+use crate::SocketAddr;
+
+const ADDRESS_BOOK_KEY: &[u8] = b"address-book";
+
+/// Peers that haven't been connected for this many seconds are forgotten
+const MAX_AGE: u64 = 7 * 24 * 60 * 60;
+/// Peers that failed this many times in a row are forgotten
+const MAX_CONSECUTIVE_FAILURES: u64 = 10;
+const MAX_ENTRIES: usize = 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct PeerRecord {
+    /// Seconds since the epoch when the last connection was established
+    pub last_seen: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// The failures since the last connection
+    pub consecutive_failures: u64,
+    /// The public key that the peer used in the last connection
+    pub remote_public: Public,
+}
+
+impl PeerRecord {
+    fn is_stale_at(&self, now: u64) -> bool {
+        self.last_seen + MAX_AGE < now || self.consecutive_failures >= MAX_CONSECUTIVE_FAILURES
+    }
+}
+
+/// The peers that this node has connected to, which are kept in the database across restarts.
+/// They are used to fill the routing table on startup, so the node doesn't depend on the bootstrap nodes.
+pub struct AddressBook {
+    db: Arc<KeyValueDB>,
+    col: Option<u32>,
+    records: RwLock<HashMap<SocketAddr, PeerRecord>>,
+}
+
+impl AddressBook {
+    #![cfg_attr(feature = "cargo-clippy", allow(clippy::new_ret_no_self))]
+    pub fn new(db: Arc<KeyValueDB>, col: Option<u32>) -> Arc<Self> {
+        let records = match db.get(col, ADDRESS_BOOK_KEY) {
+            Ok(Some(bytes)) => decode_records(&bytes).unwrap_or_else(|err| {
+                cwarn!(NETWORK, "Cannot decode the address book: {:?}", err);
+                HashMap::new()
+            }),
+            Ok(None) => HashMap::new(),
+            Err(err) => {
+                cwarn!(NETWORK, "Cannot read the address book: {:?}", err);
+                HashMap::new()
+            }
+        };
+        Arc::new(Self {
+            db,
+            col,
+            records: RwLock::new(records),
+        })
+    }
+
+    /// The addresses that are not stale, the most recently seen first.
+    pub fn addresses(&self) -> Vec<SocketAddr> {
+        let now = now();
+        let records = self.records.read();
+        let mut addresses: Vec<_> = records.iter().filter(|(_, record)| !record.is_stale_at(now)).collect();
+        addresses.sort_unstable_by_key(|(_, record)| ::std::cmp::Reverse(record.last_seen));
+        addresses.into_iter().map(|(addr, _)| *addr).collect()
+    }
+
+    pub fn records(&self) -> Vec<(SocketAddr, PeerRecord)> {
+        self.records.read().iter().map(|(addr, record)| (*addr, *record)).collect()
+    }
+
+    pub fn record_success(&self, addr: SocketAddr, remote_public: Public) {
+        self.record_success_at(addr, remote_public, now())
+    }
+
+    fn record_success_at(&self, addr: SocketAddr, remote_public: Public, now: u64) {
+        {
+            let mut records = self.records.write();
+            if !records.contains_key(&addr) && records.len() >= MAX_ENTRIES {
+                let oldest = records.iter().min_by_key(|(_, record)| record.last_seen).map(|(addr, _)| *addr);
+                if let Some(oldest) = oldest {
+                    records.remove(&oldest);
+                }
+            }
+            let record = records.entry(addr).or_default();
+            record.last_seen = now;
+            record.successes += 1;
+            record.consecutive_failures = 0;
+            record.remote_public = remote_public;
+        }
+        self.save();
+    }
+
+    /// Counts a failed connection. The addresses that have never been connected are not recorded.
+    pub fn record_failure(&self, addr: &SocketAddr) {
+        {
+            let mut records = self.records.write();
+            match records.get_mut(addr) {
+                Some(record) => {
+                    record.failures += 1;
+                    record.consecutive_failures += 1;
+                }
+                None => return,
+            }
+        }
+        self.save();
+    }
+
+    /// Forgets the stale peers, and returns the number of them.
+    pub fn remove_stale(&self) -> usize {
+        self.remove_stale_at(now())
+    }
+
+    fn remove_stale_at(&self, now: u64) -> usize {
+        let removed = {
+            let mut records = self.records.write();
+            let before = records.len();
+            records.retain(|_, record| !record.is_stale_at(now));
+            before - records.len()
+        };
+        if removed != 0 {
+            self.save();
+        }
+        removed
+    }
+
+    fn save(&self) {
+        let mut stream = RlpStream::new();
+        {
+            let records = self.records.read();
+            stream.begin_list(records.len());
+            for (addr, record) in records.iter() {
+                stream.begin_list(2).append(addr).append(record);
+            }
+        }
+        let mut batch = DBTransaction::new();
+        batch.put(self.col, ADDRESS_BOOK_KEY, &stream.out());
+        if let Err(err) = self.db.write(batch) {
+            cwarn!(NETWORK, "Cannot write the address book: {:?}", err);
+        }
+    }
+}
+
+fn decode_records(bytes: &[u8]) -> Result<HashMap<SocketAddr, PeerRecord>, DecoderError> {
+    UntrustedRlp::new(bytes).iter().map(|entry| Ok((entry.val_at(0)?, entry.val_at(1)?))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::v4(10, 0, 0, 1, port)
+    }
+
+    #[test]
+    fn unknown_failures_are_not_recorded() {
+        let book = AddressBook::new(Arc::new(kvdb_memorydb::create(1)), Some(0));
+        book.record_failure(&addr(1));
+        assert!(book.records().is_empty());
+    }
+
+    #[test]
+    fn recently_seen_first() {
+        let book = AddressBook::new(Arc::new(kvdb_memorydb::create(1)), Some(0));
+        let now = now();
+        book.record_success_at(addr(1), Public::random(), now - 2);
+        book.record_success_at(addr(2), Public::random(), now);
+        book.record_success_at(addr(3), Public::random(), now - 1);
+        assert_eq!(vec![addr(2), addr(3), addr(1)], book.addresses());
+    }
+
+    #[test]
+    fn stale_entries_are_removed() {
+        let book = AddressBook::new(Arc::new(kvdb_memorydb::create(1)), Some(0));
+        let now = 1000;
+        book.record_success_at(addr(1), Public::random(), now);
+        book.record_success_at(addr(2), Public::random(), now + MAX_AGE);
+        book.record_success_at(addr(3), Public::random(), now + MAX_AGE);
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            book.record_failure(&addr(3));
+        }
+        assert_eq!(2, book.remove_stale_at(now + MAX_AGE + 1));
+        assert_eq!(vec![addr(2)], book.records().into_iter().map(|(addr, _)| addr).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn records_are_persistent() {
+        let db: Arc<KeyValueDB> = Arc::new(kvdb_memorydb::create(1));
+        let book = AddressBook::new(Arc::clone(&db), Some(0));
+        let public = Public::random();
+        book.record_success(addr(1), public);
+        book.record_failure(&addr(1));
+
+        let reloaded = AddressBook::new(db, Some(0));
+        let records = reloaded.records();
+        assert_eq!(1, records.len());
+        let (address, record) = records[0];
+        assert_eq!(addr(1), address);
+        assert_eq!(1, record.successes);
+        assert_eq!(1, record.failures);
+        assert_eq!(public, record.remote_public);
+    }
+}
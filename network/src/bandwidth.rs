@@ -0,0 +1,202 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::extension::Priority;
+
+/// Limits on the bytes per second sent to each peer.
+#[derive(Clone, Debug, Default)]
+pub struct BandwidthLimits {
+    /// The limit on all the extension messages
+    pub per_peer: Option<usize>,
+    /// The limits on the messages of each extension
+    pub per_extension: HashMap<String, usize>,
+}
+
+impl BandwidthLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.per_peer.is_none() && self.per_extension.is_empty()
+    }
+}
+
+/// A token bucket that allows a burst of up to a second.
+/// The allowance can go below zero, so a message larger than the rate is delayed but never stuck.
+struct RateLimiter {
+    rate: usize,
+    allowance: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    fn new(rate: usize, now: Instant) -> Self {
+        Self {
+            rate,
+            allowance: rate as f64,
+            updated_at: now,
+        }
+    }
+
+    fn is_available(&mut self, now: Instant) -> bool {
+        if now > self.updated_at {
+            let elapsed = duration_to_secs(now - self.updated_at);
+            self.allowance = (self.allowance + elapsed * self.rate as f64).min(self.rate as f64);
+            self.updated_at = now;
+        }
+        self.allowance > 0.0
+    }
+
+    fn consume(&mut self, bytes: usize) {
+        self.allowance -= bytes as f64;
+    }
+}
+
+fn duration_to_secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + f64::from(d.subsec_nanos()) / 1_000_000_000.0
+}
+
+struct PendingMessage {
+    extension_name: String,
+    bytes: Vec<u8>,
+}
+
+/// The extension messages waiting to be written to a connection.
+/// A message of higher priority is always dequeued first, and the messages of an extension keep their order.
+pub struct OutgoingQueue {
+    queues: [VecDeque<PendingMessage>; 3],
+    peer_limiter: Option<RateLimiter>,
+    extension_limiters: HashMap<String, RateLimiter>,
+}
+
+impl OutgoingQueue {
+    pub fn new(limits: &BandwidthLimits) -> Self {
+        let now = Instant::now();
+        Self {
+            queues: Default::default(),
+            peer_limiter: limits.per_peer.map(|rate| RateLimiter::new(rate, now)),
+            extension_limiters: limits
+                .per_extension
+                .iter()
+                .map(|(name, rate)| (name.clone(), RateLimiter::new(*rate, now)))
+                .collect(),
+        }
+    }
+
+    pub fn push(&mut self, priority: Priority, extension_name: String, bytes: Vec<u8>) {
+        self.queues[priority as usize].push_back(PendingMessage {
+            extension_name,
+            bytes,
+        });
+    }
+
+    /// Returns the message to be sent at `now`, or `None` if the queue is empty or throttled.
+    pub fn pop(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if let Some(limiter) = self.peer_limiter.as_mut() {
+            if !limiter.is_available(now) {
+                return None
+            }
+        }
+        let extension_limiters = &mut self.extension_limiters;
+        for queue in self.queues.iter_mut().rev() {
+            let index = queue.iter().position(|message| {
+                extension_limiters.get_mut(&message.extension_name).map_or(true, |limiter| limiter.is_available(now))
+            });
+            if let Some(index) = index {
+                let message = queue.remove(index).expect("The index is found above");
+                if let Some(limiter) = extension_limiters.get_mut(&message.extension_name) {
+                    limiter.consume(message.bytes.len());
+                }
+                if let Some(limiter) = self.peer_limiter.as_mut() {
+                    limiter.consume(message.bytes.len());
+                }
+                return Some(message.bytes)
+            }
+        }
+        None
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(VecDeque::is_empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(per_peer: Option<usize>, per_extension: &[(&str, usize)]) -> BandwidthLimits {
+        BandwidthLimits {
+            per_peer,
+            per_extension: per_extension.iter().map(|(name, rate)| (name.to_string(), *rate)).collect(),
+        }
+    }
+
+    #[test]
+    fn higher_priority_first() {
+        let mut queue = OutgoingQueue::new(&BandwidthLimits::default());
+        queue.push(Priority::Low, "block-propagation".to_string(), vec![1]);
+        queue.push(Priority::Normal, "discovery".to_string(), vec![2]);
+        queue.push(Priority::High, "tendermint".to_string(), vec![3]);
+        queue.push(Priority::Low, "block-propagation".to_string(), vec![4]);
+
+        let now = Instant::now();
+        assert_eq!(Some(vec![3]), queue.pop(now));
+        assert_eq!(Some(vec![2]), queue.pop(now));
+        assert_eq!(Some(vec![1]), queue.pop(now));
+        assert_eq!(Some(vec![4]), queue.pop(now));
+        assert_eq!(None, queue.pop(now));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn throttled_extension_does_not_block_others() {
+        let mut queue = OutgoingQueue::new(&limits(None, &[("block-propagation", 10)]));
+        let now = Instant::now();
+        queue.push(Priority::Low, "block-propagation".to_string(), vec![0; 20]);
+        queue.push(Priority::Low, "block-propagation".to_string(), vec![1; 5]);
+        queue.push(Priority::Low, "transaction-propagation".to_string(), vec![2; 5]);
+
+        assert_eq!(Some(vec![0; 20]), queue.pop(now));
+        // The allowance of block-propagation is below zero, but the order in the extension is kept.
+        assert_eq!(Some(vec![2; 5]), queue.pop(now));
+        assert_eq!(None, queue.pop(now));
+        assert_eq!(None, queue.pop(now + Duration::from_millis(500)));
+        assert_eq!(Some(vec![1; 5]), queue.pop(now + Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn throttled_peer() {
+        let mut queue = OutgoingQueue::new(&limits(Some(100), &[]));
+        let now = Instant::now();
+        queue.push(Priority::High, "tendermint".to_string(), vec![0; 150]);
+        queue.push(Priority::High, "tendermint".to_string(), vec![1; 10]);
+
+        assert_eq!(Some(vec![0; 150]), queue.pop(now));
+        assert_eq!(None, queue.pop(now));
+        assert_eq!(None, queue.pop(now + Duration::from_millis(500)));
+        assert_eq!(Some(vec![1; 10]), queue.pop(now + Duration::from_millis(600)));
+    }
+
+    #[test]
+    fn allowance_does_not_exceed_the_rate() {
+        let mut limiter = RateLimiter::new(100, Instant::now());
+        let later = limiter.updated_at + Duration::from_secs(60);
+        assert!(limiter.is_available(later));
+        limiter.consume(101);
+        assert!(!limiter.is_available(later));
+    }
+}
@@ -25,19 +25,23 @@ use parking_lot::{Mutex, RwLock};
 use primitives::Bytes;
 use time::Duration;
 
+use crate::extension::Priority;
 use crate::p2p::Message as P2pMessage;
-use crate::{Api, IntoSocketAddr, NetworkExtension, NetworkExtensionResult, NodeId};
+use crate::{Api, IntoSocketAddr, Misbehavior, NetworkExtension, NetworkExtensionResult, NodeId, Reputation};
 
 struct ClientApi {
     p2p_channel: IoChannel<P2pMessage>,
+    reputation: Arc<Reputation>,
     timer: TimerApi,
     name: &'static str,
     need_encryption: bool,
+    priority: Priority,
 }
 
 impl Api for ClientApi {
     fn send(&self, id: &NodeId, data: Arc<Bytes>) {
         let need_encryption = self.need_encryption;
+        let priority = self.priority;
         let extension_name = self.name;
         let node_id = *id;
         let bytes = data.len();
@@ -45,6 +49,7 @@ impl Api for ClientApi {
             node_id,
             extension_name,
             need_encryption,
+            priority,
             data,
         }) {
             cerror!(
@@ -60,6 +65,18 @@ impl Api for ClientApi {
         }
     }
 
+    fn report(&self, id: &NodeId, misbehavior: Misbehavior) {
+        let extension_name = self.name;
+        let addr = id.into_addr();
+        cdebug!(NETAPI, "`{}` reports {:?} of {}", extension_name, misbehavior, addr);
+        if !self.reputation.report(addr, misbehavior) {
+            return
+        }
+        if let Err(err) = self.p2p_channel.send(P2pMessage::Disconnect(addr)) {
+            cerror!(NETAPI, "`{}` cannot ban {} : {:?}", extension_name, addr, err);
+        }
+    }
+
     fn set_timer(&self, token: TimerToken, duration: Duration) -> NetworkExtensionResult<()> {
         let duration = duration.to_std().expect("Cannot convert to standard duratino type");
         self.timer.schedule_repeat(duration, token)?;
@@ -106,6 +123,7 @@ impl Drop for Extension {
 pub struct Client {
     extensions: RwLock<HashMap<&'static str, Arc<Extension>>>,
     p2p_channel: IoChannel<P2pMessage>,
+    reputation: Arc<Reputation>,
     timer_loop: TimerLoop,
 }
 
@@ -120,6 +138,7 @@ impl Client {
         let timer = self.timer_loop.new_timer_with_name(name);
         let cloned_timer = timer.clone();
         let p2p_channel = self.p2p_channel.clone();
+        let reputation = Arc::clone(&self.reputation);
         let (channel, rx) = crossbeam::unbounded();
         let sender = channel.clone().into();
 
@@ -135,7 +154,9 @@ impl Client {
                     let api = ClientApi {
                         name,
                         need_encryption: T::need_encryption(),
+                        priority: T::priority(),
                         p2p_channel,
+                        reputation,
                         timer,
                     };
                     let mut extension = factory(Box::from(api));
@@ -223,10 +244,11 @@ impl Client {
     }
 
     #[cfg_attr(feature = "cargo-clippy", allow(clippy::new_ret_no_self))]
-    pub fn new(p2p_channel: IoChannel<P2pMessage>, timer_loop: TimerLoop) -> Arc<Self> {
+    pub fn new(p2p_channel: IoChannel<P2pMessage>, reputation: Arc<Reputation>, timer_loop: TimerLoop) -> Arc<Self> {
         Arc::new(Self {
             extensions: RwLock::new(HashMap::new()),
             p2p_channel,
+            reputation,
             timer_loop,
         })
     }
@@ -292,6 +314,10 @@ mod tests {
             unimplemented!()
         }
 
+        fn report(&self, _id: &NodeId, _misbehavior: Misbehavior) {
+            unimplemented!()
+        }
+
         fn set_timer(&self, _timer_id: usize, _duration: Duration) -> NetworkExtensionResult<()> {
             unimplemented!()
         }
@@ -372,7 +398,8 @@ mod tests {
         let p2p_service = IoService::start("P2P").unwrap();
         let timer_loop = TimerLoop::new(2);
 
-        let client = Client::new(p2p_service.channel(), timer_loop);
+        let reputation = Reputation::new(Arc::new(kvdb_memorydb::create(1)), Some(0));
+        let client = Client::new(p2p_service.channel(), reputation, timer_loop);
 
         let node_id1 = SocketAddr::v4(127, 0, 0, 1, 8081).into();
         let node_id5 = SocketAddr::v4(127, 0, 0, 1, 8085).into();
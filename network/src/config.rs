@@ -15,7 +15,8 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::filters::FilterEntry;
-use crate::SocketAddr;
+use crate::nat::PortMapping;
+use crate::{BandwidthLimits, ReservedPeer, SocketAddr};
 
 pub struct Config {
     pub address: String,
@@ -25,4 +26,8 @@ pub struct Config {
     pub max_peers: usize,
     pub whitelist: Vec<FilterEntry>,
     pub blacklist: Vec<FilterEntry>,
+    pub port_mapping: Option<PortMapping>,
+    pub bandwidth_limits: BandwidthLimits,
+    pub reserved_peers: Vec<ReservedPeer>,
+    pub reserved_only: bool,
 }
@@ -23,6 +23,7 @@ use ckey::Public;
 
 use crate::addr::SocketAddr;
 use crate::filters::FilterEntry;
+use crate::reputation::PeerScore;
 
 pub trait Control: Send + Sync {
     fn local_key_for(&self, address: IpAddr, port: u16) -> Result<Public, Error>;
@@ -50,6 +51,8 @@ pub trait Control: Send + Sync {
     fn get_blacklist(&self) -> Result<(Vec<FilterEntry>, bool), Error>;
 
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>, Error>;
+
+    fn peer_scores(&self) -> Result<Vec<(SocketAddr, PeerScore)>, Error>;
 }
 
 #[derive(Clone, Debug)]
@@ -23,7 +23,7 @@ use ctimer::{TimerScheduleError, TimerToken};
 use primitives::Bytes;
 use time::Duration;
 
-use crate::NodeId;
+use crate::{Misbehavior, NodeId};
 
 #[derive(Debug)]
 pub enum Error {
@@ -48,8 +48,18 @@ impl From<TimerScheduleError> for Error {
 
 pub type Result<T> = result::Result<T, Error>;
 
+/// The messages of an extension with higher priority are written to the connection first.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Priority {
+    Low = 0,
+    Normal = 1,
+    High = 2,
+}
+
 pub trait Api {
     fn send(&self, node: &NodeId, message: Arc<Bytes>);
+    /// Lowers the reputation of the node, which is banned for a while when the reputation gets too low.
+    fn report(&self, node: &NodeId, misbehavior: Misbehavior);
 
     fn set_timer(&self, timer: TimerToken, d: Duration) -> Result<()>;
     fn set_timer_once(&self, timer: TimerToken, d: Duration) -> Result<()>;
@@ -60,6 +70,9 @@ pub trait Extension<Event: Send> {
     fn name() -> &'static str;
     fn need_encryption() -> bool;
     fn versions() -> &'static [u64];
+    fn priority() -> Priority {
+        Priority::Normal
+    }
 
     fn on_node_added(&mut self, _node: &NodeId, _version: u64) {}
     fn on_node_removed(&mut self, _node: &NodeId) {}
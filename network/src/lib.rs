@@ -27,6 +27,9 @@ extern crate codechain_types as ctypes;
 extern crate core;
 extern crate crossbeam_channel;
 extern crate finally_block;
+extern crate kvdb;
+#[cfg(test)]
+extern crate kvdb_memorydb;
 #[macro_use]
 extern crate log;
 extern crate mio;
@@ -42,11 +45,17 @@ extern crate time;
 extern crate token_generator;
 
 mod addr;
+mod address_book;
+mod bandwidth;
 mod client;
 mod config;
 mod extension;
 mod filters;
+mod nat;
 mod node_id;
+mod node_key;
+mod reputation;
+mod reserved_peers;
 mod routing_table;
 mod service;
 mod stream;
@@ -56,12 +65,19 @@ mod p2p;
 pub mod session;
 
 pub use crate::addr::SocketAddr;
+pub use crate::address_book::{AddressBook, PeerRecord};
+pub use crate::bandwidth::BandwidthLimits;
 pub use crate::config::Config as NetworkConfig;
 pub use crate::control::{Control as NetworkControl, Error as NetworkControlError};
 pub use crate::extension::{
-    Api, Error as NetworkExtensionError, Extension as NetworkExtension, Result as NetworkExtensionResult,
+    Api, Error as NetworkExtensionError, Extension as NetworkExtension, Priority as NetworkExtensionPriority,
+    Result as NetworkExtensionResult,
 };
+pub use crate::nat::{ExternalAddress, PortMapping};
 pub use crate::node_id::{IntoSocketAddr, NodeId};
+pub use crate::node_key::load_or_create_node_key;
+pub use crate::reputation::{Misbehavior, PeerScore, Reputation};
+pub use crate::reserved_peers::{ReservedPeer, ReservedPeers};
 pub use crate::service::{Error as NetworkServiceError, Service as NetworkService};
 
 pub use crate::filters::{FilterEntry, Filters, FiltersControl};
@@ -0,0 +1,262 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod natpmp;
+mod upnp;
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::net::{self, IpAddr, Ipv4Addr, SocketAddrV4};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use parking_lot::RwLock;
+
+use crate::SocketAddr;
+
+/// The lifetime that the node requests for a port mapping
+const MAPPING_LIFETIME: Duration = Duration::from_secs(2 * 60 * 60);
+const RETRY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// The gateway may grant a much shorter lifetime than requested
+const MIN_RENEWAL_INTERVAL: Duration = Duration::from_secs(60);
+/// The number of peers that have to report the same address before the node believes it
+const MIN_OBSERVATIONS: usize = 2;
+const MAX_OBSERVATIONS: usize = 32;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PortMapping {
+    Upnp,
+    NatPmp,
+    /// Tries UPnP first, and then NAT-PMP
+    Any,
+}
+
+impl FromStr for PortMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "upnp" => Ok(PortMapping::Upnp),
+            "natpmp" => Ok(PortMapping::NatPmp),
+            "any" => Ok(PortMapping::Any),
+            _ => Err(format!("Unknown port mapping: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Protocol(String),
+    Timeout,
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+#[derive(Debug)]
+pub struct Mapping {
+    pub external: SocketAddrV4,
+    pub lifetime: Duration,
+}
+
+/// The address that the other nodes can reach this node with.
+/// It comes from the port mapping of the gateway, or from the addresses that the peers observed.
+pub struct ExternalAddress {
+    port: u16,
+    mapped: RwLock<Option<SocketAddr>>,
+    /// The reporter and the IP that it observed, the oldest first
+    observations: RwLock<VecDeque<(IpAddr, IpAddr)>>,
+}
+
+impl ExternalAddress {
+    #![cfg_attr(feature = "cargo-clippy", allow(clippy::new_ret_no_self))]
+    pub fn new(port: u16) -> Arc<Self> {
+        Arc::new(Self {
+            port,
+            mapped: Default::default(),
+            observations: Default::default(),
+        })
+    }
+
+    pub fn get(&self) -> Option<SocketAddr> {
+        if let Some(mapped) = *self.mapped.read() {
+            return Some(mapped)
+        }
+        let observations = self.observations.read();
+        let mut candidates: Vec<(IpAddr, usize)> = Vec::new();
+        for (_, ip) in observations.iter() {
+            match candidates.iter_mut().find(|(candidate, _)| candidate == ip) {
+                Some((_, count)) => *count += 1,
+                None => candidates.push((*ip, 1)),
+            }
+        }
+        candidates
+            .into_iter()
+            .filter(|(_, count)| *count >= MIN_OBSERVATIONS)
+            .max_by_key(|(_, count)| *count)
+            .map(|(ip, _)| SocketAddr::new(ip, self.port))
+    }
+
+    fn set_mapped(&self, mapped: Option<SocketAddr>) {
+        *self.mapped.write() = mapped;
+    }
+
+    /// Records the address of this node that the peer observed during the handshake.
+    pub fn observe(&self, reporter: &SocketAddr, observed: &SocketAddr) {
+        if !observed.is_global() || observed.ip().is_unspecified() {
+            return
+        }
+        let mut observations = self.observations.write();
+        observations.retain(|(r, _)| *r != reporter.ip());
+        if observations.len() >= MAX_OBSERVATIONS {
+            observations.pop_front();
+        }
+        observations.push_back((reporter.ip(), observed.ip()));
+    }
+}
+
+/// Maps the port on the gateway, and renews the mapping before it expires.
+pub fn start(
+    port_mapping: PortMapping,
+    port: u16,
+    external_address: Arc<ExternalAddress>,
+) -> io::Result<JoinHandle<()>> {
+    thread::Builder::new().name("NAT".to_string()).spawn(move || loop {
+        let interval = match map_port(port_mapping, port) {
+            Ok(mapping) => {
+                let external = SocketAddr::from(net::SocketAddr::V4(mapping.external));
+                cinfo!(NETWORK, "The port is mapped to {} for {:?}", external, mapping.lifetime);
+                external_address.set_mapped(Some(external));
+                match renewal_interval(mapping.lifetime) {
+                    Some(interval) => interval,
+                    None => {
+                        cinfo!(NETWORK, "The port mapping is permanent");
+                        return
+                    }
+                }
+            }
+            Err(err) => {
+                cwarn!(NETWORK, "Cannot map the port {}: {:?}", port, err);
+                external_address.set_mapped(None);
+                RETRY_INTERVAL
+            }
+        };
+        thread::sleep(interval);
+    })
+}
+
+/// Returns None if the mapping is permanent, which is represented by the zero lifetime.
+fn renewal_interval(lifetime: Duration) -> Option<Duration> {
+    if lifetime == Duration::from_secs(0) {
+        return None
+    }
+    Some(cmp::max(lifetime / 2, MIN_RENEWAL_INTERVAL))
+}
+
+fn map_port(port_mapping: PortMapping, port: u16) -> Result<Mapping, Error> {
+    let upnp = || {
+        let ssdp_address = net::SocketAddr::from_str(upnp::SSDP_ADDRESS).expect("The SSDP address is valid");
+        upnp::Gateway::search(ssdp_address)?.map_port(port, MAPPING_LIFETIME)
+    };
+    let natpmp = || {
+        let gateway = default_gateway()?;
+        natpmp::map_port(SocketAddrV4::new(gateway, natpmp::NATPMP_PORT), port, MAPPING_LIFETIME)
+    };
+    match port_mapping {
+        PortMapping::Upnp => upnp(),
+        PortMapping::NatPmp => natpmp(),
+        PortMapping::Any => upnp().or_else(|err| {
+            cdebug!(NETWORK, "UPnP is not available: {:?}", err);
+            natpmp()
+        }),
+    }
+}
+
+/// Reads the default gateway from the routing table of the kernel.
+fn default_gateway() -> Result<Ipv4Addr, Error> {
+    let routes = fs::read_to_string("/proc/net/route")?;
+    parse_default_gateway(&routes).ok_or_else(|| Error::Protocol("Cannot find the default gateway".to_string()))
+}
+
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<_> = line.split_whitespace().collect();
+        // The destination of the default route is 0.0.0.0
+        if fields.len() < 3 || fields[1] != "00000000" {
+            return None
+        }
+        // The gateway is printed as a number, whose bytes in the memory are in the network byte order.
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        Some(Ipv4Addr::from(u32::from_be(gateway)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gateway_from_proc() {
+        let routes = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+                      eth0\t0000A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0\n\
+                      eth0\t00000000\t0100A8C0\t0003\t0\t0\t0\t00000000\t0\t0\t0\n";
+        assert_eq!(Some(Ipv4Addr::new(192, 168, 0, 1)), parse_default_gateway(routes));
+        assert_eq!(None, parse_default_gateway("Iface\tDestination\tGateway\n"));
+    }
+
+    #[test]
+    fn renew_before_the_mapping_expires() {
+        assert_eq!(Some(Duration::from_secs(3600)), renewal_interval(MAPPING_LIFETIME));
+        assert_eq!(Some(MIN_RENEWAL_INTERVAL), renewal_interval(Duration::from_secs(1)));
+        assert_eq!(None, renewal_interval(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn observed_address_needs_multiple_reporters() {
+        let external_address = ExternalAddress::new(3485);
+        let observed = SocketAddr::v4(1, 2, 3, 4, 40000);
+        external_address.observe(&SocketAddr::v4(5, 6, 7, 8, 3485), &observed);
+        // The same reporter is counted once.
+        external_address.observe(&SocketAddr::v4(5, 6, 7, 8, 3486), &observed);
+        assert_eq!(None, external_address.get());
+
+        external_address.observe(&SocketAddr::v4(5, 6, 7, 9, 3485), &observed);
+        assert_eq!(Some(SocketAddr::v4(1, 2, 3, 4, 3485)), external_address.get());
+
+        // Private addresses are ignored.
+        let external_address = ExternalAddress::new(3485);
+        external_address.observe(&SocketAddr::v4(5, 6, 7, 8, 3485), &SocketAddr::v4(192, 168, 0, 2, 3485));
+        external_address.observe(&SocketAddr::v4(5, 6, 7, 9, 3485), &SocketAddr::v4(192, 168, 0, 2, 3485));
+        assert_eq!(None, external_address.get());
+    }
+
+    #[test]
+    fn mapped_address_comes_first() {
+        let external_address = ExternalAddress::new(3485);
+        external_address.observe(&SocketAddr::v4(5, 6, 7, 8, 3485), &SocketAddr::v4(1, 2, 3, 4, 3485));
+        external_address.observe(&SocketAddr::v4(5, 6, 7, 9, 3485), &SocketAddr::v4(1, 2, 3, 4, 3485));
+        external_address.set_mapped(Some(SocketAddr::v4(1, 2, 3, 4, 4485)));
+        assert_eq!(Some(SocketAddr::v4(1, 2, 3, 4, 4485)), external_address.get());
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A NAT-PMP(RFC 6886) client that maps the TCP port of the node.
+
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::time::Duration;
+
+use super::{Error, Mapping};
+
+pub const NATPMP_PORT: u16 = 5351;
+
+const VERSION: u8 = 0;
+const OPCODE_EXTERNAL_ADDRESS: u8 = 0;
+const OPCODE_MAP_TCP: u8 = 2;
+const RESPONSE_BIT: u8 = 128;
+
+const INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+const MAX_RETRIES: u32 = 4;
+
+/// Maps the external port to the internal port, and returns the external address.
+pub fn map_port(gateway: SocketAddrV4, internal_port: u16, lifetime: Duration) -> Result<Mapping, Error> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(gateway)?;
+
+    let response = request(&socket, &[VERSION, OPCODE_EXTERNAL_ADDRESS], OPCODE_EXTERNAL_ADDRESS, 12)?;
+    let external_ip = Ipv4Addr::new(response[8], response[9], response[10], response[11]);
+
+    let mut map_request = [0u8; 12];
+    map_request[0] = VERSION;
+    map_request[1] = OPCODE_MAP_TCP;
+    map_request[4..6].copy_from_slice(&internal_port.to_be_bytes());
+    // Suggests the same port, which the gateway may not honor.
+    map_request[6..8].copy_from_slice(&internal_port.to_be_bytes());
+    map_request[8..12].copy_from_slice(&(lifetime.as_secs() as u32).to_be_bytes());
+    let response = request(&socket, &map_request, OPCODE_MAP_TCP, 16)?;
+    let external_port = u16::from_be_bytes([response[10], response[11]]);
+    let lifetime = u32::from_be_bytes([response[12], response[13], response[14], response[15]]);
+
+    Ok(Mapping {
+        external: SocketAddrV4::new(external_ip, external_port),
+        lifetime: Duration::from_secs(u64::from(lifetime)),
+    })
+}
+
+/// Sends the request and waits for the response, doubling the timeout on every retry as RFC 6886 recommends.
+fn request(socket: &UdpSocket, request: &[u8], opcode: u8, response_len: usize) -> Result<Vec<u8>, Error> {
+    let mut timeout = INITIAL_TIMEOUT;
+    let mut buf = [0u8; 16];
+    for _ in 0..MAX_RETRIES {
+        socket.send(request)?;
+        socket.set_read_timeout(Some(timeout))?;
+        let len = match socket.recv(&mut buf) {
+            Ok(len) => len,
+            Err(_) => {
+                timeout *= 2;
+                continue
+            }
+        };
+        if len < response_len || buf[0] != VERSION || buf[1] != opcode | RESPONSE_BIT {
+            return Err(Error::Protocol(format!("Unexpected NAT-PMP response: {:?}", &buf[..len])))
+        }
+        let result_code = u16::from_be_bytes([buf[2], buf[3]]);
+        if result_code != 0 {
+            return Err(Error::Protocol(format!("NAT-PMP request failed with the result code {}", result_code)))
+        }
+        return Ok(buf[..response_len].to_vec())
+    }
+    Err(Error::Timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    /// A fake gateway that maps every port to port + 1000 of 1.2.3.4
+    fn spawn_gateway() -> SocketAddrV4 {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = match socket.local_addr().unwrap() {
+            ::std::net::SocketAddr::V4(addr) => addr,
+            ::std::net::SocketAddr::V6(_) => unreachable!(),
+        };
+        thread::spawn(move || {
+            let mut buf = [0u8; 12];
+            for _ in 0..2 {
+                let (len, from) = socket.recv_from(&mut buf).unwrap();
+                let response = match buf[1] {
+                    OPCODE_EXTERNAL_ADDRESS => {
+                        assert_eq!(2, len);
+                        vec![0, 128, 0, 0, 0, 0, 0, 1, 1, 2, 3, 4]
+                    }
+                    OPCODE_MAP_TCP => {
+                        assert_eq!(12, len);
+                        let internal_port = u16::from_be_bytes([buf[4], buf[5]]);
+                        let mut response = vec![0, 130, 0, 0, 0, 0, 0, 1];
+                        response.extend_from_slice(&internal_port.to_be_bytes());
+                        response.extend_from_slice(&(internal_port + 1000).to_be_bytes());
+                        response.extend_from_slice(&buf[8..12]);
+                        response
+                    }
+                    _ => unreachable!(),
+                };
+                socket.send_to(&response, from).unwrap();
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn map_port_with_fake_gateway() {
+        let gateway = spawn_gateway();
+        let mapping = map_port(gateway, 3485, Duration::from_secs(7200)).unwrap();
+        assert_eq!(SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 4485), mapping.external);
+        assert_eq!(Duration::from_secs(7200), mapping.lifetime);
+    }
+
+    #[test]
+    fn no_gateway() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = match socket.local_addr().unwrap() {
+            ::std::net::SocketAddr::V4(addr) => addr,
+            ::std::net::SocketAddr::V6(_) => unreachable!(),
+        };
+        // The socket doesn't answer.
+        match map_port(addr, 3485, Duration::from_secs(7200)) {
+            Err(Error::Timeout) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A UPnP IGD client that maps the TCP port of the node.
+
+use std::io::{Read, Write};
+use std::net::{self, Ipv4Addr, SocketAddrV4, TcpStream, UdpSocket};
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::{Error, Mapping};
+
+pub const SSDP_ADDRESS: &str = "239.255.255.250:1900";
+
+const SEARCH_TARGET: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+const SERVICE_TYPES: &[&str] =
+    &["urn:schemas-upnp-org:service:WANIPConnection:1", "urn:schemas-upnp-org:service:WANPPPConnection:1"];
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug)]
+pub struct Gateway {
+    addr: net::SocketAddr,
+    control_path: String,
+    service_type: &'static str,
+}
+
+impl Gateway {
+    /// Finds the gateway with SSDP, and reads the control URL from its description.
+    pub fn search(ssdp_address: net::SocketAddr) -> Result<Self, Error> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_read_timeout(Some(TIMEOUT))?;
+        let request = format!(
+            "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\n\r\n",
+            SSDP_ADDRESS, SEARCH_TARGET
+        );
+        socket.send_to(request.as_bytes(), ssdp_address)?;
+
+        let mut buf = [0u8; 1500];
+        let (len, _) = socket.recv_from(&mut buf).map_err(|_| Error::Timeout)?;
+        let response = String::from_utf8_lossy(&buf[..len]);
+        let location = header(&response, "location")
+            .ok_or_else(|| Error::Protocol("The SSDP response has no location".to_string()))?;
+        let (addr, description_path) = parse_url(location)?;
+
+        let (_, description) = http_request(
+            addr,
+            &format!("GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", description_path, addr),
+        )?;
+        let (service_type, control_url) = find_control_url(&description)
+            .ok_or_else(|| Error::Protocol("The gateway has no WAN connection service".to_string()))?;
+        let control_path = if control_url.starts_with("http://") {
+            parse_url(&control_url)?.1
+        } else {
+            control_url
+        };
+        Ok(Self {
+            addr,
+            control_path,
+            service_type,
+        })
+    }
+
+    pub fn external_ip(&self) -> Result<Ipv4Addr, Error> {
+        let response = self.soap_request("GetExternalIPAddress", "")?;
+        let ip = element(&response, "NewExternalIPAddress")
+            .ok_or_else(|| Error::Protocol("The response has no external address".to_string()))?;
+        Ipv4Addr::from_str(ip.trim()).map_err(|_| Error::Protocol(format!("Invalid external address: {}", ip)))
+    }
+
+    /// Maps the same external port to the internal port, and returns the external address.
+    pub fn map_port(&self, internal_port: u16, lifetime: Duration) -> Result<Mapping, Error> {
+        // The address of the interface that the gateway sees
+        let internal_ip = match TcpStream::connect_timeout(&self.addr, TIMEOUT)?.local_addr()? {
+            net::SocketAddr::V4(addr) => *addr.ip(),
+            net::SocketAddr::V6(addr) => return Err(Error::Protocol(format!("IPv6 is not supported: {}", addr))),
+        };
+        let arguments = format!(
+            "<NewRemoteHost></NewRemoteHost>\
+             <NewExternalPort>{port}</NewExternalPort>\
+             <NewProtocol>TCP</NewProtocol>\
+             <NewInternalPort>{port}</NewInternalPort>\
+             <NewInternalClient>{ip}</NewInternalClient>\
+             <NewEnabled>1</NewEnabled>\
+             <NewPortMappingDescription>CodeChain</NewPortMappingDescription>\
+             <NewLeaseDuration>{lifetime}</NewLeaseDuration>",
+            port = internal_port,
+            ip = internal_ip,
+            lifetime = lifetime.as_secs()
+        );
+        self.soap_request("AddPortMapping", &arguments)?;
+        Ok(Mapping {
+            external: SocketAddrV4::new(self.external_ip()?, internal_port),
+            lifetime,
+        })
+    }
+
+    fn soap_request(&self, action: &str, arguments: &str) -> Result<String, Error> {
+        let body = format!(
+            "<?xml version=\"1.0\"?>\
+             <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+             s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+             <s:Body><u:{action} xmlns:u=\"{service}\">{arguments}</u:{action}></s:Body></s:Envelope>",
+            action = action,
+            service = self.service_type,
+            arguments = arguments
+        );
+        let request = format!(
+            "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: text/xml; charset=\"utf-8\"\r\n\
+             SOAPAction: \"{service}#{action}\"\r\nContent-Length: {len}\r\nConnection: close\r\n\r\n{body}",
+            path = self.control_path,
+            host = self.addr,
+            service = self.service_type,
+            action = action,
+            len = body.len(),
+            body = body
+        );
+        let (status, response) = http_request(self.addr, &request)?;
+        if status != 200 {
+            return Err(Error::Protocol(format!("{} failed with the status {}", action, status)))
+        }
+        Ok(response)
+    }
+}
+
+/// Sends the request and reads the response until the gateway closes the connection.
+fn http_request(addr: net::SocketAddr, request: &str) -> Result<(u16, String), Error> {
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    stream.write_all(request.as_bytes())?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| Error::Protocol("Invalid HTTP response".to_string()))?;
+    let body = match response.find("\r\n\r\n") {
+        Some(index) => response[index + 4..].to_string(),
+        None => String::new(),
+    };
+    Ok((status, body))
+}
+
+fn header<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+    response.lines().find_map(|line| {
+        let index = line.find(':')?;
+        if line[..index].trim().eq_ignore_ascii_case(name) {
+            Some(line[index + 1..].trim())
+        } else {
+            None
+        }
+    })
+}
+
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(&xml[start..end])
+}
+
+fn find_control_url(description: &str) -> Option<(&'static str, String)> {
+    description.split("<service>").skip(1).find_map(|service| {
+        let service_type = element(service, "serviceType")?.trim();
+        let service_type = SERVICE_TYPES.iter().find(|t| **t == service_type)?;
+        Some((*service_type, element(service, "controlURL")?.trim().to_string()))
+    })
+}
+
+/// Splits "http://host:port/path" into the address and the path.
+fn parse_url(url: &str) -> Result<(net::SocketAddr, String), Error> {
+    let invalid_url = || Error::Protocol(format!("Invalid URL: {}", url));
+    if !url.starts_with("http://") {
+        return Err(invalid_url())
+    }
+    let url = &url["http://".len()..];
+    let (host, path) = match url.find('/') {
+        Some(index) => (&url[..index], &url[index..]),
+        None => (url, "/"),
+    };
+    let addr = if host.contains(':') {
+        net::SocketAddr::from_str(host)
+    } else {
+        net::SocketAddr::from_str(&format!("{}:80", host))
+    }
+    .map_err(|_| invalid_url())?;
+    Ok((addr, path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    /// A fake gateway that answers SSDP, serves its description and accepts the SOAP requests.
+    fn spawn_gateway() -> net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let http_addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 {
+                        break
+                    }
+                    if let Some(len) = header(&line, "content-length") {
+                        content_length = len.parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break
+                    }
+                }
+                if request.is_empty() {
+                    // The client only connected to find its local address.
+                    continue
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let response = if request.starts_with("GET /rootDesc.xml") {
+                    "<root><device><serviceList>\
+                     <service><serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>\
+                     <controlURL>/ctl/L3F</controlURL></service>\
+                     <service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>\
+                     <controlURL>/ctl/IPConn</controlURL></service>\
+                     </serviceList></device></root>"
+                        .to_string()
+                } else if request.starts_with("POST /ctl/IPConn") && request.contains("#GetExternalIPAddress") {
+                    "<s:Envelope><s:Body><u:GetExternalIPAddressResponse>\
+                     <NewExternalIPAddress>1.2.3.4</NewExternalIPAddress>\
+                     </u:GetExternalIPAddressResponse></s:Body></s:Envelope>"
+                        .to_string()
+                } else if request.starts_with("POST /ctl/IPConn") && request.contains("#AddPortMapping") {
+                    let body = String::from_utf8(body).unwrap();
+                    assert_eq!(Some("3485"), element(&body, "NewExternalPort"));
+                    assert_eq!(Some("127.0.0.1"), element(&body, "NewInternalClient"));
+                    String::new()
+                } else {
+                    panic!("Unexpected request: {}", request)
+                };
+                write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", response.len(), response).unwrap();
+            }
+        });
+
+        let ssdp = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let ssdp_addr = ssdp.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0u8; 1500];
+            let (len, from) = ssdp.recv_from(&mut buf).unwrap();
+            assert!(String::from_utf8_lossy(&buf[..len]).starts_with("M-SEARCH"));
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nST: {}\r\nLOCATION: http://{}/rootDesc.xml\r\n\r\n",
+                SEARCH_TARGET, http_addr
+            );
+            ssdp.send_to(response.as_bytes(), from).unwrap();
+        });
+        ssdp_addr
+    }
+
+    #[test]
+    fn map_port_with_fake_gateway() {
+        let gateway = Gateway::search(spawn_gateway()).unwrap();
+        assert_eq!("/ctl/IPConn", gateway.control_path);
+        let mapping = gateway.map_port(3485, Duration::from_secs(7200)).unwrap();
+        assert_eq!(SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 3485), mapping.external);
+    }
+
+    #[test]
+    fn parse_urls() {
+        assert_eq!(
+            ("192.168.0.1:5000".parse().unwrap(), "/rootDesc.xml".to_string()),
+            parse_url("http://192.168.0.1:5000/rootDesc.xml").unwrap()
+        );
+        assert_eq!(("192.168.0.1:80".parse().unwrap(), "/".to_string()), parse_url("http://192.168.0.1").unwrap());
+        assert!(parse_url("https://192.168.0.1").is_err());
+    }
+}
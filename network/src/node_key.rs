@@ -0,0 +1,52 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ckey::{Generator, KeyPair, Private, Random};
+use kvdb::{DBTransaction, KeyValueDB};
+use primitives::H256;
+
+const NODE_KEY: &[u8] = b"node-key";
+
+/// Loads the key pair that identifies this node, or creates and saves a new one.
+/// The public key is what the other nodes put in their reserved peers.
+pub fn load_or_create_node_key(db: &KeyValueDB, col: Option<u32>) -> Result<KeyPair, String> {
+    if let Some(bytes) = db.get(col, NODE_KEY).map_err(|err| format!("Cannot read the node key: {:?}", err))? {
+        if bytes.len() != 32 {
+            return Err(format!("The node key must be 32 bytes, but it's {} bytes", bytes.len()))
+        }
+        let private = Private::from(H256::from_slice(&bytes));
+        return KeyPair::from_private(private).map_err(|err| format!("Invalid node key: {:?}", err))
+    }
+
+    let key_pair = Random.generate().map_err(|err| format!("Cannot generate the node key: {:?}", err))?;
+    let mut batch = DBTransaction::new();
+    batch.put(col, NODE_KEY, &key_pair.private()[..]);
+    db.write(batch).map_err(|err| format!("Cannot save the node key: {:?}", err))?;
+    Ok(key_pair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_key_is_kept() {
+        let db = kvdb_memorydb::create(1);
+        let created = load_or_create_node_key(&db, Some(0)).unwrap();
+        let loaded = load_or_create_node_key(&db, Some(0)).unwrap();
+        assert_eq!(created.public(), loaded.public());
+    }
+}
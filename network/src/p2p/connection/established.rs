@@ -16,8 +16,10 @@
 
 use std::io;
 use std::sync::Arc;
+use std::time::Instant;
 
 use cio::IoManager;
+use ckey::{KeyPair, Public};
 use mio::deprecated::EventLoop;
 use mio::unix::UnixReady;
 use mio::{PollOpt, Ready, Token};
@@ -25,22 +27,31 @@ use primitives::Bytes;
 
 use super::super::message::{Message, Version};
 use super::super::stream::SignedStream;
-use super::super::{ExtensionMessage, NegotiationMessage};
+use super::super::{ExtensionMessage, IdentityMessage, NegotiationMessage};
 use super::Result;
+use crate::bandwidth::OutgoingQueue;
+use crate::extension::Priority;
 use crate::session::Session;
 use crate::stream::Stream;
-use crate::SocketAddr;
+use crate::{BandwidthLimits, SocketAddr};
 
 pub struct EstablishedConnection {
     stream: SignedStream,
     peer_addr: SocketAddr,
+    // The extension messages are moved to the stream one by one when the stream is flushed,
+    // so that a message of higher priority doesn't wait for the messages queued before it.
+    queue: OutgoingQueue,
+    // The node key of the peer, which is known after it sends a valid identity message.
+    remote_node_key: Option<Public>,
 }
 
 impl EstablishedConnection {
-    pub fn new(stream: Stream, session: Session, peer_addr: SocketAddr) -> Self {
+    pub fn new(stream: Stream, session: Session, peer_addr: SocketAddr, bandwidth_limits: &BandwidthLimits) -> Self {
         Self {
             stream: SignedStream::new(stream, session),
             peer_addr,
+            queue: OutgoingQueue::new(bandwidth_limits),
+            remote_node_key: None,
         }
     }
 
@@ -48,6 +59,19 @@ impl EstablishedConnection {
         self.stream.write(message)
     }
 
+    pub fn enqueue_identity(&mut self, node_key: &KeyPair) -> usize {
+        let message = IdentityMessage::new(node_key, self.stream.session());
+        self.write(&Message::Identity(message))
+    }
+
+    pub fn remote_node_key(&self) -> Option<&Public> {
+        self.remote_node_key.as_ref()
+    }
+
+    pub fn set_remote_node_key(&mut self, public: Public) {
+        self.remote_node_key = Some(public);
+    }
+
     pub fn enqueue_negotiation_request(&mut self, name: String, extension_versions: Vec<Version>) -> usize {
         self.write(&Message::Negotiation(NegotiationMessage::request(name, extension_versions)))
     }
@@ -60,15 +84,23 @@ impl EstablishedConnection {
         &mut self,
         extension_name: String,
         need_encryption: bool,
+        priority: Priority,
         message: Arc<Bytes>,
     ) -> Result<usize> {
         let message = if need_encryption {
-            ExtensionMessage::encrypted_from_unencrypted_data(extension_name, &message, self.stream.session())?
+            ExtensionMessage::encrypted_from_unencrypted_data(extension_name.clone(), &message, self.stream.session())?
         } else {
-            ExtensionMessage::unencrypted(extension_name, message)
+            ExtensionMessage::unencrypted(extension_name.clone(), message)
         };
 
-        Ok(self.write(&Message::Extension(message)))
+        let bytes = self.stream.encode(&Message::Extension(message));
+        let result = bytes.len();
+        self.queue.push(priority, extension_name, bytes);
+        Ok(result)
+    }
+
+    pub fn has_pending_messages(&self) -> bool {
+        !self.queue.is_empty()
     }
 
     fn interest(&self) -> Ready {
@@ -77,6 +109,14 @@ impl EstablishedConnection {
 
     pub fn flush(&mut self) -> Result<()> {
         self.stream.flush()?;
+        let now = Instant::now();
+        while self.stream.is_flushed() {
+            match self.queue.pop(now) {
+                Some(bytes) => self.stream.write_bytes(bytes),
+                None => break,
+            }
+            self.stream.flush()?;
+        }
         Ok(())
     }
 
@@ -26,7 +26,7 @@ use primitives::Bytes;
 use super::{EstablishedConnection, IncomingMessage, OutgoingMessage, Result};
 use crate::session::Session;
 use crate::stream::Stream;
-use crate::SocketAddr;
+use crate::{BandwidthLimits, SocketAddr};
 
 pub struct IncomingConnection {
     stream: Stream,
@@ -39,19 +39,30 @@ impl IncomingConnection {
         }
     }
 
-    pub fn establish(self, session: Session, port: u16) -> Result<EstablishedConnection> {
+    pub fn establish(
+        self,
+        session: Session,
+        port: u16,
+        bandwidth_limits: &BandwidthLimits,
+    ) -> Result<EstablishedConnection> {
         let peer_addr = SocketAddr::new(self.stream.peer_addr()?.ip(), port);
-        Ok(EstablishedConnection::new(self.stream, session, peer_addr))
+        Ok(EstablishedConnection::new(self.stream, session, peer_addr, bandwidth_limits))
     }
 
     fn interest(&self) -> Ready {
         Ready::writable() | Ready::readable() | UnixReady::hup()
     }
 
-    pub fn send_ack(&mut self, recipient_pub_key: Public, encrypted_nonce: Bytes) -> usize {
+    pub fn send_ack(
+        &mut self,
+        recipient_pub_key: Public,
+        encrypted_nonce: Bytes,
+        observed_addr: Option<SocketAddr>,
+    ) -> usize {
         self.stream.write(&IncomingMessage::Ack {
             recipient_pub_key,
             encrypted_nonce,
+            observed_addr,
         })
     }
 
@@ -18,18 +18,24 @@ use ckey::{NetworkId, Public};
 use primitives::Bytes;
 use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
 
+use crate::SocketAddr;
+
 #[derive(Debug, PartialEq)]
 pub enum OutgoingMessage {
     Sync1 {
         initiator_pub_key: Public,
         network_id: NetworkId,
         initiator_port: u16,
+        /// Whether the initiator understands the observed address in `Ack`
+        supports_observed_addr: bool,
     },
     Sync2 {
         initiator_pub_key: Public,
         recipient_pub_key: Public,
         network_id: NetworkId,
         initiator_port: u16,
+        /// Whether the initiator understands the observed address in `Ack`
+        supports_observed_addr: bool,
     },
 }
 
@@ -38,6 +44,8 @@ pub enum IncomingMessage {
     Ack {
         recipient_pub_key: Public,
         encrypted_nonce: Bytes,
+        /// The address of the initiator that the recipient observed
+        observed_addr: Option<SocketAddr>,
     },
     Nack,
 }
@@ -54,21 +62,43 @@ impl Encodable for OutgoingMessage {
                 initiator_pub_key,
                 network_id,
                 initiator_port,
+                supports_observed_addr,
             } => {
-                s.begin_list(4).append(&SYNC1_ID).append(initiator_pub_key).append(network_id).append(initiator_port);
+                let item_count = if *supports_observed_addr {
+                    5
+                } else {
+                    4
+                };
+                s.begin_list(item_count)
+                    .append(&SYNC1_ID)
+                    .append(initiator_pub_key)
+                    .append(network_id)
+                    .append(initiator_port);
+                if *supports_observed_addr {
+                    s.append(&true);
+                }
             }
             OutgoingMessage::Sync2 {
                 initiator_pub_key,
                 recipient_pub_key,
                 network_id,
                 initiator_port,
+                supports_observed_addr,
             } => {
-                s.begin_list(5)
+                let item_count = if *supports_observed_addr {
+                    6
+                } else {
+                    5
+                };
+                s.begin_list(item_count)
                     .append(&SYNC2_ID)
                     .append(initiator_pub_key)
                     .append(recipient_pub_key)
                     .append(network_id)
                     .append(initiator_port);
+                if *supports_observed_addr {
+                    s.append(&true);
+                }
             }
         }
     }
@@ -79,9 +109,10 @@ impl Decodable for OutgoingMessage {
         match rlp.val_at(0)? {
             SYNC1_ID => {
                 let item_count = rlp.item_count()?;
-                if item_count != 4 {
+                // The nodes that don't understand the observed address don't send the last item.
+                if item_count != 4 && item_count != 5 {
                     return Err(DecoderError::RlpInvalidLength {
-                        expected: 4,
+                        expected: 5,
                         got: item_count,
                     })
                 }
@@ -89,13 +120,19 @@ impl Decodable for OutgoingMessage {
                     initiator_pub_key: rlp.val_at(1)?,
                     network_id: rlp.val_at(2)?,
                     initiator_port: rlp.val_at(3)?,
+                    supports_observed_addr: if item_count == 5 {
+                        rlp.val_at(4)?
+                    } else {
+                        false
+                    },
                 })
             }
             SYNC2_ID => {
                 let item_count = rlp.item_count()?;
-                if item_count != 5 {
+                // The nodes that don't understand the observed address don't send the last item.
+                if item_count != 5 && item_count != 6 {
                     return Err(DecoderError::RlpInvalidLength {
-                        expected: 5,
+                        expected: 6,
                         got: item_count,
                     })
                 }
@@ -104,6 +141,11 @@ impl Decodable for OutgoingMessage {
                     recipient_pub_key: rlp.val_at(2)?,
                     network_id: rlp.val_at(3)?,
                     initiator_port: rlp.val_at(4)?,
+                    supports_observed_addr: if item_count == 6 {
+                        rlp.val_at(5)?
+                    } else {
+                        false
+                    },
                 })
             }
             _ => Err(DecoderError::Custom("Invalid id")),
@@ -117,8 +159,17 @@ impl Encodable for IncomingMessage {
             IncomingMessage::Ack {
                 recipient_pub_key,
                 encrypted_nonce,
+                observed_addr,
             } => {
-                s.begin_list(3).append(&ACK_ID).append(recipient_pub_key).append(encrypted_nonce);
+                let item_count = if observed_addr.is_some() {
+                    4
+                } else {
+                    3
+                };
+                s.begin_list(item_count).append(&ACK_ID).append(recipient_pub_key).append(encrypted_nonce);
+                if let Some(observed_addr) = observed_addr {
+                    s.append(observed_addr);
+                }
             }
             IncomingMessage::Nack => {
                 s.begin_list(1).append(&NACK_ID);
@@ -132,15 +183,21 @@ impl Decodable for IncomingMessage {
         match rlp.val_at(0)? {
             ACK_ID => {
                 let item_count = rlp.item_count()?;
-                if item_count != 3 {
+                // The observed address is optional for the nodes that don't send it.
+                if item_count != 3 && item_count != 4 {
                     return Err(DecoderError::RlpInvalidLength {
-                        expected: 3,
+                        expected: 4,
                         got: item_count,
                     })
                 }
                 Ok(IncomingMessage::Ack {
                     recipient_pub_key: rlp.val_at(1)?,
                     encrypted_nonce: rlp.val_at(2)?,
+                    observed_addr: if item_count == 4 {
+                        Some(rlp.val_at(3)?)
+                    } else {
+                        None
+                    },
                 })
             }
             NACK_ID => {
@@ -169,7 +226,18 @@ mod tests {
         rlp_encode_and_decode_test!(OutgoingMessage::Sync1 {
             initiator_pub_key: Public::random(),
             network_id: "ab".into(),
-            initiator_port: 3100
+            initiator_port: 3100,
+            supports_observed_addr: false
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_sync1_supporting_observed_addr() {
+        rlp_encode_and_decode_test!(OutgoingMessage::Sync1 {
+            initiator_pub_key: Public::random(),
+            network_id: "ab".into(),
+            initiator_port: 3100,
+            supports_observed_addr: true
         });
     }
 
@@ -179,15 +247,68 @@ mod tests {
             initiator_pub_key: Public::random(),
             recipient_pub_key: Public::random(),
             network_id: "ab".into(),
-            initiator_port: 3100
+            initiator_port: 3100,
+            supports_observed_addr: false
         });
     }
 
+    #[test]
+    fn encode_and_decode_sync2_supporting_observed_addr() {
+        rlp_encode_and_decode_test!(OutgoingMessage::Sync2 {
+            initiator_pub_key: Public::random(),
+            recipient_pub_key: Public::random(),
+            network_id: "ab".into(),
+            initiator_port: 3100,
+            supports_observed_addr: true
+        });
+    }
+
+    #[test]
+    fn decode_sync1_of_the_previous_version() {
+        let initiator_pub_key = Public::random();
+        let mut s = RlpStream::new_list(4);
+        s.append(&SYNC1_ID).append(&initiator_pub_key).append(&NetworkId::from("ab")).append(&3100u16);
+        assert_eq!(
+            Ok(OutgoingMessage::Sync1 {
+                initiator_pub_key,
+                network_id: "ab".into(),
+                initiator_port: 3100,
+                supports_observed_addr: false,
+            }),
+            UntrustedRlp::new(&s.out()).as_val()
+        );
+    }
+
+    #[test]
+    fn decode_ack_of_the_previous_version() {
+        let recipient_pub_key = Public::random();
+        let mut s = RlpStream::new_list(3);
+        s.append(&ACK_ID).append(&recipient_pub_key).append(&vec![1u8, 23, 4, 5, 6]);
+        assert_eq!(
+            Ok(IncomingMessage::Ack {
+                recipient_pub_key,
+                encrypted_nonce: vec![1, 23, 4, 5, 6],
+                observed_addr: None,
+            }),
+            UntrustedRlp::new(&s.out()).as_val()
+        );
+    }
+
     #[test]
     fn encode_and_decode_ack() {
         rlp_encode_and_decode_test!(IncomingMessage::Ack {
             recipient_pub_key: Public::random(),
             encrypted_nonce: vec![1, 23, 4, 5, 6],
+            observed_addr: None,
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_ack_with_observed_addr() {
+        rlp_encode_and_decode_test!(IncomingMessage::Ack {
+            recipient_pub_key: Public::random(),
+            encrypted_nonce: vec![1, 23, 4, 5, 6],
+            observed_addr: Some(SocketAddr::v4(1, 2, 3, 4, 3485)),
         });
     }
 
@@ -25,7 +25,7 @@ use mio::{PollOpt, Ready, Token};
 use super::{EstablishedConnection, IncomingMessage, OutgoingMessage, Result};
 use crate::session::Session;
 use crate::stream::Stream;
-use crate::SocketAddr;
+use crate::{BandwidthLimits, SocketAddr};
 
 pub struct OutgoingConnection {
     stream: Stream,
@@ -63,12 +63,14 @@ impl OutgoingConnection {
                 network_id: self.network_id,
                 initiator_port: self.initiator_port,
                 recipient_pub_key,
+                supports_observed_addr: true,
             })
         } else {
             self.stream.write(&OutgoingMessage::Sync1 {
                 initiator_pub_key: self.initiator_pub_key,
                 network_id: self.network_id,
                 initiator_port: self.initiator_port,
+                supports_observed_addr: true,
             })
         }
     }
@@ -86,9 +88,9 @@ impl OutgoingConnection {
         &self.peer_addr
     }
 
-    pub fn establish(self, session: Session) -> Result<EstablishedConnection> {
+    pub fn establish(self, session: Session, bandwidth_limits: &BandwidthLimits) -> Result<EstablishedConnection> {
         let peer_addr = self.stream.peer_addr()?;
-        Ok(EstablishedConnection::new(self.stream, session, peer_addr))
+        Ok(EstablishedConnection::new(self.stream, session, peer_addr, bandwidth_limits))
     }
 
     pub fn register<Message>(&self, reg: Token, event_loop: &mut EventLoop<IoManager<Message>>) -> io::Result<()>
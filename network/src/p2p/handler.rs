@@ -22,7 +22,7 @@ use std::time::{Duration, Instant};
 
 use ccrypto::aes::SymmetricCipherError;
 use cio::{IoChannel, IoContext, IoHandler, IoHandlerResult, IoManager, StreamToken, TimerToken};
-use ckey::NetworkId;
+use ckey::{KeyPair, NetworkId};
 use finally_block::finally;
 use mio::deprecated::EventLoop;
 use mio::{PollOpt, Ready, Token};
@@ -37,11 +37,15 @@ use super::connection::{
     EstablishedConnection, IncomingConnection, IncomingMessage, OutgoingConnection, OutgoingMessage,
 };
 use super::listener::Listener;
-use super::{NegotiationMessage, NetworkMessage};
+use super::{IdentityMessage, NegotiationMessage, NetworkMessage};
 use crate::client::Client;
+use crate::extension::Priority;
+use crate::nat::ExternalAddress;
 use crate::session::Session;
 use crate::stream::Stream;
-use crate::{FiltersControl, NodeId, RoutingTable, SocketAddr};
+use crate::{
+    AddressBook, BandwidthLimits, FiltersControl, NodeId, Reputation, ReservedPeers, RoutingTable, SocketAddr,
+};
 
 pub const MAX_INBOUND_CONNECTIONS: usize = 1000;
 pub const MAX_OUTBOUND_CONNECTIONS: usize = 1000;
@@ -64,6 +68,10 @@ const LAST_OUTGOING: StreamToken = FIRST_OUTGOING + MAX_OUTGOING_CONNECTIONS - 1
 
 const CREATE_CONNECTIONS: TimerToken = 0;
 const CONNECT_TO_BOOTSTRAP: TimerToken = CREATE_CONNECTIONS + 1;
+const EXPIRE_BANS: TimerToken = CONNECT_TO_BOOTSTRAP + 1;
+const REMOVE_STALE_PEERS: TimerToken = EXPIRE_BANS + 1;
+const FLUSH_THROTTLED: TimerToken = REMOVE_STALE_PEERS + 1;
+const CONNECT_TO_RESERVED: TimerToken = FLUSH_THROTTLED + 1;
 
 const FIRST_WAIT_SYNC: TimerToken = FIRST_INCOMING;
 const LAST_WAIT_SYNC: TimerToken = LAST_INCOMING;
@@ -75,6 +83,10 @@ const FIRST_TRY_SYNC: TimerToken = FIRST_OUTGOING + 1000;
 const LAST_TRY_SYNC: TimerToken = LAST_OUTGOING + 1000;
 
 const CREATE_CONNECTION_INTERVAL: Duration = Duration::from_secs(3);
+const EXPIRE_BANS_INTERVAL: Duration = Duration::from_secs(60);
+const REMOVE_STALE_PEERS_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const FLUSH_THROTTLED_INTERVAL: Duration = Duration::from_millis(100);
+const CONNECT_TO_RESERVED_INTERVAL: Duration = Duration::from_secs(10);
 
 const RETRY_SYNC_MAX: Duration = Duration::from_secs(10); // T1
 const RTT: Duration = Duration::from_secs(10); // T2
@@ -103,6 +115,12 @@ pub struct Handler {
 
     routing_table: Arc<RoutingTable>,
     filters: Arc<FiltersControl>,
+    reputation: Arc<Reputation>,
+    address_book: Arc<AddressBook>,
+    external_address: Arc<ExternalAddress>,
+    bandwidth_limits: BandwidthLimits,
+    node_key: KeyPair,
+    reserved_peers: ReservedPeers,
 
     remote_node_ids: RwLock<HashMap<StreamToken, NodeId>>,
     remote_node_ids_reverse: RwLock<HashMap<NodeId, StreamToken>>,
@@ -127,6 +145,12 @@ impl Handler {
         client: Arc<Client>,
         routing_table: Arc<RoutingTable>,
         filters: Arc<FiltersControl>,
+        reputation: Arc<Reputation>,
+        address_book: Arc<AddressBook>,
+        external_address: Arc<ExternalAddress>,
+        bandwidth_limits: BandwidthLimits,
+        node_key: KeyPair,
+        reserved_peers: ReservedPeers,
         bootstrap_addresses: Vec<SocketAddr>,
         min_peers: usize,
         max_peers: usize,
@@ -157,6 +181,12 @@ impl Handler {
 
             routing_table,
             filters,
+            reputation,
+            address_book,
+            external_address,
+            bandwidth_limits,
+            node_key,
+            reserved_peers,
 
             remote_node_ids: Default::default(),
             remote_node_ids_reverse: Default::default(),
@@ -200,6 +230,10 @@ impl Handler {
         if !self.filters.is_allowed(&ip) {
             return Err(format!("New connection to {} is requested. But it's not allowed", ip).into())
         }
+        if !self.reserved_peers.is_dialable(&socket_address) {
+            ctrace!(NETWORK, "{} is not a reserved peer", socket_address);
+            return Ok(())
+        }
 
         let initiator_pub_key = if let Some(initiator_pub_key) = self.routing_table.local_public(socket_address) {
             initiator_pub_key
@@ -229,10 +263,32 @@ impl Handler {
             cinfo!(NETWORK, "New connection to {}({})", socket_address, token);
         } else {
             cwarn!(NETWORK, "Cannot create a connection to {}", socket_address);
+            self.address_book.record_failure(&socket_address);
+        }
+        Ok(())
+    }
+
+    fn verify_identity(&self, con: &mut EstablishedConnection, identity: &IdentityMessage) -> Result<(), String> {
+        let peer_addr = *con.peer_addr();
+        if !identity.is_valid(con.session()) {
+            return Err(format!("{} sent an identity message with an invalid signature", peer_addr))
+        }
+        let public = *identity.public();
+        if !self.reserved_peers.is_acceptable(&public) {
+            return Err(format!("{}({:x}) is not a reserved peer", peer_addr, public))
         }
+        ctrace!(NETWORK, "The node key of {} is {:x}", peer_addr, public);
+        con.set_remote_node_key(public);
+        self.routing_table.set_remote_node_key(peer_addr, public);
         Ok(())
     }
 
+    fn record_success(&self, peer_addr: SocketAddr) {
+        if let Some(remote_public) = self.routing_table.remote_public(&peer_addr) {
+            self.address_book.record_success(peer_addr, remote_public);
+        }
+    }
+
     pub fn recent_network_usage(&self) -> HashMap<String, usize> {
         let mut network_usage_in_10_seconds = self.network_usage_in_10_seconds.lock();
         let mut result = HashMap::with_capacity(network_usage_in_10_seconds.len());
@@ -289,6 +345,23 @@ impl IoHandler<Message> for Handler {
         io.register_stream(ACCEPT);
         io.register_timer_once(CREATE_CONNECTIONS, CREATE_CONNECTION_INTERVAL);
         io.register_timer_once(CONNECT_TO_BOOTSTRAP, Duration::default());
+        self.address_book.remove_stale();
+        let known_addresses = self.address_book.addresses();
+        cinfo!(NETWORK, "{} peers are loaded from the address book", known_addresses.len());
+        self.routing_table.touch_addresses(known_addresses);
+        io.register_timer_once(REMOVE_STALE_PEERS, REMOVE_STALE_PEERS_INTERVAL);
+        for addr in self.reputation.banned_addresses() {
+            self.routing_table.ban(addr);
+        }
+        io.register_timer_once(EXPIRE_BANS, EXPIRE_BANS_INTERVAL);
+        cinfo!(NETWORK, "The node key is {:x}", self.node_key.public());
+        if !self.reserved_peers.addresses().is_empty() {
+            self.routing_table.touch_addresses(self.reserved_peers.addresses());
+            io.register_timer_once(CONNECT_TO_RESERVED, Duration::default());
+        }
+        if !self.bandwidth_limits.is_unlimited() {
+            io.register_timer_once(FLUSH_THROTTLED, FLUSH_THROTTLED_INTERVAL);
+        }
         Ok(())
     }
 
@@ -316,6 +389,7 @@ impl IoHandler<Message> for Handler {
                 for addr in candidates.into_iter().take(self.min_peers - current_connections) {
                     if let Err(err) = self.connect(io, addr) {
                         self.routing_table.remove(&addr);
+                        self.address_book.record_failure(&addr);
                         cwarn!(NETWORK, "Cannot connect to {}: {:?}", addr, err);
                     }
                 }
@@ -363,6 +437,53 @@ impl IoHandler<Message> for Handler {
                 const CHECK_BOOTSTRAP_INTERVAL: Duration = Duration::from_secs(15);
                 io.register_timer_once(CONNECT_TO_BOOTSTRAP, CHECK_BOOTSTRAP_INTERVAL);
             }
+            EXPIRE_BANS => {
+                for addr in self.reputation.expire_bans() {
+                    if self.routing_table.unban(addr) {
+                        cinfo!(NETWORK, "The ban on {} has expired", addr);
+                    }
+                }
+                io.register_timer_once(EXPIRE_BANS, EXPIRE_BANS_INTERVAL);
+            }
+            REMOVE_STALE_PEERS => {
+                let removed = self.address_book.remove_stale();
+                if removed != 0 {
+                    cinfo!(NETWORK, "{} stale peers are removed from the address book", removed);
+                }
+                io.register_timer_once(REMOVE_STALE_PEERS, REMOVE_STALE_PEERS_INTERVAL);
+            }
+            CONNECT_TO_RESERVED => {
+                // The reserved peers are connected again whenever they are disconnected, even if they were banned.
+                let _l = self.connecting_lock.lock();
+                for addr in self.reserved_peers.addresses() {
+                    if self.routing_table.is_establishing_or_established(&addr) {
+                        continue
+                    }
+                    if self.routing_table.unban(addr) {
+                        cinfo!(NETWORK, "{} is unbanned because it's a reserved peer", addr);
+                    }
+                    self.routing_table.touch(addr);
+                    if let Err(err) = self.connect(io, addr) {
+                        self.routing_table.remove(&addr);
+                        cwarn!(NETWORK, "Cannot connect to the reserved peer {}: {:?}", addr, err);
+                    }
+                }
+                io.register_timer_once(CONNECT_TO_RESERVED, CONNECT_TO_RESERVED_INTERVAL);
+            }
+            FLUSH_THROTTLED => {
+                // The writable event doesn't come again for the messages held back by the bandwidth limits.
+                for connections in &[&self.inbound_connections, &self.outbound_connections] {
+                    for (stream, con) in connections.write().iter_mut() {
+                        if !con.has_pending_messages() {
+                            continue
+                        }
+                        if let Err(err) = con.flush() {
+                            cdebug!(NETWORK, "Cannot flush the throttled messages to {}: {:?}", stream, err);
+                        }
+                    }
+                }
+                io.register_timer_once(FLUSH_THROTTLED, FLUSH_THROTTLED_INTERVAL);
+            }
             FIRST_WAIT_SYNC...LAST_WAIT_SYNC => {
                 cwarn!(NETWORK, "No sync message from {}", timer);
                 io.deregister_stream(wait_sync_stream(timer));
@@ -425,6 +546,7 @@ impl IoHandler<Message> for Handler {
                 node_id,
                 extension_name,
                 need_encryption,
+                priority,
                 data,
             } => {
                 let stream =
@@ -438,7 +560,12 @@ impl IoHandler<Message> for Handler {
                             });
 
                             (
-                                con.enqueue_extension_message(extension_name.to_string(), need_encryption, data)?,
+                                con.enqueue_extension_message(
+                                    extension_name.to_string(),
+                                    need_encryption,
+                                    priority,
+                                    data,
+                                )?,
                                 *con.peer_addr(),
                             )
                         } else {
@@ -452,7 +579,12 @@ impl IoHandler<Message> for Handler {
                                 io.update_registration(stream);
                             });
                             (
-                                con.enqueue_extension_message(extension_name.to_string(), need_encryption, data)?,
+                                con.enqueue_extension_message(
+                                    extension_name.to_string(),
+                                    need_encryption,
+                                    priority,
+                                    data,
+                                )?,
                                 *con.peer_addr(),
                             )
                         } else {
@@ -489,7 +621,7 @@ impl IoHandler<Message> for Handler {
                 }
             }
             Message::Established {
-                connection,
+                mut connection,
                 is_inbound: true,
             } => {
                 let mut inbound_connections = self.inbound_connections.write();
@@ -510,6 +642,17 @@ impl IoHandler<Message> for Handler {
                         token
                     );
 
+                    self.record_success(*connection.peer_addr());
+                    let network_message_size = connection.enqueue_identity(&self.node_key);
+                    {
+                        let mut network_usage_in_10_seconds = self.network_usage_in_10_seconds.lock();
+                        insert_network_usage(
+                            network_usage_in_10_seconds
+                                .entry(format!("negotiation@{}", connection.peer_addr()))
+                                .or_default(),
+                            network_message_size,
+                        );
+                    }
                     let t = inbound_connections.insert(token, connection);
                     assert!(t.is_none());
                     io.register_stream(token);
@@ -524,6 +667,7 @@ impl IoHandler<Message> for Handler {
                 let mut outbound_connections = self.outbound_connections.write();
                 if let Some(token) = self.outbound_tokens.lock().gen() {
                     let peer_addr = *connection.peer_addr();
+                    self.record_success(peer_addr);
                     let remote_node_id = peer_addr.into();
                     assert_eq!(
                         None,
@@ -540,7 +684,8 @@ impl IoHandler<Message> for Handler {
                         token
                     );
 
-                    let mut network_message_size = 0;
+                    // The identity goes first, so that the peer can check it before the negotiation.
+                    let mut network_message_size = connection.enqueue_identity(&self.node_key);
                     for (name, versions) in self.client.extension_versions() {
                         network_message_size += connection.enqueue_negotiation_request(name.clone(), versions);
                     }
@@ -617,7 +762,8 @@ impl IoHandler<Message> for Handler {
                             + incoming_connections.len()
                             + outgoing_connections.len();
 
-                        if self.max_peers < current_connections {
+                        let is_reserved = self.reserved_peers.contains_ip(&socket_address.ip());
+                        if self.max_peers < current_connections && !is_reserved {
                             cinfo!(
                                 NETWORK,
                                 "New connection from {} is dropped because there are too many connections({} < {})",
@@ -634,6 +780,10 @@ impl IoHandler<Message> for Handler {
                         cwarn!(NETWORK, "P2P connection request from {} is received. But it's not allowed", ip);
                         return Ok(())
                     }
+                    if self.reserved_peers.is_reserved_only() && !self.reserved_peers.contains_ip(&ip) {
+                        cinfo!(NETWORK, "P2P connection request from {} is refused in the reserved only mode", ip);
+                        return Ok(())
+                    }
                     let token = self
                         .incoming_tokens
                         .lock()
@@ -664,10 +814,26 @@ impl IoHandler<Message> for Handler {
                             let unencrypted = msg.unencrypted_data(con.session()).map_err(|e| format!("{:?}", e))?;
                             self.client.on_message(msg.extension_name(), &remote_node_id, unencrypted);
                         }
+                        Some(NetworkMessage::Identity(identity)) => {
+                            if let Err(err) = self.verify_identity(con, &identity) {
+                                should_update.store(false, Ordering::SeqCst);
+                                io.deregister_stream(stream_token);
+                                return Err(err.into())
+                            }
+                        }
                         Some(NetworkMessage::Negotiation(NegotiationMessage::Request {
                             extension_name,
                             extension_versions,
                         })) => {
+                            if self.reserved_peers.is_reserved_only() && con.remote_node_key().is_none() {
+                                should_update.store(false, Ordering::SeqCst);
+                                io.deregister_stream(stream_token);
+                                return Err(format!(
+                                    "{} requested a negotiation without its identity",
+                                    con.peer_addr()
+                                )
+                                .into())
+                            }
                             let versions = self
                                 .client
                                 .extension_versions()
@@ -733,6 +899,13 @@ impl IoHandler<Message> for Handler {
                             let unencrypted = msg.unencrypted_data(con.session()).map_err(|e| format!("{:?}", e))?;
                             self.client.on_message(msg.extension_name(), &remote_node_id, unencrypted);
                         }
+                        Some(NetworkMessage::Identity(identity)) => {
+                            if let Err(err) = self.verify_identity(con, &identity) {
+                                should_update.store(false, Ordering::SeqCst);
+                                io.deregister_stream(stream_token);
+                                return Err(err.into())
+                            }
+                        }
                         Some(NetworkMessage::Negotiation(NegotiationMessage::Request {
                             ..
                         })) => {
@@ -748,6 +921,15 @@ impl IoHandler<Message> for Handler {
                             extension_name,
                             allowed_version,
                         })) => {
+                            if self.reserved_peers.is_reserved_only() && con.remote_node_key().is_none() {
+                                should_update.store(false, Ordering::SeqCst);
+                                io.deregister_stream(stream_token);
+                                return Err(format!(
+                                    "{} responded to a negotiation without its identity",
+                                    con.peer_addr()
+                                )
+                                .into())
+                            }
                             let remote_node_id = *self.remote_node_ids.read().get(&stream_token).unwrap_or_else(|| {
                                 unreachable!("Node id for {}:{} must exist", stream_token, con.peer_addr())
                             });
@@ -775,6 +957,7 @@ impl IoHandler<Message> for Handler {
                             initiator_pub_key,
                             network_id,
                             initiator_port,
+                            supports_observed_addr,
                         }) => {
                             let from = con.remote_addr(initiator_port)?;
                             if network_id != self.network_id {
@@ -786,7 +969,13 @@ impl IoHandler<Message> for Handler {
                                 self.routing_table.set_recipient_establish1(from, initiator_pub_key)?
                             {
                                 cinfo!(NETWORK, "Send ack to {}", from);
-                                let network_message_size = con.send_ack(local_public, encrypted_nonce);
+                                // The initiators of the previous version cannot decode the observed address.
+                                let observed_addr = if supports_observed_addr {
+                                    Some(from)
+                                } else {
+                                    None
+                                };
+                                let network_message_size = con.send_ack(local_public, encrypted_nonce, observed_addr);
                                 let t = self
                                     .establishing_incoming_session
                                     .lock()
@@ -813,6 +1002,7 @@ impl IoHandler<Message> for Handler {
                             recipient_pub_key,
                             network_id,
                             initiator_port,
+                            supports_observed_addr,
                         }) => {
                             let from = con.remote_addr(initiator_port)?;
                             if network_id != self.network_id {
@@ -825,7 +1015,13 @@ impl IoHandler<Message> for Handler {
                                 .set_recipient_establish2(from, recipient_pub_key, initiator_pub_key)?
                             {
                                 cinfo!(NETWORK, "Send ack to {}", from);
-                                let network_message_size = con.send_ack(local_public, encrypted_nonce);
+                                // The initiators of the previous version cannot decode the observed address.
+                                let observed_addr = if supports_observed_addr {
+                                    Some(from)
+                                } else {
+                                    None
+                                };
+                                let network_message_size = con.send_ack(local_public, encrypted_nonce, observed_addr);
                                 let t = self
                                     .establishing_incoming_session
                                     .lock()
@@ -869,7 +1065,11 @@ impl IoHandler<Message> for Handler {
                         Some(IncomingMessage::Ack {
                             recipient_pub_key,
                             encrypted_nonce,
+                            observed_addr,
                         }) => {
+                            if let Some(observed_addr) = observed_addr {
+                                self.external_address.observe(&from, &observed_addr);
+                            }
                             let session = self.routing_table.set_initiator_establish(
                                 from,
                                 recipient_pub_key,
@@ -1084,7 +1284,7 @@ impl IoHandler<Message> for Handler {
                     con.deregister(event_loop)?;
                     self.incoming_tokens.lock().restore(stream);
                     if let Some((port, session)) = self.establishing_incoming_session.lock().remove(&stream) {
-                        let connection = con.establish(session, port)?;
+                        let connection = con.establish(session, port, &self.bandwidth_limits)?;
                         {
                             let peer_addr = connection.peer_addr();
                             if !self.filters.is_allowed(&peer_addr.ip()) {
@@ -1113,7 +1313,7 @@ impl IoHandler<Message> for Handler {
                     con.deregister(event_loop)?;
                     self.outgoing_tokens.lock().restore(stream);
                     if let Some(session) = self.establishing_outgoing_session.lock().remove(&stream) {
-                        let connection = con.establish(session)?;
+                        let connection = con.establish(session, &self.bandwidth_limits)?;
                         {
                             let peer_addr = connection.peer_addr();
                             if !self.filters.is_allowed(&peer_addr.ip()) {
@@ -1131,6 +1331,7 @@ impl IoHandler<Message> for Handler {
                         ctrace!(NETWORK, "Outgoing connect({}) established", stream);
                     } else {
                         self.routing_table.remove(con.peer_addr());
+                        self.address_book.record_failure(con.peer_addr());
                         ctrace!(NETWORK, "Outgoing connect({}) removed", stream);
                     }
                 } else {
@@ -1157,6 +1358,7 @@ pub enum Message {
         node_id: NodeId,
         extension_name: &'static str,
         need_encryption: bool,
+        priority: Priority,
         data: Arc<Bytes>,
     },
     Disconnect(SocketAddr),
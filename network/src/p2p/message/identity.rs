@@ -0,0 +1,106 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccrypto::Blake;
+use ckey::{sign, verify, KeyPair, Public, Signature};
+use primitives::H256;
+use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
+
+use crate::session::Session;
+
+use super::IDENTITY_ID;
+
+/// Proves that the sender owns the node key.
+/// The signature is bound to the session, so it cannot be replayed on another connection.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Message {
+    public: Public,
+    signature: Signature,
+}
+
+fn message_to_sign(session: &Session) -> H256 {
+    H256::blake_with_key(session.secret(), b"identity")
+}
+
+impl Message {
+    pub fn new(key_pair: &KeyPair, session: &Session) -> Self {
+        let signature = sign(key_pair.private(), &message_to_sign(session)).expect("The node key must be valid");
+        Self {
+            public: *key_pair.public(),
+            signature,
+        }
+    }
+
+    pub fn is_valid(&self, session: &Session) -> bool {
+        verify(&self.public, &self.signature, &message_to_sign(session)).unwrap_or(false)
+    }
+
+    pub fn public(&self) -> &Public {
+        &self.public
+    }
+}
+
+impl Encodable for Message {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3).append(&IDENTITY_ID).append(&self.public).append(&self.signature);
+    }
+}
+
+impl Decodable for Message {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let item_count = rlp.item_count()?;
+        if item_count != 3 {
+            return Err(DecoderError::RlpInvalidLength {
+                expected: 3,
+                got: item_count,
+            })
+        }
+        if rlp.val_at::<u8>(0)? != IDENTITY_ID {
+            return Err(DecoderError::Custom("Invalid id in identity message"))
+        }
+        Ok(Self {
+            public: rlp.val_at(1)?,
+            signature: rlp.val_at(2)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckey::{Generator, Random, Secret};
+    use rlp::rlp_encode_and_decode_test;
+
+    use super::*;
+
+    #[test]
+    fn encode_and_decode() {
+        let key_pair = Random.generate().unwrap();
+        let session = Session::new(Secret::random(), 3);
+        rlp_encode_and_decode_test!(Message::new(&key_pair, &session));
+    }
+
+    #[test]
+    fn valid_only_in_the_same_session() {
+        let key_pair = Random.generate().unwrap();
+        let session = Session::new(Secret::random(), 3);
+        let message = Message::new(&key_pair, &session);
+        assert!(message.is_valid(&session));
+        assert_eq!(key_pair.public(), message.public());
+
+        let other_session = Session::new(Secret::random(), 3);
+        assert!(!message.is_valid(&other_session));
+    }
+}
@@ -17,15 +17,18 @@
 use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
 
 use super::ExtensionMessage;
+use super::IdentityMessage;
 use super::NegotiationMessage;
 
 #[derive(Debug)]
 pub enum Message {
     Extension(ExtensionMessage),
     Negotiation(NegotiationMessage),
+    Identity(IdentityMessage),
 }
 
 use super::ENCRYPTED_ID;
+use super::IDENTITY_ID;
 use super::REQUEST_ID;
 use super::RESPONSE_ID;
 use super::UNENCRYPTED_ID;
@@ -35,6 +38,7 @@ impl Encodable for Message {
         match self {
             Message::Extension(message) => message.rlp_append(s),
             Message::Negotiation(message) => message.rlp_append(s),
+            Message::Identity(message) => message.rlp_append(s),
         }
     }
 }
@@ -47,6 +51,7 @@ impl Decodable for Message {
             RESPONSE_ID => Ok(Message::Negotiation(Decodable::decode(rlp)?)),
             ENCRYPTED_ID => Ok(Message::Extension(Decodable::decode(rlp)?)),
             UNENCRYPTED_ID => Ok(Message::Extension(Decodable::decode(rlp)?)),
+            IDENTITY_ID => Ok(Message::Identity(Decodable::decode(rlp)?)),
             _ => Err(DecoderError::Custom("unexpected protocol id")),
         }
     }
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod extension;
+mod identity;
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::module_inception))]
 mod message;
 mod negotiation;
@@ -23,6 +24,7 @@ mod signed_message;
 use primitives::H256;
 
 pub use self::extension::Message as ExtensionMessage;
+pub use self::identity::Message as IdentityMessage;
 pub use self::message::Message;
 pub use self::negotiation::Message as NegotiationMessage;
 pub use self::signed_message::SignedMessage;
@@ -35,10 +37,12 @@ pub const REQUEST_ID: u8 = 0x05;
 pub const RESPONSE_ID: u8 = 0x06;
 pub const ENCRYPTED_ID: u8 = 0x07;
 pub const UNENCRYPTED_ID: u8 = 0x08;
+pub const IDENTITY_ID: u8 = 0x09;
 
 #[cfg(test)]
 mod tests {
     use super::ENCRYPTED_ID;
+    use super::IDENTITY_ID;
     use super::REQUEST_ID;
     use super::RESPONSE_ID;
     use super::UNENCRYPTED_ID;
@@ -70,4 +74,12 @@ mod tests {
         assert_ne!(UNENCRYPTED_ID, RESPONSE_ID);
         assert_ne!(UNENCRYPTED_ID, ENCRYPTED_ID);
     }
+
+    #[test]
+    fn identity_id_is_a_unique() {
+        assert_ne!(IDENTITY_ID, REQUEST_ID);
+        assert_ne!(IDENTITY_ID, RESPONSE_ID);
+        assert_ne!(IDENTITY_ID, ENCRYPTED_ID);
+        assert_ne!(IDENTITY_ID, UNENCRYPTED_ID);
+    }
 }
@@ -21,4 +21,4 @@ mod message;
 mod stream;
 
 pub use self::handler::{Handler, Message};
-use self::message::{ExtensionMessage, Message as NetworkMessage, NegotiationMessage, SignedMessage};
+use self::message::{ExtensionMessage, IdentityMessage, Message as NetworkMessage, NegotiationMessage, SignedMessage};
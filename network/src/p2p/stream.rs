@@ -99,6 +99,21 @@ impl SignedStream {
         self.stream.write(&SignedMessage::new(message, &self.session))
     }
 
+    /// Signs the message without writing it, so that it can be queued before `write_bytes`.
+    pub fn encode<M>(&self, message: &M) -> Vec<u8>
+    where
+        M: Encodable, {
+        SignedMessage::new(message, &self.session).rlp_bytes().into_vec()
+    }
+
+    pub fn write_bytes(&mut self, bytes: Vec<u8>) {
+        self.stream.write_bytes(bytes);
+    }
+
+    pub fn is_flushed(&self) -> bool {
+        self.stream.is_flushed()
+    }
+
     pub fn flush(&mut self) -> Result<()> {
         self.stream.flush()?;
         Ok(())
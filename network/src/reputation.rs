@@ -0,0 +1,308 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use kvdb::{DBTransaction, KeyValueDB};
+use parking_lot::RwLock;
+use rlp::{DecoderError, RlpStream, UntrustedRlp};
+
+use crate::SocketAddr;
+
+const REPUTATION_KEY: &[u8] = b"reputation";
+
+/// A peer is banned when its penalty reaches this
+const BAN_THRESHOLD: u64 = 100;
+/// Penalty decays by half in this many seconds
+const PENALTY_HALF_LIFE: u64 = 60 * 60;
+/// The first ban lasts this many seconds, and each following ban doubles it
+const BASE_BAN_DURATION: u64 = 10 * 60;
+const MAX_BAN_DURATION: u64 = 24 * 60 * 60;
+
+/// Misbehaviors that the extensions report about their peers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Misbehavior {
+    /// Sent a message that cannot be decoded
+    MalformedMessage,
+    /// Sent a message that is not allowed by the protocol, e.g. a response of another type
+    ProtocolViolation,
+    /// Sent a block, a header or a transaction that fails the verification
+    InvalidData,
+    /// Sent a consensus message with an invalid signature or from a non-validator
+    InvalidConsensusMessage,
+}
+
+impl Misbehavior {
+    fn penalty(self) -> u64 {
+        match self {
+            Misbehavior::MalformedMessage => 25,
+            Misbehavior::ProtocolViolation => 25,
+            Misbehavior::InvalidData => BAN_THRESHOLD,
+            Misbehavior::InvalidConsensusMessage => 50,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct PeerScore {
+    /// The penalty at `updated_at`
+    pub penalty: u64,
+    /// Seconds since the epoch
+    pub updated_at: u64,
+    /// The number of times that the peer was banned
+    pub bans: u64,
+    /// Seconds since the epoch, zero if the peer is not banned
+    pub banned_until: u64,
+}
+
+impl PeerScore {
+    /// The penalty at `now`, which halves every `PENALTY_HALF_LIFE`.
+    pub fn penalty_at(&self, now: u64) -> u64 {
+        let elapsed = now.saturating_sub(self.updated_at);
+        let halvings = elapsed / PENALTY_HALF_LIFE;
+        if halvings >= 64 {
+            return 0
+        }
+        let penalty = self.penalty >> halvings;
+        // Decreases linearly between the halvings.
+        penalty - penalty * (elapsed % PENALTY_HALF_LIFE) / (2 * PENALTY_HALF_LIFE)
+    }
+
+    pub fn is_banned_at(&self, now: u64) -> bool {
+        self.banned_until > now
+    }
+}
+
+/// Scores of the peers that misbehaved, which are kept in the database across restarts.
+pub struct Reputation {
+    db: Arc<KeyValueDB>,
+    col: Option<u32>,
+    scores: RwLock<HashMap<SocketAddr, PeerScore>>,
+}
+
+impl Reputation {
+    #![cfg_attr(feature = "cargo-clippy", allow(clippy::new_ret_no_self))]
+    pub fn new(db: Arc<KeyValueDB>, col: Option<u32>) -> Arc<Self> {
+        let scores = match db.get(col, REPUTATION_KEY) {
+            Ok(Some(bytes)) => decode_scores(&bytes).unwrap_or_else(|err| {
+                cwarn!(NETWORK, "Cannot decode the peer scores: {:?}", err);
+                HashMap::new()
+            }),
+            Ok(None) => HashMap::new(),
+            Err(err) => {
+                cwarn!(NETWORK, "Cannot read the peer scores: {:?}", err);
+                HashMap::new()
+            }
+        };
+        Arc::new(Self {
+            db,
+            col,
+            scores: RwLock::new(scores),
+        })
+    }
+
+    /// Adds the penalty of the misbehavior to the peer.
+    /// Returns true if the peer has to be banned.
+    pub fn report(&self, addr: SocketAddr, misbehavior: Misbehavior) -> bool {
+        self.report_at(addr, misbehavior, now())
+    }
+
+    fn report_at(&self, addr: SocketAddr, misbehavior: Misbehavior, now: u64) -> bool {
+        let banned = {
+            let mut scores = self.scores.write();
+            let score = scores.entry(addr).or_default();
+            if score.is_banned_at(now) {
+                return false
+            }
+            let penalty = score.penalty_at(now) + misbehavior.penalty();
+            cinfo!(NETWORK, "{} misbehaved({:?}), penalty: {}", addr, misbehavior, penalty);
+            if penalty >= BAN_THRESHOLD {
+                let duration = ban_duration(score.bans);
+                cinfo!(NETWORK, "{} is banned for {} seconds", addr, duration);
+                *score = PeerScore {
+                    penalty: 0,
+                    updated_at: now,
+                    bans: score.bans + 1,
+                    banned_until: now + duration,
+                };
+                true
+            } else {
+                score.penalty = penalty;
+                score.updated_at = now;
+                false
+            }
+        };
+        self.save();
+        banned
+    }
+
+    pub fn banned_addresses(&self) -> Vec<SocketAddr> {
+        let now = now();
+        self.scores.read().iter().filter(|(_, score)| score.is_banned_at(now)).map(|(addr, _)| *addr).collect()
+    }
+
+    /// Lifts the bans that are over, and returns the addresses of them.
+    pub fn expire_bans(&self) -> Vec<SocketAddr> {
+        self.expire_bans_at(now())
+    }
+
+    fn expire_bans_at(&self, now: u64) -> Vec<SocketAddr> {
+        let expired: Vec<_> = {
+            let mut scores = self.scores.write();
+            let expired: Vec<_> = scores
+                .iter()
+                .filter(|(_, score)| score.banned_until != 0 && !score.is_banned_at(now))
+                .map(|(addr, _)| *addr)
+                .collect();
+            for addr in &expired {
+                scores.get_mut(addr).expect("The address is found above").banned_until = 0;
+            }
+            expired
+        };
+        if !expired.is_empty() {
+            self.save();
+        }
+        expired
+    }
+
+    /// The scores with the penalties at the current time.
+    pub fn scores(&self) -> Vec<(SocketAddr, PeerScore)> {
+        let now = now();
+        self.scores
+            .read()
+            .iter()
+            .map(|(addr, score)| {
+                (*addr, PeerScore {
+                    penalty: score.penalty_at(now),
+                    updated_at: now,
+                    ..*score
+                })
+            })
+            .collect()
+    }
+
+    fn save(&self) {
+        let mut stream = RlpStream::new();
+        {
+            let scores = self.scores.read();
+            stream.begin_list(scores.len());
+            for (addr, score) in scores.iter() {
+                stream.begin_list(2).append(addr).append(score);
+            }
+        }
+        let mut batch = DBTransaction::new();
+        batch.put(self.col, REPUTATION_KEY, &stream.out());
+        if let Err(err) = self.db.write(batch) {
+            cwarn!(NETWORK, "Cannot write the peer scores: {:?}", err);
+        }
+    }
+}
+
+fn decode_scores(bytes: &[u8]) -> Result<HashMap<SocketAddr, PeerScore>, DecoderError> {
+    UntrustedRlp::new(bytes).iter().map(|entry| Ok((entry.val_at(0)?, entry.val_at(1)?))).collect()
+}
+
+fn ban_duration(bans: u64) -> u64 {
+    if bans >= 8 {
+        return MAX_BAN_DURATION
+    }
+    (BASE_BAN_DURATION << bans).min(MAX_BAN_DURATION)
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("Current time should be later than unix epoch").as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::v4(10, 0, 0, 1, port)
+    }
+
+    #[test]
+    fn penalty_decays() {
+        let score = PeerScore {
+            penalty: 80,
+            updated_at: 1000,
+            bans: 0,
+            banned_until: 0,
+        };
+        assert_eq!(80, score.penalty_at(1000));
+        assert_eq!(60, score.penalty_at(1000 + PENALTY_HALF_LIFE / 2));
+        assert_eq!(40, score.penalty_at(1000 + PENALTY_HALF_LIFE));
+        assert_eq!(20, score.penalty_at(1000 + 2 * PENALTY_HALF_LIFE));
+        assert_eq!(0, score.penalty_at(1000 + 100 * PENALTY_HALF_LIFE));
+    }
+
+    #[test]
+    fn ban_when_penalty_reaches_threshold() {
+        let reputation = Reputation::new(Arc::new(kvdb_memorydb::create(1)), Some(0));
+        let now = 1000;
+        for _ in 0..3 {
+            assert!(!reputation.report_at(addr(1), Misbehavior::MalformedMessage, now));
+        }
+        assert!(reputation.report_at(addr(1), Misbehavior::MalformedMessage, now));
+        // Reports during the ban don't extend it.
+        assert!(!reputation.report_at(addr(1), Misbehavior::InvalidData, now));
+
+        // The penalty decays before it reaches the threshold.
+        for hour in 0..10 {
+            assert!(!reputation.report_at(addr(2), Misbehavior::MalformedMessage, now + hour * PENALTY_HALF_LIFE));
+        }
+    }
+
+    #[test]
+    fn ban_is_time_boxed() {
+        let reputation = Reputation::new(Arc::new(kvdb_memorydb::create(1)), Some(0));
+        let now = 1000;
+        assert!(reputation.report_at(addr(1), Misbehavior::InvalidData, now));
+        assert!(reputation.expire_bans_at(now + BASE_BAN_DURATION - 1).is_empty());
+        assert_eq!(vec![addr(1)], reputation.expire_bans_at(now + BASE_BAN_DURATION));
+
+        // The next ban lasts twice as long.
+        let now = now + BASE_BAN_DURATION;
+        assert!(reputation.report_at(addr(1), Misbehavior::InvalidData, now));
+        assert!(reputation.expire_bans_at(now + BASE_BAN_DURATION).is_empty());
+        assert_eq!(vec![addr(1)], reputation.expire_bans_at(now + 2 * BASE_BAN_DURATION));
+    }
+
+    #[test]
+    fn scores_are_persistent() {
+        let db: Arc<KeyValueDB> = Arc::new(kvdb_memorydb::create(1));
+        let reputation = Reputation::new(Arc::clone(&db), Some(0));
+        assert!(!reputation.report(addr(1), Misbehavior::InvalidConsensusMessage));
+        assert!(reputation.report(addr(2), Misbehavior::InvalidData));
+
+        let reloaded = Reputation::new(db, Some(0));
+        let mut scores = reloaded.scores();
+        scores.sort_by_key(|(addr, _)| *addr);
+        assert_eq!(2, scores.len());
+        assert_eq!(50, scores[0].1.penalty);
+        assert_eq!(1, scores[1].1.bans);
+        assert_eq!(vec![addr(2)], reloaded.banned_addresses());
+    }
+
+    #[test]
+    fn ban_duration_is_bounded() {
+        assert_eq!(BASE_BAN_DURATION, ban_duration(0));
+        assert_eq!(2 * BASE_BAN_DURATION, ban_duration(1));
+        assert_eq!(MAX_BAN_DURATION, ban_duration(100));
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use ckey::Public;
+
+use crate::SocketAddr;
+
+/// A peer that this node always keeps connected, written as `<public key>@<ip>:<port>`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReservedPeer {
+    pub public: Public,
+    pub address: SocketAddr,
+}
+
+impl FromStr for ReservedPeer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.splitn(2, '@');
+        let (public, address) = match (split.next(), split.next()) {
+            (Some(public), Some(address)) => (public, address),
+            _ => return Err(format!("Invalid reserved peer {}, expected PUBLIC@IP:PORT", s)),
+        };
+        let public = public.trim_start_matches("0x");
+        Ok(Self {
+            public: public.parse().map_err(|err| format!("Invalid public key {}: {:?}", public, err))?,
+            address: address.parse().map_err(|err| format!("Invalid address {}: {:?}", address, err))?,
+        })
+    }
+}
+
+impl fmt::Display for ReservedPeer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:x}@{}", self.public, self.address)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ReservedPeers {
+    peers: Vec<ReservedPeer>,
+    /// Only the reserved peers are connected and all the others are refused.
+    only: bool,
+}
+
+impl ReservedPeers {
+    pub fn new(peers: Vec<ReservedPeer>, only: bool) -> Self {
+        Self {
+            peers,
+            only,
+        }
+    }
+
+    pub fn is_reserved_only(&self) -> bool {
+        self.only
+    }
+
+    pub fn addresses(&self) -> Vec<SocketAddr> {
+        self.peers.iter().map(|peer| peer.address).collect()
+    }
+
+    pub fn contains_address(&self, address: &SocketAddr) -> bool {
+        self.peers.iter().any(|peer| &peer.address == address)
+    }
+
+    pub fn contains_ip(&self, ip: &IpAddr) -> bool {
+        self.peers.iter().any(|peer| &peer.address.ip() == ip)
+    }
+
+    pub fn contains_public(&self, public: &Public) -> bool {
+        self.peers.iter().any(|peer| &peer.public == public)
+    }
+
+    /// Whether a connection to the address can be made in the current mode.
+    pub fn is_dialable(&self, address: &SocketAddr) -> bool {
+        !self.only || self.contains_address(address)
+    }
+
+    /// Whether a peer that proved the ownership of the key is accepted in the current mode.
+    pub fn is_acceptable(&self, public: &Public) -> bool {
+        !self.only || self.contains_public(public)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let public = Public::random();
+        let s = format!("{:x}@127.0.0.1:3485", public);
+        let peer: ReservedPeer = s.parse().unwrap();
+        assert_eq!(public, peer.public);
+        assert_eq!(SocketAddr::v4(127, 0, 0, 1, 3485), peer.address);
+        assert_eq!(s, peer.to_string());
+
+        let with_prefix: ReservedPeer = format!("0x{}", s).parse().unwrap();
+        assert_eq!(peer, with_prefix);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!("127.0.0.1:3485".parse::<ReservedPeer>().is_err());
+        assert!("1234@127.0.0.1:3485".parse::<ReservedPeer>().is_err());
+        assert!(format!("{:x}@127.0.0.1", Public::random()).parse::<ReservedPeer>().is_err());
+    }
+
+    #[test]
+    fn reserved_only() {
+        let peer = ReservedPeer {
+            public: Public::random(),
+            address: SocketAddr::v4(10, 0, 0, 1, 3485),
+        };
+        let other_address = SocketAddr::v4(10, 0, 0, 2, 3485);
+        let other_public = Public::random();
+
+        let reserved = ReservedPeers::new(vec![peer], false);
+        assert!(reserved.is_dialable(&other_address));
+        assert!(reserved.is_acceptable(&other_public));
+
+        let reserved = ReservedPeers::new(vec![peer], true);
+        assert!(reserved.is_dialable(&peer.address));
+        assert!(reserved.is_acceptable(&peer.public));
+        assert!(!reserved.is_dialable(&other_address));
+        assert!(!reserved.is_acceptable(&other_public));
+        assert!(reserved.contains_ip(&peer.address.ip()));
+    }
+}
@@ -123,6 +123,8 @@ impl Default for State {
 
 pub struct RoutingTable {
     entries: RwLock<HashMap<SocketAddr, State>>,
+    // The node keys verified by the identity messages of the established peers
+    node_keys: RwLock<HashMap<SocketAddr, Public>>,
 
     rng: Mutex<OsRng>,
 }
@@ -132,6 +134,7 @@ impl RoutingTable {
     pub fn new() -> Arc<Self> {
         Arc::new(Self {
             entries: RwLock::new(HashMap::new()),
+            node_keys: RwLock::new(HashMap::new()),
             rng: Mutex::new(OsRng::new().unwrap()),
         })
     }
@@ -580,6 +583,7 @@ impl RoutingTable {
 
     // true if the connection is established
     pub fn ban(&self, target: SocketAddr) -> bool {
+        self.node_keys.write().remove(&target);
         let mut entries = self.entries.write();
         let entry = entries.entry(target).or_default();
         let mut new_state = State::Banned;
@@ -611,9 +615,23 @@ impl RoutingTable {
             return false
         }
         entries.remove(target);
+        self.node_keys.write().remove(target);
         true
     }
 
+    pub fn remote_public(&self, target: &SocketAddr) -> Option<Public> {
+        let entries = self.entries.read();
+        entries.get(target).and_then(State::remote_public).cloned()
+    }
+
+    pub fn set_remote_node_key(&self, target: SocketAddr, node_key: Public) {
+        self.node_keys.write().insert(target, node_key);
+    }
+
+    pub fn remote_node_key(&self, target: &SocketAddr) -> Option<Public> {
+        self.node_keys.read().get(target).cloned()
+    }
+
     pub fn local_public(&self, target: SocketAddr) -> Option<Public> {
         let mut entries = self.entries.write();
         let entry = entries.entry(target).or_default();
@@ -20,20 +20,25 @@ use std::sync::Arc;
 
 use cidr::IpCidr;
 use cio::{IoError, IoService};
-use ckey::{NetworkId, Public};
+use ckey::{KeyPair, NetworkId, Public};
 use crossbeam_channel::Sender;
 use ctimer::TimerLoop;
 
 use crate::client::Client;
 use crate::control::{Control, Error as ControlError};
 use crate::filters::{FilterEntry, FiltersControl};
+use crate::nat::{self, ExternalAddress, PortMapping};
 use crate::routing_table::RoutingTable;
-use crate::{p2p, Api, NetworkExtension, SocketAddr};
+use crate::{
+    p2p, AddressBook, Api, BandwidthLimits, NetworkExtension, PeerScore, Reputation, ReservedPeers, SocketAddr,
+};
 
 pub struct Service {
     p2p: IoService<p2p::Message>,
     client: Arc<Client>,
     routing_table: Arc<RoutingTable>,
+    reputation: Arc<Reputation>,
+    external_address: Arc<ExternalAddress>,
     p2p_handler: Arc<p2p::Handler>,
     filters_control: Arc<FiltersControl>,
 }
@@ -48,10 +53,23 @@ impl Service {
         max_peers: usize,
         filters_control: Arc<FiltersControl>,
         routing_table: Arc<RoutingTable>,
+        reputation: Arc<Reputation>,
+        address_book: Arc<AddressBook>,
+        port_mapping: Option<PortMapping>,
+        bandwidth_limits: BandwidthLimits,
+        node_key: KeyPair,
+        reserved_peers: ReservedPeers,
     ) -> Result<Arc<Self>, Error> {
         let p2p = IoService::start("P2P")?;
 
-        let client = Client::new(p2p.channel(), timer_loop);
+        let external_address = ExternalAddress::new(address.port());
+        if let Some(port_mapping) = port_mapping {
+            // The thread keeps renewing the mapping until the process exits.
+            nat::start(port_mapping, address.port(), Arc::clone(&external_address))
+                .map_err(|err| format!("Cannot start the NAT thread: {:?}", err))?;
+        }
+
+        let client = Client::new(p2p.channel(), Arc::clone(&reputation), timer_loop);
 
         let p2p_handler = Arc::new(p2p::Handler::try_new(
             p2p.channel(),
@@ -60,6 +78,12 @@ impl Service {
             Arc::clone(&client),
             Arc::clone(&routing_table),
             Arc::clone(&filters_control),
+            Arc::clone(&reputation),
+            address_book,
+            Arc::clone(&external_address),
+            bandwidth_limits,
+            node_key,
+            reserved_peers,
             bootstrap_addresses,
             min_peers,
             max_peers,
@@ -70,6 +94,8 @@ impl Service {
             p2p,
             client,
             routing_table,
+            reputation,
+            external_address,
             p2p_handler,
             filters_control,
         }))
@@ -83,6 +109,10 @@ impl Service {
         self.client.register_extension(factory)
     }
 
+    pub fn external_address(&self) -> Arc<ExternalAddress> {
+        Arc::clone(&self.external_address)
+    }
+
     pub fn connect_to(&self, address: SocketAddr) -> Result<(), String> {
         self.p2p.send_message(p2p::Message::RequestConnection(address)).map_err(|e| format!("{:?}", e))?;
         Ok(())
@@ -201,6 +231,10 @@ impl Control for Service {
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>, ControlError> {
         Ok(self.p2p_handler.recent_network_usage())
     }
+
+    fn peer_scores(&self) -> Result<Vec<(SocketAddr, PeerScore)>, ControlError> {
+        Ok(self.reputation.scores())
+    }
 }
 
 #[derive(Debug)]
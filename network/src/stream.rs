@@ -305,10 +305,18 @@ impl Stream {
         M: Encodable, {
         let bytes = message.rlp_bytes().to_vec();
         let result = bytes.len();
-        self.try_stream.write_bytes(bytes);
+        self.write_bytes(bytes);
         result
     }
 
+    pub fn write_bytes(&mut self, bytes: Vec<u8>) {
+        self.try_stream.write_bytes(bytes);
+    }
+
+    pub fn is_flushed(&self) -> bool {
+        self.try_stream.write.is_empty()
+    }
+
     pub fn flush(&mut self) -> Result<()> {
         self.try_stream.flush()?;
         Ok(())
@@ -18,12 +18,15 @@ use std::fmt;
 
 use ccore::AccountProviderError;
 use ccore::Error as CoreError;
+use ccore::{LogFilterError, StateDiffError};
 use ckey::Error as KeyError;
 use ckeystore::Error as KeystoreError;
 use cnetwork::control::Error as NetworkControlError;
 use cstate::StateError;
 use ctypes::errors::{HistoryError, RuntimeError, SyntaxError};
+use ctypes::ShardId;
 use kvdb::Error as KVDBError;
+use primitives::H160;
 use rlp::DecoderError;
 use rustc_serialize::hex::FromHexError as HexError;
 
@@ -32,6 +35,8 @@ use jsonrpc_core::{Error, ErrorCode, Value};
 pub enum ConversionError {
     Key(KeyError),
     Hex(HexError),
+    /// The action can be converted only with the accounts in the keystore.
+    AccountOnly,
 }
 
 impl From<KeyError> for ConversionError {
@@ -74,6 +79,12 @@ mod codes {
     pub const ASSET_TRANSACTION_ONLY_IN_EXECUTE_TRANSACITON: i64 = -32047;
     pub const STATE_NOT_EXIST: i64 = -32048;
     pub const ACTION_DATA_HANDLER_NOT_FOUND: i64 = -32049;
+    pub const INVALID_CHILD_INDEX: i64 = -32050;
+    pub const NOT_ENOUGH_ASSETS: i64 = -32051;
+    pub const INVALID_CUSTOM_ACTION: i64 = -32052;
+    pub const TOO_LARGE_LOG_RANGE: i64 = -32053;
+    pub const STATE_PRUNED: i64 = -32054;
+    pub const TOO_LARGE_STATE_DIFF: i64 = -32055;
     pub const UNKNOWN_ERROR: i64 = -32099;
 }
 
@@ -95,6 +106,9 @@ pub fn conversion<T: Into<ConversionError>>(error: T) -> Error {
             message: error.to_string(),
             data: Some(Value::String(format!("{:?}", error))),
         },
+        ConversionError::AccountOnly => Error::invalid_params(
+            "TransferAssetFromAccount is only allowed in account_sendTransaction and account_signTransaction",
+        ),
     }
 }
 
@@ -179,6 +193,14 @@ pub fn transaction_core<T: Into<CoreError>>(error: T) -> Error {
             data: Some(Value::String(format!("{:?}", error))),
         },
         CoreError::Syntax(SyntaxError::InvalidCustomAction) => action_handler_not_found(),
+        CoreError::Syntax(SyntaxError::InvalidCustomActionBytes {
+            hint,
+            ..
+        }) => Error {
+            code: ErrorCode::ServerError(codes::INVALID_CUSTOM_ACTION),
+            message: "Invalid Custom Action".into(),
+            data: Some(Value::String(hint)),
+        },
         _ => unknown_error,
     }
 }
@@ -304,6 +326,53 @@ pub fn action_handler_not_found() -> Error {
     }
 }
 
+pub fn invalid_child_index(index: u32) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::INVALID_CHILD_INDEX),
+        message: format!("{} is not a valid child index. It should be less than 2^31", index),
+        data: None,
+    }
+}
+
+pub fn not_enough_assets(asset_type: H160, shard_id: ShardId, required: u64, spendable: u64) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::NOT_ENOUGH_ASSETS),
+        message: format!(
+            "Only {} of {} in shard {} can be spent from the candidates, but {} is required",
+            spendable, asset_type, shard_id, required
+        ),
+        data: None,
+    }
+}
+
+pub fn log_filter(error: &LogFilterError) -> Error {
+    match error {
+        LogFilterError::TooLargeRange {
+            ..
+        } => Error {
+            code: ErrorCode::ServerError(codes::TOO_LARGE_LOG_RANGE),
+            message: error.to_string(),
+            data: None,
+        },
+    }
+}
+
+pub fn state_diff(error: StateDiffError) -> Error {
+    match error {
+        StateDiffError::PrunedState(_) => Error {
+            code: ErrorCode::ServerError(codes::STATE_PRUNED),
+            message: error.to_string(),
+            data: None,
+        },
+        StateDiffError::TooManyChanges => Error {
+            code: ErrorCode::ServerError(codes::TOO_LARGE_STATE_DIFF),
+            message: error.to_string(),
+            data: None,
+        },
+        StateDiffError::Trie(err) => transaction_state(err),
+    }
+}
+
 /// Internal error signifying a logic error in code.
 /// Should not be used when function can just fail
 /// because of invalid parameters or incomplete node state.
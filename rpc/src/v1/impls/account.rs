@@ -14,24 +14,39 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::convert::TryInto;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 use std::time::Duration;
 
-use ccore::{AccountProvider, MinerService, MiningBlockChainClient, RegularKey, RegularKeyOwner, Seq};
-use ckey::{NetworkId, Password, PlatformAddress, Signature};
-use ctypes::transaction::IncompleteTransaction;
+use ccore::{
+    AccountProvider, AssetClient, BlockId, MinerService, MiningBlockChainClient, RegularKey, RegularKeyOwner, Seq,
+    UnverifiedTransaction,
+};
+use cjson::bytes::Bytes;
+use cjson::uint::Uint;
+use ckey::{Address, DerivationPath, NetworkId, Password, PlatformAddress, Signature, HARDENED_INDEX};
+use ckeystore::generate_mnemonic;
+use ctypes::transaction::{
+    Action as ActionType, AssetOutPoint as AssetOutPointType, AssetTransferInput as AssetTransferInputType,
+    AssetTransferOutput as AssetTransferOutputType, IncompleteTransaction, PartialHashing, ShardTransaction,
+    Transaction,
+};
+use ctypes::util::tag::Tag;
+use ctypes::ShardId;
+use cvm::{p2pkh_lock_script_hash, p2pkh_owner, p2pkh_unlock_script, P2PKH_LOCK_SCRIPT, SIGN_ALL_TAG};
 use jsonrpc_core::Result;
 use parking_lot::Mutex;
-use primitives::H256;
+use primitives::{H160, H256};
+use rlp::{Encodable, UntrustedRlp};
 
 use super::super::errors::{self, account_provider};
 use super::super::traits::Account;
-use super::super::types::{SendTransactionResult, UnsignedTransaction};
+use super::super::types::{Action, AssetOutPoint, CreateFromMnemonicResult, SendTransactionResult, UnsignedTransaction};
 
 pub struct AccountClient<C, M>
 where
-    C: MiningBlockChainClient + Seq + RegularKey + RegularKeyOwner,
+    C: MiningBlockChainClient + Seq + RegularKey + RegularKeyOwner + AssetClient,
     M: MinerService, {
     account_provider: Arc<AccountProvider>,
     network_id: NetworkId,
@@ -41,7 +56,7 @@ where
 
 impl<C, M> AccountClient<C, M>
 where
-    C: MiningBlockChainClient + Seq + RegularKey + RegularKeyOwner,
+    C: MiningBlockChainClient + Seq + RegularKey + RegularKeyOwner + AssetClient,
     M: MinerService,
 {
     pub fn new(account_provider: Arc<AccountProvider>, client: Arc<C>, miner: Arc<M>, network_id: NetworkId) -> Self {
@@ -52,11 +67,189 @@ where
             miner,
         }
     }
+
+    fn incomplete_transaction(
+        &self,
+        tx: UnsignedTransaction,
+        passphrase: Option<&Password>,
+    ) -> Result<(IncompleteTransaction, Option<u64>)> {
+        match tx {
+            UnsignedTransaction {
+                seq,
+                fee,
+                network_id,
+                action:
+                    Action::TransferAssetFromAccount {
+                        network_id: transfer_network_id,
+                        candidates,
+                        outputs,
+                        metadata,
+                        expiration,
+                    },
+            } => {
+                let outputs = outputs
+                    .into_iter()
+                    .map(AssetTransferOutputType::try_from)
+                    .collect::<::std::result::Result<_, _>>()
+                    .map_err(errors::conversion)?;
+                let action = self.transfer_asset_from_account(
+                    transfer_network_id,
+                    candidates,
+                    outputs,
+                    metadata,
+                    expiration,
+                    passphrase,
+                )?;
+                Ok((
+                    IncompleteTransaction {
+                        fee: fee.into(),
+                        network_id,
+                        action,
+                    },
+                    seq,
+                ))
+            }
+            tx => tx.try_into(),
+        }
+    }
+
+    /// Creates a TransferAsset that spends the candidates which are locked by P2PKH and owned by the accounts.
+    /// The candidates are selected from the largest one until they cover the outputs,
+    /// and the change is returned to the owner of the first selected input.
+    fn transfer_asset_from_account(
+        &self,
+        network_id: NetworkId,
+        candidates: Vec<AssetOutPoint>,
+        outputs: Vec<AssetTransferOutputType>,
+        metadata: String,
+        expiration: Option<Uint>,
+        passphrase: Option<&Password>,
+    ) -> Result<ActionType> {
+        if outputs.is_empty() {
+            return Err(jsonrpc_core::Error::invalid_params("TransferAssetFromAccount requires at least one output"))
+        }
+        // A transaction with a zero quantity output is invalid, and it would select no inputs to sign.
+        if let Some(output) = outputs.iter().find(|output| output.quantity == 0) {
+            return Err(jsonrpc_core::Error::invalid_params(format!(
+                "The output of {} in shard #{} has zero quantity",
+                output.asset_type, output.shard_id
+            )))
+        }
+        let mut spendables = self.spendable_assets(candidates)?;
+        spendables.sort_by(|(a, _), (b, _)| b.quantity.cmp(&a.quantity));
+
+        let mut required: BTreeMap<(ShardId, H160), u64> = BTreeMap::new();
+        for output in &outputs {
+            let quantity = required.entry((output.shard_id, output.asset_type)).or_insert(0);
+            *quantity = quantity.checked_add(output.quantity).ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params(format!("The outputs of {} overflow", output.asset_type))
+            })?;
+        }
+
+        let mut inputs = Vec::new();
+        let mut owners = Vec::new();
+        let mut changes = Vec::new();
+        for ((shard_id, asset_type), quantity) in required {
+            let mut selected = 0u64;
+            let mut change_owner = None;
+            for (prev_out, owner) in &spendables {
+                if selected >= quantity {
+                    break
+                }
+                if prev_out.shard_id != shard_id || prev_out.asset_type != asset_type {
+                    continue
+                }
+                selected += prev_out.quantity;
+                change_owner.get_or_insert(*owner);
+                inputs.push(AssetTransferInputType {
+                    prev_out: prev_out.clone(),
+                    timelock: None,
+                    lock_script: P2PKH_LOCK_SCRIPT.to_vec(),
+                    unlock_script: Vec::new(),
+                });
+                owners.push(*owner);
+            }
+            if selected < quantity {
+                return Err(errors::not_enough_assets(asset_type, shard_id, quantity, selected))
+            }
+            if let (Some(owner), true) = (change_owner, selected > quantity) {
+                changes.push(AssetTransferOutputType {
+                    lock_script_hash: p2pkh_lock_script_hash(),
+                    parameters: vec![owner.to_vec()],
+                    asset_type,
+                    shard_id,
+                    quantity: selected - quantity,
+                });
+            }
+        }
+        let outputs: Vec<_> = outputs.into_iter().chain(changes).collect();
+
+        let message = ShardTransaction::TransferAsset {
+            network_id,
+            burns: Vec::new(),
+            inputs: inputs.clone(),
+            outputs: outputs.clone(),
+            orders: Vec::new(),
+        }
+        .hash_partially(Tag::try_new(vec![SIGN_ALL_TAG]).expect("SIGN_ALL_TAG is a valid tag"), &inputs[0], false)
+        .expect("Signing all inputs and outputs always succeeds");
+        for (input, owner) in inputs.iter_mut().zip(owners) {
+            let account = self.account_provider.get_account(&owner, passphrase).map_err(account_provider)?;
+            let signature = account.sign(&message).map_err(|e| account_provider(e.into()))?;
+            let public = account.public().map_err(|e| account_provider(e.into()))?;
+            input.unlock_script = p2pkh_unlock_script(&signature, &public);
+        }
+
+        Ok(ActionType::TransferAsset {
+            network_id,
+            burns: Vec::new(),
+            inputs,
+            outputs,
+            orders: Vec::new(),
+            metadata,
+            approvals: Vec::new(),
+            expiration: expiration.map(Into::into),
+        })
+    }
+
+    /// Returns the unspent candidates that are locked by P2PKH with the accounts in the keystore.
+    fn spendable_assets(&self, candidates: Vec<AssetOutPoint>) -> Result<Vec<(AssetOutPointType, Address)>> {
+        let mut visited = HashSet::new();
+        let mut spendables = Vec::new();
+        for candidate in candidates {
+            let candidate = AssetOutPointType::from(candidate);
+            if !visited.insert((candidate.tracker, candidate.index, candidate.shard_id)) {
+                continue
+            }
+            let asset = self
+                .client
+                .get_asset(candidate.tracker, candidate.index, candidate.shard_id, BlockId::Latest)
+                .map_err(errors::transaction_state)?;
+            let asset = match asset {
+                Some(asset) => asset,
+                None => continue,
+            };
+            if *asset.asset_type() != candidate.asset_type
+                || asset.quantity() != candidate.quantity
+                || asset.order_hash().is_some()
+            {
+                continue
+            }
+            let owner = match p2pkh_owner(asset.lock_script_hash(), asset.parameters()) {
+                Some(owner) => Address::from(owner),
+                None => continue,
+            };
+            if self.account_provider.has_account(&owner).map_err(account_provider)? {
+                spendables.push((candidate, owner));
+            }
+        }
+        Ok(spendables)
+    }
 }
 
 impl<C, M> Account for AccountClient<C, M>
 where
-    C: MiningBlockChainClient + Seq + RegularKey + RegularKeyOwner + 'static,
+    C: MiningBlockChainClient + Seq + RegularKey + RegularKeyOwner + AssetClient + 'static,
     M: MinerService + 'static,
 {
     fn get_account_list(&self) -> Result<Vec<PlatformAddress>> {
@@ -81,6 +274,49 @@ where
             .map_err(account_provider)
     }
 
+    fn create_account_from_mnemonic(
+        &self,
+        mnemonic: Option<String>,
+        passphrase: Option<Password>,
+        path: Option<String>,
+    ) -> Result<CreateFromMnemonicResult> {
+        let path: DerivationPath = match path {
+            Some(path) => path.parse().map_err(Into::into).map_err(account_provider)?,
+            None => DerivationPath::new(vec![HARDENED_INDEX]),
+        };
+        let mnemonic = mnemonic.unwrap_or_else(generate_mnemonic);
+        let address = self
+            .account_provider
+            .insert_account_from_mnemonic(&mnemonic, path, &passphrase.unwrap_or_default())
+            .map_err(account_provider)?;
+        Ok(CreateFromMnemonicResult {
+            address: PlatformAddress::new_v1(self.network_id, address),
+            mnemonic,
+        })
+    }
+
+    fn derive_child(
+        &self,
+        address: PlatformAddress,
+        index: u32,
+        hardened: bool,
+        passphrase: Option<Password>,
+    ) -> Result<PlatformAddress> {
+        if index >= HARDENED_INDEX {
+            return Err(errors::invalid_child_index(index))
+        }
+        let index = if hardened {
+            index + HARDENED_INDEX
+        } else {
+            index
+        };
+        let address = address.try_into_address().map_err(errors::core)?;
+        self.account_provider
+            .derive_account(&address, &passphrase.unwrap_or_default(), index)
+            .map(|address| PlatformAddress::new_v1(self.network_id, address))
+            .map_err(account_provider)
+    }
+
     fn sign(&self, message_digest: H256, address: PlatformAddress, passphrase: Option<Password>) -> Result<Signature> {
         let address = address.try_into_address().map_err(errors::core)?;
         self.account_provider
@@ -99,7 +335,7 @@ where
             static ref LOCK: Mutex<()> = Mutex::new(());
         }
         let _guard = LOCK.lock();
-        let (tx, seq): (IncompleteTransaction, Option<u64>) = tx.try_into()?;
+        let (tx, seq) = self.incomplete_transaction(tx, passphrase.as_ref())?;
 
         let (hash, seq) = self
             .miner
@@ -119,6 +355,43 @@ where
         })
     }
 
+    fn sign_transaction(
+        &self,
+        tx: UnsignedTransaction,
+        platform_address: PlatformAddress,
+        passphrase: Option<Password>,
+    ) -> Result<Bytes> {
+        let (tx, seq) = self.incomplete_transaction(tx, passphrase.as_ref())?;
+        let address = platform_address.try_into_address().map_err(errors::core)?;
+        let seq = seq.unwrap_or_else(|| {
+            let fee_payer = self.client.latest_regular_key_owner(&address).unwrap_or(address);
+            self.client.latest_seq(&fee_payer)
+        });
+        let tx = tx.complete(seq);
+        let sig = self
+            .account_provider
+            .get_account(&address, passphrase.as_ref())
+            .and_then(|account| Ok(account.sign(&tx.hash())?))
+            .map_err(account_provider)?;
+        Ok(UnverifiedTransaction::new(tx, sig).rlp_bytes().to_vec().into())
+    }
+
+    fn co_sign_transaction(
+        &self,
+        raw: Bytes,
+        platform_address: PlatformAddress,
+        passphrase: Option<Password>,
+    ) -> Result<Bytes> {
+        let tx: UnverifiedTransaction = UntrustedRlp::new(&raw.into_vec()).as_val().map_err(|e| errors::rlp(&e))?;
+        let address = platform_address.try_into_address().map_err(errors::core)?;
+        let sig = self
+            .account_provider
+            .get_account(&address, passphrase.as_ref())
+            .and_then(|account| Ok(account.sign(&Transaction::hash(&tx))?))
+            .map_err(account_provider)?;
+        Ok(tx.add_co_signature(sig).rlp_bytes().to_vec().into())
+    }
+
     fn change_password(&self, address: PlatformAddress, old_password: Password, new_password: Password) -> Result<()> {
         self.account_provider
             .change_password(address.into_address(), &old_password, &new_password)
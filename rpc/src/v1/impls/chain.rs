@@ -19,12 +19,12 @@ use std::sync::Arc;
 
 use ccore::{
     AssetClient, BlockId, EngineInfo, ExecuteClient, MiningBlockChainClient, RegularKey, RegularKeyOwner, Shard,
-    TextClient,
+    StateInfo, TextClient,
 };
 use ccrypto::Blake;
 use cjson::uint::Uint;
 use ckey::{public_to_address, NetworkId, PlatformAddress, Public};
-use cstate::FindActionHandler;
+use cstate::{verify_state_proof, FindActionHandler, StateKey};
 use ctypes::transaction::{Action, ShardTransaction as ShardTransactionType};
 use ctypes::{BlockNumber, ShardId};
 use primitives::{Bytes as BytesArray, H160, H256};
@@ -33,11 +33,21 @@ use jsonrpc_core::Result;
 
 use super::super::errors;
 use super::super::traits::Chain;
-use super::super::types::{AssetScheme, Block, BlockNumberAndHash, OwnedAsset, Text, Transaction, UnsignedTransaction};
+use super::super::types::{
+    AssetScheme, Block, BlockNumberAndHash, LocalizedEvent, LogFilter, OwnedAsset, ProofTarget, StateChange,
+    StateProof, Text, Transaction, TransactionReceipt, UnsignedTransaction,
+};
 
 pub struct ChainClient<C>
 where
-    C: AssetClient + MiningBlockChainClient + Shard + RegularKey + RegularKeyOwner + ExecuteClient + EngineInfo, {
+    C: AssetClient
+        + MiningBlockChainClient
+        + Shard
+        + RegularKey
+        + RegularKeyOwner
+        + ExecuteClient
+        + EngineInfo
+        + StateInfo, {
     client: Arc<C>,
 }
 
@@ -50,6 +60,7 @@ where
         + RegularKeyOwner
         + ExecuteClient
         + EngineInfo
+        + StateInfo
         + TextClient,
 {
     pub fn new(client: Arc<C>) -> Self {
@@ -69,6 +80,7 @@ where
         + ExecuteClient
         + EngineInfo
         + FindActionHandler
+        + StateInfo
         + TextClient
         + 'static,
 {
@@ -77,6 +89,10 @@ where
         Ok(self.client.transaction(&id).map(From::from))
     }
 
+    fn get_transaction_receipt(&self, transaction_hash: H256) -> Result<Option<TransactionReceipt>> {
+        Ok(self.client.transaction_receipt(&transaction_hash).map(From::from))
+    }
+
     fn contains_transaction(&self, transaction_hash: H256) -> Result<bool> {
         Ok(self.client.transaction_block(&transaction_hash.into()).is_some())
     }
@@ -250,6 +266,9 @@ where
             "unwrapCCC" => Some(common_parameters.min_asset_unwrap_ccc_cost),
             "pay" => Some(common_parameters.min_pay_transaction_cost),
             "setRegularKey" => Some(common_parameters.min_set_regular_key_tranasction_cost),
+            "setMultisig" => Some(common_parameters.min_set_regular_key_tranasction_cost),
+            "grantVesting" => Some(common_parameters.min_pay_transaction_cost),
+            "setSpendingLimit" => Some(common_parameters.min_set_regular_key_tranasction_cost),
             "createShard" => Some(common_parameters.min_create_shard_transaction_cost),
             "setShardOwners" => Some(common_parameters.min_set_shard_owners_transaction_cost),
             "setShardUsers" => Some(common_parameters.min_set_shard_users_transaction_cost),
@@ -304,4 +323,40 @@ where
             Err(errors::transfer_only())
         }
     }
+
+    fn get_logs(
+        &self,
+        filter: LogFilter,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+    ) -> Result<Vec<LocalizedEvent>> {
+        let from_block = from_block.map(BlockId::Number).unwrap_or(BlockId::Latest);
+        let to_block = to_block.map(BlockId::Number).unwrap_or(BlockId::Latest);
+        let filter = filter.try_into_core(from_block, to_block).map_err(errors::conversion)?;
+        let network_id = self.client.common_params().network_id;
+        let logs = self.client.logs(&filter).map_err(|err| errors::log_filter(&err))?;
+        Ok(logs.into_iter().map(|log| LocalizedEvent::from_core(log, network_id)).collect())
+    }
+
+    fn get_state_diff(&self, from_block: u64, to_block: u64) -> Result<Option<Vec<StateChange>>> {
+        let network_id = self.client.common_params().network_id;
+        Ok(self
+            .client
+            .state_diff(BlockId::Number(from_block), BlockId::Number(to_block))
+            .map_err(errors::state_diff)?
+            .map(|changes| changes.into_iter().map(|change| StateChange::from_core(change, network_id)).collect()))
+    }
+
+    fn get_proof(&self, target: ProofTarget, block_number: Option<u64>) -> Result<Option<StateProof>> {
+        let block_id = block_number.map(BlockId::Number).unwrap_or(BlockId::Latest);
+        let key = StateKey::try_from(target).map_err(errors::conversion)?;
+        let (state_root, proof) = match self.client.state_proof(&key, block_id).map_err(errors::transaction_state)? {
+            Some(proof) => proof,
+            None => return Ok(None),
+        };
+        let value = verify_state_proof(&state_root, &key, &proof)
+            .map_err(|err| errors::internal("The proof made from the state is invalid", err))?;
+        let network_id = self.client.common_params().network_id;
+        Ok(Some(StateProof::from_core(state_root, proof, value, network_id)))
+    }
 }
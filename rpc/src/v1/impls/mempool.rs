@@ -70,7 +70,12 @@ where
             .client
             .error_hints_by_tracker(&tracker)
             .into_iter()
-            .map(|(_hash, error_hint)| error_hint.is_none())
+            .map(|(hash, error_hint)| {
+                self.client
+                    .transaction_receipt(&hash)
+                    .map(|receipt| receipt.error.is_none())
+                    .unwrap_or_else(|| error_hint.is_none())
+            })
             .collect())
     }
 
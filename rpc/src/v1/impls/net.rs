@@ -25,7 +25,7 @@ use jsonrpc_core::Result;
 
 use super::super::errors;
 use super::super::traits::Net;
-use super::super::types::FilterStatus;
+use super::super::types::{FilterStatus, PeerScore};
 
 pub struct NetClient {
     network_control: Arc<NetworkControl>,
@@ -131,4 +131,21 @@ impl Net for NetClient {
     fn recent_network_usage(&self) -> Result<HashMap<String, usize>> {
         Ok(self.network_control.recent_network_usage().map_err(|e| errors::network_control(&e))?)
     }
+
+    fn get_peer_scores(&self) -> Result<Vec<PeerScore>> {
+        let scores = self.network_control.peer_scores().map_err(|e| errors::network_control(&e))?;
+        Ok(scores
+            .into_iter()
+            .map(|(addr, score)| PeerScore {
+                address: addr.into(),
+                penalty: score.penalty,
+                bans: score.bans,
+                banned_until: if score.banned_until == 0 {
+                    None
+                } else {
+                    Some(score.banned_until)
+                },
+            })
+            .collect())
+    }
 }
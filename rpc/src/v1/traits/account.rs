@@ -14,11 +14,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use cjson::bytes::Bytes;
 use ckey::{Password, PlatformAddress, Signature};
 use jsonrpc_core::Result;
 use primitives::H256;
 
-use super::super::types::{SendTransactionResult, UnsignedTransaction};
+use super::super::types::{CreateFromMnemonicResult, SendTransactionResult, UnsignedTransaction};
 
 build_rpc_trait! {
     pub trait Account {
@@ -34,6 +35,14 @@ build_rpc_trait! {
         # [rpc(name = "account_importRaw")]
         fn create_account_from_secret(&self, H256, Option<Password>) -> Result<PlatformAddress>;
 
+        /// Creates an account from a BIP39 mnemonic, generating a new mnemonic if it's not given
+        # [rpc(name = "account_createFromMnemonic")]
+        fn create_account_from_mnemonic(&self, Option<String>, Option<Password>, Option<String>) -> Result<CreateFromMnemonicResult>;
+
+        /// Creates a child account of an account created from a mnemonic
+        # [rpc(name = "account_deriveChild")]
+        fn derive_child(&self, PlatformAddress, u32, bool, Option<Password>) -> Result<PlatformAddress>;
+
         /// Unlocks the specified account for use.
         # [rpc(name = "account_unlock")]
         fn unlock(&self, PlatformAddress, Password, Option<u64>) -> Result<()>;
@@ -46,6 +55,14 @@ build_rpc_trait! {
         # [rpc(name = "account_sendTransaction")]
         fn send_transaction(&self, UnsignedTransaction, PlatformAddress, Option<Password>) -> Result<SendTransactionResult>;
 
+        /// Signs a transaction with the account without sending it, returning the signed RLP
+        # [rpc(name = "account_signTransaction")]
+        fn sign_transaction(&self, UnsignedTransaction, PlatformAddress, Option<Password>) -> Result<Bytes>;
+
+        /// Adds a co-signature of the account to a signed transaction of a multisig account
+        # [rpc(name = "account_coSignTransaction")]
+        fn co_sign_transaction(&self, Bytes, PlatformAddress, Option<Password>) -> Result<Bytes>;
+
         /// Changes the account's password
         # [rpc(name = "account_changePassword")]
         fn change_password(&self, PlatformAddress, Password, Password) -> Result<()>;
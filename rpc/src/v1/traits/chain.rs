@@ -21,7 +21,10 @@ use primitives::{Bytes as BytesArray, H160, H256};
 
 use jsonrpc_core::Result;
 
-use super::super::types::{AssetScheme, Block, BlockNumberAndHash, OwnedAsset, Text, Transaction, UnsignedTransaction};
+use super::super::types::{
+    AssetScheme, Block, BlockNumberAndHash, LocalizedEvent, LogFilter, OwnedAsset, ProofTarget, StateChange,
+    StateProof, Text, Transaction, TransactionReceipt, UnsignedTransaction,
+};
 
 build_rpc_trait! {
     pub trait Chain {
@@ -29,6 +32,10 @@ build_rpc_trait! {
         # [rpc(name = "chain_getTransaction")]
         fn get_transaction(&self, H256) -> Result<Option<Transaction>>;
 
+        /// Gets the receipt of the transaction with given hash.
+        # [rpc(name = "chain_getTransactionReceipt")]
+        fn get_transaction_receipt(&self, H256) -> Result<Option<TransactionReceipt>>;
+
         /// Query whether the chain has the transaction with given transaction hash.
         # [rpc(name = "chain_containsTransaction")]
         fn contains_transaction(&self, H256) -> Result<bool>;
@@ -143,5 +150,17 @@ build_rpc_trait! {
         /// Execute AssetTransfer transaction inputs in VM
         # [rpc(name = "chain_executeVM")]
         fn execute_vm(&self, UnsignedTransaction, Vec<Vec<BytesArray>>, Vec<usize>) -> Result<Vec<String>>;
+
+        /// Gets the events emitted by the transactions in the given block range.
+        # [rpc(name = "chain_getLogs")]
+        fn get_logs(&self, LogFilter, Option<u64>, Option<u64>) -> Result<Vec<LocalizedEvent>>;
+
+        /// Gets the state entries that differ between the given blocks.
+        # [rpc(name = "chain_getStateDiff")]
+        fn get_state_diff(&self, u64, u64) -> Result<Option<Vec<StateChange>>>;
+
+        /// Gets the Merkle proof of the state entry at the given block number.
+        # [rpc(name = "chain_getProof")]
+        fn get_proof(&self, ProofTarget, Option<u64>) -> Result<Option<StateProof>>;
     }
 }
@@ -21,7 +21,7 @@ use cidr::IpCidr;
 use ckey::Public;
 use jsonrpc_core::Result;
 
-use super::super::types::FilterStatus;
+use super::super::types::{FilterStatus, PeerScore};
 
 build_rpc_trait! {
     pub trait Net {
@@ -81,5 +81,8 @@ build_rpc_trait! {
 
         #[rpc(name = "net_recentNetworkUsage")]
         fn recent_network_usage(&self) -> Result<HashMap<String, usize>>;
+
+        #[rpc(name = "net_getPeerScores")]
+        fn get_peer_scores(&self) -> Result<Vec<PeerScore>>;
     }
 }
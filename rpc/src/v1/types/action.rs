@@ -24,7 +24,7 @@ use primitives::{Bytes, H160, H256};
 use rustc_serialize::hex::{FromHex, ToHex};
 
 use super::super::errors::ConversionError;
-use super::{AssetMintOutput, AssetTransferInput, AssetTransferOutput, OrderOnTransfer};
+use super::{AssetMintOutput, AssetOutPoint, AssetTransferInput, AssetTransferOutput, OrderOnTransfer};
 
 #[derive(Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type")]
@@ -111,6 +111,21 @@ pub enum Action {
     SetRegularKey {
         key: Public,
     },
+    SetMultisig {
+        publics: Vec<Public>,
+        threshold: u8,
+    },
+    #[serde(rename_all = "camelCase")]
+    GrantVesting {
+        receiver: PlatformAddress,
+        quantity: Uint,
+        unlock_block_number: u64,
+        unlock_timestamp: u64,
+    },
+    SetSpendingLimit {
+        limit: Uint,
+        period: u64,
+    },
     CreateShard {
         users: Vec<PlatformAddress>,
     },
@@ -146,6 +161,17 @@ pub enum Action {
         handler_id: u64,
         bytes: Bytes,
     },
+    /// TransferAsset whose inputs are selected from `candidates` and unlocked by the accounts in the keystore.
+    /// Only the assets locked by P2PKH can be spent.
+    #[serde(rename_all = "camelCase")]
+    TransferAssetFromAccount {
+        network_id: NetworkId,
+        candidates: Vec<AssetOutPoint>,
+        outputs: Vec<AssetTransferOutput>,
+
+        metadata: String,
+        expiration: Option<Uint>,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -247,6 +273,21 @@ pub enum ActionWithTracker {
     SetRegularKey {
         key: Public,
     },
+    SetMultisig {
+        publics: Vec<Public>,
+        threshold: u8,
+    },
+    #[serde(rename_all = "camelCase")]
+    GrantVesting {
+        receiver: PlatformAddress,
+        quantity: Uint,
+        unlock_block_number: u64,
+        unlock_timestamp: u64,
+    },
+    SetSpendingLimit {
+        limit: Uint,
+        period: u64,
+    },
     CreateShard {
         users: Vec<PlatformAddress>,
     },
@@ -423,6 +464,31 @@ impl ActionWithTracker {
             } => ActionWithTracker::SetRegularKey {
                 key,
             },
+            ActionType::SetMultisig {
+                publics,
+                threshold,
+            } => ActionWithTracker::SetMultisig {
+                publics,
+                threshold,
+            },
+            ActionType::GrantVesting {
+                receiver,
+                quantity,
+                unlock_block_number,
+                unlock_timestamp,
+            } => ActionWithTracker::GrantVesting {
+                receiver: PlatformAddress::new_v1(network_id, receiver),
+                quantity: quantity.into(),
+                unlock_block_number,
+                unlock_timestamp,
+            },
+            ActionType::SetSpendingLimit {
+                limit,
+                period,
+            } => ActionWithTracker::SetSpendingLimit {
+                limit: limit.into(),
+                period,
+            },
             ActionType::CreateShard {
                 users,
             } => {
@@ -666,6 +732,31 @@ impl TryFrom<Action> for ActionType {
             } => ActionType::SetRegularKey {
                 key,
             },
+            Action::SetMultisig {
+                publics,
+                threshold,
+            } => ActionType::SetMultisig {
+                publics,
+                threshold,
+            },
+            Action::GrantVesting {
+                receiver,
+                quantity,
+                unlock_block_number,
+                unlock_timestamp,
+            } => ActionType::GrantVesting {
+                receiver: receiver.try_into_address()?,
+                quantity: quantity.into(),
+                unlock_block_number,
+                unlock_timestamp,
+            },
+            Action::SetSpendingLimit {
+                limit,
+                period,
+            } => ActionType::SetSpendingLimit {
+                limit: limit.into(),
+                period,
+            },
             Action::CreateShard {
                 users,
             } => {
@@ -733,6 +824,9 @@ impl TryFrom<Action> for ActionType {
                 handler_id,
                 bytes,
             },
+            Action::TransferAssetFromAccount {
+                ..
+            } => return Err(ConversionError::AccountOnly),
         })
     }
 }
@@ -0,0 +1,168 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::{BlockId, LocalizedEvent as LocalizedEventType, LogFilter as LogFilterType};
+use cjson::uint::Uint;
+use ckey::{Error as KeyError, NetworkId, PlatformAddress};
+use ctypes::event::Event as EventType;
+use ctypes::{BlockNumber, ShardId};
+use primitives::{Bytes, H160, H256};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Event {
+    #[serde(rename_all = "camelCase")]
+    BalanceIncreased {
+        address: PlatformAddress,
+        quantity: Uint,
+    },
+    #[serde(rename_all = "camelCase")]
+    BalanceDecreased {
+        address: PlatformAddress,
+        quantity: Uint,
+    },
+    #[serde(rename_all = "camelCase")]
+    AssetCreated {
+        shard_id: ShardId,
+        tracker: H256,
+        index: usize,
+        asset_type: H160,
+        quantity: Uint,
+    },
+    #[serde(rename_all = "camelCase")]
+    AssetSpent {
+        shard_id: ShardId,
+        tracker: H256,
+        index: usize,
+        asset_type: H160,
+        quantity: Uint,
+    },
+    #[serde(rename_all = "camelCase")]
+    Custom {
+        handler_id: u64,
+        topic: Bytes,
+        data: Bytes,
+    },
+}
+
+impl Event {
+    pub fn from_core(from: EventType, network_id: NetworkId) -> Self {
+        match from {
+            EventType::BalanceIncreased {
+                address,
+                quantity,
+            } => Event::BalanceIncreased {
+                address: PlatformAddress::new_v1(network_id, address),
+                quantity: quantity.into(),
+            },
+            EventType::BalanceDecreased {
+                address,
+                quantity,
+            } => Event::BalanceDecreased {
+                address: PlatformAddress::new_v1(network_id, address),
+                quantity: quantity.into(),
+            },
+            EventType::AssetCreated {
+                shard_id,
+                tracker,
+                index,
+                asset_type,
+                quantity,
+            } => Event::AssetCreated {
+                shard_id,
+                tracker,
+                index,
+                asset_type,
+                quantity: quantity.into(),
+            },
+            EventType::AssetSpent {
+                shard_id,
+                tracker,
+                index,
+                asset_type,
+                quantity,
+            } => Event::AssetSpent {
+                shard_id,
+                tracker,
+                index,
+                asset_type,
+                quantity: quantity.into(),
+            },
+            EventType::Custom {
+                handler_id,
+                topic,
+                data,
+            } => Event::Custom {
+                handler_id,
+                topic,
+                data,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizedEvent {
+    #[serde(flatten)]
+    event: Event,
+    block_number: BlockNumber,
+    block_hash: H256,
+    transaction_index: usize,
+    transaction_hash: H256,
+    event_index: usize,
+}
+
+impl LocalizedEvent {
+    pub fn from_core(from: LocalizedEventType, network_id: NetworkId) -> Self {
+        Self {
+            event: Event::from_core(from.event, network_id),
+            block_number: from.block_number,
+            block_hash: from.block_hash,
+            transaction_index: from.transaction_index,
+            transaction_hash: from.transaction_hash,
+            event_index: from.event_index,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilter {
+    address: Option<PlatformAddress>,
+    asset_type: Option<H160>,
+    tracker: Option<H256>,
+    handler_id: Option<u64>,
+    topic: Option<Bytes>,
+}
+
+impl LogFilter {
+    pub fn try_into_core(self, from_block: BlockId, to_block: BlockId) -> Result<LogFilterType, KeyError> {
+        let address = match self.address {
+            Some(address) => Some(address.try_into_address()?),
+            None => None,
+        };
+        Ok(LogFilterType {
+            from_block,
+            to_block,
+            address,
+            asset_type: self.asset_type,
+            tracker: self.tracker,
+            handler_id: self.handler_id,
+            topic: self.topic,
+        })
+    }
+}
@@ -20,24 +20,34 @@ mod asset_input;
 mod asset_output;
 mod asset_scheme;
 mod block;
+mod event;
 mod order;
+mod proof;
+mod receipt;
+mod state_diff;
 mod text;
 mod transaction;
 mod unsigned_transaction;
 mod work;
 
+use ckey::PlatformAddress;
 use primitives::H256;
 
 use self::asset::Asset;
-use self::asset_input::{AssetOutPoint, AssetTransferInput};
+use self::asset_input::AssetTransferInput;
 use self::asset_output::{AssetMintOutput, AssetTransferOutput};
 use self::order::OrderOnTransfer;
 
 pub use self::action::{Action, ActionWithTracker};
 pub use self::asset::OwnedAsset;
+pub use self::asset_input::AssetOutPoint;
 pub use self::asset_scheme::AssetScheme;
 pub use self::block::Block;
 pub use self::block::BlockNumberAndHash;
+pub use self::event::{LocalizedEvent, LogFilter};
+pub use self::proof::{ProofTarget, StateProof};
+pub use self::receipt::TransactionReceipt;
+pub use self::state_diff::StateChange;
 pub use self::text::Text;
 pub use self::transaction::{PendingTransactions, Transaction};
 pub use self::unsigned_transaction::UnsignedTransaction;
@@ -51,12 +61,27 @@ pub struct FilterStatus {
     pub enabled: bool,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerScore {
+    pub address: ::std::net::SocketAddr,
+    pub penalty: u64,
+    pub bans: u64,
+    pub banned_until: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendTransactionResult {
     pub hash: H256,
     pub seq: u64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct CreateFromMnemonicResult {
+    pub address: PlatformAddress,
+    pub mnemonic: String,
+}
+
 #[derive(Debug)]
 pub enum TPSTestOption {
     PayOnly,
@@ -0,0 +1,150 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::convert::TryFrom;
+
+use cjson::bytes::Bytes;
+use ckey::{NetworkId, PlatformAddress, Public};
+use cstate::{StateKey, StateProof as StateProofType, StateValue as StateValueType};
+use ctypes::ShardId;
+use primitives::{H160, H256};
+
+use super::super::errors::ConversionError;
+use super::state_diff::StateValue;
+
+/// An entry of the state to prove.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ProofTarget {
+    #[serde(rename_all = "camelCase")]
+    Account {
+        address: PlatformAddress,
+    },
+    #[serde(rename_all = "camelCase")]
+    RegularKey {
+        public: Public,
+    },
+    Metadata,
+    #[serde(rename_all = "camelCase")]
+    Shard {
+        shard_id: ShardId,
+    },
+    #[serde(rename_all = "camelCase")]
+    AssetScheme {
+        shard_id: ShardId,
+        asset_type: H160,
+    },
+    #[serde(rename_all = "camelCase")]
+    OwnedAsset {
+        shard_id: ShardId,
+        tracker: H256,
+        index: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    Text {
+        hash: H256,
+    },
+    #[serde(rename_all = "camelCase")]
+    ActionData {
+        key: H256,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateProof {
+    state_root: H256,
+    /// The nodes on the path in the top level trie.
+    proof: Vec<Bytes>,
+    /// The nodes on the path in the shard trie, for an asset scheme or an owned asset.
+    shard_proof: Option<Vec<Bytes>>,
+    value: Option<StateValue>,
+}
+
+impl TryFrom<ProofTarget> for StateKey {
+    type Error = ConversionError;
+    fn try_from(from: ProofTarget) -> Result<Self, Self::Error> {
+        Ok(match from {
+            ProofTarget::Account {
+                address,
+            } => StateKey::Account(address.try_into_address()?),
+            ProofTarget::RegularKey {
+                public,
+            } => StateKey::RegularAccount(public),
+            ProofTarget::Metadata => StateKey::Metadata,
+            ProofTarget::Shard {
+                shard_id,
+            } => StateKey::Shard(shard_id),
+            ProofTarget::AssetScheme {
+                shard_id,
+                asset_type,
+            } => StateKey::AssetScheme(shard_id, asset_type),
+            ProofTarget::OwnedAsset {
+                shard_id,
+                tracker,
+                index,
+            } => StateKey::OwnedAsset(shard_id, tracker, index),
+            ProofTarget::Text {
+                hash,
+            } => StateKey::Text(hash),
+            ProofTarget::ActionData {
+                key,
+            } => StateKey::ActionData(key),
+        })
+    }
+}
+
+impl StateProof {
+    pub fn from_core(
+        state_root: H256,
+        proof: StateProofType,
+        value: Option<StateValueType>,
+        network_id: NetworkId,
+    ) -> Self {
+        Self {
+            state_root,
+            proof: proof.top.into_iter().map(Into::into).collect(),
+            shard_proof: proof.shard.map(|nodes| nodes.into_iter().map(Into::into).collect()),
+            value: value.map(|value| StateValue::from_core(value, network_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_str;
+
+    use super::*;
+
+    #[test]
+    fn deserialize_proof_target() {
+        let target: ProofTarget = from_str(r#"{"type":"metadata"}"#).unwrap();
+        assert_eq!(ProofTarget::Metadata, target);
+
+        let target: ProofTarget = from_str(
+            r#"{"type":"ownedAsset","shardId":1,"tracker":"0x0000000000000000000000000000000000000000000000000000000000000001","index":2}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ProofTarget::OwnedAsset {
+                shard_id: 1,
+                tracker: H256::from(1),
+                index: 2,
+            },
+            target
+        );
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccore::{LocalizedReceipt, TransactionFailure as TransactionFailureType};
+use cjson::uint::Uint;
+use ctypes::BlockNumber;
+use primitives::H256;
+use serde_json::Value;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionFailure {
+    /// One of "runtime", "syntax" and "other".
+    kind: &'static str,
+    /// The name of the error, e.g. "InsufficientBalance".
+    code: String,
+    fields: Value,
+    message: String,
+}
+
+impl From<TransactionFailureType> for TransactionFailure {
+    fn from(from: TransactionFailureType) -> Self {
+        let message = from.to_string();
+        let (kind, tagged) = match from {
+            TransactionFailureType::Runtime(err) => ("runtime", serde_json::to_value(err)),
+            TransactionFailureType::Syntax(err) => ("syntax", serde_json::to_value(err)),
+            TransactionFailureType::Other(_) => {
+                return TransactionFailure {
+                    kind: "other",
+                    code: "Other".to_string(),
+                    fields: Value::Null,
+                    message,
+                }
+            }
+        };
+        // The errors are serialized as {"type": code, "content": fields}.
+        let tagged = tagged.expect("Errors are always serializable");
+        let code = tagged["type"].as_str().expect("Errors are tagged with their type").to_string();
+        let fields = tagged.get("content").cloned().unwrap_or(Value::Null);
+        TransactionFailure {
+            kind,
+            code,
+            fields,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReceipt {
+    transaction_hash: H256,
+    block_number: Option<BlockNumber>,
+    block_hash: Option<H256>,
+    transaction_index: Option<usize>,
+    fee: Uint,
+    error: Option<TransactionFailure>,
+}
+
+impl From<LocalizedReceipt> for TransactionReceipt {
+    fn from(from: LocalizedReceipt) -> Self {
+        TransactionReceipt {
+            transaction_hash: from.transaction_hash,
+            block_number: from.block_number,
+            block_hash: from.block_hash,
+            transaction_index: from.transaction_index,
+            fee: from.fee.into(),
+            error: from.error.map(Into::into),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckey::Address;
+    use ctypes::errors::RuntimeError;
+
+    use super::*;
+
+    #[test]
+    fn runtime_failure_has_code_and_fields() {
+        let address = Address::random();
+        let failure = TransactionFailure::from(TransactionFailureType::Runtime(RuntimeError::InsufficientBalance {
+            address,
+            balance: 10,
+            cost: 20,
+        }));
+        assert_eq!("runtime", failure.kind);
+        assert_eq!("InsufficientBalance", failure.code);
+        assert_eq!(Value::from(10), failure.fields["balance"]);
+        assert_eq!(Value::from(20), failure.fields["cost"]);
+    }
+
+    #[test]
+    fn unit_failure_has_no_fields() {
+        let failure = TransactionFailure::from(TransactionFailureType::Runtime(RuntimeError::InsufficientPermission));
+        assert_eq!("InsufficientPermission", failure.code);
+        assert_eq!(Value::Null, failure.fields);
+    }
+}
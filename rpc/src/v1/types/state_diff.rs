@@ -0,0 +1,164 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use cjson::bytes::Bytes;
+use cjson::uint::Uint;
+use ckey::{NetworkId, PlatformAddress, Public};
+use cstate::{
+    Account as AccountType, Metadata as MetadataType, RegularAccount as RegularAccountType, Shard as ShardType,
+    StateChange as StateChangeType, StateValue as StateValueType,
+};
+use ctypes::ShardId;
+use primitives::H256;
+
+use super::{AssetScheme, OwnedAsset, Text};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateChange {
+    key: H256,
+    shard_id: Option<ShardId>,
+    before: Option<StateValue>,
+    after: Option<StateValue>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum StateValue {
+    Account(Account),
+    RegularAccount(RegularAccount),
+    Metadata(Metadata),
+    Shard(Shard),
+    AssetScheme(AssetScheme),
+    OwnedAsset(OwnedAsset),
+    Text(Text),
+    ActionData(Bytes),
+    Unknown(Bytes),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    balance: Uint,
+    seq: u64,
+    regular_key: Option<Public>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegularAccount {
+    owner_public: Public,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    number_of_shards: ShardId,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shard {
+    root: H256,
+    owners: Vec<PlatformAddress>,
+    users: Vec<PlatformAddress>,
+}
+
+impl StateChange {
+    pub fn from_core(change: StateChangeType, network_id: NetworkId) -> Self {
+        Self {
+            key: change.key,
+            shard_id: change.shard_id,
+            before: change.before.map(|value| StateValue::from_core(value, network_id)),
+            after: change.after.map(|value| StateValue::from_core(value, network_id)),
+        }
+    }
+}
+
+impl StateValue {
+    pub fn from_core(value: StateValueType, network_id: NetworkId) -> Self {
+        match value {
+            StateValueType::Account(account) => StateValue::Account(account.into()),
+            StateValueType::RegularAccount(account) => StateValue::RegularAccount(account.into()),
+            StateValueType::Metadata(metadata) => StateValue::Metadata(metadata.into()),
+            StateValueType::Shard(shard) => StateValue::Shard(Shard::from_core(shard, network_id)),
+            StateValueType::AssetScheme(scheme) => StateValue::AssetScheme(AssetScheme::from_core(scheme, network_id)),
+            StateValueType::OwnedAsset(asset) => StateValue::OwnedAsset(asset.into()),
+            StateValueType::Text(text) => StateValue::Text(Text::from_core(text, network_id)),
+            StateValueType::ActionData(data) => StateValue::ActionData(Vec::from(data).into()),
+            StateValueType::Unknown(bytes) => StateValue::Unknown(bytes.into()),
+        }
+    }
+}
+
+impl From<AccountType> for Account {
+    fn from(account: AccountType) -> Self {
+        Self {
+            balance: account.balance().into(),
+            seq: account.seq(),
+            regular_key: account.regular_key(),
+        }
+    }
+}
+
+impl From<RegularAccountType> for RegularAccount {
+    fn from(account: RegularAccountType) -> Self {
+        Self {
+            owner_public: *account.owner_public(),
+        }
+    }
+}
+
+impl From<MetadataType> for Metadata {
+    fn from(metadata: MetadataType) -> Self {
+        Self {
+            number_of_shards: *metadata.number_of_shards(),
+        }
+    }
+}
+
+impl Shard {
+    pub fn from_core(shard: ShardType, network_id: NetworkId) -> Self {
+        Self {
+            root: *shard.root(),
+            owners: shard.owners().iter().map(|owner| PlatformAddress::new_v1(network_id, *owner)).collect(),
+            users: shard.users().iter().map(|user| PlatformAddress::new_v1(network_id, *user)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{self, Value};
+
+    use super::*;
+
+    #[test]
+    fn serialize_account_change() {
+        let change = StateChangeType {
+            key: H256::zero(),
+            shard_id: None,
+            before: None,
+            after: Some(StateValueType::Account(AccountType::new(10, 1))),
+        };
+        let serialized = serde_json::to_value(&StateChange::from_core(change, "tc".into())).unwrap();
+        assert_eq!(serialized["shardId"], Value::Null);
+        assert_eq!(serialized["before"], Value::Null);
+        assert_eq!(serialized["after"]["type"], "account");
+        assert_eq!(serialized["after"]["value"]["seq"], 1);
+        assert_eq!(serialized["after"]["value"]["regularKey"], Value::Null);
+    }
+}
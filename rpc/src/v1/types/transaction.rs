@@ -34,6 +34,7 @@ pub struct Transaction {
     pub action: ActionWithTracker,
     pub hash: H256,
     pub sig: Signature,
+    pub co_sigs: Vec<Signature>,
 }
 
 #[derive(Debug, Serialize)]
@@ -67,6 +68,7 @@ impl From<LocalizedTransaction> for Transaction {
             action: ActionWithTracker::from_core(p.action.clone(), p.network_id),
             hash: p.hash(),
             sig,
+            co_sigs: p.co_signatures().to_vec(),
         }
     }
 }
@@ -85,6 +87,7 @@ impl From<SignedTransaction> for Transaction {
             action: ActionWithTracker::from_core(p.action.clone(), p.network_id),
             hash: p.hash(),
             sig,
+            co_sigs: p.co_signatures().to_vec(),
         }
     }
 }
@@ -54,6 +54,10 @@ impl ActionHandler for HitHandler {
         Ok(())
     }
 
+    fn verify(&self, bytes: &[u8]) -> Result<(), String> {
+        HitAction::decode(&UntrustedRlp::new(bytes)).map(|_| ()).map_err(|err| err.to_string())
+    }
+
     /// `bytes` must be valid encoding of HitAction
     fn execute(&self, bytes: &[u8], state: &mut TopLevelState, _sender: &Address) -> StateResult<()> {
         let action = HitAction::decode(&UntrustedRlp::new(bytes))
@@ -15,6 +15,8 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod hit;
+mod registry;
+mod wasm;
 
 use std::convert::From;
 
@@ -31,6 +33,18 @@ pub trait ActionHandler: Send + Sync {
     fn init(&self, state: &mut TopLevelState) -> StateResult<()>;
     fn execute(&self, bytes: &[u8], state: &mut TopLevelState, sender: &Address) -> StateResult<()>;
 
+    /// Checks the syntax of `bytes` before the transaction enters the mem pool or a block.
+    /// The returned message is given to the RPC clients as a hint.
+    fn verify(&self, _bytes: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Returns the minimum fee of the action.
+    /// The fee cannot be lower than `min_custom_cost` of the common params.
+    fn fee(&self, _bytes: &[u8], min_custom_cost: u64) -> u64 {
+        min_custom_cost
+    }
+
     fn query(&self, key_fragment: &[u8], state: &TopLevelState) -> StateResult<Option<Vec<u8>>> {
         let key = ActionDataKeyBuilder::key_from_fragment(self.handler_id(), key_fragment);
         let some_action_data = state.action_data(&key)?.map(Vec::from);
@@ -83,6 +97,8 @@ impl ActionDataKeyBuilder {
 }
 
 pub use self::hit::HitHandler;
+pub use self::registry::ActionHandlerRegistry;
+pub use self::wasm::WasmActionHandler;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,72 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use super::{ActionHandler, WasmActionHandler};
+
+/// Collects the action handlers of a chain and makes sure that their IDs are unique.
+#[derive(Default)]
+pub struct ActionHandlerRegistry {
+    handlers: Vec<Arc<ActionHandler>>,
+}
+
+impl ActionHandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, handler: Arc<ActionHandler>) -> Result<(), String> {
+        let handler_id = handler.handler_id();
+        if self.handlers.iter().any(|registered| registered.handler_id() == handler_id) {
+            return Err(format!("The action handler {} is already registered", handler_id))
+        }
+        self.handlers.push(handler);
+        Ok(())
+    }
+
+    /// Loads the WebAssembly module and registers it as the handler of `handler_id`.
+    pub fn register_wasm(&mut self, handler_id: u64, code: &[u8], gas_limit: u64) -> Result<(), String> {
+        let handler = WasmActionHandler::new(handler_id, code, gas_limit)
+            .map_err(|err| format!("Cannot load the action handler {}: {}", handler_id, err))?;
+        self.register(Arc::new(handler))
+    }
+
+    pub fn into_handlers(self) -> Vec<Arc<ActionHandler>> {
+        self.handlers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::HitHandler;
+    use super::*;
+
+    #[test]
+    fn handler_ids_are_unique() {
+        let mut registry = ActionHandlerRegistry::new();
+        assert_eq!(Ok(()), registry.register(Arc::new(HitHandler::new())));
+        assert!(registry.register(Arc::new(HitHandler::new())).is_err());
+        assert_eq!(1, registry.into_handlers().len());
+    }
+
+    #[test]
+    fn invalid_wasm_module_is_rejected() {
+        let mut registry = ActionHandlerRegistry::new();
+        assert!(registry.register_wasm(1000, b"not a wasm module", 1_000_000).is_err());
+        assert!(registry.into_handlers().is_empty());
+    }
+}
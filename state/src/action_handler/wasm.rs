@@ -0,0 +1,609 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{cmp, fmt};
+
+use ckey::Address;
+use ctypes::errors::RuntimeError;
+use ctypes::event::Event;
+use parity_wasm::{self, elements};
+use pwasm_utils::{self, rules};
+use wasmi::{
+    Error as WasmError, Externals, FuncInstance, FuncRef, HostError, ImportsBuilder, MemoryRef, Module,
+    ModuleImportResolver, ModuleInstance, ModuleRef, RuntimeArgs, RuntimeValue, Signature, Trap, ValueType,
+};
+
+use super::{ActionDataKeyBuilder, ActionHandler};
+use crate::{StateError, StateResult, TopLevelState, TopState, TopStateView};
+
+const HOST_MODULE_NAME: &str = "env";
+
+const GAS_FUNC_INDEX: usize = 0;
+const ACTION_DATA_GET_FUNC_INDEX: usize = 1;
+const ACTION_DATA_PUT_FUNC_INDEX: usize = 2;
+const SENDER_FUNC_INDEX: usize = 3;
+const ERROR_HINT_FUNC_INDEX: usize = 4;
+const EMIT_EVENT_FUNC_INDEX: usize = 5;
+
+const ACTION_DATA_GET_GAS: u64 = 200;
+const ACTION_DATA_PUT_GAS: u64 = 5_000;
+const EMIT_EVENT_GAS: u64 = 1_000;
+const GAS_PER_BYTE: u64 = 10;
+
+const MAX_STACK_HEIGHT: u32 = 16 * 1024;
+/// The gas charged for each page(64KiB) that `memory.grow` adds
+const GROW_MEMORY_GAS_PER_PAGE: u32 = 10_000;
+/// The maximum number of memory pages(16MiB)
+const MAX_MEMORY_PAGES: u32 = 256;
+
+/// An action handler implemented as a WebAssembly module.
+///
+/// The module must export `memory`, `alloc(len: i32) -> i32` and `execute(ptr: i32, len: i32) -> i32`.
+/// It may export `init()`, `verify(ptr: i32, len: i32) -> i32` and `fee(ptr: i32, len: i32) -> i64`.
+/// `execute` and `verify` return zero on success. `verify` and `fee` cannot access the state.
+/// The host functions below are imported from the `env` module:
+///  * `action_data_get(key_ptr, key_len, value_ptr, value_capacity) -> i32` writes the value only if it fits
+///    into the capacity and returns its length, or -1 if there is no value.
+///  * `action_data_put(key_ptr, key_len, value_ptr, value_len)`
+///  * `sender(ptr)` writes the 20 bytes of the sender's address.
+///  * `error_hint(ptr, len)` sets the UTF-8 message reported when the current call fails.
+///  * `emit_event(topic_ptr, topic_len, data_ptr, data_len)` records a custom event of the transaction.
+///
+/// The module is instrumented with a gas counter so that every execution stops deterministically.
+/// Its memory cannot grow beyond `MAX_MEMORY_PAGES`, and growing it costs gas for each page.
+pub struct WasmActionHandler {
+    handler_id: u64,
+    module: Module,
+    gas_limit: u64,
+}
+
+impl WasmActionHandler {
+    pub fn new(handler_id: u64, code: &[u8], gas_limit: u64) -> Result<Self, String> {
+        let module =
+            parity_wasm::deserialize_buffer(code).map_err(|err| format!("Cannot decode the wasm module: {}", err))?;
+        if module.start_section().is_some() {
+            return Err("The start function is not allowed".to_string())
+        }
+        let module = limit_memory(module)?;
+        let rules = rules::Set::default().with_grow_cost(GROW_MEMORY_GAS_PER_PAGE);
+        let module = pwasm_utils::inject_gas_counter(module, &rules)
+            .map_err(|_| "Cannot inject the gas counter".to_string())?;
+        let module = pwasm_utils::stack_height::inject_limiter(module, MAX_STACK_HEIGHT)
+            .map_err(|err| format!("Cannot limit the stack height: {:?}", err))?;
+        let module = Module::from_parity_wasm_module(module).map_err(|err| format!("Invalid wasm module: {}", err))?;
+        module.deny_floating_point().map_err(|_| "Floating point operations are not deterministic".to_string())?;
+        Ok(Self {
+            handler_id,
+            module,
+            gas_limit,
+        })
+    }
+
+    fn instantiate(&self) -> Result<ModuleRef, String> {
+        let imports = ImportsBuilder::new().with_resolver(HOST_MODULE_NAME, &HostFunctions);
+        let instance = ModuleInstance::new(&self.module, &imports).map_err(|err| err.to_string())?;
+        Ok(instance.assert_no_start())
+    }
+
+    /// Runs `invoke` on a new instance of the module.
+    /// The outer error comes from the state, and the inner one is the failure of the module.
+    fn call<T, F>(
+        &self,
+        state: Option<&mut TopLevelState>,
+        sender: Option<&Address>,
+        invoke: F,
+    ) -> StateResult<Result<T, String>>
+    where
+        F: FnOnce(&ModuleRef, &mut Host) -> Result<T, String>, {
+        let instance = match self.instantiate() {
+            Ok(instance) => instance,
+            Err(err) => return Ok(Err(err)),
+        };
+        let memory = match instance.export_by_name("memory").and_then(|export| export.as_memory().cloned()) {
+            Some(memory) => memory,
+            None => return Ok(Err("The memory is not exported".to_string())),
+        };
+        let mut host = Host {
+            handler_id: self.handler_id,
+            memory,
+            state,
+            sender,
+            gas_left: self.gas_limit,
+            state_error: None,
+            hint: None,
+        };
+        let result = invoke(&instance, &mut host);
+        let hint = host.hint.take();
+        let result = result.map_err(|err| match hint {
+            Some(hint) => format!("{}: {}", err, hint),
+            None => err,
+        });
+        if let Some(err) = host.state_error.take() {
+            return Err(err)
+        }
+        Ok(result)
+    }
+}
+
+impl ActionHandler for WasmActionHandler {
+    fn handler_id(&self) -> u64 {
+        self.handler_id
+    }
+
+    fn init(&self, state: &mut TopLevelState) -> StateResult<()> {
+        self.call(Some(state), None, invoke_init)?.map_err(|err| RuntimeError::FailedToHandleCustomAction(err).into())
+    }
+
+    fn execute(&self, bytes: &[u8], state: &mut TopLevelState, sender: &Address) -> StateResult<()> {
+        self.call(Some(state), Some(sender), |instance, host| invoke_execute(instance, host, bytes))?
+            .map_err(|err| RuntimeError::FailedToHandleCustomAction(err).into())
+    }
+
+    fn verify(&self, bytes: &[u8]) -> Result<(), String> {
+        match self.call(None, None, |instance, host| invoke_verify(instance, host, bytes)) {
+            Ok(result) => result,
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    /// A module that fails to compute the fee makes the action unaffordable.
+    fn fee(&self, bytes: &[u8], min_custom_cost: u64) -> u64 {
+        match self.call(None, None, |instance, host| invoke_fee(instance, host, bytes)) {
+            Ok(Ok(Some(fee))) => cmp::max(fee, min_custom_cost),
+            Ok(Ok(None)) => min_custom_cost,
+            _ => u64::max_value(),
+        }
+    }
+}
+
+/// Lowers the maximum of the memory to `MAX_MEMORY_PAGES`, so that `memory.grow` fails beyond it.
+fn limit_memory(mut module: elements::Module) -> Result<elements::Module, String> {
+    if let Some(section) = module.memory_section_mut() {
+        for entry in section.entries_mut() {
+            let initial = entry.limits().initial();
+            if initial > MAX_MEMORY_PAGES {
+                return Err(format!("The memory cannot be larger than {} pages", MAX_MEMORY_PAGES))
+            }
+            let maximum = entry.limits().maximum().map_or(MAX_MEMORY_PAGES, |max| cmp::min(max, MAX_MEMORY_PAGES));
+            *entry = elements::MemoryType::new(initial, Some(maximum));
+        }
+    }
+    Ok(module)
+}
+
+fn invoke_init(instance: &ModuleRef, host: &mut Host) -> Result<(), String> {
+    if instance.export_by_name("init").is_none() {
+        return Ok(())
+    }
+    instance.invoke_export("init", &[], host).map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Copies `input` into the memory allocated by the module, and returns the arguments pointing it.
+fn pass_input(instance: &ModuleRef, host: &mut Host, input: &[u8]) -> Result<[RuntimeValue; 2], String> {
+    let len = RuntimeValue::I32(input.len() as i32);
+    let ptr = match instance.invoke_export("alloc", &[len], host).map_err(|err| err.to_string())? {
+        Some(RuntimeValue::I32(ptr)) => ptr,
+        _ => return Err("alloc must return an i32".to_string()),
+    };
+    host.memory.set(ptr as u32, input).map_err(|err| err.to_string())?;
+    Ok([RuntimeValue::I32(ptr), len])
+}
+
+fn invoke_execute(instance: &ModuleRef, host: &mut Host, input: &[u8]) -> Result<(), String> {
+    let args = pass_input(instance, host, input)?;
+    match instance.invoke_export("execute", &args, host).map_err(|err| err.to_string())? {
+        Some(RuntimeValue::I32(0)) => Ok(()),
+        Some(RuntimeValue::I32(code)) => Err(format!("The handler failed with {}", code)),
+        _ => Err("execute must return an i32".to_string()),
+    }
+}
+
+fn invoke_verify(instance: &ModuleRef, host: &mut Host, input: &[u8]) -> Result<(), String> {
+    if instance.export_by_name("verify").is_none() {
+        return Ok(())
+    }
+    let args = pass_input(instance, host, input)?;
+    match instance.invoke_export("verify", &args, host).map_err(|err| err.to_string())? {
+        Some(RuntimeValue::I32(0)) => Ok(()),
+        Some(RuntimeValue::I32(code)) => Err(format!("The handler rejected the action with {}", code)),
+        _ => Err("verify must return an i32".to_string()),
+    }
+}
+
+fn invoke_fee(instance: &ModuleRef, host: &mut Host, input: &[u8]) -> Result<Option<u64>, String> {
+    if instance.export_by_name("fee").is_none() {
+        return Ok(None)
+    }
+    let args = pass_input(instance, host, input)?;
+    match instance.invoke_export("fee", &args, host).map_err(|err| err.to_string())? {
+        Some(RuntimeValue::I64(fee)) => Ok(Some(fee as u64)),
+        _ => Err("fee must return an i64".to_string()),
+    }
+}
+
+struct HostFunctions;
+
+impl ModuleImportResolver for HostFunctions {
+    fn resolve_func(&self, field_name: &str, signature: &Signature) -> Result<FuncRef, WasmError> {
+        let (index, expected) = match field_name {
+            "gas" => (GAS_FUNC_INDEX, Signature::new(&[ValueType::I32][..], None)),
+            "action_data_get" => {
+                (ACTION_DATA_GET_FUNC_INDEX, Signature::new(&[ValueType::I32; 4][..], Some(ValueType::I32)))
+            }
+            "action_data_put" => (ACTION_DATA_PUT_FUNC_INDEX, Signature::new(&[ValueType::I32; 4][..], None)),
+            "sender" => (SENDER_FUNC_INDEX, Signature::new(&[ValueType::I32][..], None)),
+            "error_hint" => (ERROR_HINT_FUNC_INDEX, Signature::new(&[ValueType::I32; 2][..], None)),
+            "emit_event" => (EMIT_EVENT_FUNC_INDEX, Signature::new(&[ValueType::I32; 4][..], None)),
+            _ => return Err(WasmError::Instantiation(format!("Unknown host function {}", field_name))),
+        };
+        if signature != &expected {
+            return Err(WasmError::Instantiation(format!("{} has an invalid signature {:?}", field_name, signature)))
+        }
+        Ok(FuncInstance::alloc_host(expected, index))
+    }
+}
+
+#[derive(Debug)]
+enum HostFailure {
+    OutOfGas,
+    InvalidMemoryAccess,
+    NoSender,
+    NoState,
+    State,
+}
+
+impl fmt::Display for HostFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HostFailure::OutOfGas => write!(f, "Out of gas"),
+            HostFailure::InvalidMemoryAccess => write!(f, "Invalid memory access"),
+            HostFailure::NoSender => write!(f, "There is no sender"),
+            HostFailure::NoState => write!(f, "The state is not accessible"),
+            HostFailure::State => write!(f, "Cannot access the state"),
+        }
+    }
+}
+
+impl HostError for HostFailure {}
+
+struct Host<'a> {
+    handler_id: u64,
+    memory: MemoryRef,
+    state: Option<&'a mut TopLevelState>,
+    sender: Option<&'a Address>,
+    gas_left: u64,
+    /// The error is kept here, since a trap cannot carry it.
+    state_error: Option<StateError>,
+    hint: Option<String>,
+}
+
+impl<'a> Host<'a> {
+    fn charge(&mut self, gas: u64) -> Result<(), Trap> {
+        if self.gas_left < gas {
+            self.gas_left = 0;
+            return Err(HostFailure::OutOfGas.into())
+        }
+        self.gas_left -= gas;
+        Ok(())
+    }
+
+    fn read(&self, ptr: u32, len: u32) -> Result<Vec<u8>, Trap> {
+        self.memory.get(ptr, len as usize).map_err(|_| HostFailure::InvalidMemoryAccess.into())
+    }
+
+    fn write(&self, ptr: u32, data: &[u8]) -> Result<(), Trap> {
+        self.memory.set(ptr, data).map_err(|_| HostFailure::InvalidMemoryAccess.into())
+    }
+
+    fn state_failure<E: Into<StateError>>(&mut self, err: E) -> Trap {
+        self.state_error = Some(err.into());
+        HostFailure::State.into()
+    }
+
+    fn action_data_get(&mut self, args: RuntimeArgs) -> Result<Option<RuntimeValue>, Trap> {
+        let key_ptr: u32 = args.nth_checked(0)?;
+        let key_len: u32 = args.nth_checked(1)?;
+        let value_ptr: u32 = args.nth_checked(2)?;
+        let value_capacity: u32 = args.nth_checked(3)?;
+        self.charge(ACTION_DATA_GET_GAS + u64::from(key_len) * GAS_PER_BYTE)?;
+
+        let key = ActionDataKeyBuilder::new(self.handler_id, 1).append(&self.read(key_ptr, key_len)?).into_key();
+        let result = match self.state {
+            Some(ref state) => state.action_data(&key),
+            None => return Err(HostFailure::NoState.into()),
+        };
+        let action_data = match result {
+            Ok(action_data) => action_data,
+            Err(err) => return Err(self.state_failure(err)),
+        };
+        let value = match action_data {
+            Some(value) => value,
+            None => return Ok(Some(RuntimeValue::I32(-1))),
+        };
+        self.charge(value.len() as u64 * GAS_PER_BYTE)?;
+        if value.len() <= value_capacity as usize {
+            self.write(value_ptr, &value)?;
+        }
+        Ok(Some(RuntimeValue::I32(value.len() as i32)))
+    }
+
+    fn action_data_put(&mut self, args: RuntimeArgs) -> Result<Option<RuntimeValue>, Trap> {
+        let key_ptr: u32 = args.nth_checked(0)?;
+        let key_len: u32 = args.nth_checked(1)?;
+        let value_ptr: u32 = args.nth_checked(2)?;
+        let value_len: u32 = args.nth_checked(3)?;
+        self.charge(ACTION_DATA_PUT_GAS + (u64::from(key_len) + u64::from(value_len)) * GAS_PER_BYTE)?;
+
+        let key = ActionDataKeyBuilder::new(self.handler_id, 1).append(&self.read(key_ptr, key_len)?).into_key();
+        let value = self.read(value_ptr, value_len)?;
+        let result = match self.state {
+            Some(ref mut state) => state.update_action_data(&key, value),
+            None => return Err(HostFailure::NoState.into()),
+        };
+        if let Err(err) = result {
+            return Err(self.state_failure(err))
+        }
+        Ok(None)
+    }
+
+    fn sender(&mut self, args: RuntimeArgs) -> Result<Option<RuntimeValue>, Trap> {
+        let ptr: u32 = args.nth_checked(0)?;
+        let sender = match self.sender {
+            Some(sender) => *sender,
+            None => return Err(HostFailure::NoSender.into()),
+        };
+        self.write(ptr, &sender)?;
+        Ok(None)
+    }
+
+    fn error_hint(&mut self, args: RuntimeArgs) -> Result<Option<RuntimeValue>, Trap> {
+        let ptr: u32 = args.nth_checked(0)?;
+        let len: u32 = args.nth_checked(1)?;
+        self.charge(u64::from(len) * GAS_PER_BYTE)?;
+        self.hint = Some(String::from_utf8_lossy(&self.read(ptr, len)?).into_owned());
+        Ok(None)
+    }
+
+    fn emit_event(&mut self, args: RuntimeArgs) -> Result<Option<RuntimeValue>, Trap> {
+        let topic_ptr: u32 = args.nth_checked(0)?;
+        let topic_len: u32 = args.nth_checked(1)?;
+        let data_ptr: u32 = args.nth_checked(2)?;
+        let data_len: u32 = args.nth_checked(3)?;
+        self.charge(EMIT_EVENT_GAS + (u64::from(topic_len) + u64::from(data_len)) * GAS_PER_BYTE)?;
+
+        let event = Event::Custom {
+            handler_id: self.handler_id,
+            topic: self.read(topic_ptr, topic_len)?,
+            data: self.read(data_ptr, data_len)?,
+        };
+        match self.state {
+            Some(ref mut state) => state.emit_event(event),
+            None => return Err(HostFailure::NoState.into()),
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Externals for Host<'a> {
+    fn invoke_index(&mut self, index: usize, args: RuntimeArgs) -> Result<Option<RuntimeValue>, Trap> {
+        match index {
+            GAS_FUNC_INDEX => {
+                let gas: u32 = args.nth_checked(0)?;
+                self.charge(u64::from(gas))?;
+                Ok(None)
+            }
+            ACTION_DATA_GET_FUNC_INDEX => self.action_data_get(args),
+            ACTION_DATA_PUT_FUNC_INDEX => self.action_data_put(args),
+            SENDER_FUNC_INDEX => self.sender(args),
+            ERROR_HINT_FUNC_INDEX => self.error_hint(args),
+            EMIT_EVENT_FUNC_INDEX => self.emit_event(args),
+            _ => unreachable!("Only the functions resolved by HostFunctions are called"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wabt;
+
+    use super::*;
+    use crate::tests::helpers::get_temp_state;
+
+    // Stores the input under the key "last" and counts the executions under the key "count".
+    const COUNTER: &str = r#"
+        (module
+            (import "env" "action_data_get" (func $get (param i32 i32 i32 i32) (result i32)))
+            (import "env" "action_data_put" (func $put (param i32 i32 i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "count")
+            (data (i32.const 8) "last")
+            (func (export "init")
+                (i32.store8 (i32.const 16) (i32.const 0))
+                (call $put (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 1)))
+            (func (export "alloc") (param $len i32) (result i32)
+                (i32.const 1024))
+            (func (export "execute") (param $ptr i32) (param $len i32) (result i32)
+                (drop (call $get (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 1)))
+                (i32.store8 (i32.const 16) (i32.add (i32.load8_u (i32.const 16)) (i32.const 1)))
+                (call $put (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 1))
+                (call $put (i32.const 8) (i32.const 4) (get_local $ptr) (get_local $len))
+                (i32.const 0)))
+    "#;
+
+    // Emits the input as an event of the topic "echo".
+    const ECHO: &str = r#"
+        (module
+            (import "env" "emit_event" (func $emit (param i32 i32 i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "echo")
+            (func (export "alloc") (param $len i32) (result i32)
+                (i32.const 1024))
+            (func (export "execute") (param $ptr i32) (param $len i32) (result i32)
+                (call $emit (i32.const 0) (i32.const 4) (get_local $ptr) (get_local $len))
+                (i32.const 0)))
+    "#;
+
+    const INFINITE_LOOP: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param $len i32) (result i32)
+                (i32.const 1024))
+            (func (export "execute") (param $ptr i32) (param $len i32) (result i32)
+                (loop $forever (br $forever))
+                (i32.const 0)))
+    "#;
+
+    // Accepts only non-empty actions, and charges 100 for each byte.
+    const PRICED: &str = r#"
+        (module
+            (import "env" "error_hint" (func $hint (param i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "empty")
+            (func (export "alloc") (param $len i32) (result i32)
+                (i32.const 1024))
+            (func (export "execute") (param $ptr i32) (param $len i32) (result i32)
+                (i32.const 0))
+            (func (export "verify") (param $ptr i32) (param $len i32) (result i32)
+                (if (i32.eqz (get_local $len))
+                    (then
+                        (call $hint (i32.const 0) (i32.const 5))
+                        (return (i32.const 1))))
+                (i32.const 0))
+            (func (export "fee") (param $ptr i32) (param $len i32) (result i64)
+                (i64.mul (i64.extend_u/i32 (get_local $len)) (i64.const 100))))
+    "#;
+
+    // Grows the memory by the number of pages in the first two bytes of the input, in little endian.
+    const GROW: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param $len i32) (result i32)
+                (i32.const 0))
+            (func (export "execute") (param $ptr i32) (param $len i32) (result i32)
+                (if (i32.eq (grow_memory (i32.load16_u (get_local $ptr))) (i32.const -1))
+                    (then (return (i32.const 1))))
+                (i32.const 0)))
+    "#;
+
+    const LARGE_MEMORY: &str = r#"
+        (module
+            (memory (export "memory") 257)
+            (func (export "alloc") (param $len i32) (result i32)
+                (i32.const 0))
+            (func (export "execute") (param $ptr i32) (param $len i32) (result i32)
+                (i32.const 0)))
+    "#;
+
+    fn action_data(handler: &WasmActionHandler, state: &TopLevelState, key: &[u8]) -> Option<Vec<u8>> {
+        let key = ActionDataKeyBuilder::new(handler.handler_id(), 1).append(&key.to_vec()).into_key();
+        state.action_data(&key).unwrap().map(Vec::from)
+    }
+
+    #[test]
+    fn execute_updates_action_data() {
+        let code = wabt::wat2wasm(COUNTER).unwrap();
+        let handler = WasmActionHandler::new(1000, &code, 1_000_000).unwrap();
+        let mut state = get_temp_state();
+        let sender = Address::random();
+
+        handler.init(&mut state).unwrap();
+        assert_eq!(Some(vec![0]), action_data(&handler, &state, b"count"));
+
+        handler.execute(b"hello", &mut state, &sender).unwrap();
+        handler.execute(b"world", &mut state, &sender).unwrap();
+        assert_eq!(Some(vec![2]), action_data(&handler, &state, b"count"));
+        assert_eq!(Some(b"world".to_vec()), action_data(&handler, &state, b"last"));
+    }
+
+    #[test]
+    fn execute_emits_events() {
+        let code = wabt::wat2wasm(ECHO).unwrap();
+        let handler = WasmActionHandler::new(1000, &code, 1_000_000).unwrap();
+        let mut state = get_temp_state();
+
+        handler.execute(b"hello", &mut state, &Address::random()).unwrap();
+        assert_eq!(
+            vec![Event::Custom {
+                handler_id: 1000,
+                topic: b"echo".to_vec(),
+                data: b"hello".to_vec(),
+            }],
+            state.take_events()
+        );
+    }
+
+    #[test]
+    fn verify_and_fee_are_delegated_to_the_module() {
+        let code = wabt::wat2wasm(PRICED).unwrap();
+        let handler = WasmActionHandler::new(1000, &code, 1_000_000).unwrap();
+
+        assert_eq!(Ok(()), handler.verify(b"action"));
+        let err = handler.verify(b"").unwrap_err();
+        assert!(err.ends_with("empty"), "{}", err);
+
+        assert_eq!(600, handler.fee(b"action", 10));
+        assert_eq!(1_000, handler.fee(b"action", 1_000));
+    }
+
+    #[test]
+    fn execution_stops_when_gas_runs_out() {
+        let code = wabt::wat2wasm(INFINITE_LOOP).unwrap();
+        let handler = WasmActionHandler::new(1000, &code, 1_000_000).unwrap();
+        let mut state = get_temp_state();
+
+        handler.init(&mut state).unwrap();
+        match handler.execute(b"", &mut state, &Address::random()) {
+            Err(StateError::Runtime(RuntimeError::FailedToHandleCustomAction(_))) => {}
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn growing_memory_costs_gas() {
+        let code = wabt::wat2wasm(GROW).unwrap();
+        let mut state = get_temp_state();
+
+        let handler = WasmActionHandler::new(1000, &code, 1_000_000).unwrap();
+        assert_eq!(Ok(()), handler.execute(&[50, 0], &mut state, &Address::random()));
+
+        let handler = WasmActionHandler::new(1000, &code, 100_000).unwrap();
+        match handler.execute(&[50, 0], &mut state, &Address::random()) {
+            Err(StateError::Runtime(RuntimeError::FailedToHandleCustomAction(_))) => {}
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn memory_cannot_grow_beyond_the_maximum() {
+        let code = wabt::wat2wasm(GROW).unwrap();
+        let handler = WasmActionHandler::new(1000, &code, u64::max_value()).unwrap();
+        let mut state = get_temp_state();
+
+        // The memory has one page initially.
+        assert_eq!(Ok(()), handler.execute(&[255, 0], &mut state, &Address::random()));
+        match handler.execute(&[0, 1], &mut state, &Address::random()) {
+            Err(StateError::Runtime(RuntimeError::FailedToHandleCustomAction(_))) => {}
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn module_with_too_large_memory_is_rejected() {
+        let code = wabt::wat2wasm(LARGE_MEMORY).unwrap();
+        assert!(WasmActionHandler::new(1000, &code, 1_000_000).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cmp::max;
+use std::collections::HashMap;
+
+use ccrypto::{blake256, BLAKE_NULL_RLP};
+use cmerkle::{Result as TrieResult, Trie, TrieDiff, TrieFactory};
+use ctypes::ShardId;
+use hashdb::HashDB;
+use primitives::H256;
+use rlp::UntrustedRlp;
+
+use crate::{Metadata, MetadataAddress, ShardAddress, StateValue};
+
+/// An entry of the state that differs between two state roots.
+#[derive(Clone, Debug)]
+pub struct StateChange {
+    /// The path of the entry in its trie, which is the hash of its address.
+    pub key: H256,
+    /// The shard of a shard entry, or of an asset scheme or an owned asset in the shard trie.
+    pub shard_id: Option<ShardId>,
+    pub before: Option<StateValue>,
+    pub after: Option<StateValue>,
+}
+
+/// Returns the entries that differ between two states in the same database.
+/// The shard tries of the changed shards are compared too, so the changed assets are also returned.
+/// Returns None if more than `max_changes` entries differ, without comparing the rest of the tries.
+pub fn state_diff(
+    db: &HashDB,
+    before: &H256,
+    after: &H256,
+    max_changes: usize,
+) -> TrieResult<Option<Vec<StateChange>>> {
+    let shard_ids = shard_ids_by_key(db, &[before, after])?;
+    let mut changes = Vec::new();
+    for change in TrieDiff::try_new(db, before, after)? {
+        let change = change?;
+        let before = change.before.map(StateValue::from_bytes);
+        let after = change.after.map(StateValue::from_bytes);
+        let before_shard_root = shard_root(&before);
+        let after_shard_root = shard_root(&after);
+        let shard_id = if before_shard_root.is_some() || after_shard_root.is_some() {
+            shard_ids.get(&change.key).cloned()
+        } else {
+            None
+        };
+        changes.push(StateChange {
+            key: change.key,
+            shard_id,
+            before,
+            after,
+        });
+        if changes.len() > max_changes {
+            return Ok(None)
+        }
+
+        if shard_id.is_none() {
+            continue
+        }
+        let before_shard_root = before_shard_root.unwrap_or(BLAKE_NULL_RLP);
+        let after_shard_root = after_shard_root.unwrap_or(BLAKE_NULL_RLP);
+        for change in TrieDiff::try_new(db, &before_shard_root, &after_shard_root)? {
+            let change = change?;
+            changes.push(StateChange {
+                key: change.key,
+                shard_id,
+                before: change.before.map(StateValue::from_bytes),
+                after: change.after.map(StateValue::from_bytes),
+            });
+            if changes.len() > max_changes {
+                return Ok(None)
+            }
+        }
+    }
+    Ok(Some(changes))
+}
+
+fn shard_root(value: &Option<StateValue>) -> Option<H256> {
+    match value {
+        Some(StateValue::Shard(shard)) => Some(*shard.root()),
+        _ => None,
+    }
+}
+
+/// The keys of the shards are hashed, so they are matched with the hashes of all shard addresses.
+fn shard_ids_by_key(db: &HashDB, roots: &[&H256]) -> TrieResult<HashMap<H256, ShardId>> {
+    let mut number_of_shards = 0;
+    for root in roots {
+        if **root == BLAKE_NULL_RLP {
+            continue
+        }
+        let trie = TrieFactory::readonly(db, root)?;
+        let metadata = trie.get_with(MetadataAddress::new().as_ref(), |bytes: &[u8]| {
+            UntrustedRlp::new(bytes).as_val::<Metadata>().ok()
+        })?;
+        if let Some(Some(metadata)) = metadata {
+            number_of_shards = max(number_of_shards, *metadata.number_of_shards());
+        }
+    }
+    Ok((0..number_of_shards).map(|shard_id| (blake256(ShardAddress::new(shard_id)), shard_id)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use ckey::Address;
+    use hashdb::AsHashDB;
+
+    use super::*;
+    use crate::tests::helpers::get_temp_state;
+    use crate::{StateWithCache, TopState};
+
+    #[test]
+    fn changed_account() {
+        let address = Address::random();
+        let mut state = get_temp_state();
+        state.add_balance(&address, 100).unwrap();
+        let before = state.commit().unwrap();
+        state.add_balance(&address, 20).unwrap();
+        let after = state.commit().unwrap();
+        let (db, _) = state.commit_and_into_db().unwrap();
+
+        let changes = state_diff(db.as_hashdb(), &before, &after, 1).unwrap().unwrap();
+        assert_eq!(1, changes.len());
+        assert_eq!(blake256(&address), changes[0].key);
+        assert_eq!(None, changes[0].shard_id);
+        match (&changes[0].before, &changes[0].after) {
+            (Some(StateValue::Account(before)), Some(StateValue::Account(after))) => {
+                assert_eq!(100, before.balance());
+                assert_eq!(120, after.balance());
+            }
+            values => panic!("Unexpected values {:?}", values),
+        }
+
+        assert!(state_diff(db.as_hashdb(), &after, &after, 0).unwrap().unwrap().is_empty());
+    }
+
+    #[test]
+    fn too_many_changes() {
+        let mut state = get_temp_state();
+        let before = state.commit().unwrap();
+        state.add_balance(&Address::random(), 100).unwrap();
+        state.add_balance(&Address::random(), 100).unwrap();
+        let after = state.commit().unwrap();
+        let (db, _) = state.commit_and_into_db().unwrap();
+
+        assert!(state_diff(db.as_hashdb(), &before, &after, 1).unwrap().is_none());
+        assert_eq!(2, state_diff(db.as_hashdb(), &before, &after, 2).unwrap().unwrap().len());
+    }
+}
@@ -401,6 +401,35 @@ macro_rules! set_regular_key {
     };
 }
 
+macro_rules! set_multisig {
+    ($publics:expr, $threshold:expr) => {
+        $crate::ctypes::transaction::Action::SetMultisig {
+            publics: $publics,
+            threshold: $threshold,
+        }
+    };
+}
+
+macro_rules! grant_vesting {
+    ($receiver:expr, $quantity:expr, unlock_block_number: $block_number:expr, unlock_timestamp: $timestamp:expr) => {
+        $crate::ctypes::transaction::Action::GrantVesting {
+            receiver: $receiver,
+            quantity: $quantity,
+            unlock_block_number: $block_number,
+            unlock_timestamp: $timestamp,
+        }
+    };
+}
+
+macro_rules! set_spending_limit {
+    ($limit:expr, $period:expr) => {
+        $crate::ctypes::transaction::Action::SetSpendingLimit {
+            limit: $limit,
+            period: $period,
+        }
+    };
+}
+
 macro_rules! wrap_ccc {
     ($lock_script_hash:expr, $quantity:expr) => {
         $crate::ctypes::transaction::Action::WrapCCC {
@@ -478,6 +507,16 @@ macro_rules! set_top_level_state {
 
         set_top_level_state!($state, [$($x),*]);
     };
+    ($state:expr, [(multisig: $addr:expr => publics: $publics:expr, threshold: $threshold:expr) $(,$x:tt)*]) => {
+        assert_eq!(Ok(()), $state.set_multisig(&$addr, $publics, $threshold));
+
+        set_top_level_state!($state, [$($x),*]);
+    };
+    ($state:expr, [(spending_limit: $addr:expr => limit: $limit:expr, period: $period:expr) $(,$x:tt)*]) => {
+        assert_eq!(Ok(()), $state.set_spending_limit(&$addr, $limit, $period));
+
+        set_top_level_state!($state, [$($x),*]);
+    };
     ($state:expr, [(account: $addr:expr => balance: $quantity:expr) $(,$x:tt)*]) => {
         assert_eq!(Ok(()), $state.set_balance(&$addr, $quantity));
 
@@ -37,22 +37,24 @@
 
 use std::cell::{RefCell, RefMut};
 use std::collections::HashMap;
+use std::iter::once;
+use std::mem;
 
-use ccrypto::BLAKE_NULL_RLP;
+use ccrypto::{Blake, BLAKE_NULL_RLP};
 use ckey::{public_to_address, recover, verify_address, Address, NetworkId, Public, Signature};
 use cmerkle::{Result as TrieResult, TrieError, TrieFactory};
 use ctypes::errors::RuntimeError;
+use ctypes::event::Event;
 use ctypes::transaction::{
-    Action, AssetOutPoint, AssetTransferInput, AssetWrapCCCOutput, ShardTransaction, Transaction,
+    Action, AssetOutPoint, AssetTransferInput, AssetTransferOutput, AssetWrapCCCOutput, ShardTransaction, Transaction,
+    MAX_NUMBER_OF_VESTINGS,
 };
 use ctypes::util::unexpected::Mismatch;
 use ctypes::{BlockNumber, ShardId};
 use cvm::ChainTimeInfo;
 use hashdb::AsHashDB;
 use kvdb::DBTransaction;
-#[cfg(test)]
-use primitives::H160;
-use primitives::{Bytes, H256};
+use primitives::{Bytes, H160, H256};
 use util_error::UtilError;
 
 use crate::cache::{ShardCache, TopCache};
@@ -61,8 +63,8 @@ use crate::traits::{ShardState, ShardStateView, StateWithCache, TopState, TopSta
 #[cfg(test)]
 use crate::Asset;
 use crate::{
-    Account, ActionData, FindActionHandler, Metadata, MetadataAddress, RegularAccount, RegularAccountAddress, Shard,
-    ShardAddress, ShardLevelState, StateDB, StateResult, Text,
+    Account, ActionData, FindActionHandler, Metadata, MetadataAddress, Multisig, RegularAccount, RegularAccountAddress,
+    Shard, ShardAddress, ShardLevelState, SpendingLimit, StateDB, StateResult, Text, Vesting,
 };
 
 /// Representation of the entire state of all accounts in the system.
@@ -94,6 +96,9 @@ pub struct TopLevelState {
     top_cache: TopCache,
     shard_caches: HashMap<ShardId, ShardCache>,
     id_of_checkpoints: Vec<CheckpointId>,
+
+    events: Vec<Event>,
+    length_of_events: Vec<usize>,
 }
 
 impl TopStateView for TopLevelState {
@@ -196,6 +201,7 @@ impl StateWithCheckpoint for TopLevelState {
     fn create_checkpoint(&mut self, id: CheckpointId) {
         ctrace!(STATE, "Checkpoint({}) for top level is created", id);
         self.id_of_checkpoints.push(id);
+        self.length_of_events.push(self.events.len());
         self.top_cache.checkpoint();
 
         for (_, mut cache) in self.shard_caches.iter_mut() {
@@ -208,6 +214,7 @@ impl StateWithCheckpoint for TopLevelState {
         assert_eq!(expected, id);
 
         ctrace!(STATE, "Checkpoint({}) for top level is discarded", id);
+        self.length_of_events.pop().expect("The checkpoint must exist");
         self.top_cache.discard_checkpoint();
 
         for (_, mut cache) in self.shard_caches.iter_mut() {
@@ -220,6 +227,8 @@ impl StateWithCheckpoint for TopLevelState {
         assert_eq!(expected, id);
 
         ctrace!(STATE, "Checkpoint({}) for top level is reverted", id);
+        let length_of_events = self.length_of_events.pop().expect("The checkpoint must exist");
+        self.events.truncate(length_of_events);
         self.top_cache.revert_to_checkpoint();
 
         for (_, mut cache) in self.shard_caches.iter_mut() {
@@ -244,6 +253,8 @@ impl TopLevelState {
             top_cache,
             shard_caches,
             id_of_checkpoints: Default::default(),
+            events: Default::default(),
+            length_of_events: Default::default(),
         };
 
         Ok(state)
@@ -261,11 +272,40 @@ impl TopLevelState {
         parent_block_timestamp: u64,
         current_block_timestamp: u64,
     ) -> StateResult<()> {
+        self.apply_with_co_signers(
+            tx,
+            signed_hash,
+            signer_public,
+            &[],
+            client,
+            parent_block_number,
+            parent_block_timestamp,
+            current_block_timestamp,
+        )
+    }
+
+    /// Execute a given tranasction that is co-signed by `co_signer_publics`, charging tranasction fee.
+    /// The co-signers are used to satisfy the multisig requirement of the fee payer.
+    /// The events emitted by the transaction can be taken by `take_events` after this returns.
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply_with_co_signers<C: ChainTimeInfo + FindActionHandler>(
+        &mut self,
+        tx: &Transaction,
+        signed_hash: &H256,
+        signer_public: &Public,
+        co_signer_publics: &[Public],
+        client: &C,
+        parent_block_number: BlockNumber,
+        parent_block_timestamp: u64,
+        current_block_timestamp: u64,
+    ) -> StateResult<()> {
+        self.events.clear();
         self.create_checkpoint(FEE_CHECKPOINT);
         let result = self.apply_internal(
             tx,
             signed_hash,
             signer_public,
+            co_signer_publics,
             client,
             parent_block_number,
             parent_block_timestamp,
@@ -292,11 +332,13 @@ impl TopLevelState {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn apply_internal<C: ChainTimeInfo + FindActionHandler>(
         &mut self,
         tx: &Transaction,
         signed_hash: &H256,
         signer_public: &Public,
+        co_signer_publics: &[Public],
         client: &C,
         parent_block_number: BlockNumber,
         parent_block_timestamp: u64,
@@ -310,6 +352,17 @@ impl TopLevelState {
             let account = self.get_account_mut(&address)?;
             (address, !tx.is_master_key_allowed() && account.regular_key().is_some())
         };
+        if let Some(multisig) = self.account(&fee_payer)?.as_ref().and_then(Account::multisig) {
+            let got = multisig.count_signers(once(signer_public).chain(co_signer_publics));
+            if got < multisig.threshold() {
+                return Err(RuntimeError::NotEnoughSignatures {
+                    address: fee_payer,
+                    threshold: multisig.threshold(),
+                    got,
+                }
+                .into())
+            }
+        }
         let seq = self.seq(&fee_payer)?;
 
         if tx.seq != seq {
@@ -323,6 +376,7 @@ impl TopLevelState {
         let fee = tx.fee;
 
         self.inc_seq(&fee_payer)?;
+        self.record_spending(&fee_payer, signer_public, fee, parent_block_number + 1, current_block_timestamp)?;
         self.sub_balance(&fee_payer, fee)?;
 
         if restricted_master_key {
@@ -366,8 +420,9 @@ impl TopLevelState {
         client: &C,
         parent_block_number: BlockNumber,
         parent_block_timestamp: u64,
-        _current_block_timestamp: u64,
+        current_block_timestamp: u64,
     ) -> StateResult<()> {
+        let block_number = parent_block_number + 1;
         let (transaction, approvers) = match action {
             Action::MintAsset {
                 approvals,
@@ -481,6 +536,7 @@ impl TopLevelState {
                 receiver,
                 quantity,
             } => {
+                self.record_spending(fee_payer, signer_public, *quantity, block_number, current_block_timestamp)?;
                 self.transfer_balance(fee_payer, receiver, *quantity)?;
                 return Ok(())
             }
@@ -490,6 +546,46 @@ impl TopLevelState {
                 self.set_regular_key(signer_public, key)?;
                 return Ok(())
             }
+            Action::SetMultisig {
+                publics,
+                threshold,
+            } => {
+                // The regular key must not be able to change the signers of its account.
+                if self.regular_key(fee_payer)? == Some(*signer_public) {
+                    return Err(RuntimeError::InsufficientPermission.into())
+                }
+                self.set_multisig(fee_payer, publics.clone(), *threshold)?;
+                return Ok(())
+            }
+            Action::GrantVesting {
+                receiver,
+                quantity,
+                unlock_block_number,
+                unlock_timestamp,
+            } => {
+                self.record_spending(fee_payer, signer_public, *quantity, block_number, current_block_timestamp)?;
+                self.transfer_balance(fee_payer, receiver, *quantity)?;
+                self.add_vesting(
+                    receiver,
+                    *quantity,
+                    *unlock_block_number,
+                    *unlock_timestamp,
+                    block_number,
+                    current_block_timestamp,
+                )?;
+                return Ok(())
+            }
+            Action::SetSpendingLimit {
+                limit,
+                period,
+            } => {
+                // The regular key must not be able to loosen its own limit.
+                if self.regular_key(fee_payer)? == Some(*signer_public) {
+                    return Err(RuntimeError::InsufficientPermission.into())
+                }
+                self.set_spending_limit(fee_payer, *limit, *period)?;
+                return Ok(())
+            }
             Action::CreateShard {
                 users,
             } => {
@@ -517,6 +613,7 @@ impl TopLevelState {
                 quantity,
                 ..
             } => {
+                self.record_spending(fee_payer, signer_public, *quantity, block_number, current_block_timestamp)?;
                 self.sub_balance(fee_payer, *quantity)?;
                 let transaction = ShardTransaction::WrapCCC {
                     network_id,
@@ -566,6 +663,47 @@ impl TopLevelState {
         )
     }
 
+    /// Checks that `quantity` can be spent from the unlocked balance of `payer`,
+    /// and records it against the spending limit if `signer_public` is the regular key of `payer`.
+    fn record_spending(
+        &mut self,
+        payer: &Address,
+        signer_public: &Public,
+        quantity: u64,
+        block_number: BlockNumber,
+        timestamp: u64,
+    ) -> StateResult<()> {
+        if quantity == 0 {
+            return Ok(())
+        }
+        let account = match self.account(payer)? {
+            Some(account) => account,
+            None => return Ok(()),
+        };
+        let is_signed_by_regular_key = account.regular_key() == Some(*signer_public);
+        let spending_limit = account.spending_limit().filter(|_| is_signed_by_regular_key);
+        if account.vestings().is_empty() && spending_limit.is_none() {
+            return Ok(())
+        }
+
+        let spendable_balance = account.balance().saturating_sub(account.locked_balance(block_number, timestamp));
+        if spendable_balance < quantity {
+            return Err(RuntimeError::InsufficientBalance {
+                address: *payer,
+                cost: quantity,
+                balance: spendable_balance,
+            }
+            .into())
+        }
+
+        let mut account = self.get_account_mut(payer)?;
+        if is_signed_by_regular_key {
+            account.spend_by_regular_key(payer, quantity, timestamp)?;
+        }
+        account.remove_unlocked_vestings(block_number, timestamp);
+        Ok(())
+    }
+
     pub fn apply_shard_transaction<C: ChainTimeInfo>(
         &mut self,
         transaction: &ShardTransaction,
@@ -602,17 +740,22 @@ impl TopLevelState {
         let shard_root = self.shard_root(shard_id)?.ok_or_else(|| RuntimeError::InvalidShardId(shard_id))?;
         let shard_users = self.shard_users(shard_id)?.expect("Shard must exist");
 
-        let shard_cache = self.shard_caches.entry(shard_id).or_default();
-        let mut shard_level_state = ShardLevelState::from_existing(shard_id, &mut self.db, shard_root, shard_cache)?;
-        shard_level_state.apply(
-            &transaction,
-            sender,
-            &shard_users,
-            approvers,
-            client,
-            parent_block_number,
-            parent_block_timestamp,
-        )
+        {
+            let shard_cache = self.shard_caches.entry(shard_id).or_default();
+            let mut shard_level_state =
+                ShardLevelState::from_existing(shard_id, &mut self.db, shard_root, shard_cache)?;
+            shard_level_state.apply(
+                &transaction,
+                sender,
+                &shard_users,
+                approvers,
+                client,
+                parent_block_number,
+                parent_block_timestamp,
+            )?;
+        }
+        self.events.extend(asset_events(transaction, shard_id));
+        Ok(())
     }
 
     fn create_shard_level_state(
@@ -697,6 +840,16 @@ impl TopLevelState {
         self.root
     }
 
+    /// Records an event of the transaction being applied.
+    /// The event is discarded if the transaction fails.
+    pub fn emit_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn take_events(&mut self) -> Vec<Event> {
+        mem::replace(&mut self.events, Vec::new())
+    }
+
     #[cfg(test)]
     fn set_balance(&mut self, a: &Address, balance: u64) -> TrieResult<()> {
         self.get_account_mut(a)?.set_balance(balance);
@@ -787,6 +940,90 @@ where
         .collect()
 }
 
+/// Returns the assets of `shard_id` that are spent and created by the transaction.
+fn asset_events(transaction: &ShardTransaction, shard_id: ShardId) -> Vec<Event> {
+    let tracker = transaction.tracker();
+    let spent = |input: &AssetTransferInput| Event::AssetSpent {
+        shard_id,
+        tracker: input.prev_out.tracker,
+        index: input.prev_out.index,
+        asset_type: input.prev_out.asset_type,
+        quantity: input.prev_out.quantity,
+    };
+    let created = |(index, output): (usize, &AssetTransferOutput)| Event::AssetCreated {
+        shard_id,
+        tracker,
+        index,
+        asset_type: output.asset_type,
+        quantity: output.quantity,
+    };
+    let minted = |asset_type: H160, quantity: u64| Event::AssetCreated {
+        shard_id,
+        tracker,
+        index: 0,
+        asset_type,
+        quantity,
+    };
+    let is_in_shard = |input: &&AssetTransferInput| input.prev_out.shard_id == shard_id;
+    let is_output_in_shard = |(_, output): &(usize, &AssetTransferOutput)| output.shard_id == shard_id;
+
+    match transaction {
+        ShardTransaction::MintAsset {
+            output,
+            ..
+        } => vec![minted(Blake::blake(tracker), output.supply)],
+        ShardTransaction::TransferAsset {
+            burns,
+            inputs,
+            outputs,
+            ..
+        } => burns
+            .iter()
+            .chain(inputs)
+            .filter(is_in_shard)
+            .map(spent)
+            .chain(outputs.iter().enumerate().filter(is_output_in_shard).map(created))
+            .collect(),
+        ShardTransaction::ChangeAssetScheme {
+            ..
+        } => vec![],
+        ShardTransaction::IncreaseAssetSupply {
+            asset_type,
+            output,
+            ..
+        } => vec![minted(*asset_type, output.supply)],
+        ShardTransaction::ComposeAsset {
+            inputs,
+            output,
+            shard_id: output_shard_id,
+            ..
+        } => {
+            let mut events: Vec<_> = inputs.iter().filter(is_in_shard).map(spent).collect();
+            if *output_shard_id == shard_id {
+                events.push(minted(Blake::blake(tracker), output.supply));
+            }
+            events
+        }
+        ShardTransaction::DecomposeAsset {
+            input,
+            outputs,
+            ..
+        } => once(input)
+            .filter(is_in_shard)
+            .map(spent)
+            .chain(outputs.iter().enumerate().filter(is_output_in_shard).map(created))
+            .collect(),
+        ShardTransaction::UnwrapCCC {
+            burn,
+            ..
+        } => vec![spent(burn)],
+        ShardTransaction::WrapCCC {
+            output,
+            ..
+        } => vec![minted(H160::zero(), output.quantity)],
+    }
+}
+
 // TODO: cloning for `State` shouldn't be possible in general; Remove this and use
 // checkpoints where possible.
 impl Clone for TopLevelState {
@@ -797,6 +1034,8 @@ impl Clone for TopLevelState {
             id_of_checkpoints: self.id_of_checkpoints.clone(),
             top_cache: self.top_cache.clone(),
             shard_caches: self.shard_caches.clone(),
+            events: self.events.clone(),
+            length_of_events: self.length_of_events.clone(),
         }
     }
 }
@@ -814,6 +1053,10 @@ impl TopState for TopLevelState {
         ctrace!(STATE, "add_balance({}, {}): {}", a, incr, self.balance(a)?);
         if incr != 0 {
             self.get_account_mut(a)?.add_balance(incr);
+            self.emit_event(Event::BalanceIncreased {
+                address: *a,
+                quantity: incr,
+            });
         }
         Ok(())
     }
@@ -833,6 +1076,10 @@ impl TopState for TopLevelState {
             .into())
         }
         self.get_account_mut(a)?.sub_balance(decr);
+        self.emit_event(Event::BalanceDecreased {
+            address: *a,
+            quantity: decr,
+        });
         Ok(())
     }
 
@@ -881,6 +1128,50 @@ impl TopState for TopLevelState {
         Ok(())
     }
 
+    fn set_multisig(&mut self, owner: &Address, publics: Vec<Public>, threshold: u8) -> StateResult<()> {
+        let mut account = self.get_account_mut(owner)?;
+        if publics.is_empty() {
+            account.remove_multisig();
+        } else {
+            account.set_multisig(Multisig::new(publics, threshold));
+        }
+        Ok(())
+    }
+
+    fn add_vesting(
+        &mut self,
+        a: &Address,
+        quantity: u64,
+        unlock_block_number: BlockNumber,
+        unlock_timestamp: u64,
+        block_number: BlockNumber,
+        timestamp: u64,
+    ) -> StateResult<()> {
+        let vesting = Vesting::new(quantity, unlock_block_number, unlock_timestamp);
+        let mut account = self.get_account_mut(a)?;
+        account.remove_unlocked_vestings(block_number, timestamp);
+        let is_merged = account.vestings().iter().any(|existing| existing.has_same_schedule(&vesting));
+        if !is_merged && account.vestings().len() >= MAX_NUMBER_OF_VESTINGS {
+            return Err(RuntimeError::TooManyVestings {
+                address: *a,
+                max: MAX_NUMBER_OF_VESTINGS,
+            }
+            .into())
+        }
+        account.add_vesting(vesting);
+        Ok(())
+    }
+
+    fn set_spending_limit(&mut self, owner: &Address, limit: u64, period: u64) -> StateResult<()> {
+        let mut account = self.get_account_mut(owner)?;
+        if limit == 0 && period == 0 {
+            account.remove_spending_limit();
+        } else {
+            account.set_spending_limit(SpendingLimit::new(limit, period));
+        }
+        Ok(())
+    }
+
     fn create_shard(&mut self, fee_payer: &Address, tx_hash: H256, users: Vec<Address>) -> StateResult<()> {
         let shard_id = {
             let mut metadata = self.get_metadata_mut()?;
@@ -1414,6 +1705,49 @@ mod tests_tx {
         ]);
     }
 
+    #[test]
+    fn apply_pay_emits_balance_events() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [(account: sender => balance: 20)]);
+
+        let receiver = 1u64.into();
+        let tx = transaction!(fee: 5, pay!(receiver, 10));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0));
+
+        assert_eq!(
+            vec![
+                Event::BalanceDecreased {
+                    address: sender,
+                    quantity: 5,
+                },
+                Event::BalanceDecreased {
+                    address: sender,
+                    quantity: 10,
+                },
+                Event::BalanceIncreased {
+                    address: receiver,
+                    quantity: 10,
+                },
+            ],
+            state.take_events()
+        );
+    }
+
+    #[test]
+    fn failed_transaction_emits_no_events() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        set_top_level_state!(state, [(account: sender => balance: 20)]);
+
+        let tx = transaction!(fee: 5, pay!(address().0, 30));
+        assert!(state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0).is_err());
+
+        assert_eq!(Vec::<Event>::new(), state.take_events());
+    }
+
     #[test]
     fn apply_set_regular_key() {
         let mut state = get_temp_state();
@@ -1747,6 +2081,284 @@ mod tests_tx {
         ]);
     }
 
+    #[test]
+    fn apply_set_multisig() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (_, public1, _) = address();
+        let (_, public2, _) = address();
+        set_top_level_state!(state, [(account: sender => balance: 20)]);
+
+        let tx = transaction!(fee: 5, set_multisig!(vec![public1, public2], 1));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0));
+
+        assert_eq!(Ok(Some(Multisig::new(vec![public1, public2], 1))), state.multisig(&sender));
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 15))
+        ]);
+    }
+
+    #[test]
+    fn fail_when_multisig_account_is_not_co_signed() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (_, public1, _) = address();
+        let (_, public2, _) = address();
+        let (receiver, ..) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20),
+            (multisig: sender => publics: vec![public1, public2], threshold: 2)
+        ]);
+
+        let tx = transaction!(fee: 5, pay!(receiver, 5));
+        assert_eq!(
+            Err(RuntimeError::NotEnoughSignatures {
+                address: sender,
+                threshold: 2,
+                got: 1,
+            }
+            .into()),
+            state.apply_with_co_signers(&tx, &H256::random(), &sender_public, &[public1], &get_test_client(), 0, 0, 0)
+        );
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 0, balance: 20)),
+            (account: receiver => (seq: 0, balance: 0))
+        ]);
+    }
+
+    #[test]
+    fn pay_from_multisig_account() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (_, public1, _) = address();
+        let (_, public2, _) = address();
+        let (receiver, ..) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20),
+            (multisig: sender => publics: vec![sender_public, public1, public2], threshold: 2)
+        ]);
+
+        let tx = transaction!(fee: 5, pay!(receiver, 5));
+        assert_eq!(
+            Ok(()),
+            state.apply_with_co_signers(&tx, &H256::random(), &sender_public, &[public2], &get_test_client(), 0, 0, 0)
+        );
+
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 10)),
+            (account: receiver => (seq: 0, balance: 5))
+        ]);
+    }
+
+    #[test]
+    fn remove_multisig() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (_, public1, _) = address();
+        let (_, public2, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20),
+            (multisig: sender => publics: vec![public1, public2], threshold: 2)
+        ]);
+
+        let tx = transaction!(fee: 5, set_multisig!(vec![], 0));
+        assert_eq!(
+            Ok(()),
+            state.apply_with_co_signers(
+                &tx,
+                &H256::random(),
+                &sender_public,
+                &[public1, public2],
+                &get_test_client(),
+                0,
+                0,
+                0
+            )
+        );
+
+        assert_eq!(Ok(None), state.multisig(&sender));
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 15))
+        ]);
+    }
+
+    #[test]
+    fn only_master_key_can_set_multisig() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (_, regular_public, _) = address();
+        let (_, public1, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 20),
+            (regular_key: sender_public => regular_public)
+        ]);
+
+        let tx = transaction!(fee: 5, set_multisig!(vec![regular_public, public1], 1));
+        assert_eq!(
+            Err(RuntimeError::InsufficientPermission.into()),
+            state.apply(&tx, &H256::random(), &regular_public, &get_test_client(), 0, 0, 0)
+        );
+        assert_eq!(Ok(None), state.multisig(&sender));
+
+        let tx = transaction!(seq: 1, fee: 5, set_multisig!(vec![regular_public, public1], 1));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0));
+        assert_eq!(Ok(Some(Multisig::new(vec![regular_public, public1], 1))), state.multisig(&sender));
+    }
+
+    #[test]
+    fn vested_balance_is_locked_until_unlocked() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (receiver, receiver_public, _) = address();
+        let (third, ..) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 100),
+            (account: receiver => balance: 20)
+        ]);
+
+        let tx = transaction!(fee: 5, grant_vesting!(receiver, 50, unlock_block_number: 10, unlock_timestamp: 0));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0));
+        check_top_level_state!(state, [
+            (account: sender => (seq: 1, balance: 45)),
+            (account: receiver => (seq: 0, balance: 70))
+        ]);
+
+        let tx = transaction!(fee: 5, pay!(third, 20));
+        assert_eq!(
+            Err(RuntimeError::InsufficientBalance {
+                address: receiver,
+                cost: 20,
+                balance: 15,
+            }
+            .into()),
+            state.apply(&tx, &H256::random(), &receiver_public, &get_test_client(), 8, 0, 0)
+        );
+        check_top_level_state!(state, [
+            (account: receiver => (seq: 1, balance: 65)),
+            (account: third => (seq: 0, balance: 0))
+        ]);
+
+        let tx = transaction!(seq: 1, fee: 5, pay!(third, 20));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &receiver_public, &get_test_client(), 9, 0, 0));
+        assert_eq!(Ok(vec![]), state.vestings(&receiver));
+        check_top_level_state!(state, [
+            (account: receiver => (seq: 2, balance: 40)),
+            (account: third => (seq: 0, balance: 20))
+        ]);
+    }
+
+    #[test]
+    fn vestings_of_an_account_are_capped() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (receiver, ..) = address();
+        set_top_level_state!(state, [(account: sender => balance: 1000)]);
+
+        for seq in 0..MAX_NUMBER_OF_VESTINGS as u64 {
+            let grant = grant_vesting!(receiver, 1, unlock_block_number: 10 + seq, unlock_timestamp: 0);
+            let tx = transaction!(seq: seq, fee: 5, grant);
+            assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0));
+        }
+
+        let seq = MAX_NUMBER_OF_VESTINGS as u64;
+        let grant = grant_vesting!(receiver, 1, unlock_block_number: 100, unlock_timestamp: 0);
+        let tx = transaction!(seq: seq, fee: 5, grant);
+        assert_eq!(
+            Err(RuntimeError::TooManyVestings {
+                address: receiver,
+                max: MAX_NUMBER_OF_VESTINGS,
+            }
+            .into()),
+            state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0)
+        );
+
+        // A grant with the schedule of an existing vesting is merged into it.
+        let grant = grant_vesting!(receiver, 1, unlock_block_number: 10, unlock_timestamp: 0);
+        let tx = transaction!(seq: seq + 1, fee: 5, grant);
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0));
+        let vestings = state.vestings(&receiver).unwrap();
+        assert_eq!(MAX_NUMBER_OF_VESTINGS, vestings.len());
+        assert_eq!(2, vestings[0].quantity());
+
+        // The unlocked vestings don't count.
+        let grant = grant_vesting!(receiver, 1, unlock_block_number: 100, unlock_timestamp: 0);
+        let tx = transaction!(seq: seq + 2, fee: 5, grant);
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 9, 0, 0));
+        assert_eq!(MAX_NUMBER_OF_VESTINGS, state.vestings(&receiver).unwrap().len());
+    }
+
+    #[test]
+    fn regular_key_cannot_spend_more_than_spending_limit() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (_, regular_public, _) = address();
+        let (receiver, ..) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 100),
+            (regular_key: sender_public => regular_public),
+            (spending_limit: sender => limit: 20, period: 60)
+        ]);
+
+        let tx = transaction!(fee: 5, pay!(receiver, 10));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &regular_public, &get_test_client(), 0, 0, 1000));
+
+        let tx = transaction!(seq: 1, fee: 5, pay!(receiver, 10));
+        assert_eq!(
+            Err(RuntimeError::SpendingLimitExceeded {
+                address: sender,
+                limit: 20,
+                spent: 20,
+                cost: 10,
+            }
+            .into()),
+            state.apply(&tx, &H256::random(), &regular_public, &get_test_client(), 0, 0, 1059)
+        );
+        check_top_level_state!(state, [
+            (account: sender => (seq: 2, balance: 80, key: regular_public)),
+            (account: receiver => (seq: 0, balance: 10))
+        ]);
+
+        let tx = transaction!(seq: 2, fee: 5, pay!(receiver, 10));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &regular_public, &get_test_client(), 0, 0, 1060));
+        check_top_level_state!(state, [
+            (account: sender => (seq: 3, balance: 65, key: regular_public)),
+            (account: receiver => (seq: 0, balance: 20))
+        ]);
+    }
+
+    #[test]
+    fn only_master_key_can_set_spending_limit() {
+        let mut state = get_temp_state();
+
+        let (sender, sender_public, _) = address();
+        let (_, regular_public, _) = address();
+        set_top_level_state!(state, [
+            (account: sender => balance: 100),
+            (regular_key: sender_public => regular_public),
+            (spending_limit: sender => limit: 20, period: 60)
+        ]);
+
+        let tx = transaction!(fee: 5, set_spending_limit!(0, 0));
+        assert_eq!(
+            Err(RuntimeError::InsufficientPermission.into()),
+            state.apply(&tx, &H256::random(), &regular_public, &get_test_client(), 0, 0, 0)
+        );
+        assert_eq!(Ok(Some(SpendingLimit::new(20, 60))), state.spending_limit(&sender));
+
+        let tx = transaction!(seq: 1, fee: 5, set_spending_limit!(0, 0));
+        assert_eq!(Ok(()), state.apply(&tx, &H256::random(), &sender_public, &get_test_client(), 0, 0, 0));
+        assert_eq!(Ok(None), state.spending_limit(&sender));
+    }
+
     #[test]
     fn apply_error_for_action_failure() {
         let mut state = get_temp_state();
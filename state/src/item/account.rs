@@ -18,7 +18,9 @@
 
 use std::fmt;
 
-use ckey::{self, Public};
+use ckey::{self, Address, Public};
+use ctypes::errors::RuntimeError;
+use ctypes::BlockNumber;
 use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
 
 use crate::CacheableItem;
@@ -33,6 +35,152 @@ pub struct Account {
     seq: u64,
     // Regular key of the account.
     regular_key: Option<Public>,
+    // Publics that must co-sign the transactions of the account.
+    multisig: Option<Multisig>,
+    // Portions of the balance that are not spendable yet.
+    vestings: Vec<Vesting>,
+    // Cap on the quantity that the regular key can spend in a period.
+    spending_limit: Option<SpendingLimit>,
+}
+
+/// M-of-N signature requirement of a platform account.
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct Multisig {
+    publics: Vec<Public>,
+    threshold: u8,
+}
+
+impl Multisig {
+    pub fn new(publics: Vec<Public>, threshold: u8) -> Self {
+        Self {
+            publics,
+            threshold,
+        }
+    }
+
+    pub fn publics(&self) -> &[Public] {
+        &self.publics
+    }
+
+    pub fn threshold(&self) -> usize {
+        usize::from(self.threshold)
+    }
+
+    /// Returns the number of distinct multisig publics in `signers`.
+    pub fn count_signers<'a, I>(&self, signers: I) -> usize
+    where
+        I: IntoIterator<Item = &'a Public>, {
+        let mut signed = vec![false; self.publics.len()];
+        for signer in signers {
+            if let Some(index) = self.publics.iter().position(|public| public == signer) {
+                signed[index] = true;
+            }
+        }
+        signed.into_iter().filter(|signed| *signed).count()
+    }
+}
+
+/// A portion of the balance that is locked until both the block number and the timestamp are reached.
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct Vesting {
+    quantity: u64,
+    unlock_block_number: BlockNumber,
+    unlock_timestamp: u64,
+}
+
+impl Vesting {
+    pub fn new(quantity: u64, unlock_block_number: BlockNumber, unlock_timestamp: u64) -> Self {
+        Self {
+            quantity,
+            unlock_block_number,
+            unlock_timestamp,
+        }
+    }
+
+    pub fn quantity(&self) -> u64 {
+        self.quantity
+    }
+
+    pub fn unlock_block_number(&self) -> BlockNumber {
+        self.unlock_block_number
+    }
+
+    pub fn unlock_timestamp(&self) -> u64 {
+        self.unlock_timestamp
+    }
+
+    pub fn is_unlocked(&self, block_number: BlockNumber, timestamp: u64) -> bool {
+        self.unlock_block_number <= block_number && self.unlock_timestamp <= timestamp
+    }
+
+    pub fn has_same_schedule(&self, other: &Vesting) -> bool {
+        self.unlock_block_number == other.unlock_block_number && self.unlock_timestamp == other.unlock_timestamp
+    }
+}
+
+/// Cap on the quantity that the regular key can spend in every `period` seconds.
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct SpendingLimit {
+    limit: u64,
+    period: u64,
+    period_begin: u64,
+    spent: u64,
+}
+
+impl SpendingLimit {
+    pub fn new(limit: u64, period: u64) -> Self {
+        Self {
+            limit,
+            period,
+            period_begin: 0,
+            spent: 0,
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    /// Returns the quantity spent in the period that contains `timestamp`.
+    pub fn spent(&self, timestamp: u64) -> u64 {
+        if self.is_expired(timestamp) {
+            0
+        } else {
+            self.spent
+        }
+    }
+
+    /// Returns the quantity that can be spent in the period that contains `timestamp`.
+    pub fn remaining(&self, timestamp: u64) -> u64 {
+        self.limit.saturating_sub(self.spent(timestamp))
+    }
+
+    /// Records `quantity` as spent, starting a new period if the current one is over.
+    /// Returns an error without recording anything if `quantity` exceeds the remaining limit.
+    pub fn spend(&mut self, owner: &Address, quantity: u64, timestamp: u64) -> Result<(), RuntimeError> {
+        if self.remaining(timestamp) < quantity {
+            return Err(RuntimeError::SpendingLimitExceeded {
+                address: *owner,
+                limit: self.limit,
+                spent: self.spent(timestamp),
+                cost: quantity,
+            })
+        }
+        if self.is_expired(timestamp) {
+            self.period_begin = timestamp;
+            self.spent = 0;
+        }
+        self.spent += quantity;
+        Ok(())
+    }
+
+    fn is_expired(&self, timestamp: u64) -> bool {
+        self.period_begin.saturating_add(self.period) <= timestamp
+    }
 }
 
 impl Account {
@@ -41,6 +189,9 @@ impl Account {
             balance,
             seq,
             regular_key: None,
+            multisig: None,
+            vestings: Vec::new(),
+            spending_limit: None,
         }
     }
 
@@ -49,6 +200,9 @@ impl Account {
             balance,
             seq,
             regular_key,
+            multisig: None,
+            vestings: Vec::new(),
+            spending_limit: None,
         }
     }
 
@@ -71,6 +225,30 @@ impl Account {
         self.regular_key
     }
 
+    /// return the multisig requirement associated with this account.
+    pub fn multisig(&self) -> Option<&Multisig> {
+        self.multisig.as_ref()
+    }
+
+    /// return the vestings associated with this account.
+    pub fn vestings(&self) -> &[Vesting] {
+        &self.vestings
+    }
+
+    /// return the quantity of the balance that is still locked at the given block.
+    pub fn locked_balance(&self, block_number: BlockNumber, timestamp: u64) -> u64 {
+        self.vestings
+            .iter()
+            .filter(|vesting| !vesting.is_unlocked(block_number, timestamp))
+            .map(Vesting::quantity)
+            .sum()
+    }
+
+    /// return the spending limit of the regular key associated with this account.
+    pub fn spending_limit(&self) -> Option<&SpendingLimit> {
+        self.spending_limit.as_ref()
+    }
+
     /// Increment the seq of the account by one.
     pub fn inc_seq(&mut self) {
         self.seq += 1;
@@ -108,6 +286,51 @@ impl Account {
     pub fn remove_regular_key(&mut self) {
         self.regular_key = None;
     }
+
+    /// Set the multisig requirement of the account.
+    /// Overwrite if the requirement already exists.
+    pub fn set_multisig(&mut self, multisig: Multisig) {
+        self.multisig = Some(multisig);
+    }
+
+    /// Remove the multisig requirement of the account.
+    pub fn remove_multisig(&mut self) {
+        self.multisig = None;
+    }
+
+    /// Lock a portion of the balance until the vesting is unlocked.
+    /// The vesting is merged into the existing one that has the same schedule.
+    pub fn add_vesting(&mut self, vesting: Vesting) {
+        match self.vestings.iter_mut().find(|existing| existing.has_same_schedule(&vesting)) {
+            Some(existing) => existing.quantity += vesting.quantity,
+            None => self.vestings.push(vesting),
+        }
+    }
+
+    /// Remove the vestings that are unlocked at the given block.
+    pub fn remove_unlocked_vestings(&mut self, block_number: BlockNumber, timestamp: u64) {
+        self.vestings.retain(|vesting| !vesting.is_unlocked(block_number, timestamp));
+    }
+
+    /// Set the spending limit of the regular key.
+    /// Overwrite if the limit already exists.
+    pub fn set_spending_limit(&mut self, spending_limit: SpendingLimit) {
+        self.spending_limit = Some(spending_limit);
+    }
+
+    /// Remove the spending limit of the regular key.
+    pub fn remove_spending_limit(&mut self) {
+        self.spending_limit = None;
+    }
+
+    /// Record `quantity` as spent by the regular key.
+    /// Returns an error if `quantity` exceeds the remaining spending limit.
+    pub fn spend_by_regular_key(&mut self, owner: &Address, quantity: u64, timestamp: u64) -> Result<(), RuntimeError> {
+        match &mut self.spending_limit {
+            Some(spending_limit) => spending_limit.spend(owner, quantity, timestamp),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Default for Account {
@@ -129,18 +352,38 @@ const PREFIX: u8 = super::ADDRESS_PREFIX;
 
 impl Encodable for Account {
     fn rlp_append(&self, s: &mut RlpStream) {
-        s.begin_list(4);
+        // The optional fields follow the regular key.
+        // The trailing ones that are not set are omitted to keep the encoding of plain accounts.
+        let optional_fields = if self.spending_limit.is_some() {
+            3
+        } else if !self.vestings.is_empty() {
+            2
+        } else if self.multisig.is_some() {
+            1
+        } else {
+            0
+        };
+        s.begin_list(4 + optional_fields);
         s.append(&PREFIX);
         s.append(&self.balance);
         s.append(&self.seq);
         s.append(&self.regular_key);
+        if optional_fields > 0 {
+            s.append(&self.multisig);
+        }
+        if optional_fields > 1 {
+            s.append_list(&self.vestings);
+        }
+        if optional_fields > 2 {
+            s.append(&self.spending_limit);
+        }
     }
 }
 
 impl Decodable for Account {
     fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
         let item_count = rlp.item_count()?;
-        if item_count != 4 {
+        if item_count < 4 || item_count > 7 {
             return Err(DecoderError::RlpInvalidLength {
                 expected: 4,
                 got: item_count,
@@ -156,13 +399,34 @@ impl Decodable for Account {
             balance: rlp.val_at(1)?,
             seq: rlp.val_at(2)?,
             regular_key: rlp.val_at(3)?,
+            multisig: if item_count > 4 {
+                rlp.val_at(4)?
+            } else {
+                None
+            },
+            vestings: if item_count > 5 {
+                rlp.list_at(5)?
+            } else {
+                Vec::new()
+            },
+            spending_limit: if item_count > 6 {
+                rlp.val_at(6)?
+            } else {
+                None
+            },
         })
     }
 }
 
 impl fmt::Debug for Account {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Account").field("balance", &self.balance).field("seq", &self.seq).finish()
+        f.debug_struct("Account")
+            .field("balance", &self.balance)
+            .field("seq", &self.seq)
+            .field("multisig", &self.multisig)
+            .field("vestings", &self.vestings)
+            .field("spending_limit", &self.spending_limit)
+            .finish()
     }
 }
 
@@ -186,6 +450,91 @@ mod tests {
         assert_eq!(a.regular_key(), b.regular_key());
     }
 
+    #[test]
+    fn rlpio_multisig() {
+        let mut a = Account::new(69, 0);
+        let publics = vec![Public::random(), Public::random()];
+        a.set_multisig(Multisig::new(publics.clone(), 1));
+        let b = ::rlp::decode::<Account>(&a.rlp_bytes());
+        assert_eq!(a.balance(), b.balance());
+        assert_eq!(a.seq(), b.seq());
+        assert_eq!(Some(&Multisig::new(publics, 1)), b.multisig());
+
+        a.remove_multisig();
+        assert_eq!(a.rlp_bytes().to_hex(), "c4434580c0");
+    }
+
+    #[test]
+    fn count_multisig_signers() {
+        let publics = vec![Public::random(), Public::random(), Public::random()];
+        let multisig = Multisig::new(publics.clone(), 2);
+        assert_eq!(0, multisig.count_signers(&[Public::random()]));
+        assert_eq!(1, multisig.count_signers(&[publics[0], publics[0]]));
+        assert_eq!(2, multisig.count_signers(&[publics[2], Public::random(), publics[0]]));
+    }
+
+    #[test]
+    fn rlpio_vestings_and_spending_limit() {
+        let mut a = Account::new(69, 0);
+        a.add_vesting(Vesting::new(30, 10, 1000));
+        a.set_spending_limit(SpendingLimit::new(20, 3600));
+        let b = ::rlp::decode::<Account>(&a.rlp_bytes());
+        assert_eq!(a.vestings(), b.vestings());
+        assert_eq!(a.spending_limit(), b.spending_limit());
+        assert_eq!(None, b.multisig());
+
+        a.remove_unlocked_vestings(10, 1000);
+        a.remove_spending_limit();
+        assert_eq!(a.rlp_bytes().to_hex(), "c4434580c0");
+    }
+
+    #[test]
+    fn locked_balance() {
+        let mut a = Account::new(100, 0);
+        a.add_vesting(Vesting::new(30, 10, 0));
+        a.add_vesting(Vesting::new(20, 0, 1000));
+        assert_eq!(50, a.locked_balance(9, 999));
+        assert_eq!(20, a.locked_balance(10, 999));
+        assert_eq!(30, a.locked_balance(9, 1000));
+        assert_eq!(0, a.locked_balance(10, 1000));
+
+        a.remove_unlocked_vestings(10, 999);
+        assert_eq!(&[Vesting::new(20, 0, 1000)], a.vestings());
+    }
+
+    #[test]
+    fn vestings_with_the_same_schedule_are_merged() {
+        let mut a = Account::new(100, 0);
+        a.add_vesting(Vesting::new(30, 10, 1000));
+        a.add_vesting(Vesting::new(20, 0, 1000));
+        a.add_vesting(Vesting::new(40, 10, 1000));
+        assert_eq!(&[Vesting::new(70, 10, 1000), Vesting::new(20, 0, 1000)], a.vestings());
+    }
+
+    #[test]
+    fn spending_limit_is_reset_every_period() {
+        let owner = Address::random();
+        let mut limit = SpendingLimit::new(100, 60);
+        assert_eq!(100, limit.remaining(1000));
+        assert_eq!(Ok(()), limit.spend(&owner, 70, 1000));
+        assert_eq!(30, limit.remaining(1059));
+        assert_eq!(
+            Err(RuntimeError::SpendingLimitExceeded {
+                address: owner,
+                limit: 100,
+                spent: 70,
+                cost: 31,
+            }),
+            limit.spend(&owner, 31, 1059)
+        );
+        assert_eq!(Ok(()), limit.spend(&owner, 30, 1059));
+        assert_eq!(0, limit.remaining(1059));
+        assert_eq!(100, limit.remaining(1060));
+        assert_eq!(Ok(()), limit.spend(&owner, 10, 1060));
+        assert_eq!(10, limit.spent(1119));
+        assert_eq!(0, limit.spent(1120));
+    }
+
     #[test]
     fn new_account() {
         let a = Account::new(69, 0);
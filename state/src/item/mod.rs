@@ -25,6 +25,7 @@ pub mod metadata;
 pub mod regular_account;
 pub mod shard;
 pub mod text;
+pub mod value;
 
 const OWNED_ASSET_PREFIX: u8 = b'A';
 const ADDRESS_PREFIX: u8 = b'C';
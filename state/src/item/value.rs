@@ -0,0 +1,111 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use primitives::Bytes;
+use rlp::{Decodable, DecoderError, UntrustedRlp};
+
+use super::account::Account;
+use super::action_data::ActionData;
+use super::asset::OwnedAsset;
+use super::asset_scheme::AssetScheme;
+use super::metadata::Metadata;
+use super::regular_account::RegularAccount;
+use super::shard::Shard;
+use super::text::Text;
+
+/// A value stored in the state tries, decoded by its prefix.
+#[derive(Clone, Debug)]
+pub enum StateValue {
+    Account(Account),
+    RegularAccount(RegularAccount),
+    Metadata(Metadata),
+    Shard(Shard),
+    AssetScheme(AssetScheme),
+    OwnedAsset(OwnedAsset),
+    Text(Text),
+    ActionData(ActionData),
+    /// A value that cannot be decoded as any item.
+    Unknown(Bytes),
+}
+
+impl StateValue {
+    /// Decodes the value, or keeps the raw bytes if it is not a known item.
+    pub fn from_bytes(bytes: Bytes) -> Self {
+        UntrustedRlp::new(&bytes).as_val().unwrap_or_else(|_| StateValue::Unknown(bytes))
+    }
+}
+
+impl Decodable for StateValue {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        // The action data is the only item that is not a list with a prefix.
+        if !rlp.is_list() {
+            return Ok(StateValue::ActionData(rlp.as_val()?))
+        }
+        match rlp.val_at::<u8>(0)? {
+            super::ADDRESS_PREFIX => Ok(StateValue::Account(rlp.as_val()?)),
+            super::REGULAR_ACCOUNT_PREFIX => Ok(StateValue::RegularAccount(rlp.as_val()?)),
+            super::METADATA_PREFIX => Ok(StateValue::Metadata(rlp.as_val()?)),
+            super::SHARD_PREFIX => Ok(StateValue::Shard(rlp.as_val()?)),
+            super::ASSET_SCHEME_PREFIX => Ok(StateValue::AssetScheme(rlp.as_val()?)),
+            super::OWNED_ASSET_PREFIX => Ok(StateValue::OwnedAsset(rlp.as_val()?)),
+            super::TEXT_PREFIX => Ok(StateValue::Text(rlp.as_val()?)),
+            _ => Err(DecoderError::Custom("Unexpected prefix")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckey::Address;
+    use rlp::{encode, RlpStream};
+
+    use super::*;
+
+    #[test]
+    fn decode_by_prefix() {
+        let account = Account::new(10, 3);
+        match StateValue::from_bytes(encode(&account).into_vec()) {
+            StateValue::Account(decoded) => {
+                assert_eq!(10, decoded.balance());
+                assert_eq!(3, decoded.seq());
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+
+        let text = Text::new("CodeChain", &Address::random());
+        match StateValue::from_bytes(encode(&text).into_vec()) {
+            StateValue::Text(decoded) => assert_eq!(text, decoded),
+            value => panic!("Unexpected value {:?}", value),
+        }
+
+        let action_data = ActionData::from(vec![1, 2, 3]);
+        match StateValue::from_bytes(encode(&action_data).into_vec()) {
+            StateValue::ActionData(decoded) => assert_eq!(action_data, decoded),
+            value => panic!("Unexpected value {:?}", value),
+        }
+    }
+
+    #[test]
+    fn unknown_prefix_is_kept_as_bytes() {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&b'Z').append(&1u8);
+        let bytes = stream.out();
+        match StateValue::from_bytes(bytes.clone()) {
+            StateValue::Unknown(unknown) => assert_eq!(bytes, unknown),
+            value => panic!("Unexpected value {:?}", value),
+        }
+    }
+}
@@ -28,12 +28,17 @@ extern crate kvdb_memorydb;
 extern crate lru_cache;
 #[macro_use]
 extern crate log;
+extern crate parity_wasm;
 extern crate parking_lot;
 extern crate primitives;
+extern crate pwasm_utils;
 extern crate rlp;
 #[cfg(test)]
 extern crate rustc_hex;
 extern crate util_error;
+#[cfg(test)]
+extern crate wabt;
+extern crate wasmi;
 #[macro_use]
 extern crate rlp_derive;
 
@@ -41,19 +46,24 @@ mod action_handler;
 mod cache;
 mod checkpoint;
 mod db;
+mod diff;
 mod error;
 mod impls;
 mod item;
+mod proof;
 mod traits;
 
 pub mod tests;
 
-pub use crate::action_handler::{ActionDataKeyBuilder, ActionHandler, FindActionHandler, HitHandler};
+pub use crate::action_handler::{
+    ActionDataKeyBuilder, ActionHandler, ActionHandlerRegistry, FindActionHandler, HitHandler, WasmActionHandler,
+};
 pub use crate::checkpoint::{CheckpointId, StateWithCheckpoint};
 pub use crate::db::StateDB;
+pub use crate::diff::{state_diff, StateChange};
 pub use crate::error::Error as StateError;
 pub use crate::impls::{ShardLevelState, TopLevelState};
-pub use crate::item::account::Account;
+pub use crate::item::account::{Account, Multisig, SpendingLimit, Vesting};
 pub use crate::item::action_data::ActionData;
 pub use crate::item::asset::{Asset, OwnedAsset, OwnedAssetAddress};
 pub use crate::item::asset_scheme::{AssetScheme, AssetSchemeAddress};
@@ -61,6 +71,8 @@ pub use crate::item::metadata::{Metadata, MetadataAddress};
 pub use crate::item::regular_account::{RegularAccount, RegularAccountAddress};
 pub use crate::item::shard::{Shard, ShardAddress};
 pub use crate::item::text::Text;
+pub use crate::item::value::StateValue;
+pub use crate::proof::{prove, verify_state_proof, StateKey, StateProof};
 pub use crate::traits::{ShardState, ShardStateView, StateWithCache, TopState, TopStateView};
 
 use crate::cache::CacheableItem;
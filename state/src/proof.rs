@@ -0,0 +1,162 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ckey::{Address, Public};
+use cmerkle::{verify_proof, ProofError, Result as TrieResult, Trie, TrieFactory};
+use ctypes::ShardId;
+use hashdb::HashDB;
+use primitives::{Bytes, H160, H256};
+use rlp::UntrustedRlp;
+
+use crate::{
+    AssetSchemeAddress, MetadataAddress, OwnedAssetAddress, RegularAccountAddress, Shard, ShardAddress, StateValue,
+};
+
+/// An entry of the state that can be proven.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StateKey {
+    Account(Address),
+    RegularAccount(Public),
+    Metadata,
+    Shard(ShardId),
+    Text(H256),
+    ActionData(H256),
+    AssetScheme(ShardId, H160),
+    /// The tracker and the index of the output that made the asset.
+    OwnedAsset(ShardId, H256, usize),
+}
+
+impl StateKey {
+    /// The key in the top level trie. A shard level entry is under its shard.
+    fn top_level_key(&self) -> Bytes {
+        match self {
+            StateKey::Account(address) => address.to_vec(),
+            StateKey::RegularAccount(public) => RegularAccountAddress::new(public).as_ref().to_vec(),
+            StateKey::Metadata => MetadataAddress::new().as_ref().to_vec(),
+            StateKey::Shard(shard_id) | StateKey::AssetScheme(shard_id, _) | StateKey::OwnedAsset(shard_id, ..) => {
+                ShardAddress::new(*shard_id).as_ref().to_vec()
+            }
+            StateKey::Text(hash) | StateKey::ActionData(hash) => hash.to_vec(),
+        }
+    }
+
+    fn shard_level_key(&self) -> Option<Bytes> {
+        match self {
+            StateKey::AssetScheme(shard_id, asset_type) => {
+                Some(AssetSchemeAddress::new(*asset_type, *shard_id).as_ref().to_vec())
+            }
+            StateKey::OwnedAsset(shard_id, tracker, index) => {
+                Some(OwnedAssetAddress::new(*tracker, *index, *shard_id).as_ref().to_vec())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The trie nodes that prove an entry of the state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateProof {
+    /// The path in the top level trie.
+    pub top: Vec<Bytes>,
+    /// The path in the shard trie, if the entry is an asset scheme or an owned asset of an existing shard.
+    pub shard: Option<Vec<Bytes>>,
+}
+
+/// Builds the proof of `key` in the state of `state_root`.
+pub fn prove(db: &HashDB, state_root: &H256, key: &StateKey) -> TrieResult<StateProof> {
+    let top_key = key.top_level_key();
+    let trie = TrieFactory::readonly(db, state_root)?;
+    let top = trie.get_proof(&top_key)?;
+
+    let shard = match key.shard_level_key() {
+        Some(shard_key) => {
+            let shard = trie.get_with(&top_key, |bytes: &[u8]| UntrustedRlp::new(bytes).as_val::<Shard>().ok())?;
+            match shard {
+                Some(Some(shard)) => Some(TrieFactory::readonly(db, shard.root())?.get_proof(&shard_key)?),
+                _ => None,
+            }
+        }
+        None => None,
+    };
+    Ok(StateProof {
+        top,
+        shard,
+    })
+}
+
+/// Verifies the proof of `key` against `state_root` and returns the proven value.
+///
+/// Returns `None` if the proof shows that the state doesn't have the entry.
+pub fn verify_state_proof(
+    state_root: &H256,
+    key: &StateKey,
+    proof: &StateProof,
+) -> Result<Option<StateValue>, ProofError> {
+    let top_value = verify_proof(state_root, &key.top_level_key(), &proof.top)?.map(StateValue::from_bytes);
+    let shard_key = match key.shard_level_key() {
+        Some(shard_key) => shard_key,
+        None => return Ok(top_value),
+    };
+    let shard = match top_value {
+        Some(StateValue::Shard(shard)) => shard,
+        _ => return Ok(None),
+    };
+    let shard_proof = proof.shard.as_ref().map(Vec::as_slice).unwrap_or(&[]);
+    Ok(verify_proof(shard.root(), &shard_key, shard_proof)?.map(StateValue::from_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use hashdb::AsHashDB;
+
+    use super::*;
+    use crate::tests::helpers::get_temp_state;
+    use crate::{StateWithCache, TopState};
+
+    #[test]
+    fn prove_account() {
+        let address = Address::random();
+        let mut state = get_temp_state();
+        state.add_balance(&address, 100).unwrap();
+        let (db, root) = state.commit_and_into_db().unwrap();
+
+        let key = StateKey::Account(address);
+        let proof = prove(db.as_hashdb(), &root, &key).unwrap();
+        assert_eq!(None, proof.shard);
+        match verify_state_proof(&root, &key, &proof) {
+            Ok(Some(StateValue::Account(account))) => assert_eq!(100, account.balance()),
+            value => panic!("Unexpected value {:?}", value),
+        }
+
+        let other = StateKey::Account(Address::random());
+        let proof = prove(db.as_hashdb(), &root, &other).unwrap();
+        assert!(verify_state_proof(&root, &other, &proof).unwrap().is_none());
+        assert!(verify_state_proof(&H256::random(), &key, &proof).is_err());
+    }
+
+    #[test]
+    fn prove_asset_scheme_without_shard() {
+        let address = Address::random();
+        let mut state = get_temp_state();
+        state.add_balance(&address, 100).unwrap();
+        let (db, root) = state.commit_and_into_db().unwrap();
+
+        let key = StateKey::AssetScheme(0, H160::random());
+        let proof = prove(db.as_hashdb(), &root, &key).unwrap();
+        assert_eq!(None, proof.shard);
+        assert!(verify_state_proof(&root, &key, &proof).unwrap().is_none());
+    }
+}
@@ -22,8 +22,8 @@ use cvm::ChainTimeInfo;
 use primitives::{Bytes, H160, H256};
 
 use crate::{
-    Account, ActionData, AssetScheme, CacheableItem, Metadata, OwnedAsset, RegularAccount, Shard, StateDB, StateResult,
-    Text,
+    Account, ActionData, AssetScheme, CacheableItem, Metadata, Multisig, OwnedAsset, RegularAccount, Shard,
+    SpendingLimit, StateDB, StateResult, Text, Vesting,
 };
 
 
@@ -68,6 +68,21 @@ pub trait TopStateView {
         Ok(self.account(a)?.and_then(|account| account.regular_key()))
     }
 
+    /// Get the multisig requirement of account `a`.
+    fn multisig(&self, a: &Address) -> TrieResult<Option<Multisig>> {
+        Ok(self.account(a)?.and_then(|account| account.multisig().cloned()))
+    }
+
+    /// Get the vestings of account `a`.
+    fn vestings(&self, a: &Address) -> TrieResult<Vec<Vesting>> {
+        Ok(self.account(a)?.map_or_else(Vec::new, |account| account.vestings().to_vec()))
+    }
+
+    /// Get the spending limit of the regular key of account `a`.
+    fn spending_limit(&self, a: &Address) -> TrieResult<Option<SpendingLimit>> {
+        Ok(self.account(a)?.and_then(|account| account.spending_limit().cloned()))
+    }
+
     fn regular_key_owner(&self, address: &Address) -> TrieResult<Option<Address>> {
         Ok(self
             .regular_account_by_address(&address)?
@@ -165,6 +180,21 @@ pub trait TopState {
 
     /// Set the regular key of account `owner_public`
     fn set_regular_key(&mut self, owner_public: &Public, key: &Public) -> StateResult<()>;
+    /// Set the multisig requirement of account `owner`. An empty `publics` removes the requirement.
+    fn set_multisig(&mut self, owner: &Address, publics: Vec<Public>, threshold: u8) -> StateResult<()>;
+    /// Lock `quantity` of the balance of account `a` until both the given block number and timestamp are reached.
+    /// The vestings already unlocked at `block_number` and `timestamp` are removed first.
+    fn add_vesting(
+        &mut self,
+        a: &Address,
+        quantity: u64,
+        unlock_block_number: BlockNumber,
+        unlock_timestamp: u64,
+        block_number: BlockNumber,
+        timestamp: u64,
+    ) -> StateResult<()>;
+    /// Set the spending limit of the regular key of account `owner`. Zero `limit` with zero `period` removes the limit.
+    fn set_spending_limit(&mut self, owner: &Address, limit: u64, period: u64) -> StateResult<()>;
 
     fn create_shard(&mut self, fee_payer: &Address, tx_hash: H256, users: Vec<Address>) -> StateResult<()>;
     fn change_shard_owners(&mut self, shard_id: ShardId, owners: &[Address], sender: &Address) -> StateResult<()>;
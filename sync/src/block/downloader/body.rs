@@ -17,7 +17,9 @@
 use std::collections::{HashMap, HashSet};
 
 use ccore::{Header, UnverifiedTransaction};
+use cmerkle::skewed_merkle_root;
 use primitives::H256;
+use rlp::Encodable;
 
 use super::super::message::RequestMessage;
 
@@ -37,14 +39,13 @@ pub struct BodyDownloader {
 }
 
 impl BodyDownloader {
-    pub fn create_request(&mut self) -> Option<RequestMessage> {
-        const MAX_BODY_REQEUST_LENGTH: usize = 128;
+    pub fn create_request(&mut self, max_count: usize) -> Option<RequestMessage> {
         let mut hashes = Vec::new();
         for t in &self.targets {
             if !self.downloading.contains(&t.hash) && !self.downloaded.contains_key(&t.hash) {
                 hashes.push(t.hash);
             }
-            if hashes.len() >= MAX_BODY_REQEUST_LENGTH {
+            if hashes.len() >= max_count {
                 break
             }
         }
@@ -56,18 +57,30 @@ impl BodyDownloader {
         }
     }
 
-    pub fn import_bodies(&mut self, hashes: Vec<H256>, bodies: Vec<Vec<UnverifiedTransaction>>) {
+    /// Returns false if any of the bodies doesn't match its header.
+    /// An empty body of a non-empty block means that the peer doesn't have it, so it's not invalid.
+    pub fn import_bodies(&mut self, hashes: Vec<H256>, bodies: Vec<Vec<UnverifiedTransaction>>) -> bool {
+        let mut is_valid = true;
         for (hash, body) in hashes.into_iter().zip(bodies) {
             if self.downloading.remove(&hash) {
+                let target = self.targets.iter().find(|t| t.hash == hash).expect("Downloading target must exist");
                 if body.is_empty() {
-                    let target = self.targets.iter().find(|t| t.hash == hash).expect("Downloading target must exist");
                     if target.transaction_root != target.transactions_root {
                         continue
                     }
+                } else {
+                    let transactions_root =
+                        skewed_merkle_root(target.transaction_root, body.iter().map(|tx| tx.rlp_bytes().into_vec()));
+                    if transactions_root != target.transactions_root {
+                        cwarn!(SYNC, "The transactions root of the body doesn't match the header {}", hash);
+                        is_valid = false;
+                        continue
+                    }
                 }
                 self.downloaded.insert(hash, body);
             }
         }
+        is_valid
     }
 
     pub fn add_target(&mut self, header: &Header, parent: &Header) {
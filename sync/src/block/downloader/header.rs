@@ -113,6 +113,18 @@ impl HeaderDownloader {
         self.pivot.total_score
     }
 
+    /// Moves the pivot to the header imported from the other peers if it's ahead.
+    /// The pivot goes back by itself if the peer doesn't have the header.
+    pub fn advance_pivot(&mut self, hash: H256, total_score: U256) {
+        if self.pivot.total_score < total_score && total_score <= self.total_score {
+            self.pivot = Pivot {
+                hash,
+                total_score,
+            };
+            self.request_time = None;
+        }
+    }
+
     pub fn is_idle(&self) -> bool {
         let can_request = self.request_time.is_none() && self.total_score > self.pivot.total_score;
 
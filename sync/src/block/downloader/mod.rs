@@ -16,6 +16,10 @@
 
 mod body;
 mod header;
+mod skeleton;
+mod throughput;
 
 pub use self::body::BodyDownloader;
 pub use self::header::HeaderDownloader;
+pub use self::skeleton::HeaderSkeleton;
+pub use self::throughput::Throughput;
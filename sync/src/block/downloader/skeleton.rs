@@ -0,0 +1,341 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use ccore::encoded::Header;
+use cnetwork::NodeId;
+use ctypes::BlockNumber;
+use primitives::H256;
+
+use super::super::message::RequestMessage;
+use super::Throughput;
+
+const SEGMENT_LENGTH: u64 = 128;
+const MAX_SEGMENTS: u64 = 16;
+
+enum Segment {
+    Downloading {
+        node: NodeId,
+        requested_at: Instant,
+        deadline: Instant,
+    },
+    Downloaded {
+        node: NodeId,
+        headers: Vec<Header>,
+    },
+}
+
+/// Splits the headers after the anchor into segments and downloads them from several peers at once.
+///
+/// Each segment starts with the last header of the previous one,
+/// so the segments are linked by hash before they are imported.
+pub struct HeaderSkeleton {
+    anchor_number: BlockNumber,
+    anchor_hash: H256,
+    segments: BTreeMap<BlockNumber, Segment>,
+    /// The last header number that a peer has
+    end: Option<BlockNumber>,
+}
+
+impl HeaderSkeleton {
+    pub fn new(anchor_number: BlockNumber, anchor_hash: H256) -> Self {
+        Self {
+            anchor_number,
+            anchor_hash,
+            segments: BTreeMap::new(),
+            end: None,
+        }
+    }
+
+    pub fn reset(&mut self, anchor_number: BlockNumber, anchor_hash: H256) {
+        cdebug!(SYNC, "Reset the header skeleton to #{}({})", anchor_number, anchor_hash);
+        *self = Self::new(anchor_number, anchor_hash);
+    }
+
+    /// The skeleton is finished when a peer ran out of the headers, and every segment before it is imported.
+    pub fn is_finished(&self) -> bool {
+        self.segments.is_empty() && self.end.map_or(false, |end| end <= self.anchor_number)
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.segments.is_empty()
+    }
+
+    pub fn is_downloading_from(&self, node: &NodeId) -> bool {
+        self.segments.values().any(|segment| match segment {
+            Segment::Downloading {
+                node: assignee,
+                ..
+            } => assignee == node,
+            _ => false,
+        })
+    }
+
+    pub fn is_requested(&self, node: &NodeId, start_number: BlockNumber) -> bool {
+        match self.segments.get(&start_number) {
+            Some(Segment::Downloading {
+                node: assignee,
+                ..
+            }) => assignee == node,
+            _ => false,
+        }
+    }
+
+    /// Assigns the first segment that is neither downloaded nor being downloaded to the peer.
+    pub fn create_request(&mut self, node: NodeId, throughput: &Throughput) -> Option<RequestMessage> {
+        if self.is_downloading_from(&node) {
+            return None
+        }
+        let end = self.end;
+        let start_number = (0..MAX_SEGMENTS)
+            .map(|index| self.anchor_number + index * SEGMENT_LENGTH)
+            .take_while(|start| end.map_or(true, |end| *start < end))
+            .find(|start| !self.segments.contains_key(start))?;
+        let requested_at = Instant::now();
+        self.segments.insert(start_number, Segment::Downloading {
+            node,
+            requested_at,
+            deadline: requested_at + throughput.timeout((SEGMENT_LENGTH + 1) as usize),
+        });
+        Some(RequestMessage::Headers {
+            start_number,
+            max_count: SEGMENT_LENGTH + 1,
+        })
+    }
+
+    /// Stores the headers of the segment, which are sorted and continuous.
+    /// Returns how long the peer took to send them, or None if the segment is not requested to the peer.
+    pub fn import_headers(
+        &mut self,
+        node: &NodeId,
+        start_number: BlockNumber,
+        headers: Vec<Header>,
+    ) -> Option<Duration> {
+        let elapsed = match self.segments.get(&start_number) {
+            Some(Segment::Downloading {
+                node: assignee,
+                requested_at,
+                ..
+            }) if assignee == node => requested_at.elapsed(),
+            _ => {
+                ctrace!(SYNC, "Ignore the expired segment #{} from {}", start_number, node);
+                return None
+            }
+        };
+        let count = headers.len() as u64;
+        if count < SEGMENT_LENGTH + 1 {
+            let end = (start_number + count).saturating_sub(1);
+            cdebug!(SYNC, "Peer #{} has the headers until #{}", node, end);
+            self.end = Some(self.end.map_or(end, |old| old.min(end)));
+        }
+        if headers.is_empty() {
+            self.segments.remove(&start_number);
+        } else {
+            self.segments.insert(start_number, Segment::Downloaded {
+                node: *node,
+                headers,
+            });
+        }
+        Some(elapsed)
+    }
+
+    /// Takes the segments linked to the anchor, and moves the anchor to the last header of them.
+    /// The first header of each segment is excluded because it's already taken.
+    pub fn drain(&mut self) -> Vec<(NodeId, Vec<Header>)> {
+        let mut drained = Vec::new();
+        loop {
+            let is_linked = match self.segments.get(&self.anchor_number) {
+                Some(Segment::Downloaded {
+                    headers,
+                    ..
+                }) => headers[0].hash() == self.anchor_hash,
+                _ => break,
+            };
+            let segment = self.segments.remove(&self.anchor_number).expect("The segment exists");
+            if !is_linked {
+                // The peer forked from the anchor. Leave it to the header downloader of each peer.
+                cdebug!(SYNC, "The segment #{} is not linked to {}", self.anchor_number, self.anchor_hash);
+                self.end = Some(self.anchor_number);
+                break
+            }
+            if let Segment::Downloaded {
+                node,
+                mut headers,
+            } = segment
+            {
+                if headers.len() == 1 {
+                    break
+                }
+                let last = headers.last().expect("A segment has more than one header");
+                self.anchor_number = last.number();
+                self.anchor_hash = last.hash();
+                headers.remove(0);
+                drained.push((node, headers));
+            }
+        }
+        let anchor_number = self.anchor_number;
+        if self.end.map_or(false, |end| end <= anchor_number) {
+            self.segments.retain(|start, _| *start < anchor_number);
+        }
+        drained
+    }
+
+    /// Releases the segments whose deadlines have passed.
+    /// Returns the peers that were downloading them, with the start numbers.
+    pub fn expire(&mut self, now: Instant) -> Vec<(NodeId, BlockNumber)> {
+        let expired: Vec<_> = self
+            .segments
+            .iter()
+            .filter_map(|(start, segment)| match segment {
+                Segment::Downloading {
+                    node,
+                    deadline,
+                    ..
+                } if *deadline < now => Some((*node, *start)),
+                _ => None,
+            })
+            .collect();
+        for (node, start) in &expired {
+            cdebug!(SYNC, "The segment #{} requested to {} is expired", start, node);
+            self.segments.remove(start);
+        }
+        expired
+    }
+
+    /// Releases the segments being downloaded from the peer.
+    pub fn release(&mut self, node: &NodeId) {
+        let node = *node;
+        self.segments.retain(|_, segment| match segment {
+            Segment::Downloading {
+                node: assignee,
+                ..
+            } => *assignee != node,
+            _ => true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use ccore::Header as BlockHeader;
+    use rlp::Encodable;
+
+    use super::*;
+
+    fn chain(length: u64) -> Vec<Header> {
+        let mut headers: Vec<Header> = Vec::new();
+        for number in 0..length {
+            let mut header = BlockHeader::default();
+            header.set_number(number);
+            if let Some(parent) = headers.last() {
+                header.set_parent_hash(parent.hash());
+            }
+            headers.push(Header::new(header.rlp_bytes().into_vec()));
+        }
+        headers
+    }
+
+    fn node(port: u16) -> NodeId {
+        NodeId::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), port)
+    }
+
+    fn start_of(request: Option<RequestMessage>) -> BlockNumber {
+        match request {
+            Some(RequestMessage::Headers {
+                start_number,
+                max_count,
+            }) => {
+                assert_eq!(SEGMENT_LENGTH + 1, max_count);
+                start_number
+            }
+            _ => panic!("A header request is expected"),
+        }
+    }
+
+    #[test]
+    fn segments_are_split_across_peers() {
+        let headers = chain(300);
+        let mut skeleton = HeaderSkeleton::new(0, headers[0].hash());
+        let throughput = Throughput::default();
+
+        assert_eq!(0, start_of(skeleton.create_request(node(1), &throughput)));
+        assert_eq!(SEGMENT_LENGTH, start_of(skeleton.create_request(node(2), &throughput)));
+        assert_eq!(None, skeleton.create_request(node(1), &throughput));
+
+        // The second segment can't be imported before the first one.
+        skeleton.import_headers(&node(2), SEGMENT_LENGTH, headers[128..257].to_vec());
+        assert!(skeleton.drain().is_empty());
+
+        skeleton.import_headers(&node(1), 0, headers[0..129].to_vec());
+        let drained = skeleton.drain();
+        assert_eq!(2, drained.len());
+        assert_eq!(node(1), drained[0].0);
+        assert_eq!(headers[1..129].to_vec(), drained[0].1);
+        assert_eq!(headers[129..257].to_vec(), drained[1].1);
+        assert_eq!(headers[256].hash(), skeleton.anchor_hash);
+    }
+
+    #[test]
+    fn short_segment_finishes_skeleton() {
+        let headers = chain(200);
+        let mut skeleton = HeaderSkeleton::new(0, headers[0].hash());
+        let throughput = Throughput::default();
+
+        assert_eq!(0, start_of(skeleton.create_request(node(1), &throughput)));
+        assert_eq!(SEGMENT_LENGTH, start_of(skeleton.create_request(node(2), &throughput)));
+        skeleton.import_headers(&node(2), SEGMENT_LENGTH, headers[128..].to_vec());
+        assert_eq!(None, skeleton.create_request(node(3), &throughput));
+
+        skeleton.import_headers(&node(1), 0, headers[0..129].to_vec());
+        assert_eq!(2, skeleton.drain().len());
+        assert!(skeleton.is_finished());
+    }
+
+    #[test]
+    fn expired_segment_is_reassigned() {
+        let headers = chain(10);
+        let mut skeleton = HeaderSkeleton::new(0, headers[0].hash());
+        let throughput = Throughput::default();
+
+        assert_eq!(0, start_of(skeleton.create_request(node(1), &throughput)));
+        assert!(skeleton.expire(Instant::now()).is_empty());
+        let expired = skeleton.expire(Instant::now() + Duration::from_secs(60));
+        assert_eq!(vec![(node(1), 0)], expired);
+
+        assert_eq!(0, start_of(skeleton.create_request(node(2), &throughput)));
+        // The response of the expired request is ignored.
+        skeleton.import_headers(&node(1), 0, headers.clone());
+        assert!(skeleton.drain().is_empty());
+
+        skeleton.import_headers(&node(2), 0, headers.clone());
+        assert_eq!(headers[1..].to_vec(), skeleton.drain()[0].1);
+    }
+
+    #[test]
+    fn unlinked_segment_is_dropped() {
+        let headers = chain(10);
+        let mut skeleton = HeaderSkeleton::new(0, H256::random());
+
+        assert_eq!(0, start_of(skeleton.create_request(node(1), &Throughput::default())));
+        skeleton.import_headers(&node(1), 0, headers);
+        assert!(skeleton.drain().is_empty());
+        assert!(skeleton.is_finished());
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+/// The number of items per second that a new peer is assumed to serve
+const INITIAL_RATE: f64 = 64.0;
+const MIN_RATE: f64 = 1.0;
+/// The weight of the latest measurement in the moving average
+const RATE_WEIGHT: f64 = 0.3;
+/// Requests are sized to be answered in this many seconds
+const TARGET_RESPONSE_TIME: f64 = 2.0;
+/// A request expires when it takes this many times longer than expected
+const TIMEOUT_FACTOR: f64 = 3.0;
+const MIN_TIMEOUT: Duration = Duration::from_secs(3);
+const MAX_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Moving average of the number of items that a peer serves per second.
+#[derive(Clone, Copy, Debug)]
+pub struct Throughput {
+    rate: f64,
+}
+
+impl Default for Throughput {
+    fn default() -> Self {
+        Self {
+            rate: INITIAL_RATE,
+        }
+    }
+}
+
+impl Throughput {
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Updates the rate with a response of `items` received `elapsed` after its request.
+    pub fn update(&mut self, items: usize, elapsed: Duration) {
+        let seconds = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_millis()) / 1000.0;
+        let measured = items as f64 / seconds.max(0.001);
+        self.rate = (self.rate * (1.0 - RATE_WEIGHT) + measured * RATE_WEIGHT).max(MIN_RATE);
+    }
+
+    /// Halves the rate when the peer didn't answer in time.
+    pub fn penalize(&mut self) {
+        self.rate = (self.rate / 2.0).max(MIN_RATE);
+    }
+
+    pub fn request_size(&self, max_size: usize) -> usize {
+        let size = (self.rate * TARGET_RESPONSE_TIME) as usize;
+        size.max(1).min(max_size)
+    }
+
+    pub fn timeout(&self, items: usize) -> Duration {
+        let millis = (items as f64 / self.rate * TIMEOUT_FACTOR * 1000.0) as u64;
+        Duration::from_millis(millis).max(MIN_TIMEOUT).min(MAX_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_peer_gets_larger_requests() {
+        let mut fast = Throughput::default();
+        let mut slow = Throughput::default();
+        for _ in 0..10 {
+            fast.update(128, Duration::from_millis(500));
+            slow.update(16, Duration::from_secs(2));
+        }
+        assert!(fast.rate() > slow.rate());
+        assert_eq!(128, fast.request_size(128));
+        assert!(slow.request_size(128) < 32);
+    }
+
+    #[test]
+    fn timeout_is_bounded() {
+        let mut throughput = Throughput::default();
+        assert_eq!(MIN_TIMEOUT, throughput.timeout(1));
+        for _ in 0..10 {
+            throughput.penalize();
+        }
+        assert!(throughput.rate() <= MIN_RATE);
+        assert_eq!(MAX_TIMEOUT, throughput.timeout(128));
+    }
+}
@@ -14,20 +14,26 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 
 use ccore::encoded::Header as EncodedHeader;
 use ccore::{
-    Block, BlockChainClient, BlockId, BlockImportError, BlockInfo, ChainInfo, ChainNotify, Client, Header, ImportBlock,
-    ImportError, Seal, UnverifiedTransaction,
+    Block, BlockChainClient, BlockError, BlockId, BlockImportError, BlockInfo, ChainInfo, ChainNotify, Client,
+    DatabaseClient, EngineInfo, Header, ImportBlock, ImportError, Seal, UnverifiedTransaction, COL_STATE,
 };
-use cnetwork::{Api, EventSender, NetworkExtension, NodeId};
-use cstate::FindActionHandler;
+use cnetwork::{Api, EventSender, Misbehavior, NetworkExtension, NetworkExtensionPriority, NodeId};
+use cstate::{FindActionHandler, StateDB, TopLevelState, TopStateView};
 use ctimer::TimerToken;
 use ctypes::transaction::Action;
-use ctypes::BlockNumber;
+use ctypes::{BlockNumber, ShardId};
+use journaldb::{self, Algorithm};
+use kvdb::KeyValueDB;
 use primitives::{H256, U256};
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
@@ -35,8 +41,9 @@ use rlp::{Encodable, UntrustedRlp};
 use time::Duration;
 use token_generator::TokenGenerator;
 
-use super::downloader::{BodyDownloader, HeaderDownloader};
+use super::downloader::{BodyDownloader, HeaderDownloader, HeaderSkeleton, Throughput};
 use super::message::{Message, RequestMessage, ResponseMessage};
+use crate::snapshot::{Manifest as SnapshotManifest, ReadSnapshot, Snapshot};
 
 const SYNC_TIMER_TOKEN: TimerToken = 0;
 const SYNC_EXPIRE_TOKEN_BEGIN: TimerToken = SYNC_TIMER_TOKEN + 1;
@@ -44,60 +51,87 @@ const SYNC_EXPIRE_TOKEN_LIMIT: usize = 1000;
 const SYNC_EXPIRE_TOKEN_END: TimerToken = SYNC_EXPIRE_TOKEN_BEGIN + SYNC_EXPIRE_TOKEN_LIMIT;
 
 const SYNC_TIMER_INTERVAL: i64 = 1000;
-const SYNC_EXPIRE_REQUEST_INTERVAL: i64 = 15000;
 
-const SNAPSHOT_PERIOD: u64 = (1 << 14);
+const MAX_BODY_REQUEST_LENGTH: usize = 128;
 
 #[derive(Debug, PartialEq)]
 pub struct TokenInfo {
     node_id: NodeId,
     request_id: Option<u64>,
+    request_time: Option<Instant>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct PeerThroughput {
+    headers: Throughput,
+    bodies: Throughput,
+}
+
+enum State {
+    /// Downloading headers to choose the block to restore the state from a snapshot
+    SnapshotHeader,
+    /// Downloading the state chunks and the body of the snapshot block
+    SnapshotChunk(SnapshotTarget),
+    /// Downloading the bodies of the blocks whose headers are imported
+    Full,
+}
+
+struct SnapshotTarget {
+    header: Header,
+    snapshot: Snapshot,
+    head_restored: bool,
+    pending_chunks: Vec<H256>,
+    shard_roots: Option<Vec<(ShardId, H256)>>,
+    body: Option<Vec<UnverifiedTransaction>>,
 }
 
 pub struct Extension {
     requests: HashMap<NodeId, Vec<(u64, RequestMessage)>>,
     connected_nodes: HashSet<NodeId>,
     header_downloaders: HashMap<NodeId, HeaderDownloader>,
+    skeleton: HeaderSkeleton,
     body_downloader: BodyDownloader,
+    throughputs: HashMap<NodeId, PeerThroughput>,
     tokens: HashMap<NodeId, TimerToken>,
     tokens_info: HashMap<TimerToken, TokenInfo>,
     token_generator: TokenGenerator,
     client: Arc<Client>,
     api: Box<Api>,
     last_request: u64,
+    state: State,
+    snapshot_dir: Option<String>,
 }
 
 impl Extension {
-    pub fn new(client: Arc<Client>, api: Box<Api>) -> Extension {
+    pub fn new(client: Arc<Client>, api: Box<Api>, snapshot_dir: Option<String>) -> Extension {
         api.set_timer(SYNC_TIMER_TOKEN, Duration::milliseconds(SYNC_TIMER_INTERVAL)).expect("Timer set succeeds");
 
-        let mut header = client.best_header();
-        let mut hollow_headers = vec![header.decode()];
-        while client.block_body(&BlockId::Hash(header.hash())).is_none() {
-            header = client
-                .block_header(&BlockId::Hash(header.parent_hash()))
-                .expect("Every imported header must have parent");
-            hollow_headers.push(header.decode());
-        }
         let mut body_downloader = BodyDownloader::default();
-        for neighbors in hollow_headers.windows(2).rev() {
-            let child = &neighbors[0];
-            let parent = &neighbors[1];
-            cdebug!(SYNC, "Adding block #{} (hash: {}) for initial body download target", child.number(), child.hash());
-            body_downloader.add_target(child, parent);
-        }
+        let state = if snapshot_dir.is_some() && client.chain_info().best_block_number == 0 {
+            cinfo!(SYNC, "Sync extension starts with snapshot sync");
+            State::SnapshotHeader
+        } else {
+            add_hollow_headers(&client, &mut body_downloader);
+            State::Full
+        };
+        let best_header = client.best_header();
+        let skeleton = HeaderSkeleton::new(best_header.number(), best_header.hash());
         cinfo!(SYNC, "Sync extension initialized");
         Extension {
             requests: Default::default(),
             connected_nodes: Default::default(),
             header_downloaders: Default::default(),
+            skeleton,
             body_downloader,
+            throughputs: Default::default(),
             tokens: Default::default(),
             tokens_info: Default::default(),
             token_generator: TokenGenerator::new(SYNC_EXPIRE_TOKEN_BEGIN, SYNC_EXPIRE_TOKEN_END),
             client,
             api,
             last_request: Default::default(),
+            state,
+            snapshot_dir,
         }
     }
 
@@ -117,8 +151,19 @@ impl Extension {
         }
     }
 
+    fn send_state_request(&mut self, id: &NodeId, request: RequestMessage) {
+        if let Some(requests) = self.requests.get_mut(id) {
+            ctrace!(SYNC, "Send state request to {}", id);
+            let request_id = self.last_request;
+            self.last_request += 1;
+            requests.push((request_id, request.clone()));
+            self.api.send(id, Arc::new(Message::Request(request_id, request).rlp_bytes().into_vec()));
+        }
+    }
+
     fn send_body_request(&mut self, id: &NodeId) {
         self.check_sync_variable();
+        let throughput = self.throughputs.get(id).cloned().unwrap_or_default().bodies;
         if let Some(requests) = self.requests.get_mut(id) {
             let have_body_request = {
                 requests.iter().any(|r| match r {
@@ -131,8 +176,13 @@ impl Extension {
                 return
             }
 
-            if let Some(request) = self.body_downloader.create_request() {
+            let max_count = throughput.request_size(MAX_BODY_REQUEST_LENGTH);
+            if let Some(request) = self.body_downloader.create_request(max_count) {
                 cdebug!(SYNC, "Request body to {} {:?}", id, request);
+                let timeout = match &request {
+                    RequestMessage::Bodies(hashes) => throughput.timeout(hashes.len()),
+                    _ => unreachable!(),
+                };
                 let request_id = self.last_request;
                 self.last_request += 1;
                 requests.push((request_id, request.clone()));
@@ -143,14 +193,68 @@ impl Extension {
 
                 let _ = self.api.clear_timer(*token);
                 self.api
-                    .set_timer_once(*token, Duration::milliseconds(SYNC_EXPIRE_REQUEST_INTERVAL))
+                    .set_timer_once(*token, Duration::from_std(timeout).expect("Timeout is in the range"))
                     .expect("Timer set succeeds");
                 token_info.request_id = Some(request_id);
+                token_info.request_time = Some(Instant::now());
             }
         }
         self.check_sync_variable();
     }
 
+    /// Sends body requests to the peers ahead, the fastest peer gets the earliest blocks.
+    fn send_body_requests(&mut self) {
+        let total_score = self.client.chain_info().best_proposal_score;
+        for id in self.peers_by_throughput(|throughput| throughput.bodies.rate()) {
+            let peer_score = self.header_downloaders.get(&id).map_or_else(U256::zero, HeaderDownloader::total_score);
+            if peer_score > total_score {
+                self.send_body_request(&id);
+            }
+        }
+    }
+
+    /// Assigns the segments of the header skeleton to the idle peers ahead.
+    fn send_skeleton_requests(&mut self) {
+        if self.skeleton.is_finished() {
+            return
+        }
+        let best_header_hash = self.client.best_header().hash();
+        let best_score =
+            self.client.block_total_score(&BlockId::Hash(best_header_hash)).expect("Best header must exist");
+        for id in self.peers_by_throughput(|throughput| throughput.headers.rate()) {
+            let is_ahead = self.header_downloaders.get(&id).map_or(false, |peer| peer.total_score() > best_score);
+            if !is_ahead {
+                continue
+            }
+            let throughput = self.throughputs.get(&id).cloned().unwrap_or_default().headers;
+            match self.skeleton.create_request(id, &throughput) {
+                Some(request) => self.send_header_request(&id, request),
+                None if self.skeleton.is_downloading_from(&id) => {}
+                None => break,
+            }
+        }
+    }
+
+    fn peers_by_throughput<F>(&self, rate: F) -> Vec<NodeId>
+    where
+        F: Fn(&PeerThroughput) -> f64, {
+        let rate_of = |id: &NodeId| self.throughputs.get(id).map_or(0.0, &rate);
+        let mut peer_ids: Vec<_> = self.header_downloaders.keys().cloned().collect();
+        peer_ids.sort_by(|a, b| rate_of(b).partial_cmp(&rate_of(a)).unwrap_or(Ordering::Equal));
+        peer_ids
+    }
+
+    fn reset_skeleton(&mut self) {
+        let best_header = self.client.best_header();
+        self.skeleton.reset(best_header.number(), best_header.hash());
+    }
+
+    /// Lowers the reputation of the peer, which is banned for a while if it keeps misbehaving.
+    fn report(&self, id: &NodeId, misbehavior: Misbehavior, reason: &str) {
+        cwarn!(SYNC, "Peer #{} misbehaved: {}", id, reason);
+        self.api.report(id, misbehavior);
+    }
+
     fn check_sync_variable(&self) {
         let mut has_error = false;
         for id in self.header_downloaders.keys() {
@@ -208,6 +312,10 @@ impl NetworkExtension<Event> for Extension {
         &VERSIONS
     }
 
+    fn priority() -> NetworkExtensionPriority {
+        NetworkExtensionPriority::Low
+    }
+
     fn on_node_added(&mut self, id: &NodeId, _version: u64) {
         cinfo!(SYNC, "New peer detected #{}", id);
         let chain_info = self.client.chain_info();
@@ -230,6 +338,7 @@ impl NetworkExtension<Event> for Extension {
         let token_info = TokenInfo {
             node_id: *id,
             request_id: None,
+            request_time: None,
         };
 
         let t = self.requests.insert(*id, Vec::new());
@@ -239,6 +348,7 @@ impl NetworkExtension<Event> for Extension {
         let t = self.tokens.insert(*id, token);
         debug_assert_eq!(None, t);
         debug_assert!(t.is_none());
+        self.throughputs.insert(*id, PeerThroughput::default());
     }
 
     fn on_node_removed(&mut self, id: &NodeId) {
@@ -246,10 +356,21 @@ impl NetworkExtension<Event> for Extension {
             cinfo!(SYNC, "Peer removed #{}", id);
 
             self.header_downloaders.remove(id);
+            self.skeleton.release(id);
+            self.throughputs.remove(id);
 
             for (_, request) in self.requests.remove(id).into_iter().flatten() {
-                if let RequestMessage::Bodies(hashes) = request {
-                    self.body_downloader.reset_downloading(&hashes);
+                match request {
+                    RequestMessage::Bodies(hashes) => self.body_downloader.reset_downloading(&hashes),
+                    RequestMessage::StateChunk {
+                        tree_root,
+                        ..
+                    } => {
+                        if let State::SnapshotChunk(target) = &mut self.state {
+                            target.pending_chunks.push(tree_root);
+                        }
+                    }
+                    _ => {}
                 }
             }
 
@@ -279,36 +400,35 @@ impl NetworkExtension<Event> for Extension {
                 Message::Response(request_id, response) => self.on_peer_response(id, request_id, response),
             }
         } else {
-            cinfo!(SYNC, "Invalid message from peer {}", id);
+            self.report(id, Misbehavior::MalformedMessage, "Invalid message");
         }
     }
 
     fn on_timeout(&mut self, token: TimerToken) {
         match token {
             SYNC_TIMER_TOKEN => {
-                let best_proposal_score = self.client.chain_info().best_proposal_score;
-                let mut peer_ids: Vec<_> = self.header_downloaders.keys().cloned().collect();
-                peer_ids.shuffle(&mut thread_rng());
-
-                for id in &peer_ids {
-                    let request = self.header_downloaders.get_mut(id).and_then(HeaderDownloader::create_request);
-                    if let Some(request) = request {
-                        self.send_header_request(id, request);
-                        break
+                self.expire_skeleton();
+                self.send_skeleton_requests();
+                if !self.skeleton.is_active() {
+                    let mut peer_ids: Vec<_> = self.header_downloaders.keys().cloned().collect();
+                    peer_ids.shuffle(&mut thread_rng());
+
+                    for id in &peer_ids {
+                        let request = self.header_downloaders.get_mut(id).and_then(HeaderDownloader::create_request);
+                        if let Some(request) = request {
+                            self.send_header_request(id, request);
+                            break
+                        }
                     }
                 }
 
-                for id in peer_ids {
-                    let peer_score = if let Some(peer) = self.header_downloaders.get(&id) {
-                        peer.total_score()
-                    } else {
-                        U256::zero()
-                    };
-
-                    if peer_score > best_proposal_score {
-                        self.send_body_request(&id);
-                    }
+                match self.state {
+                    State::SnapshotHeader => self.choose_snapshot_target(),
+                    State::SnapshotChunk(..) => self.send_snapshot_requests(),
+                    State::Full => {}
                 }
+
+                self.send_body_requests();
             }
             SYNC_EXPIRE_TOKEN_BEGIN...SYNC_EXPIRE_TOKEN_END => {
                 self.check_sync_variable();
@@ -321,11 +441,15 @@ impl NetworkExtension<Event> for Extension {
                     match token_info.request_id {
                         Some(request_id) => {
                             token_info.request_id = None;
+                            token_info.request_time = None;
                             (token_info.node_id, request_id)
                         }
                         None => return,
                     }
                 };
+                if let Some(throughput) = self.throughputs.get_mut(&id) {
+                    throughput.bodies.penalize();
+                }
 
                 if let Some(requests) = self.requests.get_mut(&id) {
                     let expired_request = requests.iter().find(|(r, _)| *r == request_id);
@@ -341,6 +465,8 @@ impl NetworkExtension<Event> for Extension {
 
                 self.dismiss_request(&id, request_id);
                 self.check_sync_variable();
+                // Re-assign the expired bodies.
+                self.send_body_requests();
             }
             _ => unreachable!(),
         }
@@ -385,12 +511,22 @@ pub enum Event {
 
 impl Extension {
     fn new_headers(&mut self, imported: Vec<H256>, enacted: Vec<H256>, retracted: Vec<H256>) {
+        let best_header_hash = self.client.best_header().hash();
+        let best_score =
+            self.client.block_total_score(&BlockId::Hash(best_header_hash)).expect("Best header must exist");
         let peer_ids: Vec<_> = self.header_downloaders.keys().cloned().collect();
         for id in peer_ids {
             if let Some(peer) = self.header_downloaders.get_mut(&id) {
                 peer.mark_as_imported(imported.clone());
+                // Headers may be imported from the skeleton.
+                peer.advance_pivot(best_header_hash, best_score);
             }
         }
+        match self.state {
+            State::Full => {}
+            // Bodies are downloaded after the state is restored from the snapshot.
+            State::SnapshotHeader | State::SnapshotChunk(..) => return,
+        }
         let mut headers_to_download: Vec<_> = enacted
             .into_iter()
             .map(|hash| self.client.block_header(&BlockId::Hash(hash)).expect("Enacted header must exist"))
@@ -458,6 +594,9 @@ impl Extension {
                 e.insert(HeaderDownloader::new(self.client.clone(), total_score, best_hash));
             }
         }
+        if self.skeleton.is_finished() {
+            self.reset_skeleton();
+        }
         cinfo!(SYNC, "Peer #{} status update: total_score: {}, best_hash: {}", from, total_score, best_hash);
     }
 
@@ -500,21 +639,19 @@ impl Extension {
                 ..
             } => true,
             RequestMessage::Bodies(hashes) => !hashes.is_empty(),
-            RequestMessage::StateHead(hash) => match self.client.block_number(&BlockId::Hash(*hash)) {
-                Some(number) if number % SNAPSHOT_PERIOD == 0 => true,
-                _ => false,
-            },
+            RequestMessage::StateHead(hash) => self.is_checkpoint(hash),
             RequestMessage::StateChunk {
                 block_hash,
                 ..
-            } => {
-                let _is_checkpoint = match self.client.block_number(&BlockId::Hash(*block_hash)) {
-                    Some(number) if number % SNAPSHOT_PERIOD == 0 => true,
-                    _ => false,
-                };
-                // FIXME:  check tree_root
-                unimplemented!()
-            }
+            } => self.is_checkpoint(block_hash),
+        }
+    }
+
+    fn is_checkpoint(&self, hash: &H256) -> bool {
+        let period = self.client.common_params().snapshot_period;
+        match self.client.block_number(&BlockId::Hash(*hash)) {
+            Some(number) if number % period == 0 => true,
+            _ => false,
         }
     }
 
@@ -552,12 +689,25 @@ impl Extension {
         ResponseMessage::Bodies(bodies)
     }
 
-    fn create_state_head_response(&self, _hash: H256) -> ResponseMessage {
-        unimplemented!()
+    fn create_state_head_response(&self, hash: H256) -> ResponseMessage {
+        let head = self
+            .client
+            .block_header(&BlockId::Hash(hash))
+            .and_then(|header| self.read_snapshot_chunk(&hash, &header.state_root()));
+        ResponseMessage::StateHead(head.unwrap_or_default())
+    }
+
+    fn create_state_chunk_response(&self, hash: H256, tree_root: H256) -> ResponseMessage {
+        ResponseMessage::StateChunk(self.read_snapshot_chunk(&hash, &tree_root).unwrap_or_default())
     }
 
-    fn create_state_chunk_response(&self, _hash: H256, _tree_root: H256) -> ResponseMessage {
-        unimplemented!()
+    fn read_snapshot_chunk(&self, block_hash: &H256, root: &H256) -> Option<Vec<u8>> {
+        let snapshot_dir = self.snapshot_dir.as_ref()?;
+        let path: PathBuf = [snapshot_dir.clone(), format!("{:x}", block_hash)].iter().collect();
+        if !path.exists() {
+            return None
+        }
+        Snapshot::try_new(path).and_then(|snapshot| snapshot.read_raw_chunk(root)).ok()
     }
 
     fn on_peer_response(&mut self, from: &NodeId, id: u64, mut response: ResponseMessage) {
@@ -568,13 +718,25 @@ impl Extension {
             }
 
             if !self.is_valid_response(&request, &response) {
+                self.report(from, Misbehavior::InvalidData, "Invalid response");
                 return
             }
 
             match response {
                 ResponseMessage::Headers(headers) => {
                     self.dismiss_request(from, id);
-                    self.on_header_response(from, &headers)
+                    let start_number = match request {
+                        RequestMessage::Headers {
+                            start_number,
+                            ..
+                        } => start_number,
+                        _ => unreachable!(),
+                    };
+                    if self.skeleton.is_requested(from, start_number) {
+                        self.on_skeleton_response(from, start_number, &headers)
+                    } else if !headers.is_empty() {
+                        self.on_header_response(from, &headers)
+                    }
                 }
                 ResponseMessage::Bodies(bodies) => {
                     self.check_sync_variable();
@@ -591,13 +753,32 @@ impl Extension {
                             }
                             self.api.clear_timer(*token).expect("Timer clear succeed");
                             token_info.request_id = None;
+                            if let Some(request_time) = token_info.request_time.take() {
+                                if let Some(throughput) = self.throughputs.get_mut(from) {
+                                    throughput.bodies.update(hashes.len(), request_time.elapsed());
+                                }
+                            }
                         }
                     }
                     self.dismiss_request(from, id);
-                    self.on_body_response(hashes, bodies);
+                    self.on_body_response(from, hashes, bodies);
                     self.check_sync_variable();
                 }
-                _ => unimplemented!(),
+                ResponseMessage::StateHead(head) => {
+                    self.dismiss_request(from, id);
+                    self.on_state_head_response(from, &head);
+                }
+                ResponseMessage::StateChunk(chunk) => {
+                    let tree_root = match request {
+                        RequestMessage::StateChunk {
+                            tree_root,
+                            ..
+                        } => tree_root,
+                        _ => unreachable!(),
+                    };
+                    self.dismiss_request(from, id);
+                    self.on_state_chunk_response(from, tree_root, &chunk);
+                }
             }
         }
     }
@@ -622,7 +803,8 @@ impl Extension {
                     }
                 }
 
-                headers.first().map(Header::number) == Some(*start_number)
+                // An empty response means that the peer doesn't have the header.
+                headers.first().map_or(true, |header| header.number() == *start_number)
             }
             (RequestMessage::Bodies(hashes), ResponseMessage::Bodies(bodies)) => {
                 if hashes.len() != bodies.len() {
@@ -651,13 +833,14 @@ impl Extension {
                 }
                 true
             }
-            (RequestMessage::StateHead(..), ResponseMessage::StateHead(..)) => unimplemented!(),
+            // The chunks are verified against the state root when they are written.
+            (RequestMessage::StateHead(..), ResponseMessage::StateHead(..)) => true,
             (
                 RequestMessage::StateChunk {
                     ..
                 },
                 ResponseMessage::StateChunk(..),
-            ) => unimplemented!(),
+            ) => true,
             _ => {
                 cwarn!(SYNC, "Invalid response type");
                 false
@@ -702,12 +885,74 @@ impl Extension {
         }
     }
 
-    fn on_body_response(&mut self, hashes: Vec<H256>, bodies: Vec<Vec<UnverifiedTransaction>>) {
+    fn on_skeleton_response(&mut self, from: &NodeId, start_number: BlockNumber, headers: &[Header]) {
+        ctrace!(SYNC, "Received the segment #{} from({}) with length({})", start_number, from, headers.len());
+        let encoded: Vec<_> = headers.iter().map(|h| EncodedHeader::new(h.rlp_bytes().to_vec())).collect();
+        if let Some(elapsed) = self.skeleton.import_headers(from, start_number, encoded) {
+            if let Some(throughput) = self.throughputs.get_mut(from) {
+                throughput.headers.update(headers.len(), elapsed);
+            }
+        }
+
+        for (node, headers) in self.skeleton.drain() {
+            for header in headers {
+                match self.client.import_header(header.clone().into_inner()) {
+                    Ok(_)
+                    | Err(BlockImportError::Import(ImportError::AlreadyInChain))
+                    | Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {}
+                    Err(err) => {
+                        if is_invalid_header(&err) {
+                            let reason = format!("Invalid header({}): {:?}", header.hash(), err);
+                            self.report(&node, Misbehavior::InvalidData, &reason);
+                        } else {
+                            cwarn!(SYNC, "Cannot import header({}): {:?}", header.hash(), err);
+                        }
+                        self.reset_skeleton();
+                        return
+                    }
+                }
+            }
+        }
+        self.send_skeleton_requests();
+    }
+
+    fn expire_skeleton(&mut self) {
+        for (id, start) in self.skeleton.expire(Instant::now()) {
+            if let Some(throughput) = self.throughputs.get_mut(&id) {
+                throughput.headers.penalize();
+            }
+            if let Some(requests) = self.requests.get_mut(&id) {
+                requests.retain(|(_, request)| match request {
+                    RequestMessage::Headers {
+                        start_number,
+                        ..
+                    } => *start_number != start,
+                    _ => true,
+                });
+            }
+        }
+    }
+
+    fn on_body_response(&mut self, from: &NodeId, hashes: Vec<H256>, bodies: Vec<Vec<UnverifiedTransaction>>) {
         ctrace!(SYNC, "Received body response with lenth({}) {:?}", hashes.len(), hashes);
         {
-            self.body_downloader.import_bodies(hashes, bodies);
+            if !self.body_downloader.import_bodies(hashes, bodies) {
+                self.report(from, Misbehavior::InvalidData, "Bodies don't match the headers");
+            }
             let completed = self.body_downloader.drain();
             for (hash, transactions) in completed {
+                if let State::SnapshotChunk(target) = &mut self.state {
+                    if target.header.hash() == hash {
+                        cdebug!(
+                            SYNC,
+                            "Body download completed for the snapshot block #{}({})",
+                            target.header.number(),
+                            hash
+                        );
+                        target.body = Some(transactions);
+                        continue
+                    }
+                }
                 let header = self
                     .client
                     .block_header(&BlockId::Hash(hash))
@@ -731,22 +976,338 @@ impl Extension {
                 }
             }
         }
+        self.restore_snapshot();
+        self.send_body_requests();
+    }
 
-        let total_score = self.client.chain_info().best_proposal_score;
-        let mut peer_ids: Vec<_> = self.header_downloaders.keys().cloned().collect();
+    fn choose_snapshot_target(&mut self) {
+        if self.client.chain_info().best_block_number != 0 {
+            cinfo!(SYNC, "Blocks are imported before choosing a snapshot, switch to the full sync");
+            self.switch_to_full_sync();
+            return
+        }
+        if self.header_downloaders.is_empty() {
+            return
+        }
+
+        let best_header = self.client.best_header();
+        let best_header_score =
+            self.client.block_total_score(&BlockId::Hash(best_header.hash())).expect("Best header must exist");
+        if self.header_downloaders.values().any(|peer| peer.total_score() > best_header_score) {
+            // Headers are still being downloaded.
+            return
+        }
+
+        let period = self.client.common_params().snapshot_period;
+        let number = (best_header.number() / period).saturating_sub(1) * period;
+        if number == 0 {
+            cinfo!(SYNC, "There is no snapshot to restore, switch to the full sync");
+            self.switch_to_full_sync();
+            return
+        }
+
+        let header = self.client.block_header(&BlockId::Number(number)).expect("Snapshot target must exist").decode();
+        let parent = self
+            .client
+            .block_header(&BlockId::Hash(*header.parent_hash()))
+            .expect("Every imported header must have parent")
+            .decode();
+        let snapshot_dir = self.snapshot_dir.clone().expect("Snapshot sync requires the snapshot directory");
+        let path: PathBuf = [snapshot_dir, format!("{:x}", header.hash())].iter().collect();
+        let snapshot = match Snapshot::try_new(path) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                cerror!(SYNC, "Cannot create the snapshot directory: {}", err);
+                self.switch_to_full_sync();
+                return
+            }
+        };
+
+        cinfo!(SYNC, "Start downloading the snapshot of #{}({})", number, header.hash());
+        self.body_downloader.add_target(&header, &parent);
+        self.state = State::SnapshotChunk(SnapshotTarget {
+            header,
+            snapshot,
+            head_restored: false,
+            pending_chunks: Vec::new(),
+            shard_roots: None,
+            body: None,
+        });
+        self.send_snapshot_requests();
+    }
+
+    fn send_snapshot_requests(&mut self) {
+        let (block_hash, head_restored) = match &self.state {
+            State::SnapshotChunk(target) => (target.header.hash(), target.head_restored),
+            _ => return,
+        };
+
+        let has_state_request = |requests: &Vec<(u64, RequestMessage)>| {
+            requests.iter().any(|(_, request)| match request {
+                RequestMessage::StateHead(..)
+                | RequestMessage::StateChunk {
+                    ..
+                } => true,
+                _ => false,
+            })
+        };
+        if !head_restored && self.requests.values().any(has_state_request) {
+            // Wait for the head because the other chunks are known after it is restored.
+            return
+        }
+
+        let mut peer_ids: Vec<_> = self
+            .header_downloaders
+            .keys()
+            .filter(|id| self.requests.get(*id).map_or(false, |requests| !has_state_request(requests)))
+            .cloned()
+            .collect();
         peer_ids.shuffle(&mut thread_rng());
 
+        if !head_restored {
+            if let Some(id) = peer_ids.first() {
+                self.send_state_request(id, RequestMessage::StateHead(block_hash));
+            }
+            return
+        }
+
         for id in peer_ids {
-            let peer_score = if let Some(peer) = self.header_downloaders.get(&id) {
-                peer.total_score()
-            } else {
-                U256::zero()
+            let tree_root = match &mut self.state {
+                State::SnapshotChunk(target) => target.pending_chunks.pop(),
+                _ => None,
             };
+            match tree_root {
+                Some(tree_root) => self.send_state_request(
+                    &id,
+                    RequestMessage::StateChunk {
+                        block_hash,
+                        tree_root,
+                    },
+                ),
+                None => break,
+            }
+        }
+    }
 
-            if peer_score > total_score {
-                self.send_body_request(&id);
+    fn on_state_head_response(&mut self, from: &NodeId, head: &[u8]) {
+        let db = self.client.database();
+        let mut is_invalid = false;
+        if let State::SnapshotChunk(target) = &mut self.state {
+            if target.head_restored {
+                return
+            }
+            if head.is_empty() {
+                cdebug!(SYNC, "Peer #{} doesn't have the snapshot of {}", from, target.header.hash());
+            } else {
+                match target.snapshot.write_raw_head(db, target.header.state_root(), head) {
+                    Ok(chunk_roots) => {
+                        cinfo!(SYNC, "Snapshot head is downloaded, {} chunks remain", chunk_roots.len());
+                        target.head_restored = true;
+                        target.pending_chunks = chunk_roots;
+                    }
+                    Err(err) => {
+                        cwarn!(SYNC, "Peer #{} sent an invalid snapshot head: {}", from, err);
+                        is_invalid = true;
+                    }
+                }
+            }
+        }
+        if is_invalid {
+            self.report(from, Misbehavior::InvalidData, "Invalid snapshot head");
+        }
+        self.send_snapshot_requests();
+        self.restore_snapshot();
+    }
+
+    fn on_state_chunk_response(&mut self, from: &NodeId, tree_root: H256, chunk: &[u8]) {
+        let db = self.client.database();
+        let mut is_invalid = false;
+        if let State::SnapshotChunk(target) = &mut self.state {
+            if chunk.is_empty() {
+                cdebug!(SYNC, "Peer #{} doesn't have the snapshot chunk {}", from, tree_root);
+                target.pending_chunks.push(tree_root);
+            } else {
+                // The chunk of a shard root is the head of the shard state, which misses its subtries.
+                // Every other chunk must be a complete trie.
+                let is_shard_root = target
+                    .shard_roots
+                    .as_ref()
+                    .map_or(false, |shard_roots| shard_roots.iter().any(|(_, root)| *root == tree_root));
+                let result = if is_shard_root {
+                    target.snapshot.write_raw_head(db, &tree_root, chunk)
+                } else {
+                    target.snapshot.write_raw_chunk(db, &tree_root, chunk).map(|_| Vec::new())
+                };
+                match result {
+                    Ok(missing) => target.pending_chunks.extend(missing),
+                    Err(err) => {
+                        cwarn!(SYNC, "Peer #{} sent an invalid snapshot chunk {}: {}", from, tree_root, err);
+                        target.pending_chunks.push(tree_root);
+                        is_invalid = true;
+                    }
+                }
+            }
+        }
+        if is_invalid {
+            self.report(from, Misbehavior::InvalidData, "Invalid snapshot chunk");
+        }
+        self.send_snapshot_requests();
+        self.restore_snapshot();
+    }
+
+    fn is_snapshot_downloaded(&self) -> bool {
+        let is_requested = self.requests.values().flatten().any(|(_, request)| match request {
+            RequestMessage::StateChunk {
+                ..
+            } => true,
+            _ => false,
+        });
+        match &self.state {
+            State::SnapshotChunk(target) => target.head_restored && target.pending_chunks.is_empty() && !is_requested,
+            _ => false,
+        }
+    }
+
+    fn restore_snapshot(&mut self) {
+        if !self.is_snapshot_downloaded() {
+            return
+        }
+        let is_top_level_restored = match &self.state {
+            State::SnapshotChunk(target) => target.shard_roots.is_some(),
+            _ => unreachable!(),
+        };
+        if !is_top_level_restored {
+            self.restore_top_level_state();
+            self.send_snapshot_requests();
+            if !self.is_snapshot_downloaded() {
+                return
             }
         }
+        let has_body = match &self.state {
+            State::SnapshotChunk(target) => target.body.is_some(),
+            _ => false,
+        };
+        if !has_body {
+            return
+        }
+
+        let target = match mem::replace(&mut self.state, State::SnapshotHeader) {
+            State::SnapshotChunk(target) => target,
+            _ => unreachable!(),
+        };
+        let hash = target.header.hash();
+        let number = target.header.number();
+        let shard_roots = target.shard_roots.expect("Shard roots are known after the top-level state is restored");
+        for (shard_id, shard_root) in &shard_roots {
+            if let Err(err) = target.snapshot.read_snapshot(self.client.database(), shard_root) {
+                cerror!(
+                    SYNC,
+                    "Cannot restore the shard #{} from the snapshot of #{}({}): {}",
+                    shard_id,
+                    number,
+                    hash,
+                    err
+                );
+                return
+            }
+        }
+
+        let state_root = *target.header.state_root();
+        let block = Block {
+            header: target.header,
+            transactions: target.body.expect("Body of the snapshot block is downloaded"),
+        };
+        if let Err(err) = self.client.import_bootstrap_block(block.rlp_bytes(&Seal::With)) {
+            cwarn!(SYNC, "Cannot import the snapshot block #{}({}): {:?}", number, hash, err);
+            return
+        }
+        cinfo!(SYNC, "State is restored from the snapshot of #{}({})", number, hash);
+
+        // Write the manifest to serve the snapshot to other peers.
+        let manifest = target.snapshot.chunks().and_then(|chunks| {
+            SnapshotManifest {
+                block_hash: hash,
+                block_number: number,
+                state_root,
+                shard_roots,
+                chunks,
+            }
+            .write(target.snapshot.path())
+        });
+        if let Err(err) = manifest {
+            cwarn!(SYNC, "Cannot write the manifest of the snapshot: {}", err);
+        }
+        self.switch_to_full_sync();
+    }
+
+    fn restore_top_level_state(&mut self) {
+        let db = self.client.database();
+        let result = match &mut self.state {
+            State::SnapshotChunk(target) => {
+                let state_root = *target.header.state_root();
+                let result = target
+                    .snapshot
+                    .read_snapshot(db.clone(), &state_root)
+                    .map_err(|err| err.to_string())
+                    .and_then(|_| shard_roots_of(db, state_root));
+                if let Ok(shard_roots) = &result {
+                    cinfo!(SYNC, "Top-level state is restored from the snapshot, {} shards remain", shard_roots.len());
+                    target.pending_chunks = shard_roots.iter().map(|(_, root)| *root).collect();
+                    target.shard_roots = Some(shard_roots.clone());
+                }
+                result
+            }
+            _ => return,
+        };
+        if let Err(err) = result {
+            cerror!(SYNC, "Cannot restore the top-level state from the snapshot: {}", err);
+            self.state = State::SnapshotHeader;
+        }
+    }
+
+    fn switch_to_full_sync(&mut self) {
+        self.state = State::Full;
+        add_hollow_headers(&self.client, &mut self.body_downloader);
+    }
+}
+
+fn shard_roots_of(db: Arc<KeyValueDB>, state_root: H256) -> Result<Vec<(ShardId, H256)>, String> {
+    let state_db = StateDB::new(journaldb::new(db, Algorithm::Archive, COL_STATE));
+    let state = TopLevelState::from_existing(state_db, state_root).map_err(|err| err.to_string())?;
+    let number_of_shards = state.number_of_shards().map_err(|err| err.to_string())?;
+    (0..number_of_shards)
+        .map(|shard_id| match state.shard_root(shard_id) {
+            Ok(Some(shard_root)) => Ok((shard_id, shard_root)),
+            Ok(None) => Err(format!("Shard #{} doesn't exist", shard_id)),
+            Err(err) => Err(err.to_string()),
+        })
+        .collect()
+}
+
+/// Whether the header itself is invalid, not just ahead of the local chain.
+fn is_invalid_header(err: &BlockImportError) -> bool {
+    match err {
+        BlockImportError::Block(BlockError::TemporarilyInvalid(..))
+        | BlockImportError::Block(BlockError::UnknownParent(..)) => false,
+        BlockImportError::Block(..) | BlockImportError::Import(ImportError::KnownBad) => true,
+        _ => false,
+    }
+}
+
+/// Adds the blocks whose headers are imported but bodies are not to the targets of the body download.
+fn add_hollow_headers(client: &Client, body_downloader: &mut BodyDownloader) {
+    let mut header = client.best_header();
+    let mut hollow_headers = vec![header.decode()];
+    while client.block_body(&BlockId::Hash(header.hash())).is_none() {
+        header =
+            client.block_header(&BlockId::Hash(header.parent_hash())).expect("Every imported header must have parent");
+        hollow_headers.push(header.decode());
+    }
+    for neighbors in hollow_headers.windows(2).rev() {
+        let child = &neighbors[0];
+        let parent = &neighbors[1];
+        cdebug!(SYNC, "Adding block #{} (hash: {}) for initial body download target", child.number(), child.hash());
+        body_downloader.add_target(child, parent);
     }
 }
 
@@ -17,6 +17,7 @@
 extern crate parking_lot;
 
 extern crate codechain_core as ccore;
+extern crate codechain_crypto as ccrypto;
 extern crate codechain_merkle as cmerkle;
 #[macro_use]
 extern crate codechain_logger as clogger;
@@ -29,7 +30,6 @@ extern crate codechain_types as ctypes;
 extern crate hashdb;
 extern crate journaldb;
 extern crate kvdb;
-#[cfg(test)]
 extern crate kvdb_memorydb;
 #[macro_use]
 extern crate log;
@@ -51,7 +51,7 @@ mod snapshot;
 mod transaction;
 
 pub use crate::block::{BlockSyncEvent, BlockSyncExtension, BlockSyncSender};
-pub use crate::snapshot::SnapshotService;
+pub use crate::snapshot::{list_snapshots, Manifest as SnapshotManifest, ReadSnapshot, Snapshot, SnapshotService};
 pub use crate::transaction::TransactionSyncExtension;
 
 #[cfg(test)]
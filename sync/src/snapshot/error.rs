@@ -28,6 +28,8 @@ pub enum Error {
     DBError(DBError),
     FileError(ErrorKind),
     UtilError(UtilError),
+    InvalidManifest(String),
+    InvalidSnapshot(String),
 }
 
 impl From<DBError> for Error {
@@ -56,6 +58,8 @@ impl Display for Error {
             Error::DBError(error) => write!(f, "DB Error: {:?}", error),
             Error::FileError(kind) => write!(f, "File system error: {:?}", kind),
             Error::UtilError(error) => write!(f, "Util error: {:?}", error),
+            Error::InvalidManifest(reason) => write!(f, "Invalid manifest: {}", reason),
+            Error::InvalidSnapshot(reason) => write!(f, "Invalid snapshot: {}", reason),
         }
     }
 }
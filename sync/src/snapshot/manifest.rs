@@ -0,0 +1,226 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::{read_dir, remove_dir_all, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use ctypes::{BlockNumber, ShardId};
+use primitives::H256;
+use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
+
+use super::error::Error;
+
+pub const MANIFEST_FILE_NAME: &str = "manifest";
+
+/// A chunk file of a snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkInfo {
+    /// The root of the subtrie stored in the chunk
+    pub root: H256,
+    /// Blake256 hash of the chunk file
+    pub hash: H256,
+    /// Size of the chunk file in bytes
+    pub size: u64,
+}
+
+/// Describes a snapshot written in a directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Manifest {
+    pub block_hash: H256,
+    pub block_number: BlockNumber,
+    pub state_root: H256,
+    pub shard_roots: Vec<(ShardId, H256)>,
+    pub chunks: Vec<ChunkInfo>,
+}
+
+impl Manifest {
+    pub fn load<P>(dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>, {
+        let mut file = File::open(dir.as_ref().join(MANIFEST_FILE_NAME))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        UntrustedRlp::new(&buf).as_val().map_err(|err| Error::InvalidManifest(format!("{}", err)))
+    }
+
+    pub fn write<P>(&self, dir: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>, {
+        let mut file = File::create(dir.as_ref().join(MANIFEST_FILE_NAME))?;
+        file.write_all(&self.rlp_bytes())?;
+        Ok(())
+    }
+}
+
+/// Returns the manifests of the snapshots in `root_dir` sorted by the block number.
+/// The directories without a manifest are ignored.
+pub fn list_snapshots<P>(root_dir: P) -> Result<Vec<Manifest>, Error>
+where
+    P: AsRef<Path>, {
+    let mut manifests = Vec::new();
+    for entry in read_dir(root_dir)? {
+        let path = entry?.path();
+        if !path.join(MANIFEST_FILE_NAME).is_file() {
+            continue
+        }
+        match Manifest::load(&path) {
+            Ok(manifest) => manifests.push(manifest),
+            Err(err) => cwarn!(SNAPSHOT, "Cannot load the manifest in {}: {}", path.display(), err),
+        }
+    }
+    manifests.sort_unstable_by_key(|manifest| manifest.block_number);
+    Ok(manifests)
+}
+
+/// Removes all snapshots in `root_dir` except the latest `keep` ones, and returns the removed ones.
+pub fn prune_snapshots<P>(root_dir: P, keep: usize) -> Result<Vec<Manifest>, Error>
+where
+    P: AsRef<Path>, {
+    let mut manifests = list_snapshots(&root_dir)?;
+    let count = manifests.len().saturating_sub(keep);
+    let removed: Vec<_> = manifests.drain(..count).collect();
+    for manifest in &removed {
+        remove_dir_all(root_dir.as_ref().join(format!("{:x}", manifest.block_hash)))?;
+        cinfo!(SNAPSHOT, "Snapshot of #{}({}) is removed", manifest.block_number, manifest.block_hash);
+    }
+    Ok(removed)
+}
+
+impl Encodable for ChunkInfo {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3).append(&self.root).append(&self.hash).append(&self.size);
+    }
+}
+
+impl Decodable for ChunkInfo {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let item_count = rlp.item_count()?;
+        if item_count != 3 {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: item_count,
+                expected: 3,
+            })
+        }
+        Ok(ChunkInfo {
+            root: rlp.val_at(0)?,
+            hash: rlp.val_at(1)?,
+            size: rlp.val_at(2)?,
+        })
+    }
+}
+
+impl Encodable for Manifest {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(5).append(&self.block_hash).append(&self.block_number).append(&self.state_root);
+        s.begin_list(self.shard_roots.len());
+        for (shard_id, root) in &self.shard_roots {
+            s.begin_list(2).append(shard_id).append(root);
+        }
+        s.append_list(&self.chunks);
+    }
+}
+
+impl Decodable for Manifest {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let item_count = rlp.item_count()?;
+        if item_count != 5 {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: item_count,
+                expected: 5,
+            })
+        }
+        let shard_roots = rlp
+            .at(3)?
+            .iter()
+            .map(|pair| Ok((pair.val_at(0)?, pair.val_at(1)?)))
+            .collect::<Result<Vec<_>, DecoderError>>()?;
+        Ok(Manifest {
+            block_hash: rlp.val_at(0)?,
+            block_number: rlp.val_at(1)?,
+            state_root: rlp.val_at(2)?,
+            shard_roots,
+            chunks: rlp.list_at(4)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir, read_dir};
+
+    use primitives::H256;
+    use rlp::rlp_encode_and_decode_test;
+    use tempfile::tempdir;
+
+    use super::{list_snapshots, prune_snapshots, ChunkInfo, Manifest};
+
+    fn manifest() -> Manifest {
+        Manifest {
+            block_hash: H256::random(),
+            block_number: 16384,
+            state_root: H256::random(),
+            shard_roots: vec![(0, H256::random()), (1, H256::random())],
+            chunks: vec![
+                ChunkInfo {
+                    root: H256::random(),
+                    hash: H256::random(),
+                    size: 1024,
+                },
+                ChunkInfo {
+                    root: H256::random(),
+                    hash: H256::random(),
+                    size: 0,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn encode_and_decode_manifest() {
+        rlp_encode_and_decode_test!(manifest());
+    }
+
+    #[test]
+    fn write_and_load_manifest() {
+        let dir = tempdir().unwrap();
+        let manifest = manifest();
+        manifest.write(&dir).unwrap();
+        assert_eq!(manifest, Manifest::load(&dir).unwrap());
+    }
+
+    #[test]
+    fn prune_old_snapshots() {
+        let root_dir = tempdir().unwrap();
+        let mut manifests = Vec::new();
+        for block_number in &[32768, 16384, 49152] {
+            let mut manifest = manifest();
+            manifest.block_number = *block_number;
+            let dir = root_dir.path().join(format!("{:x}", manifest.block_hash));
+            create_dir(&dir).unwrap();
+            manifest.write(&dir).unwrap();
+            manifests.push(manifest);
+        }
+        // A snapshot being written doesn't have a manifest yet.
+        create_dir(root_dir.path().join(format!("{:x}", H256::random()))).unwrap();
+        manifests.sort_unstable_by_key(|manifest| manifest.block_number);
+
+        assert_eq!(manifests, list_snapshots(&root_dir).unwrap());
+        assert_eq!(manifests[..1].to_vec(), prune_snapshots(&root_dir, 2).unwrap());
+        assert_eq!(manifests[1..].to_vec(), list_snapshots(&root_dir).unwrap());
+        assert_eq!(3, read_dir(&root_dir).unwrap().count());
+    }
+}
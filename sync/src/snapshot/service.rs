@@ -19,11 +19,13 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread::spawn;
 
-use ccore::{BlockChainClient, BlockId, BlockInfo, ChainInfo, ChainNotify, Client, DatabaseClient};
-
+use ccore::{BlockChainClient, BlockId, BlockInfo, ChainInfo, ChainNotify, Client, DatabaseClient, Shard};
+use ctypes::{BlockNumber, ShardId};
+use kvdb::KeyValueDB;
 use primitives::H256;
 
 use super::error::Error;
+use super::manifest::{prune_snapshots, Manifest};
 use super::snapshot::{Snapshot, WriteSnapshot};
 
 pub struct Service {
@@ -32,15 +34,18 @@ pub struct Service {
     root_dir: String,
     /// Snapshot creation period in unit of block numbers
     period: u64,
+    /// The number of the latest snapshots to keep. Every snapshot is kept if it is `None`.
+    keep: Option<usize>,
 }
 
 impl Service {
     #![cfg_attr(feature = "cargo-clippy", allow(clippy::new_ret_no_self))]
-    pub fn new(client: Arc<Client>, root_dir: String, period: u64) -> Arc<Self> {
+    pub fn new(client: Arc<Client>, root_dir: String, period: u64, keep: Option<usize>) -> Arc<Self> {
         Arc::new(Self {
             client,
             root_dir,
             period,
+            keep,
         })
     }
 }
@@ -64,15 +69,64 @@ impl ChainNotify for Service {
         if is_checkpoint && best_number > self.period {
             let number = (best_number / self.period - 1) * self.period;
             let header = self.client.block_header(&BlockId::Number(number)).expect("Snapshot target must exist");
+            let number_of_shards =
+                self.client.number_of_shards(BlockId::Number(number).into()).expect("Snapshot target must exist");
+            let shard_roots: Vec<_> = (0..number_of_shards)
+                .map(|shard_id| {
+                    let shard_root = self
+                        .client
+                        .shard_root(shard_id, BlockId::Number(number).into())
+                        .expect("Every shard must have its root");
+                    (shard_id, shard_root)
+                })
+                .collect();
 
             let db = self.client.database();
-            let path: PathBuf = [self.root_dir.clone(), format!("{:x}", header.hash())].iter().collect();
+            let root_dir = self.root_dir.clone();
+            let keep = self.keep;
+            let block_hash = header.hash();
             let root = header.state_root();
-            spawn(move || match Snapshot::try_new(path).map(|s| s.write_snapshot(db.as_ref(), &root)) {
-                Ok(_) => {}
-                Err(Error::FileError(ErrorKind::AlreadyExists)) => {}
-                Err(e) => cerror!(SNAPSHOT, "{}", e),
+            spawn(move || {
+                match write_snapshot(db.as_ref(), &root_dir, block_hash, number, root, shard_roots) {
+                    Ok(_) => {}
+                    Err(Error::FileError(ErrorKind::AlreadyExists)) => {}
+                    Err(e) => cerror!(SNAPSHOT, "{}", e),
+                }
+                if let Some(keep) = keep {
+                    if let Err(e) = prune_snapshots(&root_dir, keep) {
+                        cerror!(SNAPSHOT, "Cannot prune the old snapshots: {}", e);
+                    }
+                }
             });
         }
     }
 }
+
+/// Writes the chunks of the top-level state and the shard states, and then the manifest.
+/// The manifest is written last, so a directory without it is an incomplete snapshot.
+fn write_snapshot(
+    db: &KeyValueDB,
+    root_dir: &str,
+    block_hash: H256,
+    block_number: BlockNumber,
+    state_root: H256,
+    shard_roots: Vec<(ShardId, H256)>,
+) -> Result<(), Error> {
+    let path: PathBuf = [root_dir.to_string(), format!("{:x}", block_hash)].iter().collect();
+    let snapshot = Snapshot::try_new(path)?;
+    snapshot.write_snapshot(db, &state_root)?;
+    for (_, shard_root) in &shard_roots {
+        snapshot.write_snapshot(db, shard_root)?;
+    }
+
+    let manifest = Manifest {
+        block_hash,
+        block_number,
+        state_root,
+        shard_roots,
+        chunks: snapshot.chunks()?,
+    };
+    manifest.write(snapshot.path())?;
+    cinfo!(SNAPSHOT, "Snapshot of #{}({}) is written", block_number, block_hash);
+    Ok(())
+}
@@ -16,21 +16,25 @@
 
 use std::collections::HashSet;
 use std::convert::AsRef;
-use std::fs::{create_dir_all, File};
-use std::io::{Read, Write};
+use std::fs::{create_dir_all, read_dir, remove_file, File};
+use std::io::{ErrorKind, Read, Write};
 use std::iter::once;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 
-use ccore::COL_STATE;
+use ccore::{COL_STATE, NUM_COLUMNS};
+use ccrypto::blake256;
 use cmerkle::Node;
 use journaldb::{self, Algorithm, JournalDB};
 use kvdb::KeyValueDB;
+use kvdb_memorydb;
 use primitives::H256;
 use rlp::{Rlp, RlpStream};
 use snap;
 
 use super::error::Error;
+use super::manifest::{ChunkInfo, Manifest, MANIFEST_FILE_NAME};
 
 pub struct Snapshot {
     path: PathBuf,
@@ -45,9 +49,129 @@ impl Snapshot {
             path: path.as_ref().to_owned(),
         })
     }
+
+    /// Opens the snapshot already written in `path`.
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>, {
+        if !path.as_ref().is_dir() {
+            return Err(Error::FileError(ErrorKind::NotFound))
+        }
+        Ok(Snapshot {
+            path: path.as_ref().to_owned(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the chunk files in the snapshot directory.
+    pub fn chunks(&self) -> Result<Vec<ChunkInfo>, Error> {
+        let mut chunks = Vec::new();
+        for entry in read_dir(&self.path)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let root = match file_name.to_str() {
+                Some(MANIFEST_FILE_NAME) | None => continue,
+                Some(name) => match H256::from_str(name) {
+                    Ok(root) => root,
+                    Err(_) => continue,
+                },
+            };
+            let bytes = self.read_raw_chunk(&root)?;
+            chunks.push(ChunkInfo {
+                root,
+                hash: blake256(&bytes),
+                size: bytes.len() as u64,
+            });
+        }
+        chunks.sort_unstable_by_key(|chunk| chunk.root);
+        Ok(chunks)
+    }
+
+    /// Checks that the chunk files match the manifest and that they hold complete tries.
+    pub fn verify(&self, manifest: &Manifest) -> Result<(), Error> {
+        for chunk in &manifest.chunks {
+            let bytes = self.read_raw_chunk(&chunk.root).map_err(|err| match err {
+                Error::FileError(ErrorKind::NotFound) => {
+                    Error::InvalidSnapshot(format!("Chunk {} is missing", chunk.root))
+                }
+                err => err,
+            })?;
+            if bytes.len() as u64 != chunk.size || blake256(&bytes) != chunk.hash {
+                return Err(Error::InvalidSnapshot(format!("Chunk {} is corrupted", chunk.root)))
+            }
+        }
+
+        // The chunks are read on an empty database to load only one chunk at a time.
+        let backing: Arc<KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS.unwrap_or(0)));
+        let roots = once(&manifest.state_root).chain(manifest.shard_roots.iter().map(|(_, root)| root));
+        for root in roots {
+            let mut head = self.read_chunk(backing.clone(), root)?;
+            head.purge();
+            if !head.journal.contains(root) || head.is_deeper_than(root, 2) {
+                return Err(Error::InvalidSnapshot(format!("Head chunk of {} has an invalid shape", root)))
+            }
+            for chunk_root in head.missing_keys(root) {
+                let mut chunk = self.read_chunk(backing.clone(), &chunk_root)?;
+                chunk.purge();
+                if !chunk.journal.contains(&chunk_root) || !chunk.missing_keys(&chunk_root).is_empty() {
+                    return Err(Error::InvalidSnapshot(format!("Chunk {} is an incomplete trie", chunk_root)))
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Snapshot {
+    /// Returns the compressed chunk whose root is `root` as it is stored.
+    pub fn read_raw_chunk(&self, root: &H256) -> Result<Vec<u8>, Error> {
+        let mut file = File::open(self.file_for(root))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Stores a compressed head received from a peer after checking that it is the top of a trie rooted at `root`.
+    /// Returns the roots of the subtries that are not included in the head.
+    pub fn write_raw_head(&self, backing: Arc<KeyValueDB>, root: &H256, bytes: &[u8]) -> Result<Vec<H256>, Error> {
+        self.write_raw(backing, root, bytes, |chunk| {
+            if chunk.is_deeper_than(root, 2) {
+                return Err(Error::SyncError("Head has an invalid shape".to_string()))
+            }
+            Ok(chunk.missing_keys(root))
+        })
+    }
+
+    /// Stores a compressed chunk received from a peer after checking that it is a complete trie rooted at `root`.
+    pub fn write_raw_chunk(&self, backing: Arc<KeyValueDB>, root: &H256, bytes: &[u8]) -> Result<(), Error> {
+        self.write_raw(backing, root, bytes, |chunk| {
+            if !chunk.missing_keys(root).is_empty() {
+                return Err(Error::SyncError("Chunk doesn't cover the whole trie of its root".to_string()))
+            }
+            Ok(())
+        })
+    }
+
+    fn write_raw<F, T>(&self, backing: Arc<KeyValueDB>, root: &H256, bytes: &[u8], check: F) -> Result<T, Error>
+    where
+        F: FnOnce(&Chunk) -> Result<T, Error>, {
+        File::create(self.file_for(root))?.write_all(bytes)?;
+        let result = self.read_chunk(backing, root).and_then(|mut chunk| {
+            if !chunk.journal.contains(root) {
+                return Err(Error::SyncError("Chunk doesn't contain its root".to_string()))
+            }
+            chunk.purge();
+            check(&chunk)
+        });
+        if result.is_err() {
+            remove_file(self.file_for(root))?;
+        }
+        result
+    }
+
     fn file_for(&self, root: &H256) -> PathBuf {
         self.path.join(format!("{:x}", root))
     }
@@ -145,7 +269,7 @@ impl Chunk {
         while let Some((key, depth)) = stack.pop() {
             match self.journal.get(&key) {
                 None => continue,
-                Some(_) if depth >= max_depth => return false,
+                Some(_) if depth >= max_depth => return true,
                 Some(value) => {
                     if let Some(Node::Branch(_, childs)) = Node::decoded(&value) {
                         for child in childs.iter() {
@@ -377,6 +501,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn transfer_raw_chunks_and_restore() {
+        let mut seed = H256::new();
+        let x = StandardMap {
+            alphabet: Alphabet::Custom(b"@QWERTYUIOPASDFGHJKLZXCVBNM[/]^_".to_vec()),
+            min_key: 5,
+            journal_key: 0,
+            value_mode: ValueMode::Index,
+            count: 1000,
+        }
+        .make_with(&mut seed);
+
+        let source_dir = tempdir().unwrap();
+        let source = Snapshot::try_new(&source_dir).unwrap();
+        let mut root = H256::new();
+        {
+            let kvdb = Arc::new(kvdb_memorydb::create(1));
+            let mut jdb = journaldb::new(kvdb.clone(), Algorithm::Archive, COL_STATE);
+            {
+                let mut t = TrieDBMut::new(jdb.as_hashdb_mut(), &mut root);
+                for &(ref key, ref value) in &x {
+                    t.insert(key, value).unwrap();
+                }
+            }
+            let mut batch = jdb.backing().transaction();
+            let _ = jdb.inject(&mut batch).unwrap();
+            jdb.backing().write(batch).unwrap();
+
+            source.write_snapshot(kvdb.as_ref(), &root).unwrap();
+        }
+
+        let target_dir = tempdir().unwrap();
+        let target = Snapshot::try_new(&target_dir).unwrap();
+        let kvdb = Arc::new(kvdb_memorydb::create(1));
+        let head = source.read_raw_chunk(&root).unwrap();
+        assert!(target.write_raw_chunk(kvdb.clone(), &root, &head).is_err(), "The head is an incomplete trie");
+        let chunk_roots = target.write_raw_head(kvdb.clone(), &root, &head).unwrap();
+        assert!(!chunk_roots.is_empty());
+        for chunk_root in &chunk_roots {
+            let chunk = source.read_raw_chunk(chunk_root).unwrap();
+            target.write_raw_chunk(kvdb.clone(), chunk_root, &chunk).unwrap();
+        }
+        target.read_snapshot(kvdb.clone(), &root).unwrap();
+
+        let mut jdb = journaldb::new(kvdb.clone(), Algorithm::Archive, COL_STATE);
+        let t = TrieDB::try_new(jdb.as_hashdb_mut(), &root).unwrap();
+        for &(ref key, ref value) in &x {
+            assert_eq!(t.get(key).unwrap(), Some(DBValue::from_slice(value)));
+        }
+    }
+
+    #[test]
+    fn reject_raw_chunk_of_another_root() {
+        let mut root = H256::new();
+        let kvdb = Arc::new(kvdb_memorydb::create(1));
+        let mut jdb = journaldb::new(kvdb.clone(), Algorithm::Archive, COL_STATE);
+        {
+            let mut t = TrieDBMut::new(jdb.as_hashdb_mut(), &mut root);
+            t.insert(b"key", b"value").unwrap();
+        }
+        let mut batch = jdb.backing().transaction();
+        let _ = jdb.inject(&mut batch).unwrap();
+        jdb.backing().write(batch).unwrap();
+
+        let source_dir = tempdir().unwrap();
+        let source = Snapshot::try_new(&source_dir).unwrap();
+        source.write_snapshot(kvdb.as_ref(), &root).unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let target = Snapshot::try_new(&target_dir).unwrap();
+        let another_root = H256::random();
+        let chunk = source.read_raw_chunk(&root).unwrap();
+        assert!(target.write_raw_chunk(Arc::new(kvdb_memorydb::create(1)), &another_root, &chunk).is_err());
+        assert!(target.read_raw_chunk(&another_root).is_err());
+    }
+
     #[test]
     fn random_insert_and_restore_1() {
         random_insert_and_restore_with_count(1);
@@ -18,7 +18,7 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 
 use ccore::{BlockChainClient, UnverifiedTransaction};
-use cnetwork::{Api, NetworkExtension, NodeId};
+use cnetwork::{Api, Misbehavior, NetworkExtension, NetworkExtensionPriority, NodeId};
 use ctimer::TimerToken;
 use never_type::Never;
 use primitives::H256;
@@ -85,6 +85,10 @@ impl NetworkExtension<Never> for Extension {
         &VERSIONS
     }
 
+    fn priority() -> NetworkExtensionPriority {
+        NetworkExtensionPriority::Low
+    }
+
     fn on_node_added(&mut self, token: &NodeId, _version: u64) {
         self.peers.insert(*token, KnownTxs::default());
     }
@@ -133,6 +137,7 @@ impl NetworkExtension<Never> for Extension {
             }
         } else {
             cwarn!(SYNC_TX, "Invalid message from peer {}", token);
+            self.api.report(token, Misbehavior::MalformedMessage);
         }
     }
 
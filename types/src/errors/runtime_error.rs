@@ -115,6 +115,24 @@ pub enum Error {
         address: Address,
         name: String,
     },
+    /// The transaction of a multisig account is not signed by enough publics
+    NotEnoughSignatures {
+        address: Address,
+        threshold: usize,
+        got: usize,
+    },
+    /// The regular key tried to spend more than its spending limit allows in the current period
+    SpendingLimitExceeded {
+        address: Address,
+        limit: u64,
+        spent: u64,
+        cost: u64,
+    },
+    /// The receiver of a vesting already has as many locked vestings as an account can hold
+    TooManyVestings {
+        address: Address,
+        max: usize,
+    },
 }
 
 const ERROR_ID_ASSET_NOT_FOUND: u8 = 1;
@@ -147,6 +165,9 @@ const ERROR_ID_INVALID_SEQ: u8 = 28;
 const ERROR_ID_ASSET_SUPPLY_OVERFLOW: u8 = 29;
 const ERROR_ID_NON_ACTIVE_ACCOUNT: u8 = 30;
 const ERROR_ID_FAILED_TO_HANDLE_CUSTOM_ACTION: u8 = 31;
+const ERROR_ID_NOT_ENOUGH_SIGNATURES: u8 = 32;
+const ERROR_ID_SPENDING_LIMIT_EXCEEDED: u8 = 33;
+const ERROR_ID_TOO_MANY_VESTINGS: u8 = 34;
 
 struct RlpHelper;
 impl TaggedRlp for RlpHelper {
@@ -184,6 +205,9 @@ impl TaggedRlp for RlpHelper {
             ERROR_ID_TEXT_VERIFICATION_FAIL => 2,
             ERROR_ID_CANNOT_USE_MASTER_KEY => 1,
             ERROR_ID_NON_ACTIVE_ACCOUNT => 3,
+            ERROR_ID_NOT_ENOUGH_SIGNATURES => 4,
+            ERROR_ID_SPENDING_LIMIT_EXCEEDED => 5,
+            ERROR_ID_TOO_MANY_VESTINGS => 3,
             _ => return Err(DecoderError::Custom("Invalid RuntimeError")),
         })
     }
@@ -304,6 +328,28 @@ impl Encodable for Error {
                 address,
                 name,
             } => RlpHelper::new_tagged_list(s, ERROR_ID_NON_ACTIVE_ACCOUNT).append(address).append(name),
+            Error::NotEnoughSignatures {
+                address,
+                threshold,
+                got,
+            } => RlpHelper::new_tagged_list(s, ERROR_ID_NOT_ENOUGH_SIGNATURES)
+                .append(address)
+                .append(threshold)
+                .append(got),
+            Error::SpendingLimitExceeded {
+                address,
+                limit,
+                spent,
+                cost,
+            } => RlpHelper::new_tagged_list(s, ERROR_ID_SPENDING_LIMIT_EXCEEDED)
+                .append(address)
+                .append(limit)
+                .append(spent)
+                .append(cost),
+            Error::TooManyVestings {
+                address,
+                max,
+            } => RlpHelper::new_tagged_list(s, ERROR_ID_TOO_MANY_VESTINGS).append(address).append(max),
         };
     }
 }
@@ -387,6 +433,21 @@ impl Decodable for Error {
                 address: rlp.val_at(1)?,
                 name: rlp.val_at(2)?,
             },
+            ERROR_ID_NOT_ENOUGH_SIGNATURES => Error::NotEnoughSignatures {
+                address: rlp.val_at(1)?,
+                threshold: rlp.val_at(2)?,
+                got: rlp.val_at(3)?,
+            },
+            ERROR_ID_SPENDING_LIMIT_EXCEEDED => Error::SpendingLimitExceeded {
+                address: rlp.val_at(1)?,
+                limit: rlp.val_at(2)?,
+                spent: rlp.val_at(3)?,
+                cost: rlp.val_at(4)?,
+            },
+            ERROR_ID_TOO_MANY_VESTINGS => Error::TooManyVestings {
+                address: rlp.val_at(1)?,
+                max: rlp.val_at(2)?,
+            },
             _ => return Err(DecoderError::Custom("Invalid RuntimeError")),
         };
         RlpHelper::check_size(rlp, tag)?;
@@ -481,6 +542,25 @@ impl Display for Error {
             } => {
                 write!(f, "Non active account({}) cannot be {}", address, name)
             }
+            Error::NotEnoughSignatures {
+                address,
+                threshold,
+                got,
+            } => write!(f, "{} requires {} signatures of its multisig publics, but got {}", address, threshold, got),
+            Error::SpendingLimitExceeded {
+                address,
+                limit,
+                spent,
+                cost,
+            } => write!(
+                f,
+                "The regular key of {} already spent {} of its limit {} in this period, so it cannot spend {}",
+                address, spent, limit, cost
+            ),
+            Error::TooManyVestings {
+                address,
+                max,
+            } => write!(f, "{} already has {} locked vestings", address, max),
         }
     }
 }
@@ -17,7 +17,7 @@
 
 use std::fmt::{Display, Formatter, Result as FormatResult};
 
-use ckey::NetworkId;
+use ckey::{NetworkId, Public};
 use primitives::{Bytes, H160, H256};
 use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
 
@@ -53,6 +53,11 @@ pub enum Error {
         got: u64,
     },
     InvalidCustomAction,
+    /// The action handler rejected the bytes of a custom action.
+    InvalidCustomActionBytes {
+        handler_id: u64,
+        hint: String,
+    },
     InvalidDecomposedInputAmount {
         asset_type: H160,
         shard_id: ShardId,
@@ -93,6 +98,16 @@ pub enum Error {
         asset_quantity_from: u64,
         spent_quantity: u64,
     },
+    /// The same public key is listed more than once in a multisig account.
+    DuplicatedMultisigPublic(Public),
+    InvalidMultisigThreshold {
+        threshold: usize,
+        number_of_publics: usize,
+    },
+    TooManyMultisigPublics(usize),
+    TooManyCoSignatures(usize),
+    /// Returned when a non-zero spending limit has no period.
+    ZeroSpendingLimitPeriod,
 }
 
 const ERORR_ID_DUPLICATED_PREVIOUS_OUTPUT: u8 = 1;
@@ -124,6 +139,12 @@ const ERROR_ID_DISABLED_TRANSACTION: u8 = 28;
 const ERROR_ID_INVALID_SIGNER_OF_WRAP_CCC: u8 = 29;
 const ERROR_ID_INVALID_CUSTOM_ACTION: u8 = 30;
 const ERROR_ID_INVALID_SPENT_QUANTITY: u8 = 31;
+const ERROR_ID_DUPLICATED_MULTISIG_PUBLIC: u8 = 32;
+const ERROR_ID_INVALID_MULTISIG_THRESHOLD: u8 = 33;
+const ERROR_ID_TOO_MANY_MULTISIG_PUBLICS: u8 = 34;
+const ERROR_ID_TOO_MANY_CO_SIGNATURES: u8 = 35;
+const ERROR_ID_ZERO_SPENDING_LIMIT_PERIOD: u8 = 36;
+const ERROR_ID_INVALID_CUSTOM_ACTION_BYTES: u8 = 37;
 
 struct RlpHelper;
 impl TaggedRlp for RlpHelper {
@@ -141,6 +162,7 @@ impl TaggedRlp for RlpHelper {
             ERROR_ID_INVALID_ASSET_TYPE => 2,
             ERROR_ID_INVALID_COMPOSED_OUTPUT_AMOUNT => 2,
             ERROR_ID_INVALID_CUSTOM_ACTION => 1,
+            ERROR_ID_INVALID_CUSTOM_ACTION_BYTES => 3,
             ERROR_ID_INVALID_DECOMPOSED_INPUT_AMOUNT => 4,
             ERROR_ID_INVALID_NETWORK_ID => 2,
             ERROR_ID_INVALID_ORDER_ASSET_QUANTITIES => 4,
@@ -160,6 +182,11 @@ impl TaggedRlp for RlpHelper {
             ERROR_ID_DISABLED_TRANSACTION => 1,
             ERROR_ID_INVALID_SIGNER_OF_WRAP_CCC => 1,
             ERROR_ID_INVALID_SPENT_QUANTITY => 3,
+            ERROR_ID_DUPLICATED_MULTISIG_PUBLIC => 2,
+            ERROR_ID_INVALID_MULTISIG_THRESHOLD => 3,
+            ERROR_ID_TOO_MANY_MULTISIG_PUBLICS => 2,
+            ERROR_ID_TOO_MANY_CO_SIGNATURES => 2,
+            ERROR_ID_ZERO_SPENDING_LIMIT_PERIOD => 1,
             _ => return Err(DecoderError::Custom("Invalid SyntaxError")),
         })
     }
@@ -192,6 +219,10 @@ impl Encodable for Error {
                 got,
             } => RlpHelper::new_tagged_list(s, ERROR_ID_INVALID_COMPOSED_OUTPUT_AMOUNT).append(got),
             Error::InvalidCustomAction => RlpHelper::new_tagged_list(s, ERROR_ID_INVALID_CUSTOM_ACTION),
+            Error::InvalidCustomActionBytes {
+                handler_id,
+                hint,
+            } => RlpHelper::new_tagged_list(s, ERROR_ID_INVALID_CUSTOM_ACTION_BYTES).append(handler_id).append(hint),
             Error::InvalidDecomposedInputAmount {
                 asset_type,
                 shard_id,
@@ -240,6 +271,20 @@ impl Encodable for Error {
             } => RlpHelper::new_tagged_list(s, ERROR_ID_INVALID_SPENT_QUANTITY)
                 .append(asset_quantity_from)
                 .append(spent_quantity),
+            Error::DuplicatedMultisigPublic(public) => {
+                RlpHelper::new_tagged_list(s, ERROR_ID_DUPLICATED_MULTISIG_PUBLIC).append(public)
+            }
+            Error::InvalidMultisigThreshold {
+                threshold,
+                number_of_publics,
+            } => RlpHelper::new_tagged_list(s, ERROR_ID_INVALID_MULTISIG_THRESHOLD)
+                .append(threshold)
+                .append(number_of_publics),
+            Error::TooManyMultisigPublics(num) => {
+                RlpHelper::new_tagged_list(s, ERROR_ID_TOO_MANY_MULTISIG_PUBLICS).append(num)
+            }
+            Error::TooManyCoSignatures(num) => RlpHelper::new_tagged_list(s, ERROR_ID_TOO_MANY_CO_SIGNATURES).append(num),
+            Error::ZeroSpendingLimitPeriod => RlpHelper::new_tagged_list(s, ERROR_ID_ZERO_SPENDING_LIMIT_PERIOD),
         };
     }
 }
@@ -266,6 +311,10 @@ impl Decodable for Error {
                 got: rlp.val_at(1)?,
             },
             ERROR_ID_INVALID_CUSTOM_ACTION => Error::InvalidCustomAction,
+            ERROR_ID_INVALID_CUSTOM_ACTION_BYTES => Error::InvalidCustomActionBytes {
+                handler_id: rlp.val_at(1)?,
+                hint: rlp.val_at(2)?,
+            },
             ERROR_ID_INVALID_DECOMPOSED_INPUT_AMOUNT => Error::InvalidDecomposedInputAmount {
                 asset_type: rlp.val_at(1)?,
                 shard_id: rlp.val_at(2)?,
@@ -296,6 +345,14 @@ impl Decodable for Error {
                 asset_quantity_from: rlp.val_at(1)?,
                 spent_quantity: rlp.val_at(2)?,
             },
+            ERROR_ID_DUPLICATED_MULTISIG_PUBLIC => Error::DuplicatedMultisigPublic(rlp.val_at(1)?),
+            ERROR_ID_INVALID_MULTISIG_THRESHOLD => Error::InvalidMultisigThreshold {
+                threshold: rlp.val_at(1)?,
+                number_of_publics: rlp.val_at(2)?,
+            },
+            ERROR_ID_TOO_MANY_MULTISIG_PUBLICS => Error::TooManyMultisigPublics(rlp.val_at(1)?),
+            ERROR_ID_TOO_MANY_CO_SIGNATURES => Error::TooManyCoSignatures(rlp.val_at(1)?),
+            ERROR_ID_ZERO_SPENDING_LIMIT_PERIOD => Error::ZeroSpendingLimitPeriod,
             _ => return Err(DecoderError::Custom("Invalid SyntaxError")),
         };
         RlpHelper::check_size(rlp, tag)?;
@@ -325,6 +382,10 @@ impl Display for Error {
                 got,
             } => write!(f, "The composed output is note valid. The supply must be 1, but {}.", got),
             Error::InvalidCustomAction => write!(f, "CustomAction handlerId is invalid"),
+            Error::InvalidCustomActionBytes {
+                handler_id,
+                hint,
+            } => write!(f, "The action handler {} rejected the custom action: {}", handler_id, hint),
             Error::InvalidDecomposedInputAmount {
                 asset_type,
                 shard_id,
@@ -357,6 +418,14 @@ impl Display for Error {
             } => {
                 write!(f, "The spentQuantity value {} in an OrderOnTransfer cannot exceed the assetQuantityFrom value {}", spent_quantity, asset_quantity_from)
             }
+            Error::DuplicatedMultisigPublic(public) => write!(f, "The public {} is duplicated in the multisig publics", public),
+            Error::InvalidMultisigThreshold {
+                threshold,
+                number_of_publics,
+            } => write!(f, "The multisig threshold {} is invalid for {} publics", threshold, number_of_publics),
+            Error::TooManyMultisigPublics(num) => write!(f, "The number of multisig publics is {}. It should be {} or less.", num, crate::transaction::MAX_NUMBER_OF_MULTISIG_PUBLICS),
+            Error::TooManyCoSignatures(num) => write!(f, "The number of co-signatures is {}. It should be {} or less.", num, crate::transaction::MAX_NUMBER_OF_MULTISIG_PUBLICS),
+            Error::ZeroSpendingLimitPeriod => write!(f, "The period of a spending limit cannot be 0"),
         }
     }
 }
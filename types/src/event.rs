@@ -0,0 +1,317 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ccrypto::blake256;
+use ckey::Address;
+use primitives::{Bytes, H160, H256, H512};
+use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
+
+use crate::ShardId;
+
+/// An effect of a transaction on the state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    BalanceIncreased {
+        address: Address,
+        quantity: u64,
+    },
+    BalanceDecreased {
+        address: Address,
+        quantity: u64,
+    },
+    AssetCreated {
+        shard_id: ShardId,
+        tracker: H256,
+        index: usize,
+        asset_type: H160,
+        quantity: u64,
+    },
+    AssetSpent {
+        shard_id: ShardId,
+        tracker: H256,
+        index: usize,
+        asset_type: H160,
+        quantity: u64,
+    },
+    /// An event emitted by a custom action handler.
+    Custom {
+        handler_id: u64,
+        topic: Bytes,
+        data: Bytes,
+    },
+}
+
+type EventType = u8;
+const BALANCE_INCREASED: EventType = 0x01;
+const BALANCE_DECREASED: EventType = 0x02;
+const ASSET_CREATED: EventType = 0x03;
+const ASSET_SPENT: EventType = 0x04;
+const CUSTOM: EventType = 0xFF;
+
+impl Event {
+    /// Returns the values that the event is looked up by.
+    pub fn topics(&self) -> Vec<Bytes> {
+        match self {
+            Event::BalanceIncreased {
+                address,
+                ..
+            }
+            | Event::BalanceDecreased {
+                address,
+                ..
+            } => vec![address.to_vec()],
+            Event::AssetCreated {
+                tracker,
+                asset_type,
+                ..
+            }
+            | Event::AssetSpent {
+                tracker,
+                asset_type,
+                ..
+            } => vec![asset_type.to_vec(), tracker.to_vec()],
+            Event::Custom {
+                handler_id,
+                topic,
+                ..
+            } => vec![handler_id.rlp_bytes().into_vec(), topic.clone()],
+        }
+    }
+}
+
+impl Encodable for Event {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            Event::BalanceIncreased {
+                address,
+                quantity,
+            } => s.begin_list(3).append(&BALANCE_INCREASED).append(address).append(quantity),
+            Event::BalanceDecreased {
+                address,
+                quantity,
+            } => s.begin_list(3).append(&BALANCE_DECREASED).append(address).append(quantity),
+            Event::AssetCreated {
+                shard_id,
+                tracker,
+                index,
+                asset_type,
+                quantity,
+            } => s
+                .begin_list(6)
+                .append(&ASSET_CREATED)
+                .append(shard_id)
+                .append(tracker)
+                .append(index)
+                .append(asset_type)
+                .append(quantity),
+            Event::AssetSpent {
+                shard_id,
+                tracker,
+                index,
+                asset_type,
+                quantity,
+            } => s
+                .begin_list(6)
+                .append(&ASSET_SPENT)
+                .append(shard_id)
+                .append(tracker)
+                .append(index)
+                .append(asset_type)
+                .append(quantity),
+            Event::Custom {
+                handler_id,
+                topic,
+                data,
+            } => s.begin_list(4).append(&CUSTOM).append(handler_id).append(topic).append(data),
+        };
+    }
+}
+
+impl Decodable for Event {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let item_count = rlp.item_count()?;
+        let (tag, expected) = match rlp.val_at(0)? {
+            BALANCE_INCREASED => (BALANCE_INCREASED, 3),
+            BALANCE_DECREASED => (BALANCE_DECREASED, 3),
+            ASSET_CREATED => (ASSET_CREATED, 6),
+            ASSET_SPENT => (ASSET_SPENT, 6),
+            CUSTOM => (CUSTOM, 4),
+            _ => return Err(DecoderError::Custom("Unexpected event type")),
+        };
+        if item_count != expected {
+            return Err(DecoderError::RlpIncorrectListLen {
+                got: item_count,
+                expected,
+            })
+        }
+        Ok(match tag {
+            BALANCE_INCREASED => Event::BalanceIncreased {
+                address: rlp.val_at(1)?,
+                quantity: rlp.val_at(2)?,
+            },
+            BALANCE_DECREASED => Event::BalanceDecreased {
+                address: rlp.val_at(1)?,
+                quantity: rlp.val_at(2)?,
+            },
+            ASSET_CREATED => Event::AssetCreated {
+                shard_id: rlp.val_at(1)?,
+                tracker: rlp.val_at(2)?,
+                index: rlp.val_at(3)?,
+                asset_type: rlp.val_at(4)?,
+                quantity: rlp.val_at(5)?,
+            },
+            ASSET_SPENT => Event::AssetSpent {
+                shard_id: rlp.val_at(1)?,
+                tracker: rlp.val_at(2)?,
+                index: rlp.val_at(3)?,
+                asset_type: rlp.val_at(4)?,
+                quantity: rlp.val_at(5)?,
+            },
+            CUSTOM => Event::Custom {
+                handler_id: rlp.val_at(1)?,
+                topic: rlp.val_at(2)?,
+                data: rlp.val_at(3)?,
+            },
+            _ => unreachable!(),
+        })
+    }
+}
+
+/// A bloom filter of the topics of events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogBloom(H512);
+
+/// The number of bits set by a topic.
+const BITS_PER_TOPIC: usize = 3;
+
+impl LogBloom {
+    pub fn from_events<'a, I: IntoIterator<Item = &'a Event>>(events: I) -> Self {
+        let mut bloom = Self::default();
+        for event in events {
+            bloom.accrue_event(event);
+        }
+        bloom
+    }
+
+    pub fn accrue_event(&mut self, event: &Event) {
+        for topic in event.topics() {
+            self.accrue(&topic);
+        }
+    }
+
+    pub fn accrue(&mut self, topic: &[u8]) {
+        for (byte, mask) in Self::bits(topic) {
+            self.0[byte] |= mask;
+        }
+    }
+
+    /// Returns false if the topic is definitely not in the bloom.
+    pub fn contains(&self, topic: &[u8]) -> bool {
+        Self::bits(topic).all(|(byte, mask)| self.0[byte] & mask == mask)
+    }
+
+    pub fn accrue_bloom(&mut self, other: &LogBloom) {
+        for (byte, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *byte |= other;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    fn bits(topic: &[u8]) -> impl Iterator<Item = (usize, u8)> {
+        const BYTES: usize = 64;
+        let hash = blake256(topic);
+        (0..BITS_PER_TOPIC).map(move |i| {
+            let bit = ((usize::from(hash[i * 2]) << 8) | usize::from(hash[i * 2 + 1])) % (BYTES * 8);
+            (BYTES - 1 - bit / 8, 1u8 << (bit % 8))
+        })
+    }
+}
+
+impl Encodable for LogBloom {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        self.0.rlp_append(s);
+    }
+}
+
+impl Decodable for LogBloom {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        Ok(LogBloom(rlp.as_val()?))
+    }
+}
+
+impl From<H512> for LogBloom {
+    fn from(h: H512) -> Self {
+        LogBloom(h)
+    }
+}
+
+impl From<LogBloom> for H512 {
+    fn from(bloom: LogBloom) -> Self {
+        bloom.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlp::rlp_encode_and_decode_test;
+
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_balance_increased() {
+        rlp_encode_and_decode_test!(Event::BalanceIncreased {
+            address: Address::random(),
+            quantity: 100,
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_asset_spent() {
+        rlp_encode_and_decode_test!(Event::AssetSpent {
+            shard_id: 3,
+            tracker: H256::random(),
+            index: 2,
+            asset_type: H160::random(),
+            quantity: 1_000,
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_custom() {
+        rlp_encode_and_decode_test!(Event::Custom {
+            handler_id: 2,
+            topic: b"TransferCCS".to_vec(),
+            data: vec![1, 2, 3],
+        });
+    }
+
+    #[test]
+    fn bloom_contains_the_topics_of_accrued_events() {
+        let address = Address::random();
+        let event = Event::BalanceDecreased {
+            address,
+            quantity: 10,
+        };
+        let bloom = LogBloom::from_events(&[event]);
+        assert!(bloom.contains(&address));
+        assert!(!bloom.is_empty());
+        assert!(LogBloom::default().is_empty());
+        assert!(!LogBloom::default().contains(&address));
+    }
+}
@@ -25,6 +25,7 @@ extern crate serde;
 extern crate serde_derive;
 
 pub mod errors;
+pub mod event;
 pub mod machine;
 pub mod transaction;
 pub mod util;
@@ -23,7 +23,7 @@ use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
 
 use crate::errors::SyntaxError;
 use crate::transaction::{AssetMintOutput, AssetTransferInput, AssetTransferOutput, OrderOnTransfer, ShardTransaction};
-use crate::ShardId;
+use crate::{BlockNumber, ShardId};
 
 const PAY: u8 = 0x02;
 const SET_REGULAR_KEY: u8 = 0x03;
@@ -33,6 +33,9 @@ const SET_SHARD_USERS: u8 = 0x06;
 const WRAP_CCC: u8 = 0x07;
 const STORE: u8 = 0x08;
 const REMOVE: u8 = 0x09;
+const SET_MULTISIG: u8 = 0x0A;
+const GRANT_VESTING: u8 = 0x0B;
+const SET_SPENDING_LIMIT: u8 = 0x0C;
 const UNWRAP_CCC: u8 = 0x11;
 const MINT_ASSET: u8 = 0x13;
 const TRANSFER_ASSET: u8 = 0x14;
@@ -43,6 +46,13 @@ const INCREASE_ASSET_SUPPLY: u8 = 0x18;
 
 const CUSTOM: u8 = 0xFF;
 
+/// The maximum number of publics that can control a multisig platform account.
+pub const MAX_NUMBER_OF_MULTISIG_PUBLICS: usize = 16;
+
+/// The maximum number of locked vestings of a platform account.
+/// The vestings with the same schedule are merged, so they count as one.
+pub const MAX_NUMBER_OF_VESTINGS: usize = 16;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Action {
     MintAsset {
@@ -114,6 +124,25 @@ pub enum Action {
     SetRegularKey {
         key: Public,
     },
+    /// Converts the sender's account into an M-of-N multisig account.
+    /// An empty `publics` with zero `threshold` converts it back into a single key account.
+    SetMultisig {
+        publics: Vec<Public>,
+        threshold: u8,
+    },
+    /// Pays `quantity` to `receiver`, locked until both `unlock_block_number` and `unlock_timestamp` are reached.
+    GrantVesting {
+        receiver: Address,
+        quantity: u64,
+        unlock_block_number: BlockNumber,
+        unlock_timestamp: u64,
+    },
+    /// Limits the quantity that the regular key of the sender can spend in every `period` seconds.
+    /// Zero `limit` with zero `period` removes the limit.
+    SetSpendingLimit {
+        limit: u64,
+        period: u64,
+    },
     CreateShard {
         users: Vec<Address>,
     },
@@ -343,6 +372,44 @@ impl Action {
                     return Err(SyntaxError::TextContentTooBig)
                 }
             }
+            Action::SetMultisig {
+                publics,
+                threshold,
+            } => {
+                let number_of_publics = publics.len();
+                if number_of_publics > MAX_NUMBER_OF_MULTISIG_PUBLICS {
+                    return Err(SyntaxError::TooManyMultisigPublics(number_of_publics))
+                }
+                let threshold = usize::from(*threshold);
+                if threshold > number_of_publics || (threshold == 0 && number_of_publics != 0) {
+                    return Err(SyntaxError::InvalidMultisigThreshold {
+                        threshold,
+                        number_of_publics,
+                    })
+                }
+                let mut public_set = HashSet::new();
+                for public in publics {
+                    if !public_set.insert(public) {
+                        return Err(SyntaxError::DuplicatedMultisigPublic(*public))
+                    }
+                }
+            }
+            Action::GrantVesting {
+                quantity,
+                ..
+            } => {
+                if *quantity == 0 {
+                    return Err(SyntaxError::ZeroQuantity)
+                }
+            }
+            Action::SetSpendingLimit {
+                limit,
+                period,
+            } => {
+                if *period == 0 && *limit != 0 {
+                    return Err(SyntaxError::ZeroSpendingLimitPeriod)
+                }
+            }
             _ => {}
         }
         Ok(())
@@ -696,6 +763,37 @@ impl Encodable for Action {
                 s.append(&SET_REGULAR_KEY);
                 s.append(key);
             }
+            Action::SetMultisig {
+                publics,
+                threshold,
+            } => {
+                s.begin_list(3);
+                s.append(&SET_MULTISIG);
+                s.append_list(publics);
+                s.append(threshold);
+            }
+            Action::GrantVesting {
+                receiver,
+                quantity,
+                unlock_block_number,
+                unlock_timestamp,
+            } => {
+                s.begin_list(5);
+                s.append(&GRANT_VESTING);
+                s.append(receiver);
+                s.append(quantity);
+                s.append(unlock_block_number);
+                s.append(unlock_timestamp);
+            }
+            Action::SetSpendingLimit {
+                limit,
+                period,
+            } => {
+                s.begin_list(3);
+                s.append(&SET_SPENDING_LIMIT);
+                s.append(limit);
+                s.append(period);
+            }
             Action::CreateShard {
                 users,
             } => {
@@ -933,6 +1031,47 @@ impl Decodable for Action {
                     key: rlp.val_at(1)?,
                 })
             }
+            SET_MULTISIG => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 3,
+                    })
+                }
+                Ok(Action::SetMultisig {
+                    publics: rlp.list_at(1)?,
+                    threshold: rlp.val_at(2)?,
+                })
+            }
+            GRANT_VESTING => {
+                let item_count = rlp.item_count()?;
+                if item_count != 5 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 5,
+                    })
+                }
+                Ok(Action::GrantVesting {
+                    receiver: rlp.val_at(1)?,
+                    quantity: rlp.val_at(2)?,
+                    unlock_block_number: rlp.val_at(3)?,
+                    unlock_timestamp: rlp.val_at(4)?,
+                })
+            }
+            SET_SPENDING_LIMIT => {
+                let item_count = rlp.item_count()?;
+                if item_count != 3 {
+                    return Err(DecoderError::RlpIncorrectListLen {
+                        got: item_count,
+                        expected: 3,
+                    })
+                }
+                Ok(Action::SetSpendingLimit {
+                    limit: rlp.val_at(1)?,
+                    period: rlp.val_at(2)?,
+                })
+            }
             CREATE_SHARD => {
                 let item_count = rlp.item_count()?;
                 if item_count != 2 {
@@ -1309,6 +1448,32 @@ mod tests {
         });
     }
 
+    #[test]
+    fn encode_and_decode_set_multisig() {
+        rlp_encode_and_decode_test!(Action::SetMultisig {
+            publics: vec![Public::random(), Public::random(), Public::random()],
+            threshold: 2,
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_grant_vesting() {
+        rlp_encode_and_decode_test!(Action::GrantVesting {
+            receiver: Address::random(),
+            quantity: 300,
+            unlock_block_number: 100,
+            unlock_timestamp: 1_600_000_000,
+        });
+    }
+
+    #[test]
+    fn encode_and_decode_set_spending_limit() {
+        rlp_encode_and_decode_test!(Action::SetSpendingLimit {
+            limit: 1000,
+            period: 86400,
+        });
+    }
+
     #[test]
     fn encode_and_decode_set_shard_owners() {
         rlp_encode_and_decode_test!(Action::SetShardOwners {
@@ -2123,4 +2288,77 @@ mod tests {
         };
         assert_eq!(tx_zero_quantity.verify(NetworkId::default(), 1000, 1000, 1000), Err(SyntaxError::ZeroQuantity));
     }
+
+    #[test]
+    fn verify_set_multisig() {
+        let publics = vec![Public::random(), Public::random(), Public::random()];
+        let valid = Action::SetMultisig {
+            publics: publics.clone(),
+            threshold: 2,
+        };
+        assert_eq!(valid.verify(NetworkId::default(), 1000, 1000, 1000), Ok(()));
+
+        let reset = Action::SetMultisig {
+            publics: vec![],
+            threshold: 0,
+        };
+        assert_eq!(reset.verify(NetworkId::default(), 1000, 1000, 1000), Ok(()));
+
+        let too_high_threshold = Action::SetMultisig {
+            publics: publics.clone(),
+            threshold: 4,
+        };
+        assert_eq!(
+            too_high_threshold.verify(NetworkId::default(), 1000, 1000, 1000),
+            Err(SyntaxError::InvalidMultisigThreshold {
+                threshold: 4,
+                number_of_publics: 3,
+            })
+        );
+
+        let zero_threshold = Action::SetMultisig {
+            publics: publics.clone(),
+            threshold: 0,
+        };
+        assert_eq!(
+            zero_threshold.verify(NetworkId::default(), 1000, 1000, 1000),
+            Err(SyntaxError::InvalidMultisigThreshold {
+                threshold: 0,
+                number_of_publics: 3,
+            })
+        );
+
+        let duplicated = Action::SetMultisig {
+            publics: vec![publics[0], publics[1], publics[0]],
+            threshold: 2,
+        };
+        assert_eq!(
+            duplicated.verify(NetworkId::default(), 1000, 1000, 1000),
+            Err(SyntaxError::DuplicatedMultisigPublic(publics[0]))
+        );
+    }
+
+    #[test]
+    fn verify_set_spending_limit() {
+        let valid = Action::SetSpendingLimit {
+            limit: 1000,
+            period: 86400,
+        };
+        assert_eq!(valid.verify(NetworkId::default(), 1000, 1000, 1000), Ok(()));
+
+        let remove = Action::SetSpendingLimit {
+            limit: 0,
+            period: 0,
+        };
+        assert_eq!(remove.verify(NetworkId::default(), 1000, 1000, 1000), Ok(()));
+
+        let zero_period = Action::SetSpendingLimit {
+            limit: 1000,
+            period: 0,
+        };
+        assert_eq!(
+            zero_period.verify(NetworkId::default(), 1000, 1000, 1000),
+            Err(SyntaxError::ZeroSpendingLimitPeriod)
+        );
+    }
 }
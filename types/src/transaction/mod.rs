@@ -26,7 +26,7 @@ mod timelock;
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::module_inception))]
 mod transaction;
 
-pub use self::action::Action;
+pub use self::action::{Action, MAX_NUMBER_OF_MULTISIG_PUBLICS, MAX_NUMBER_OF_VESTINGS};
 pub use self::asset_out_point::AssetOutPoint;
 pub use self::incomplete_transaction::IncompleteTransaction;
 pub use self::input::AssetTransferInput;
@@ -59,6 +59,12 @@ impl Transaction {
         match self.action {
             Action::SetRegularKey {
                 ..
+            }
+            | Action::SetMultisig {
+                ..
+            }
+            | Action::SetSpendingLimit {
+                ..
             } => true,
             _ => false,
         }
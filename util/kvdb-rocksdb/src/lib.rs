@@ -215,6 +215,13 @@ impl<'a> Iterator for DatabaseIterator<'a> {
     }
 }
 
+/// The number of entries in a column and the total length of their keys and values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColumnSize {
+    pub keys: usize,
+    pub bytes: usize,
+}
+
 struct DBAndColumns {
     db: DB,
     cfs: Vec<Column>,
@@ -711,6 +718,17 @@ impl Database {
         Ok(())
     }
 
+    /// Get the size of a column by iterating over it.
+    /// The size is the length of the uncompressed data, not the disk usage.
+    pub fn column_size(&self, col: Option<u32>) -> Option<ColumnSize> {
+        self.iter(col).map(|iter| {
+            iter.fold(ColumnSize::default(), |size, (key, value)| ColumnSize {
+                keys: size.keys + 1,
+                bytes: size.bytes + key.len() + value.len(),
+            })
+        })
+    }
+
     /// The number of non-default column families.
     pub fn num_columns(&self) -> u32 {
         self.db
@@ -879,6 +897,34 @@ mod tests {
         test_db(&DatabaseConfig::default());
     }
 
+    #[test]
+    fn column_size() {
+        let tempdir = TempDir::new("").unwrap();
+        let db = Database::open(&DatabaseConfig::with_columns(Some(2)), tempdir.path().to_str().unwrap()).unwrap();
+        assert_eq!(Some(ColumnSize::default()), db.column_size(Some(0)));
+
+        let mut batch = db.transaction();
+        batch.put(Some(0), b"cat", b"meow");
+        batch.put(Some(0), b"dog", b"bark");
+        batch.put(Some(1), b"cow", b"moo");
+        db.write(batch).unwrap();
+
+        assert_eq!(
+            Some(ColumnSize {
+                keys: 2,
+                bytes: 14,
+            }),
+            db.column_size(Some(0))
+        );
+        assert_eq!(
+            Some(ColumnSize {
+                keys: 1,
+                bytes: 6,
+            }),
+            db.column_size(Some(1))
+        );
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn df_to_rotational() {
@@ -33,15 +33,19 @@ use primitives::H256;
 
 mod nibbleslice;
 pub mod node;
+mod proof;
 mod skewed;
 pub mod triedb;
 pub mod triedbmut;
+pub mod triediff;
 pub mod triehash;
 
 pub use crate::node::Node;
+pub use crate::proof::{verify_proof, ProofError};
 pub use crate::skewed::skewed_merkle_root;
 pub use crate::triedb::TrieDB;
 pub use crate::triedbmut::TrieDBMut;
+pub use crate::triediff::{TrieChange, TrieDiff};
 
 /// Trie Errors.
 ///
@@ -0,0 +1,196 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use ccrypto::{blake256, BLAKE_NULL_RLP};
+use primitives::{Bytes, H256};
+use rlp::{Prototype, UntrustedRlp};
+
+use crate::nibbleslice::NibbleSlice;
+use crate::node::Node as RlpNode;
+
+/// The reason why a proof made by `TrieDB::get_proof` is rejected.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ProofError {
+    /// The proof doesn't have the node that the path passes through.
+    MissingNode(H256),
+    /// The node is not a valid trie node.
+    InvalidNode(H256),
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofError::MissingNode(hash) => write!(f, "Proof missing expected node: {}", hash),
+            ProofError::InvalidNode(hash) => write!(f, "Proof has an invalid node: {}", hash),
+        }
+    }
+}
+
+/// Verify the `proof` of `key` against the trie `root` without any database.
+///
+/// Returns the value of `key`, or `None` if the proof shows that the trie doesn't have `key`.
+///
+/// # Example
+/// ```
+/// extern crate memorydb;
+/// extern crate primitives;
+/// extern crate codechain_merkle as cmerkle;
+///
+/// use cmerkle::*;
+/// use memorydb::*;
+/// use primitives::H256;
+///
+/// fn main() {
+///   let mut memdb = MemoryDB::new();
+///   let mut root = H256::new();
+///   TrieDBMut::new(&mut memdb, &mut root).insert(b"foo", b"bar").unwrap();
+///   let proof = TrieDB::try_new(&memdb, &root).unwrap().get_proof(b"foo").unwrap();
+///
+///   assert_eq!(verify_proof(&root, b"foo", &proof), Ok(Some(b"bar".to_vec())));
+///   assert_eq!(verify_proof(&root, b"baz", &proof), Ok(None));
+/// }
+/// ```
+pub fn verify_proof(root: &H256, key: &[u8], proof: &[Bytes]) -> Result<Option<Bytes>, ProofError> {
+    if *root == BLAKE_NULL_RLP {
+        return Ok(None)
+    }
+    let nodes: HashMap<H256, &[u8]> = proof.iter().map(|node| (blake256(node), &node[..])).collect();
+
+    let path = blake256(key);
+    let mut path = NibbleSlice::new(&path);
+    let mut hash = *root;
+    loop {
+        let node_rlp = nodes.get(&hash).ok_or_else(|| ProofError::MissingNode(hash))?;
+        if !is_valid_node(node_rlp) {
+            return Err(ProofError::InvalidNode(hash))
+        }
+        match RlpNode::decoded(node_rlp) {
+            Some(RlpNode::Leaf(partial, value)) => {
+                let value = if partial == path {
+                    Some(value.to_vec())
+                } else {
+                    None
+                };
+                return Ok(value)
+            }
+            Some(RlpNode::Branch(partial, children)) => {
+                if path.len() <= partial.len() || !path.starts_with(&partial) {
+                    return Ok(None)
+                }
+                match children[path.mid(partial.len()).at(0) as usize] {
+                    Some(child) => {
+                        hash = child;
+                        path = path.mid(partial.len() + 1);
+                    }
+                    None => return Ok(None),
+                }
+            }
+            None => return Err(ProofError::InvalidNode(hash)),
+        }
+    }
+}
+
+/// Checks the shape of the node, so that decoding a node from an untrusted proof never panics.
+fn is_valid_node(node_rlp: &[u8]) -> bool {
+    let rlp = UntrustedRlp::new(node_rlp);
+    let is_valid_partial = |rlp: &UntrustedRlp| rlp.data().map(|partial| !partial.is_empty()).unwrap_or(false);
+    match rlp.prototype() {
+        Ok(Prototype::List(2)) => {
+            rlp.at(0).map(|partial| is_valid_partial(&partial)).unwrap_or(false)
+                && rlp.at(1).and_then(|value| value.data()).is_ok()
+        }
+        Ok(Prototype::List(17)) => {
+            rlp.at(0).map(|partial| is_valid_partial(&partial)).unwrap_or(false)
+                && (1..17).all(|i| match rlp.at(i).and_then(|child| child.data()) {
+                    Ok(child) => child.is_empty() || child.len() == 32,
+                    Err(_) => false,
+                })
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use memorydb::*;
+
+    use super::*;
+    use crate::*;
+
+    fn trie(memdb: &mut MemoryDB) -> H256 {
+        let mut root = H256::new();
+        {
+            let mut t = TrieDBMut::new(memdb, &mut root);
+            for i in 0..100u8 {
+                t.insert(&[i], &[i, i]).unwrap();
+            }
+        }
+        root
+    }
+
+    #[test]
+    fn proof_of_existing_key() {
+        let mut memdb = MemoryDB::new();
+        let root = trie(&mut memdb);
+        let t = TrieDB::try_new(&memdb, &root).unwrap();
+
+        for i in 0..100u8 {
+            let proof = t.get_proof(&[i]).unwrap();
+            assert_eq!(verify_proof(&root, &[i], &proof), Ok(Some(vec![i, i])));
+        }
+    }
+
+    #[test]
+    fn proof_of_absent_key() {
+        let mut memdb = MemoryDB::new();
+        let root = trie(&mut memdb);
+        let t = TrieDB::try_new(&memdb, &root).unwrap();
+
+        let proof = t.get_proof(&[200]).unwrap();
+        assert!(!proof.is_empty());
+        assert_eq!(verify_proof(&root, &[200], &proof), Ok(None));
+        assert_eq!(verify_proof(&BLAKE_NULL_RLP, &[200], &[]), Ok(None));
+    }
+
+    #[test]
+    fn tampered_proof_is_rejected() {
+        let mut memdb = MemoryDB::new();
+        let root = trie(&mut memdb);
+        let t = TrieDB::try_new(&memdb, &root).unwrap();
+
+        let mut proof = t.get_proof(&[7]).unwrap();
+        let leaf = proof.pop().unwrap();
+        let leaf_hash = blake256(&leaf);
+        assert_eq!(verify_proof(&root, &[7], &proof), Err(ProofError::MissingNode(leaf_hash)));
+
+        let mut tampered = leaf.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        proof.push(tampered);
+        assert_eq!(verify_proof(&root, &[7], &proof), Err(ProofError::MissingNode(leaf_hash)));
+
+        assert_eq!(verify_proof(&root, &[7], &[]), Err(ProofError::MissingNode(root)));
+    }
+
+    #[test]
+    fn malformed_node_is_rejected() {
+        let node = vec![0xc2, 0x80, 0x80];
+        let root = blake256(&node);
+        assert_eq!(verify_proof(&root, &[7], &[node]), Err(ProofError::InvalidNode(root)));
+    }
+}
@@ -14,9 +14,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use ccrypto::blake256;
+use ccrypto::{blake256, BLAKE_NULL_RLP};
 use hashdb::HashDB;
-use primitives::H256;
+use primitives::{Bytes, H256};
 
 use crate::nibbleslice::NibbleSlice;
 use crate::node::Node as RlpNode;
@@ -71,6 +71,38 @@ impl<'db> TrieDB<'db> {
         self.db
     }
 
+    /// Get the nodes on the path to `key`, from the root to the leaf where the lookup ends.
+    ///
+    /// The nodes prove the value of `key` or its absence to anyone who knows the root.
+    /// See `verify_proof`.
+    pub fn get_proof(&self, key: &[u8]) -> crate::Result<Vec<Bytes>> {
+        let path = blake256(key);
+        let mut path = NibbleSlice::new(&path);
+        let mut proof = Vec::new();
+        if *self.root == BLAKE_NULL_RLP {
+            return Ok(proof)
+        }
+
+        let mut cur_node_hash = Some(*self.root);
+        while let Some(hash) = cur_node_hash {
+            let node_rlp = self.db.get(&hash).ok_or_else(|| TrieError::IncompleteDatabase(hash))?;
+            cur_node_hash = match RlpNode::decoded(&node_rlp) {
+                Some(RlpNode::Branch(partial, children)) => {
+                    if path.starts_with(&partial) {
+                        let next = children[path.mid(partial.len()).at(0) as usize];
+                        path = path.mid(partial.len() + 1);
+                        next
+                    } else {
+                        None
+                    }
+                }
+                Some(RlpNode::Leaf(..)) | None => None,
+            };
+            proof.push(node_rlp.to_vec());
+        }
+        Ok(proof)
+    }
+
     /// Get auxiliary
     fn get_aux<Q: Query>(
         &self,
@@ -143,4 +175,28 @@ mod tests {
         assert_eq!(t.get(b"B"), Ok(Some(DBValue::from_slice(b"ABCBA"))));
         assert_eq!(t.get(b"C"), Ok(None));
     }
+
+    #[test]
+    fn get_proof() {
+        let mut memdb = MemoryDB::new();
+        let mut root = H256::new();
+        {
+            let mut t = TrieDBMut::new(&mut memdb, &mut root);
+            for i in 0..100u8 {
+                t.insert(&[i], &[i, i]).unwrap();
+            }
+        }
+
+        let t = TrieDB::try_new(&memdb, &root).unwrap();
+        let proof = t.get_proof(&[7]).unwrap();
+        assert_eq!(blake256(&proof[0]), root);
+        match RlpNode::decoded(proof.last().unwrap()) {
+            Some(RlpNode::Leaf(_, value)) => assert_eq!(value, &[7, 7]),
+            node => panic!("The proof must end with the leaf, but {:?}", node),
+        }
+
+        let empty_root = BLAKE_NULL_RLP;
+        let t = TrieDB::try_new(&memdb, &empty_root).unwrap();
+        assert_eq!(t.get_proof(&[7]), Ok(vec![]));
+    }
 }
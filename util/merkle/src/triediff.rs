@@ -0,0 +1,379 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use ccrypto::BLAKE_NULL_RLP;
+use hashdb::HashDB;
+use primitives::{Bytes, H256};
+
+use crate::node::Node as RlpNode;
+use crate::TrieError;
+
+/// A key whose value differs between two tries.
+///
+/// The key is the path in the trie, which is the hash of the key that was inserted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrieChange {
+    pub key: H256,
+    /// The value in the first trie. `None` if the key was inserted.
+    pub before: Option<Bytes>,
+    /// The value in the second trie. `None` if the key was removed.
+    pub after: Option<Bytes>,
+}
+
+/// An iterator over the differences between two tries in the same `HashDB`, in the order of keys.
+///
+/// The subtrees that have the same hash in both tries are skipped without being read,
+/// so comparing two adjacent states costs about as much as reading the changed entries.
+///
+/// # Example
+/// ```
+/// extern crate hashdb;
+/// extern crate memorydb;
+/// extern crate primitives;
+/// extern crate codechain_merkle as cmerkle;
+///
+/// use cmerkle::*;
+/// use memorydb::*;
+/// use primitives::H256;
+///
+/// fn main() {
+///   let mut memdb = MemoryDB::new();
+///   let mut before = H256::new();
+///   TrieDBMut::new(&mut memdb, &mut before).insert(b"foo", b"bar").unwrap();
+///   let mut after = before;
+///   TrieDBMut::from_existing(&mut memdb, &mut after).unwrap().insert(b"foo", b"baz").unwrap();
+///
+///   let changes: Vec<_> = TrieDiff::try_new(&memdb, &before, &after).unwrap().collect::<Result<_>>().unwrap();
+///   assert_eq!(changes.len(), 1);
+///   assert_eq!(changes[0].before, Some(b"bar".to_vec()));
+///   assert_eq!(changes[0].after, Some(b"baz".to_vec()));
+/// }
+/// ```
+pub struct TrieDiff<'db> {
+    db: &'db HashDB,
+    /// The pairs of subtrees to compare, with the nibbles of their paths.
+    pending: Vec<(Vec<u8>, Option<H256>, Option<H256>)>,
+    ready: VecDeque<TrieChange>,
+}
+
+impl<'db> TrieDiff<'db> {
+    /// Returns an error if one of the roots does not exist in `db`
+    pub fn try_new(db: &'db HashDB, before: &H256, after: &H256) -> crate::Result<Self> {
+        for root in &[before, after] {
+            if **root != BLAKE_NULL_RLP && !db.contains(root) {
+                return Err(TrieError::InvalidStateRoot(**root))
+            }
+        }
+        Ok(TrieDiff {
+            db,
+            pending: vec![(Vec::new(), non_empty(*before), non_empty(*after))],
+            ready: VecDeque::new(),
+        })
+    }
+
+    fn node_rlp(&self, hash: Option<H256>) -> crate::Result<Option<Bytes>> {
+        match hash {
+            Some(hash) => {
+                let rlp = self.db.get(&hash).ok_or_else(|| TrieError::IncompleteDatabase(hash))?;
+                Ok(Some(rlp.to_vec()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn expand(&mut self, path: Vec<u8>, before: Option<H256>, after: Option<H256>) -> crate::Result<()> {
+        let before_rlp = self.node_rlp(before)?;
+        let after_rlp = self.node_rlp(after)?;
+        let before_node = before_rlp.as_ref().and_then(|rlp| RlpNode::decoded(rlp));
+        let after_node = after_rlp.as_ref().and_then(|rlp| RlpNode::decoded(rlp));
+
+        // Both branches split at the same nibble, so their children can be compared pairwise.
+        if let (
+            Some(RlpNode::Branch(before_partial, before_children)),
+            Some(RlpNode::Branch(after_partial, after_children)),
+        ) = (&before_node, &after_node)
+        {
+            if before_partial == after_partial {
+                let mut branch_path = path;
+                branch_path.extend((0..before_partial.len()).map(|i| before_partial.at(i)));
+                for i in (0..16).rev() {
+                    let mut child_path = branch_path.clone();
+                    child_path.push(i as u8);
+                    self.pending.push((child_path, before_children[i], after_children[i]));
+                }
+                return Ok(())
+            }
+        }
+
+        // The shapes are different, e.g. an inserted key split the partial that the branches shared.
+        // The children can't be paired, so all the leaves of the subtrees are compared.
+        // It reads the whole subtrees, but the paths are hashes, so long shared partials are rare above the bottom.
+        let mut before_leaves = BTreeMap::new();
+        self.collect_leaves(path.clone(), before, &mut before_leaves)?;
+        let mut after_leaves = BTreeMap::new();
+        self.collect_leaves(path, after, &mut after_leaves)?;
+
+        let mut changes = Vec::new();
+        for (key, before_value) in before_leaves {
+            match after_leaves.remove(&key) {
+                Some(ref after_value) if *after_value == before_value => {}
+                after_value => changes.push(TrieChange {
+                    key,
+                    before: Some(before_value),
+                    after: after_value,
+                }),
+            }
+        }
+        for (key, after_value) in after_leaves {
+            changes.push(TrieChange {
+                key,
+                before: None,
+                after: Some(after_value),
+            });
+        }
+        changes.sort_by(|a, b| a.key.cmp(&b.key));
+        self.ready.extend(changes);
+        Ok(())
+    }
+
+    fn collect_leaves(
+        &self,
+        mut path: Vec<u8>,
+        hash: Option<H256>,
+        leaves: &mut BTreeMap<H256, Bytes>,
+    ) -> crate::Result<()> {
+        let rlp = match self.node_rlp(hash)? {
+            Some(rlp) => rlp,
+            None => return Ok(()),
+        };
+        match RlpNode::decoded(&rlp) {
+            Some(RlpNode::Leaf(partial, value)) => {
+                path.extend((0..partial.len()).map(|i| partial.at(i)));
+                leaves.insert(key_from_nibbles(&path), value.to_vec());
+            }
+            Some(RlpNode::Branch(partial, children)) => {
+                path.extend((0..partial.len()).map(|i| partial.at(i)));
+                for (i, child) in children.iter().enumerate() {
+                    if child.is_some() {
+                        let mut child_path = path.clone();
+                        child_path.push(i as u8);
+                        self.collect_leaves(child_path, *child, leaves)?;
+                    }
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+impl<'db> Iterator for TrieDiff<'db> {
+    type Item = crate::Result<TrieChange>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(change) = self.ready.pop_front() {
+                return Some(Ok(change))
+            }
+            let (path, before, after) = self.pending.pop()?;
+            if before == after {
+                continue
+            }
+            if let Err(err) = self.expand(path, before, after) {
+                self.pending.clear();
+                return Some(Err(err))
+            }
+        }
+    }
+}
+
+fn non_empty(root: H256) -> Option<H256> {
+    if root == BLAKE_NULL_RLP {
+        None
+    } else {
+        Some(root)
+    }
+}
+
+fn key_from_nibbles(nibbles: &[u8]) -> H256 {
+    debug_assert_eq!(nibbles.len(), 64);
+    let mut key = H256::new();
+    for (i, pair) in nibbles.chunks(2).enumerate() {
+        key[i] = (pair[0] << 4) | pair.get(1).cloned().unwrap_or(0);
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use ccrypto::blake256;
+    use memorydb::*;
+
+    use super::*;
+    use crate::*;
+
+    fn diff(db: &MemoryDB, before: &H256, after: &H256) -> Vec<TrieChange> {
+        TrieDiff::try_new(db, before, after).unwrap().collect::<Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn same_root_has_no_change() {
+        let mut memdb = MemoryDB::new();
+        let mut root = H256::new();
+        {
+            let mut t = TrieDBMut::new(&mut memdb, &mut root);
+            t.insert(b"A", b"ABC").unwrap();
+            t.insert(b"B", b"ABCBA").unwrap();
+        }
+        assert_eq!(diff(&memdb, &root, &root), vec![]);
+        assert_eq!(diff(&memdb, &BLAKE_NULL_RLP, &BLAKE_NULL_RLP), vec![]);
+    }
+
+    #[test]
+    fn inserted_changed_and_removed_keys() {
+        let mut memdb = MemoryDB::new();
+        let mut before = H256::new();
+        {
+            let mut t = TrieDBMut::new(&mut memdb, &mut before);
+            for i in 0..100u8 {
+                t.insert(&[i], &[i, i]).unwrap();
+            }
+        }
+        let mut after = before;
+        {
+            let mut t = TrieDBMut::from_existing(&mut memdb, &mut after).unwrap();
+            t.insert(&[3], b"changed").unwrap();
+            t.remove(&[40]).unwrap();
+            t.insert(&[200], b"inserted").unwrap();
+        }
+
+        let mut expected = vec![
+            TrieChange {
+                key: blake256(&[3u8]),
+                before: Some(vec![3, 3]),
+                after: Some(b"changed".to_vec()),
+            },
+            TrieChange {
+                key: blake256(&[40u8]),
+                before: Some(vec![40, 40]),
+                after: None,
+            },
+            TrieChange {
+                key: blake256(&[200u8]),
+                before: None,
+                after: Some(b"inserted".to_vec()),
+            },
+        ];
+        expected.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(diff(&memdb, &before, &after), expected);
+
+        let reversed: Vec<_> = expected
+            .into_iter()
+            .map(|change| TrieChange {
+                key: change.key,
+                before: change.after,
+                after: change.before,
+            })
+            .collect();
+        assert_eq!(diff(&memdb, &after, &before), reversed);
+    }
+
+    #[test]
+    fn diff_from_empty_trie() {
+        let mut memdb = MemoryDB::new();
+        let mut root = H256::new();
+        {
+            let mut t = TrieDBMut::new(&mut memdb, &mut root);
+            t.insert(b"A", b"ABC").unwrap();
+            t.insert(b"B", b"ABCBA").unwrap();
+        }
+        let changes = diff(&memdb, &BLAKE_NULL_RLP, &root);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|change| change.before.is_none()));
+    }
+
+    #[test]
+    fn inserted_key_splits_the_shared_partial() {
+        // Finds the keys whose paths start with the nibbles `a` and `b`, and diverge after them.
+        let mut keys = Vec::new();
+        let mut third_nibbles = Vec::new();
+        let mut split_key = None;
+        for i in 0..100_000u32 {
+            let key = i.to_be_bytes();
+            let path = blake256(&key);
+            if path[0] == 0xab && keys.len() < 8 {
+                keys.push(key);
+                third_nibbles.push(path[1] >> 4);
+            } else if path[0] >> 4 == 0xa && split_key.is_none() {
+                split_key = Some(key);
+            }
+            if keys.len() == 8 && split_key.is_some() {
+                break
+            }
+        }
+        third_nibbles.sort();
+        third_nibbles.dedup();
+        assert!(third_nibbles.len() > 1);
+        let split_key = split_key.unwrap();
+
+        let mut memdb = MemoryDB::new();
+        let mut before = H256::new();
+        {
+            let mut t = TrieDBMut::new(&mut memdb, &mut before);
+            for key in &keys {
+                t.insert(key, key).unwrap();
+            }
+        }
+        let mut after = before;
+        {
+            let mut t = TrieDBMut::from_existing(&mut memdb, &mut after).unwrap();
+            t.insert(&split_key, b"inserted").unwrap();
+            t.insert(&keys[3], b"changed").unwrap();
+        }
+        // The root branches have the different partials.
+        match (RlpNode::decoded(&memdb.get(&before).unwrap()), RlpNode::decoded(&memdb.get(&after).unwrap())) {
+            (Some(RlpNode::Branch(before_partial, _)), Some(RlpNode::Branch(after_partial, _))) => {
+                assert_eq!(2, before_partial.len());
+                assert_eq!(1, after_partial.len());
+            }
+            _ => panic!("The roots must be branches"),
+        }
+
+        let mut expected = vec![
+            TrieChange {
+                key: blake256(&split_key),
+                before: None,
+                after: Some(b"inserted".to_vec()),
+            },
+            TrieChange {
+                key: blake256(&keys[3]),
+                before: Some(keys[3].to_vec()),
+                after: Some(b"changed".to_vec()),
+            },
+        ];
+        expected.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(diff(&memdb, &before, &after), expected);
+    }
+
+    #[test]
+    fn missing_root_is_an_error() {
+        let memdb = MemoryDB::new();
+        let root = blake256(b"unknown");
+        assert_eq!(TrieDiff::try_new(&memdb, &root, &BLAKE_NULL_RLP).err(), Some(TrieError::InvalidStateRoot(root)));
+    }
+}
@@ -27,7 +27,11 @@ mod decoder;
 mod executor;
 mod instruction;
 mod opcode;
+mod p2pkh;
 
 pub use crate::decoder::{decode, DecoderError};
 pub use crate::executor::{execute, ChainTimeInfo, Config as VMConfig, RuntimeError, ScriptResult};
 pub use crate::instruction::Instruction;
+pub use crate::p2pkh::{
+    p2pkh_lock_script_hash, p2pkh_owner, p2pkh_parameters, p2pkh_unlock_script, P2PKH_LOCK_SCRIPT, SIGN_ALL_TAG,
+};
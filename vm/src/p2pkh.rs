@@ -0,0 +1,61 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The standard pay-to-public-key-hash script.
+//!
+//! An asset locked by P2PKH takes the blake160 hash of a public key as its only parameter and is unlocked by the
+//! signature of the key.
+
+use ccrypto::Blake;
+use ckey::{Public, Signature, SIGNATURE_LENGTH};
+use primitives::{Bytes, H160};
+
+use crate::opcode::{BLAKE160, CHKSIG, COPY, EQ, JZ, PUSHB};
+
+pub const P2PKH_LOCK_SCRIPT: [u8; 7] = [COPY, 0x01, BLAKE160, EQ, JZ, 0xff, CHKSIG];
+
+/// The tag that signs all inputs and all outputs.
+pub const SIGN_ALL_TAG: u8 = 0b11;
+
+pub fn p2pkh_lock_script_hash() -> H160 {
+    H160::blake(&P2PKH_LOCK_SCRIPT[..])
+}
+
+pub fn p2pkh_parameters(public: &Public) -> Vec<Bytes> {
+    vec![H160::blake(&public[..]).to_vec()]
+}
+
+/// Returns the owner's public key hash if the asset is locked by P2PKH.
+pub fn p2pkh_owner(lock_script_hash: &H160, parameters: &[Bytes]) -> Option<H160> {
+    if *lock_script_hash != p2pkh_lock_script_hash() {
+        return None
+    }
+    match parameters {
+        [public_hash] if public_hash.len() == 20 => Some(H160::from(&public_hash[..])),
+        _ => None,
+    }
+}
+
+/// Creates the unlock script with a signature made with `SIGN_ALL_TAG`.
+pub fn p2pkh_unlock_script(signature: &Signature, public: &Public) -> Bytes {
+    let mut script = Vec::with_capacity(SIGNATURE_LENGTH + 64 + 7);
+    script.extend_from_slice(&[PUSHB, SIGNATURE_LENGTH as u8]);
+    script.extend_from_slice(&signature[..]);
+    script.extend_from_slice(&[PUSHB, 1, SIGN_ALL_TAG]);
+    script.extend_from_slice(&[PUSHB, 64]);
+    script.extend_from_slice(&public[..]);
+    script
+}
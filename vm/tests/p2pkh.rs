@@ -0,0 +1,137 @@
+// Copyright 2019 Kodebox, Inc.
+// This file is part of CodeChain.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+extern crate codechain_crypto as ccrypto;
+extern crate codechain_key as ckey;
+extern crate codechain_types as ctypes;
+extern crate codechain_vm as cvm;
+extern crate primitives;
+extern crate secp256k1;
+
+mod common;
+
+use ckey::{public_to_address, sign, KeyPair, NetworkId, Private};
+use ctypes::transaction::{AssetOutPoint, AssetTransferInput, AssetTransferOutput, PartialHashing, ShardTransaction};
+use ctypes::util::tag::Tag;
+use primitives::H160;
+use secp256k1::key::{MINUS_ONE_KEY, ONE_KEY};
+
+use cvm::{
+    decode, execute, p2pkh_lock_script_hash, p2pkh_owner, p2pkh_parameters, p2pkh_unlock_script, ScriptResult,
+    VMConfig, P2PKH_LOCK_SCRIPT, SIGN_ALL_TAG,
+};
+
+use common::TestClient;
+
+fn transaction() -> (ShardTransaction, AssetTransferInput) {
+    let input = AssetTransferInput {
+        prev_out: AssetOutPoint {
+            tracker: Default::default(),
+            index: 0,
+            asset_type: H160::default(),
+            shard_id: 0,
+            quantity: 10,
+        },
+        timelock: None,
+        lock_script: P2PKH_LOCK_SCRIPT.to_vec(),
+        unlock_script: Vec::new(),
+    };
+    let transaction = ShardTransaction::TransferAsset {
+        network_id: NetworkId::default(),
+        burns: Vec::new(),
+        inputs: vec![input.clone()],
+        outputs: vec![AssetTransferOutput {
+            lock_script_hash: p2pkh_lock_script_hash(),
+            parameters: vec![H160::random().to_vec()],
+            asset_type: H160::default(),
+            shard_id: 0,
+            quantity: 10,
+        }],
+        orders: Vec::new(),
+    };
+    (transaction, input)
+}
+
+#[test]
+fn p2pkh_lock_script_hash_is_standard() {
+    assert_eq!(H160::from("5f5960a7bca6ceeeb0c97bc717562914e7a1de04"), p2pkh_lock_script_hash());
+}
+
+#[test]
+fn owner_of_p2pkh_asset() {
+    let keypair = KeyPair::from_private(Private::from(ONE_KEY)).unwrap();
+    let parameters = p2pkh_parameters(keypair.public());
+    assert_eq!(Some(*public_to_address(keypair.public())), p2pkh_owner(&p2pkh_lock_script_hash(), &parameters));
+    assert_eq!(None, p2pkh_owner(&H160::random(), &parameters));
+    assert_eq!(None, p2pkh_owner(&p2pkh_lock_script_hash(), &[]));
+}
+
+#[test]
+fn unlock_p2pkh() {
+    let client = TestClient::default();
+    let (transaction, input) = transaction();
+    let keypair = KeyPair::from_private(Private::from(ONE_KEY)).unwrap();
+    let message = transaction.hash_partially(Tag::try_new(vec![SIGN_ALL_TAG]).unwrap(), &input, false).unwrap();
+    let signature = sign(keypair.private(), &message).unwrap();
+
+    let unlock_script = decode(&p2pkh_unlock_script(&signature, keypair.public())).unwrap();
+    let lock_script = decode(&P2PKH_LOCK_SCRIPT).unwrap();
+    let parameters = p2pkh_parameters(keypair.public());
+    assert_eq!(
+        execute(
+            &unlock_script,
+            &parameters,
+            &lock_script,
+            &transaction,
+            VMConfig::default(),
+            &input,
+            false,
+            &client,
+            0,
+            0
+        ),
+        Ok(ScriptResult::Unlocked)
+    );
+}
+
+#[test]
+fn cannot_unlock_p2pkh_of_another_key() {
+    let client = TestClient::default();
+    let (transaction, input) = transaction();
+    let keypair = KeyPair::from_private(Private::from(ONE_KEY)).unwrap();
+    let another = KeyPair::from_private(Private::from(MINUS_ONE_KEY)).unwrap();
+    let message = transaction.hash_partially(Tag::try_new(vec![SIGN_ALL_TAG]).unwrap(), &input, false).unwrap();
+    let signature = sign(another.private(), &message).unwrap();
+
+    let unlock_script = decode(&p2pkh_unlock_script(&signature, another.public())).unwrap();
+    let lock_script = decode(&P2PKH_LOCK_SCRIPT).unwrap();
+    let parameters = p2pkh_parameters(keypair.public());
+    assert_eq!(
+        execute(
+            &unlock_script,
+            &parameters,
+            &lock_script,
+            &transaction,
+            VMConfig::default(),
+            &input,
+            false,
+            &client,
+            0,
+            0
+        ),
+        Ok(ScriptResult::Fail)
+    );
+}